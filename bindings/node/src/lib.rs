@@ -0,0 +1,102 @@
+//! Node.js bindings for the surreal-migraine runner.
+//!
+//! ```js
+//! const migraine = require("./surreal-migraine.node");
+//!
+//! const conn = { url: "ws://localhost:8000", namespace: "app", database: "app" };
+//! console.log(JSON.parse(await migraine.pending(conn, "migrations")));
+//! const report = JSON.parse(await migraine.up(conn, "migrations"));
+//! console.log(report.outcomes);
+//! console.log(JSON.parse(await migraine.runs(conn, "migrations")));
+//! ```
+use migraine::MigrationRunner;
+use migraine::connect::{ConnectOptions, connect};
+use migraine::types::DiskSource;
+use napi::{Error, Result};
+use napi_derive::napi;
+
+/// Connection settings passed from JavaScript.
+#[napi(object)]
+pub struct Connection {
+    pub url: String,
+    pub namespace: String,
+    pub database: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl From<Connection> for ConnectOptions {
+    fn from(c: Connection) -> Self {
+        Self {
+            url: c.url,
+            namespace: c.namespace,
+            database: c.database,
+            username: c.username,
+            password: c.password,
+        }
+    }
+}
+
+fn to_napi(e: eyre::Report) -> Error {
+    Error::from_reason(format!("{e:#}"))
+}
+
+/// Apply all pending migrations found in `migrationsDir`, resolving to what
+/// was applied as a JSON object string.
+#[napi]
+pub async fn up(connection: Connection, migrations_dir: String) -> Result<String> {
+    let db = connect(&connection.into()).await.map_err(to_napi)?;
+    let report = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+        .up()
+        .await
+        .map_err(to_napi)?;
+    serde_json::to_string(&report).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// Revert applied migrations found in `migrationsDir`, resolving to what was
+/// reverted as a JSON object string.
+#[napi]
+pub async fn down(connection: Connection, migrations_dir: String) -> Result<String> {
+    let db = connect(&connection.into()).await.map_err(to_napi)?;
+    let report = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+        .down()
+        .await
+        .map_err(to_napi)?;
+    serde_json::to_string(&report).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// Resolve to the names of the migrations `up` would apply, in order, as a
+/// JSON array string.
+#[napi]
+pub async fn pending(connection: Connection, migrations_dir: String) -> Result<String> {
+    let db = connect(&connection.into()).await.map_err(to_napi)?;
+    let plan = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+        .plan()
+        .await
+        .map_err(to_napi)?;
+    let names = plan.up.into_iter().map(|m| m.name).collect::<Vec<_>>();
+    serde_json::to_string(&names).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// Resolve to the state of every migration in `migrationsDir` as a JSON
+/// array string.
+#[napi]
+pub async fn status(connection: Connection, migrations_dir: String) -> Result<String> {
+    let db = connect(&connection.into()).await.map_err(to_napi)?;
+    let status = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+        .status()
+        .await
+        .map_err(to_napi)?;
+    serde_json::to_string(&status).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+/// Resolve to the persisted run reports as a JSON array string.
+#[napi]
+pub async fn runs(connection: Connection, migrations_dir: String) -> Result<String> {
+    let db = connect(&connection.into()).await.map_err(to_napi)?;
+    let runs = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+        .runs()
+        .await
+        .map_err(to_napi)?;
+    serde_json::to_string(&runs).map_err(|e| Error::from_reason(e.to_string()))
+}
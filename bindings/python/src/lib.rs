@@ -0,0 +1,173 @@
+//! Python bindings for the surreal-migraine runner.
+//!
+//! ```python
+//! import surreal_migraine
+//!
+//! print(surreal_migraine.pending("ws://localhost:8000", "app", "app", "migrations", "root", "root"))
+//! report = surreal_migraine.up("ws://localhost:8000", "app", "app", "migrations", "root", "root")
+//! print(report)
+//! print(surreal_migraine.runs("ws://localhost:8000", "app", "app", "migrations", "root", "root"))
+//! ```
+use migraine::MigrationRunner;
+use migraine::connect::{ConnectOptions, connect};
+use migraine::types::DiskSource;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use std::future::Future;
+
+/// Run `f` to completion on a fresh runtime, releasing the GIL meanwhile.
+fn block_on<F, T>(py: Python<'_>, f: F) -> PyResult<T>
+where
+    F: Future<Output = eyre::Result<T>> + Send,
+    T: Send,
+{
+    py.detach(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(eyre::Report::from)
+            .and_then(|rt| rt.block_on(f))
+    })
+    .map_err(|e| PyRuntimeError::new_err(format!("{e:#}")))
+}
+
+fn options(
+    url: String,
+    namespace: String,
+    database: String,
+    username: Option<String>,
+    password: Option<String>,
+) -> ConnectOptions {
+    ConnectOptions {
+        url,
+        namespace,
+        database,
+        username,
+        password,
+    }
+}
+
+/// Apply all pending migrations found in `migrations_dir`, returning what
+/// was applied as a JSON object string.
+#[pyfunction]
+#[pyo3(signature = (url, namespace, database, migrations_dir, username=None, password=None))]
+fn up(
+    py: Python<'_>,
+    url: String,
+    namespace: String,
+    database: String,
+    migrations_dir: String,
+    username: Option<String>,
+    password: Option<String>,
+) -> PyResult<String> {
+    let options = options(url, namespace, database, username, password);
+    block_on(py, async move {
+        let db = connect(&options).await?;
+        let report = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+            .up()
+            .await?;
+        Ok(serde_json::to_string(&report)?)
+    })
+}
+
+/// Revert applied migrations found in `migrations_dir`, returning what was
+/// reverted as a JSON object string.
+#[pyfunction]
+#[pyo3(signature = (url, namespace, database, migrations_dir, username=None, password=None))]
+fn down(
+    py: Python<'_>,
+    url: String,
+    namespace: String,
+    database: String,
+    migrations_dir: String,
+    username: Option<String>,
+    password: Option<String>,
+) -> PyResult<String> {
+    let options = options(url, namespace, database, username, password);
+    block_on(py, async move {
+        let db = connect(&options).await?;
+        let report = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+            .down()
+            .await?;
+        Ok(serde_json::to_string(&report)?)
+    })
+}
+
+/// Return the names of the migrations `up` would apply, in order, as a
+/// JSON array string.
+#[pyfunction]
+#[pyo3(signature = (url, namespace, database, migrations_dir, username=None, password=None))]
+fn pending(
+    py: Python<'_>,
+    url: String,
+    namespace: String,
+    database: String,
+    migrations_dir: String,
+    username: Option<String>,
+    password: Option<String>,
+) -> PyResult<String> {
+    let options = options(url, namespace, database, username, password);
+    block_on(py, async move {
+        let db = connect(&options).await?;
+        let plan = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+            .plan()
+            .await?;
+        let names = plan.up.into_iter().map(|m| m.name).collect::<Vec<_>>();
+        Ok(serde_json::to_string(&names)?)
+    })
+}
+
+/// Return the state of every migration in `migrations_dir` as a JSON array
+/// string.
+#[pyfunction]
+#[pyo3(signature = (url, namespace, database, migrations_dir, username=None, password=None))]
+fn status(
+    py: Python<'_>,
+    url: String,
+    namespace: String,
+    database: String,
+    migrations_dir: String,
+    username: Option<String>,
+    password: Option<String>,
+) -> PyResult<String> {
+    let options = options(url, namespace, database, username, password);
+    block_on(py, async move {
+        let db = connect(&options).await?;
+        let status = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+            .status()
+            .await?;
+        Ok(serde_json::to_string(&status)?)
+    })
+}
+
+/// Return the persisted run reports as a JSON array string.
+#[pyfunction]
+#[pyo3(signature = (url, namespace, database, migrations_dir, username=None, password=None))]
+fn runs(
+    py: Python<'_>,
+    url: String,
+    namespace: String,
+    database: String,
+    migrations_dir: String,
+    username: Option<String>,
+    password: Option<String>,
+) -> PyResult<String> {
+    let options = options(url, namespace, database, username, password);
+    block_on(py, async move {
+        let db = connect(&options).await?;
+        let runs = MigrationRunner::new(&db, DiskSource::new(migrations_dir))
+            .runs()
+            .await?;
+        Ok(serde_json::to_string(&runs)?)
+    })
+}
+
+#[pymodule]
+fn surreal_migraine(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(up, m)?)?;
+    m.add_function(wrap_pyfunction!(down, m)?)?;
+    m.add_function(wrap_pyfunction!(pending, m)?)?;
+    m.add_function(wrap_pyfunction!(status, m)?)?;
+    m.add_function(wrap_pyfunction!(runs, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,126 @@
+use eyre::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use surreal_migraine::checksum::checksum;
+
+/// Directory holding the caches; see `Project::cache_dir`.
+pub const CACHE_DIR: &str = ".smg-cache";
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile<T> {
+    /// smg version that wrote the file; the checks may differ in others.
+    version: String,
+    entries: HashMap<String, T>,
+}
+
+/// Results of a per-script check, keyed by the script's checksum, so
+/// repeated runs only check scripts that changed.
+pub struct Cache<T> {
+    /// Where the cache is saved; `None` when caching is off.
+    path: Option<PathBuf>,
+    entries: HashMap<String, T>,
+    /// Entries for the scripts seen in this run, the ones saved.
+    used: HashMap<String, T>,
+}
+
+impl<T: Serialize + DeserializeOwned + Clone + Send> Cache<T> {
+    /// Load the `name` cache from `dir`, or start empty when it is missing,
+    /// unreadable or from another smg version. `None` turns caching off.
+    pub fn load(dir: Option<&Path>, name: &str) -> Self {
+        let path = dir.map(|dir| dir.join(format!("{name}.json")));
+        let entries = path
+            .as_deref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(
+                |bytes| match serde_json::from_slice::<CacheFile<T>>(&bytes) {
+                    Ok(file) => Some(file),
+                    Err(e) => {
+                        tracing::debug!(name, error = %e, "ignoring unreadable cache");
+                        None
+                    }
+                },
+            )
+            .filter(|file| file.version == env!("CARGO_PKG_VERSION"))
+            .map(|file| file.entries)
+            .unwrap_or_default();
+        Self {
+            path,
+            entries,
+            used: HashMap::new(),
+        }
+    }
+
+    /// Results of `check` for each of `scripts`, in order. Scripts without
+    /// a cached result are checked in parallel.
+    pub fn check<F>(&mut self, scripts: &[&str], check: F) -> Vec<T>
+    where
+        F: Fn(&str) -> T + Sync,
+    {
+        let sums = scripts.iter().map(|s| checksum(s)).collect::<Vec<_>>();
+        let missing = scripts
+            .iter()
+            .zip(&sums)
+            .filter(|(_, sum)| !self.entries.contains_key(*sum))
+            .map(|(script, _)| *script)
+            .collect::<Vec<_>>();
+        tracing::debug!(
+            checked = missing.len(),
+            cached = scripts.len() - missing.len(),
+            "checking scripts"
+        );
+
+        let checked = parallel(&missing, &check);
+        for (script, result) in missing.iter().zip(checked) {
+            self.entries.insert(checksum(script), result);
+        }
+        sums.into_iter()
+            .map(|sum| {
+                let result = self.entries[&sum].clone();
+                self.used.insert(sum, result.clone());
+                result
+            })
+            .collect()
+    }
+
+    /// Save the results of the scripts seen in this run, dropping the rest.
+    pub fn save(self) -> Result<()> {
+        let Some(path) = self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+            let ignore = dir.join(".gitignore");
+            if !ignore.exists() {
+                std::fs::write(ignore, "*\n")?;
+            }
+        }
+        let file = CacheFile {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            entries: self.used,
+        };
+        // Replace the file in one step, so a concurrent run never reads
+        // half of it.
+        let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+        std::fs::write(&partial, serde_json::to_vec(&file)?)?;
+        std::fs::rename(partial, path)?;
+        Ok(())
+    }
+}
+
+/// `check` applied to each script, spread over the available cores.
+fn parallel<T: Send, F: Fn(&str) -> T + Sync>(scripts: &[&str], check: &F) -> Vec<T> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = scripts.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let handles = scripts
+            .chunks(chunk)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(|s| check(s)).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("script check panicked"))
+            .collect()
+    })
+}
@@ -4,14 +4,739 @@ use std::path::PathBuf;
 #[derive(Parser, Debug)]
 #[command(name = "surreal-migrate")]
 pub struct Args {
+    /// Use this profile from smg.toml (overrides `default_env`)
+    #[arg(long = "env", global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Output format of status, list, up, down and runs
+    #[arg(long, global = true, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
     #[command(subcommand)]
     pub command: Commands,
 }
 
+/// How commands print their results.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Tables and log lines for people
+    Text,
+    /// One JSON object on stdout, logs on stderr
+    Json,
+}
+
+/// Embedded, file-backed storage engines `--engine` can open.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// SurrealKV (the `surrealkv` feature, on by default)
+    Surrealkv,
+    /// RocksDB (the `rocksdb` feature)
+    Rocksdb,
+}
+
+impl Engine {
+    /// The URL scheme SurrealDB opens the engine with.
+    pub fn scheme(self) -> &'static str {
+        match self {
+            Engine::Surrealkv => "surrealkv",
+            Engine::Rocksdb => "rocksdb",
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 pub enum Commands {
     /// Add a new migration file
     Add(AddArgs),
+    /// Show statistics
+    #[command(subcommand)]
+    Stats(StatsCommand),
+    /// Apply pending migrations to a database
+    Up(UpArgs),
+    /// Revert applied migrations
+    Down(DownArgs),
+    /// Show which migrations are applied
+    Status(StatusArgs),
+    /// Tag the most recently applied migration, e.g. with a release version
+    Tag(TagArgs),
+    /// Inspect the reports of past runs recorded in the database
+    #[command(subcommand)]
+    Runs(RunsCommand),
+    /// List discovered migrations in the order they apply
+    List(ListArgs),
+    /// Revert the latest (or a named) migration and apply it again
+    Redo(RedoArgs),
+    /// Print the effective configuration and where each value came from
+    Env(EnvArgs),
+    /// Wipe the database and apply every migration from scratch
+    Fresh(FreshArgs),
+    /// Run the data-population scripts of a seeds directory
+    Seed(SeedArgs),
+    /// Apply pending migrations twice on a throwaway database to find
+    /// statements that are not safe to re-run
+    Rehearse(RehearseArgs),
+    /// Check migration scripts for secrets and personal data
+    Lint(LintArgs),
+    /// Check migration naming, layout, encoding and syntax without a
+    /// database, e.g. from a pre-commit hook
+    Validate(ValidateArgs),
+    /// Print the apply order and declared dependencies of the migrations
+    /// as a DOT or Mermaid graph
+    Graph(GraphArgs),
+    /// Combine a range of old migrations into one and archive the originals
+    Squash(SquashArgs),
+    /// Report SurrealQL in migrations that a newer server version no
+    /// longer accepts
+    UpgradeAdvise(UpgradeAdviseArgs),
+    /// Generate a Rust integration test checking that the migrations apply
+    /// and produce the current schema
+    ExportTest(ExportTestArgs),
+    /// Generate the DEFINE and REMOVE statements that turn a database's
+    /// schema into the desired one
+    Diff(DiffArgs),
+    /// Report schema objects changed outside migrations, by comparing the
+    /// database with a replay of its applied migrations
+    Drift(DriftArgs),
+    /// Write a down script removing what a paired migration's up script
+    /// defines, as a starting point
+    GenerateDown(GenerateDownArgs),
+    /// Turn a single-file migration into a paired folder, or back
+    Convert(ConvertArgs),
+    /// Rename a migration, updating its applied record when connected
+    Rename(RenameArgs),
+    /// Find migrations sharing a version or out of order with a database's
+    /// history, and suggest renames
+    CheckConflicts(CheckConflictsArgs),
+    /// Write migrations.lock, listing every migration and its checksum
+    Lock(LockArgs),
+    /// CI gate: fail when migrations differ from migrations.lock or from
+    /// what a database applied
+    Check(CheckArgs),
+    /// Apply pending migrations to a dev database whenever the migrations
+    /// directory changes
+    Watch(WatchArgs),
+}
+
+/// How to reach the database.
+///
+/// Each flag falls back to a `SURREAL_*` environment variable, then to the
+/// selected smg.toml profile.
+#[derive(clap::Args, Debug, Clone)]
+pub struct ConnectArgs {
+    /// Endpoint URL (ws://, wss://, http://, https://, surrealkv:// or
+    /// rocksdb:// followed by a data directory, or mem:// for a throwaway
+    /// database) [env: SURREAL_URL] [default: ws://localhost:8000]
+    #[arg(long)]
+    pub url: Option<String>,
+
+    /// Open an embedded, file-backed database stored in --path instead of
+    /// connecting to --url
+    #[arg(long, requires = "path", conflicts_with = "url")]
+    pub engine: Option<Engine>,
+
+    /// Data directory of the --engine database
+    #[arg(long, requires = "engine")]
+    pub path: Option<PathBuf>,
+
+    /// Namespace (required to connect) [env: SURREAL_NS]
+    #[arg(long = "ns")]
+    pub namespace: Option<String>,
+
+    /// Database (required to connect) [env: SURREAL_DB]
+    #[arg(long = "db")]
+    pub database: Option<String>,
+
+    /// Root username [env: SURREAL_USER]
+    #[arg(long = "user")]
+    pub username: Option<String>,
+
+    /// Root password [env: SURREAL_PASS]
+    #[arg(long = "pass")]
+    pub password: Option<String>,
+
+    /// Retry a connection that fails with a transient error (refused,
+    /// reset, timed out) up to N times, with growing waits in between
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retries: u32,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct UpArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    #[command(flatten)]
+    pub timeouts: TimeoutArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Resolve drift (edited or missing applied migrations) from this file
+    /// instead of failing
+    #[arg(long, conflicts_with = "interactive")]
+    pub resolution_file: Option<PathBuf>,
+
+    /// Ask how to resolve each drift item
+    #[arg(short, long)]
+    pub interactive: bool,
+
+    /// Change-management ticket recorded with the run, e.g. JIRA-123
+    /// (default: $SMG_CHANGE_REF)
+    #[arg(long, value_name = "REF")]
+    pub change_ref: Option<String>,
+
+    /// Refuse to apply anything unless the migrations match
+    /// migrations.lock
+    #[arg(long)]
+    pub locked: bool,
+
+    /// Apply up to N migrations at once; only their `-- depends:` headers
+    /// order them
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub parallel: usize,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct StatusArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Only show migrations discovered after the one this tag points at
+    #[arg(long)]
+    pub since_tag: Option<String>,
+
+    /// Count the statements of pending migrations by kind (define, update,
+    /// relate, ...) to tell schema-only deploys from data-heavy ones
+    #[arg(long)]
+    pub detailed: bool,
+
+    /// Verbose logging; also lists entries skipped because they are not
+    /// migrations
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+#[group(id = "filter", multiple = false)]
+pub struct ListArgs {
+    /// Database to compare against; only needed for --pending and --applied
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Only migrations not yet applied
+    #[arg(long, group = "filter")]
+    pub pending: bool,
+
+    /// Only applied migrations
+    #[arg(long, group = "filter")]
+    pub applied: bool,
+
+    /// Every discovered migration, plus entries skipped because they are not
+    /// migrations (the default; needs no database)
+    #[arg(long, group = "filter")]
+    pub all: bool,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RedoArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Migration to redo (default: the latest applied one)
+    pub name: Option<String>,
+
+    /// Print the down and up scripts that would run, without running them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Change-management ticket recorded with the run, e.g. JIRA-123
+    /// (default: $SMG_CHANGE_REF)
+    #[arg(long, value_name = "REF")]
+    pub change_ref: Option<String>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct FreshArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    #[command(flatten)]
+    pub timeouts: TimeoutArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Confirm that everything in the database may be deleted
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Run even though the target looks like production
+    #[arg(long)]
+    pub force: bool,
+
+    /// Change-management ticket recorded with the run, e.g. JIRA-123
+    /// (default: $SMG_CHANGE_REF)
+    #[arg(long, value_name = "REF")]
+    pub change_ref: Option<String>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SeedArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Directory holding the seed scripts
+    #[arg(long, default_value = "seeds")]
+    pub seeds: PathBuf,
+
+    /// Factor substituted for `{{ seed.scale }}` in seeds, e.g. `10x`
+    #[arg(long, value_name = "FACTOR", default_value = "1x", value_parser = parse_scale)]
+    pub scale: u32,
+
+    /// Change-management ticket recorded with the run, e.g. JIRA-123
+    /// (default: $SMG_CHANGE_REF)
+    #[arg(long, value_name = "REF")]
+    pub change_ref: Option<String>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+/// Parse a scale factor such as `10x` or `10`.
+fn parse_scale(value: &str) -> Result<u32, String> {
+    match value.strip_suffix(['x', 'X']).unwrap_or(value).parse() {
+        Ok(scale) if scale > 0 => Ok(scale),
+        _ => Err(format!("`{value}` is not a scale factor such as `10x`")),
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RehearseArgs {
+    /// Database whose pending migrations to rehearse; without --ns and --db
+    /// every migration is rehearsed
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct LintArgs {
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Check every script instead of reusing results cached in
+    /// `.smg-cache/` for unchanged ones
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ValidateArgs {
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Check every script instead of reusing results cached in
+    /// `.smg-cache/` for unchanged ones
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Also report versions missing from a sequential (`001_`, `002_`, ...)
+    /// numbering
+    #[arg(long)]
+    pub gaps: bool,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct GraphArgs {
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Print a Mermaid flowchart instead of Graphviz DOT
+    #[arg(long)]
+    pub mermaid: bool,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SquashArgs {
+    /// Last migration to squash; the new migration takes its version
+    #[arg(long, value_name = "NAME")]
+    pub to: String,
+
+    /// First migration to squash (default: the first migration)
+    #[arg(long, value_name = "NAME")]
+    pub from: Option<String>,
+
+    /// Description part of the new migration's name
+    #[arg(long, default_value = "squashed")]
+    pub name: String,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct UpgradeAdviseArgs {
+    /// SurrealDB version you are upgrading to, e.g. `3.0`
+    #[arg(long, value_name = "VERSION")]
+    pub to: surreal_migraine::upgrade::Version,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ExportTestArgs {
+    /// Where to write the test
+    #[arg(long, default_value = "tests/migrations.rs")]
+    pub out: PathBuf,
+
+    /// Where to write the golden schema snapshot the test compares against
+    #[arg(long, default_value = "tests/schema.json")]
+    pub golden: PathBuf,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// Database whose schema to start from
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Start from a schema snapshot file instead of a database
+    #[arg(long, value_name = "FILE")]
+    pub from_snapshot: Option<PathBuf>,
+
+    /// The desired schema: a SurrealQL script, or a schema snapshot (`.json`)
+    #[arg(long, value_name = "FILE")]
+    pub schema: PathBuf,
+
+    /// Write the changes to a new paired migration with this name instead
+    /// of printing them
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Use temporal (timestamp) prefix for the new migration
+    #[arg(short, long)]
+    pub temporal: bool,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DriftArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct GenerateDownArgs {
+    /// Paired migration whose down script to generate
+    pub name: String,
+
+    /// Replace a down script that already has statements
+    #[arg(long)]
+    pub force: bool,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ConvertArgs {
+    /// Migration to convert
+    pub name: String,
+
+    /// Drop a down script with statements when converting to a single file
+    #[arg(long)]
+    pub force: bool,
+
+    /// Database whose applied record to update; without --ns and --db only
+    /// the migration is converted
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Don't ask before updating the applied record
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RenameArgs {
+    /// Migration to rename
+    pub from: String,
+
+    /// New name, keeping a version prefix (`.surql` is added or dropped to
+    /// match the migration's layout)
+    pub to: String,
+
+    /// Database whose applied record to update; without --ns and --db only
+    /// the migration is renamed
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Don't ask before updating the applied record
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct EnvArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TagArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Tag name
+    pub name: String,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+/// Limits that make a hung connection or runaway migration fail the run.
+#[derive(clap::Args, Debug, Clone)]
+pub struct TimeoutArgs {
+    /// Fail a migration that runs for longer than SECS seconds
+    #[arg(long, value_name = "SECS")]
+    pub migration_timeout: Option<u64>,
+
+    /// Fail the run when it takes longer than SECS seconds in total
+    #[arg(long, value_name = "SECS")]
+    pub run_timeout: Option<u64>,
+}
+
+#[derive(clap::Args, Debug)]
+#[group(id = "target", multiple = false)]
+pub struct DownArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    #[command(flatten)]
+    pub timeouts: TimeoutArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Revert the last N applied migrations (default: 1)
+    #[arg(long, group = "target")]
+    pub steps: Option<usize>,
+
+    /// Revert everything applied after this migration, keeping it applied
+    #[arg(long, group = "target")]
+    pub to: Option<String>,
+
+    /// Revert everything applied after the migration this tag points at
+    #[arg(long, group = "target")]
+    pub to_tag: Option<String>,
+
+    /// Revert the migrations of the most recent batch, i.e. everything the
+    /// last `smg up` applied
+    #[arg(long, group = "target")]
+    pub last_batch: bool,
+
+    /// Revert every applied migration (asks for confirmation)
+    #[arg(long, group = "target")]
+    pub all: bool,
+
+    /// Don't ask for confirmation
+    #[arg(short, long)]
+    pub yes: bool,
+
+    /// Change-management ticket recorded with the run, e.g. JIRA-123
+    /// (default: $SMG_CHANGE_REF)
+    #[arg(long, value_name = "REF")]
+    pub change_ref: Option<String>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RunsCommand {
+    /// List past runs, oldest first
+    List(RunsListArgs),
+    /// Show a run's fingerprint and the outcome of each migration
+    Show(RunsShowArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RunsListArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RunsShowArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// The run, as listed by `smg runs list`
+    pub id: String,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum StatsCommand {
+    /// Summarize the migration tree: kinds, down coverage, sizes, authors and growth
+    Project(ProjectStatsArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ProjectStatsArgs {
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Number of largest migrations to list
+    #[arg(long, default_value_t = 5)]
+    pub top: usize,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
 }
 
 #[derive(clap::Args, Debug)]
@@ -23,6 +748,10 @@ pub struct AddArgs {
     #[arg(short, long)]
     pub temporal: bool,
 
+    /// Use numeric prefix even when smg.toml sets `naming = "temporal"`
+    #[arg(long, conflicts_with = "temporal")]
+    pub numeric: bool,
+
     /// Create a single migration file instead of the default paired folder
     #[arg(long)]
     pub single: bool,
@@ -35,3 +764,70 @@ pub struct AddArgs {
     #[arg(short, long, action = clap::ArgAction::Count)]
     pub verbose: u8,
 }
+
+#[derive(clap::Args, Debug)]
+pub struct CheckConflictsArgs {
+    /// Database whose history to check against; without --ns and --db only
+    /// shared versions are found
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct LockArgs {
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CheckArgs {
+    /// Database to check against; without --ns and --db only
+    /// migrations.lock is checked
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Also fail when migrations.lock is missing or the database has
+    /// pending migrations
+    #[arg(long)]
+    pub frozen: bool,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub connect: ConnectArgs,
+
+    /// Override migrations directory
+    #[arg(long)]
+    pub dir: Option<PathBuf>,
+
+    /// Wait this long after the last change before applying, so a burst of
+    /// saves applies once
+    #[arg(long, value_name = "MS", default_value_t = 300)]
+    pub debounce: u64,
+
+    /// Verbose logging
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+}
@@ -0,0 +1,98 @@
+use eyre::{Result, eyre};
+use std::path::{Path, PathBuf};
+use surreal_migraine::statements;
+use surreal_migraine::types::{Migration, MigrationKind};
+
+/// Turn the single-file `migration` in `dir` into a paired folder, or a
+/// paired folder back into a single file, keeping its prefix and up script.
+/// Returns the new path.
+///
+/// A new folder gets a `down.surql` stub holding the up script's comment
+/// header. Folding a folder drops its down script, so one with statements
+/// is only dropped with `force`.
+pub fn convert(dir: &Path, migration: &Migration, force: bool) -> Result<PathBuf> {
+    match migration.kind {
+        MigrationKind::File => to_paired(dir, &migration.name),
+        MigrationKind::Paired => to_single(dir, &migration.name, force),
+        MigrationKind::Repeatable => Err(repeatable(migration)),
+    }
+}
+
+/// The name `migration` has once converted: `001_users.surql` becomes
+/// `001_users` and back.
+pub fn converted_name(migration: &Migration) -> Result<String> {
+    let name = migration.name.as_str();
+    match migration.kind {
+        MigrationKind::File => Ok(name.strip_suffix(".surql").unwrap_or(name).to_string()),
+        MigrationKind::Paired => Ok(format!("{name}.surql")),
+        MigrationKind::Repeatable => Err(repeatable(migration)),
+    }
+}
+
+fn repeatable(migration: &Migration) -> eyre::Report {
+    eyre!(
+        "{} is a repeatable migration; those have no down script and stay single files",
+        migration.name
+    )
+}
+
+fn to_paired(dir: &Path, file: &str) -> Result<PathBuf> {
+    let stem = file.strip_suffix(".surql").unwrap_or(file);
+    let folder = dir.join(stem);
+    if folder.exists() {
+        eyre::bail!("{} already exists", folder.display());
+    }
+
+    let up = std::fs::read_to_string(dir.join(file))?;
+    let header = up
+        .lines()
+        .take_while(|line| line.trim_start().starts_with("--"))
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    std::fs::create_dir(&folder)?;
+    std::fs::rename(dir.join(file), folder.join("up.surql"))?;
+    std::fs::write(folder.join("down.surql"), header)?;
+    Ok(folder)
+}
+
+fn to_single(dir: &Path, folder: &str, force: bool) -> Result<PathBuf> {
+    let path = dir.join(folder);
+    let file = dir.join(format!("{folder}.surql"));
+    if file.exists() {
+        eyre::bail!("{} already exists", file.display());
+    }
+
+    let mut others = Vec::new();
+    for entry in std::fs::read_dir(&path)? {
+        let entry = entry?.file_name().to_string_lossy().into_owned();
+        if entry != "up.surql" && entry != "down.surql" {
+            others.push(entry);
+        }
+    }
+    if !others.is_empty() {
+        others.sort();
+        eyre::bail!(
+            "{} holds more than up.surql and down.surql: {}",
+            path.display(),
+            others.join(", ")
+        );
+    }
+
+    let down = path.join("down.surql");
+    if down.exists() && !force {
+        let script = std::fs::read_to_string(&down)?;
+        if !statements::split(&script).is_empty() {
+            eyre::bail!(
+                "{} has statements that a single-file migration cannot keep; pass --force to drop it",
+                down.display()
+            );
+        }
+    }
+
+    std::fs::rename(path.join("up.surql"), &file)?;
+    if down.exists() {
+        std::fs::remove_file(down)?;
+    }
+    std::fs::remove_dir(path)?;
+    Ok(file)
+}
@@ -0,0 +1,700 @@
+use crate::cli::{
+    CheckArgs, CheckConflictsArgs, ConnectArgs, ConvertArgs, DiffArgs, DownArgs, DriftArgs, Format,
+    FreshArgs, ListArgs, RedoArgs, RehearseArgs, RenameArgs, RunsShowArgs, SeedArgs, StatusArgs,
+    TagArgs, TimeoutArgs, UpArgs,
+};
+use crate::config::{Connection, Project};
+use crate::progress::Bar;
+use crate::rehearse::Finding;
+use eyre::Result;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use surreal_migraine::MigrationRunner;
+use surreal_migraine::connect::{self, ConnectOptions};
+use surreal_migraine::drift::{Drift, DriftKind, Resolution, ResolutionFile};
+use surreal_migraine::lockfile::{LOCK_FILE, LockFile};
+use surreal_migraine::retry::RetryPolicy;
+use surreal_migraine::schema::{SchemaChange, SchemaSnapshot};
+use surreal_migraine::statements::StatementStats;
+use surreal_migraine::types::{
+    CancelToken, DiskSource, InMemorySource, MigrationKind, MigrationReport, MigrationSource,
+    MigrationStatus, RunDirection,
+};
+use surreal_migraine::validate::{Conflict, Renumber};
+use surrealdb::Surreal;
+use surrealdb::engine::any::Any;
+
+/// Run `future` to completion on a fresh Tokio runtime.
+pub fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    Ok(runtime.block_on(future))
+}
+
+/// A token cancelled on Ctrl-C, so an interrupted run stops its in-flight
+/// query and releases the lock instead of dying mid-migration.
+pub fn cancel_on_ctrl_c() -> CancelToken {
+    let token = CancelToken::new();
+    let handle = token.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            tracing::warn!("interrupted; cancelling the running migration");
+            handle.cancel();
+        }
+    });
+    token
+}
+
+/// Connect using the command-line connection flags, falling back to the
+/// selected profile.
+pub async fn connect(args: &ConnectArgs, project: &Project) -> Result<Surreal<Any>> {
+    open(&Connection::resolve(args, project).options()?, args.retries).await
+}
+
+/// Connect with `options`, retrying transient failures `retries` times.
+async fn open(options: &ConnectOptions, retries: u32) -> Result<Surreal<Any>> {
+    if retries == 0 {
+        return connect::connect(options).await;
+    }
+    connect::connect_with_retry(options, &RetryPolicy::new(retries + 1)).await
+}
+
+/// A runner set up from the project config: its migrations table and, when
+/// a profile is selected, that profile as the active environment.
+pub fn runner<'a, S: MigrationSource>(
+    db: &'a Surreal<Any>,
+    source: S,
+    project: &Project,
+) -> Result<MigrationRunner<'a, Any, S>> {
+    let mut runner = MigrationRunner::new(db, source).with_table(project.table());
+    if let Some((ns, db)) = project.bookkeeping()? {
+        runner = runner.with_bookkeeping(ns, db);
+    }
+    for (point, hook) in project.hooks()? {
+        runner = runner.with_hook(point, hook);
+    }
+    Ok(match &project.env.value {
+        Some(env) => runner.with_environment(env),
+        None => runner,
+    })
+}
+
+/// `runner` recording `change_ref` with its runs, when there is one.
+fn audited<'a, S: MigrationSource>(
+    runner: MigrationRunner<'a, Any, S>,
+    change_ref: Option<String>,
+) -> MigrationRunner<'a, Any, S> {
+    match change_ref {
+        Some(change_ref) => runner.with_change_ref(change_ref),
+        None => runner,
+    }
+}
+
+/// `runner` enforcing the `--migration-timeout` and `--run-timeout` limits.
+fn timed<'a, S: MigrationSource>(
+    mut runner: MigrationRunner<'a, Any, S>,
+    timeouts: &TimeoutArgs,
+) -> MigrationRunner<'a, Any, S> {
+    if let Some(secs) = timeouts.migration_timeout {
+        runner = runner.with_migration_timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = timeouts.run_timeout {
+        runner = runner.with_run_timeout(Duration::from_secs(secs));
+    }
+    runner
+}
+
+/// `runner` drawing a progress bar, unless the output is JSON.
+fn with_progress<S: MigrationSource>(
+    runner: MigrationRunner<'_, Any, S>,
+    format: Format,
+) -> MigrationRunner<'_, Any, S> {
+    match format {
+        Format::Text => runner.with_progress(Bar::default()),
+        Format::Json => runner,
+    }
+}
+
+/// `smg up`: apply pending migrations from `dir`.
+pub async fn up(args: &UpArgs, project: &Project, dir: &Path, format: Format) -> Result<()> {
+    let change_ref = project.change_ref(args.change_ref.clone())?;
+    let db = connect(&args.connect, project).await?;
+    let runner = runner(&db, DiskSource::new(dir), project)?.with_cancel_token(cancel_on_ctrl_c());
+    let runner = audited(runner, change_ref).with_parallelism(args.parallel);
+    let runner = with_progress(timed(runner, &args.timeouts), format);
+    let runner = if args.locked {
+        runner.with_lock_file(LockFile::read(dir.join(LOCK_FILE))?)
+    } else {
+        runner
+    };
+    let runner = if let Some(path) = &args.resolution_file {
+        runner.with_drift_resolver(ResolutionFile::load(path)?)
+    } else if args.interactive {
+        runner.with_drift_resolver(prompt)
+    } else {
+        runner
+    };
+
+    reported(&runner, RunDirection::Up, format, runner.up()).await?;
+    tracing::info!("database is up to date");
+    Ok(())
+}
+
+/// Await `run`, summarizing what it did; with `--format json`, print the
+/// report it persisted instead.
+async fn reported<S: MigrationSource>(
+    runner: &MigrationRunner<'_, Any, S>,
+    direction: RunDirection,
+    format: Format,
+    run: impl Future<Output = Result<MigrationReport>>,
+) -> Result<()> {
+    if format == Format::Text {
+        let report = run.await?;
+        if !report.is_empty() {
+            let verb = match direction {
+                RunDirection::Down => "reverted",
+                _ => "applied",
+            };
+            tracing::info!(
+                "{verb} {} migration(s) in {} ms",
+                report.outcomes.len(),
+                report.duration_ms
+            );
+        }
+        return Ok(());
+    }
+    let result = run.await;
+    let report = runner.last_run().await?;
+    println!("{}", crate::json::run(direction, report.as_ref()));
+    result.map(drop)
+}
+
+/// `smg down`: revert by step count, to a migration or tag, the last
+/// batch, or entirely.
+pub async fn down(args: &DownArgs, project: &Project, dir: &Path, format: Format) -> Result<()> {
+    let change_ref = project.change_ref(args.change_ref.clone())?;
+    let db = connect(&args.connect, project).await?;
+    let runner = runner(&db, DiskSource::new(dir), project)?.with_cancel_token(cancel_on_ctrl_c());
+    let runner = with_progress(timed(audited(runner, change_ref), &args.timeouts), format);
+    let down = RunDirection::Down;
+
+    if let Some(target) = &args.to {
+        return reported(&runner, down, format, runner.down_to(target)).await;
+    }
+    if let Some(tag) = &args.to_tag {
+        return reported(&runner, down, format, runner.down_to_tag(tag)).await;
+    }
+    if args.last_batch {
+        return reported(&runner, down, format, runner.down_last_batch()).await;
+    }
+
+    // Repeatables are never reverted, so they don't count as steps.
+    let applied = applied_order(runner.status().await?);
+    if applied.is_empty() {
+        tracing::info!("nothing to revert");
+        if format == Format::Json {
+            println!("{}", crate::json::run(down, None));
+        }
+        return Ok(());
+    }
+
+    if args.all {
+        if !args.yes
+            && !confirm(&format!(
+                "Revert all {} applied migration(s)?",
+                applied.len()
+            ))
+        {
+            eyre::bail!("aborted");
+        }
+        return reported(&runner, down, format, runner.down()).await;
+    }
+
+    let steps = args.steps.unwrap_or(1);
+    match applied.len().checked_sub(steps + 1) {
+        Some(keep) => reported(&runner, down, format, runner.down_to(&applied[keep])).await,
+        None => reported(&runner, down, format, runner.down()).await,
+    }
+}
+
+/// `smg fresh`: wipe the database and apply everything again.
+pub async fn fresh(args: &FreshArgs, project: &Project, dir: &Path) -> Result<()> {
+    if !args.yes {
+        eyre::bail!("smg fresh deletes everything in the database; pass --yes to confirm");
+    }
+    let options = Connection::resolve(&args.connect, project).options()?;
+    if let Some(hint) = production_hint(&options)
+        && !args.force
+    {
+        eyre::bail!(
+            "refusing to wipe what looks like production ({hint}); pass --force if it isn't"
+        );
+    }
+
+    let change_ref = project.change_ref(args.change_ref.clone())?;
+    let db = open(&options, args.connect.retries).await?;
+    let runner = audited(runner(&db, DiskSource::new(dir), project)?, change_ref);
+    timed(runner, &args.timeouts)
+        .with_fresh_allowed(true)
+        .with_cancel_token(cancel_on_ctrl_c())
+        .with_progress(Bar::default())
+        .fresh()
+        .await?;
+    tracing::info!("database rebuilt from scratch");
+    Ok(())
+}
+
+/// `smg seed`: run the scripts in the seeds directory, scaled by
+/// `--scale`.
+pub async fn seed(args: &SeedArgs, project: &Project, dir: &Path) -> Result<()> {
+    if !args.seeds.is_dir() {
+        eyre::bail!("seeds directory {} does not exist", args.seeds.display());
+    }
+    let change_ref = project.change_ref(args.change_ref.clone())?;
+    let db = connect(&args.connect, project).await?;
+    audited(runner(&db, DiskSource::new(dir), project)?, change_ref)
+        .with_seed_scale(args.scale)
+        .with_cancel_token(cancel_on_ctrl_c())
+        .seed(&DiskSource::new(&args.seeds))
+        .await?;
+    tracing::info!("seeded the database at scale {}x", args.scale);
+    Ok(())
+}
+
+/// Why the target looks like a production database, if it does: its URL,
+/// namespace or database name mentions `prod` or `live`.
+fn production_hint(options: &ConnectOptions) -> Option<String> {
+    [
+        ("url", &options.url),
+        ("namespace", &options.namespace),
+        ("database", &options.database),
+    ]
+    .into_iter()
+    .find_map(|(what, value)| {
+        let lower = value.to_lowercase();
+        (lower.contains("prod") || lower.contains("live")).then(|| format!("{what} {value}"))
+    })
+}
+
+/// `smg rehearse`: rehearse the target's pending migrations, or all of
+/// them when no database is given.
+pub async fn rehearse(args: &RehearseArgs, project: &Project, dir: &Path) -> Result<Vec<Finding>> {
+    let source = DiskSource::new(dir);
+    let connection = Connection::resolve(&args.connect, project);
+    let pending = if connection.namespace.value.is_some() || connection.database.value.is_some() {
+        let db = connect(&args.connect, project).await?;
+        let pending = runner(&db, &source, project)?
+            .status()
+            .await?
+            .into_iter()
+            .filter(|m| !m.applied)
+            .map(|m| m.name)
+            .collect::<HashSet<_>>();
+        Some(pending)
+    } else {
+        None
+    };
+    crate::rehearse::rehearse(&source, pending.as_ref()).await
+}
+
+/// `smg diff`: the schema to start from and the desired one.
+pub async fn diff(args: &DiffArgs, project: &Project) -> Result<(SchemaSnapshot, SchemaSnapshot)> {
+    let current = match &args.from_snapshot {
+        Some(path) => SchemaSnapshot::read(path)?,
+        None => {
+            let db = connect(&args.connect, project).await?;
+            runner(&db, InMemorySource::new(), project)?
+                .schema_snapshot()
+                .await?
+        }
+    };
+    let desired = crate::diff::desired(&args.schema).await?;
+    Ok((current, desired))
+}
+
+/// `smg drift`: how the database's schema differs from a replay of its
+/// applied migrations on an in-memory database.
+pub async fn drift(args: &DriftArgs, project: &Project, dir: &Path) -> Result<Vec<SchemaChange>> {
+    let db = connect(&args.connect, project).await?;
+    let scratch = surrealdb::engine::any::connect("mem://").await?;
+    scratch.use_ns("drift").use_db("drift").await?;
+    runner(&db, DiskSource::new(dir), project)?
+        .verify_schema(&scratch)
+        .await
+}
+
+/// `smg convert`: switch a migration between the single-file and paired
+/// layouts and, when a namespace and database are given, move its applied
+/// record to the new name. Returns the new path.
+pub async fn convert(args: &ConvertArgs, project: &Project, dir: &Path) -> Result<PathBuf> {
+    let migration = crate::fs::find_migration(dir, &args.name)?;
+    let from = migration.name.as_str();
+    let to = crate::convert::converted_name(&migration)?;
+
+    let connection = Connection::resolve(&args.connect, project);
+    if connection.namespace.value.is_none() || connection.database.value.is_none() {
+        let path = crate::convert::convert(dir, &migration, args.force)?;
+        tracing::warn!("no --ns/--db given: databases that applied {from} still record that name");
+        return Ok(path);
+    }
+
+    let db = connect(&args.connect, project).await?;
+    let runner = runner(&db, DiskSource::new(dir), project)?;
+    let applied = runner
+        .status()
+        .await?
+        .iter()
+        .any(|s| s.name == from && s.applied);
+    if applied && !args.yes && !confirm(&format!("Update the applied record of {from} to {to}?")) {
+        eyre::bail!("aborted");
+    }
+
+    // Move the record first: converting can still refuse, and a record is
+    // easier to put back than a dropped down script.
+    runner.rename_record(from, &to).await?;
+    match crate::convert::convert(dir, &migration, args.force) {
+        Ok(path) => Ok(path),
+        Err(e) => {
+            runner.rename_record(&to, from).await?;
+            Err(e)
+        }
+    }
+}
+
+/// `smg rename`: rename a migration and, when a namespace and database are
+/// given, move its applied record along. Returns the new name.
+pub async fn rename(args: &RenameArgs, project: &Project, dir: &Path) -> Result<String> {
+    let migration = crate::fs::find_migration(dir, &args.from)?;
+    let from = migration.name.as_str();
+    let to = crate::rename::target_name(&migration, &args.to)?;
+    if to == from {
+        eyre::bail!("{from} already has that name");
+    }
+
+    let connection = Connection::resolve(&args.connect, project);
+    if connection.namespace.value.is_none() || connection.database.value.is_none() {
+        crate::rename::rename_entry(dir, from, &to)?;
+        tracing::warn!("no --ns/--db given: databases that applied {from} still record that name");
+        return Ok(to);
+    }
+
+    let db = connect(&args.connect, project).await?;
+    let runner = runner(&db, DiskSource::new(dir), project)?;
+    let applied = runner
+        .status()
+        .await?
+        .iter()
+        .any(|s| s.name == from && s.applied);
+    if applied && !args.yes && !confirm(&format!("Update the applied record of {from} to {to}?")) {
+        eyre::bail!("aborted");
+    }
+
+    crate::rename::rename_entry(dir, from, &to)?;
+    if let Err(e) = runner.rename_record(from, &to).await {
+        // Put the migration back so it keeps matching its record.
+        crate::rename::rename_entry(dir, &to, from)?;
+        return Err(e);
+    }
+    Ok(to)
+}
+
+/// `smg check-conflicts`: conflicts among the migrations in `dir`, against
+/// the connected database's history when `--ns` and `--db` are given.
+pub async fn check_conflicts(
+    args: &CheckConflictsArgs,
+    project: &Project,
+    dir: &Path,
+) -> Result<(Vec<Conflict>, Vec<Renumber>)> {
+    let source = DiskSource::new(dir);
+    let connection = Connection::resolve(&args.connect, project);
+    if connection.namespace.value.is_none() || connection.database.value.is_none() {
+        tracing::debug!("no --ns/--db given: checking shared versions only");
+        return Ok(surreal_migraine::validate::conflicts(&source.list()?, &[]));
+    }
+
+    let db = connect(&args.connect, project).await?;
+    runner(&db, source, project)?.check_conflicts().await
+}
+
+/// `smg check`: one line per problem with the migrations in `dir`.
+///
+/// Migrations must match `migrations.lock` and, with `--ns` and `--db`,
+/// applied ones their recorded checksums. `--frozen` also requires the lock
+/// file and counts pending migrations as problems.
+pub async fn check(args: &CheckArgs, project: &Project, dir: &Path) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+    let lock_path = dir.join(LOCK_FILE);
+    if lock_path.exists() {
+        let lock = LockFile::read(&lock_path)?;
+        for mismatch in lock.verify(&DiskSource::new(dir))? {
+            problems.push(mismatch.to_string());
+        }
+    } else if args.frozen {
+        problems.push(format!(
+            "{} does not exist; run `smg lock`",
+            lock_path.display()
+        ));
+    } else {
+        tracing::debug!("no {LOCK_FILE}; skipping the lock check");
+    }
+
+    let connection = Connection::resolve(&args.connect, project);
+    if connection.namespace.value.is_none() || connection.database.value.is_none() {
+        tracing::debug!("no --ns/--db given: skipping the database checks");
+        return Ok(problems);
+    }
+    let db = connect(&args.connect, project).await?;
+    let runner = runner(&db, DiskSource::new(dir), project)?;
+    for mismatch in runner.verify().await? {
+        problems.push(format!("{} changed since it was applied", mismatch.name));
+    }
+    for migration in runner.plan().await?.up {
+        if args.frozen {
+            problems.push(format!("{} is pending", migration.name));
+        } else {
+            tracing::info!("{} is pending", migration.name);
+        }
+    }
+    Ok(problems)
+}
+
+/// `smg status`: the rendered status table.
+pub async fn status(
+    args: &StatusArgs,
+    project: &Project,
+    dir: &Path,
+    format: Format,
+) -> Result<String> {
+    let db = connect(&args.connect, project).await?;
+    let source = DiskSource::new(dir);
+    let ignored = if args.verbose > 0 {
+        source.ignored()?
+    } else {
+        Vec::new()
+    };
+
+    let runner = runner(&db, source, project)?;
+    let statuses = match &args.since_tag {
+        Some(tag) => runner.status_since_tag(tag).await?,
+        None => runner.status().await?,
+    };
+    let stats = if args.detailed {
+        Some(pending_stats(&DiskSource::new(dir), &statuses)?)
+    } else {
+        None
+    };
+    Ok(match format {
+        Format::Text => crate::status::render(&statuses, &ignored, stats.as_ref()),
+        Format::Json => format!(
+            "{}\n",
+            crate::json::status(&statuses, &ignored, stats.as_ref())
+        ),
+    })
+}
+
+/// Statement counts of the pending migrations in `statuses`, by name.
+fn pending_stats(
+    source: &DiskSource,
+    statuses: &[MigrationStatus],
+) -> Result<BTreeMap<String, StatementStats>> {
+    let mut stats = BTreeMap::new();
+    for migration in source.list()? {
+        if statuses
+            .iter()
+            .any(|s| s.name == migration.name && !s.applied)
+        {
+            let sql = source.get_up(&migration)?;
+            stats.insert(migration.name, StatementStats::of(&sql));
+        }
+    }
+    Ok(stats)
+}
+
+/// `smg list`: discovered migrations, optionally filtered by whether they
+/// are applied.
+pub async fn list(
+    args: &ListArgs,
+    project: &Project,
+    dir: &Path,
+    format: Format,
+) -> Result<String> {
+    let source = DiskSource::new(dir);
+    let mut out = String::new();
+
+    if !args.pending && !args.applied {
+        if format == Format::Json {
+            let value = crate::json::list(&source.list()?, &source.ignored()?);
+            return Ok(format!("{value}\n"));
+        }
+        for migration in source.list()? {
+            let kind = format!("{:?}", migration.kind).to_lowercase();
+            out.push_str(&format!("{:<10}  {}\n", kind, migration.name));
+        }
+        let ignored = source.ignored()?;
+        if !ignored.is_empty() {
+            out.push_str("\nignored:\n");
+            for entry in ignored {
+                out.push_str(&format!("  {}: {}\n", entry.name, entry.reason));
+            }
+        }
+        return Ok(out);
+    }
+
+    let db = connect(&args.connect, project).await?;
+    let statuses = runner(&db, source, project)?
+        .status()
+        .await?
+        .into_iter()
+        .filter(|s| s.applied == args.applied)
+        .collect::<Vec<_>>();
+    if format == Format::Json {
+        return Ok(format!("{}\n", crate::json::filtered(&statuses)));
+    }
+    for status in statuses {
+        out.push_str(&status.name);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// `smg redo`: revert and re-apply a migration. With `--dry-run`, returns
+/// the scripts that would run instead.
+pub async fn redo(args: &RedoArgs, project: &Project, dir: &Path) -> Result<String> {
+    let db = connect(&args.connect, project).await?;
+    let source = DiskSource::new(dir);
+
+    if !args.dry_run {
+        let change_ref = project.change_ref(args.change_ref.clone())?;
+        let runner = runner(&db, &source, project)?.with_cancel_token(cancel_on_ctrl_c());
+        let runner = audited(runner, change_ref);
+        match &args.name {
+            Some(name) => runner.redo_named(name).await?,
+            None => runner.redo().await?,
+        }
+        return Ok(String::new());
+    }
+
+    let mut applied = applied_order(runner(&db, &source, project)?.status().await?).into_iter();
+    let target = match &args.name {
+        Some(name) => applied
+            .find(|m| m == name)
+            .ok_or_else(|| eyre::eyre!("migration {name} is not applied"))?,
+        None => applied
+            .next_back()
+            .ok_or_else(|| eyre::eyre!("no applied migrations to redo"))?,
+    };
+
+    let migration = source
+        .list()?
+        .into_iter()
+        .find(|m| m.name == target)
+        .ok_or_else(|| eyre::eyre!("migration {target} not found"))?;
+    let down = source
+        .get_down(&migration)?
+        .ok_or_else(|| eyre::eyre!("migration {} has no down script", migration.name))?;
+    let up = source.get_up(&migration)?;
+    Ok(format!(
+        "-- revert {name}\n{down}\n\n-- re-apply {name}\n{up}\n",
+        name = migration.name,
+        down = down.trim_end(),
+        up = up.trim_end()
+    ))
+}
+
+/// Names of the applied versioned migrations in the order they were
+/// applied, the order `down` reverts them in (last first).
+fn applied_order(statuses: Vec<MigrationStatus>) -> Vec<String> {
+    let mut applied = statuses
+        .into_iter()
+        .filter(|m| m.applied && m.kind != MigrationKind::Repeatable)
+        .collect::<Vec<_>>();
+    // Records without a sequence predate it and were applied first.
+    applied.sort_by_key(|m| m.applied_seq);
+    applied.into_iter().map(|m| m.name).collect()
+}
+
+/// `smg tag`: tag the most recently applied migration.
+pub async fn tag(args: &TagArgs, project: &Project, dir: &Path) -> Result<()> {
+    let db = connect(&args.connect, project).await?;
+    let tag = runner(&db, DiskSource::new(dir), project)?
+        .tag(&args.name)
+        .await?;
+    tracing::info!("tagged {} as {}", tag.migration, tag.name);
+    Ok(())
+}
+
+/// `smg runs list`: the reports of past runs, oldest first.
+pub async fn runs(
+    connect_args: &ConnectArgs,
+    project: &Project,
+    dir: &Path,
+    format: Format,
+) -> Result<String> {
+    let db = connect(connect_args, project).await?;
+    let runs = runner(&db, DiskSource::new(dir), project)?.runs().await?;
+    Ok(match format {
+        Format::Text => crate::runs::render_list(&runs),
+        Format::Json => format!("{}\n", crate::json::runs(&runs)),
+    })
+}
+
+/// `smg runs show`: one run's report.
+pub async fn show_run(
+    args: &RunsShowArgs,
+    project: &Project,
+    dir: &Path,
+    format: Format,
+) -> Result<String> {
+    let db = connect(&args.connect, project).await?;
+    let run = runner(&db, DiskSource::new(dir), project)?
+        .run(&args.id)
+        .await?
+        .ok_or_else(|| eyre::eyre!("no run {} is recorded", args.id))?;
+    Ok(match format {
+        Format::Text => crate::runs::render_run(&run),
+        Format::Json => format!("{}\n", crate::json::run_report(&run)),
+    })
+}
+
+/// Ask a yes/no question on the terminal; anything but yes means no.
+fn confirm(question: &str) -> bool {
+    let mut stderr = std::io::stderr().lock();
+    let _ = write!(stderr, "{question} [y/N] ");
+    let _ = stderr.flush();
+
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim(), "y" | "yes")
+}
+
+/// Ask on the terminal how to resolve `drift`. Anything but an explicit
+/// accept or repair aborts.
+fn prompt(drift: &Drift) -> Resolution {
+    let mut stderr = std::io::stderr().lock();
+    match &drift.kind {
+        DriftKind::ChecksumMismatch { diff } => {
+            let _ = writeln!(stderr, "{} was edited after it was applied", drift.name);
+            if let Some(diff) = diff {
+                let _ = writeln!(stderr, "{diff}");
+            }
+        }
+        DriftKind::MissingSource => {
+            let _ = writeln!(stderr, "{} is applied but no longer exists", drift.name);
+        }
+    }
+    let _ = write!(stderr, "[a]ccept, [r]epair or a[b]ort? ");
+    let _ = stderr.flush();
+
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return Resolution::Abort;
+    }
+    match answer.trim() {
+        "a" | "accept" => Resolution::Accept,
+        "r" | "repair" => Resolution::Repair,
+        _ => Resolution::Abort,
+    }
+}
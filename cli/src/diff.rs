@@ -0,0 +1,34 @@
+use eyre::Result;
+use std::io::Write;
+use std::path::Path;
+use surreal_migraine::schema::SchemaSnapshot;
+use surrealdb::engine::any;
+
+/// The schema `path` describes: a snapshot file (`.json`) as is, or a
+/// SurrealQL script applied to a throwaway in-memory database.
+pub async fn desired(path: &Path) -> Result<SchemaSnapshot> {
+    if path.extension().is_some_and(|ext| ext == "json") {
+        return SchemaSnapshot::read(path);
+    }
+    let sql = std::fs::read_to_string(path)
+        .map_err(|e| eyre::eyre!("cannot read {}: {e}", path.display()))?;
+    let shadow = any::connect("mem://").await?;
+    shadow.use_ns("diff").use_db("diff").await?;
+    shadow
+        .query(sql)
+        .await?
+        .check()
+        .map_err(|e| eyre::eyre!("{} does not apply: {e}", path.display()))?;
+    SchemaSnapshot::capture(&shadow).await
+}
+
+/// Append `up` and `down` to the scripts of the paired migration `folder`.
+pub fn append(folder: &Path, up: &str, down: &str) -> Result<()> {
+    for (file, sql) in [("up.surql", up), ("down.surql", down)] {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(folder.join(file))?
+            .write_all(format!("\n{sql}").as_bytes())?;
+    }
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use eyre::Result;
+use std::path::Path;
+use surreal_migraine::MigrationRunner;
+use surreal_migraine::schema::SchemaSnapshot;
+use surreal_migraine::types::DiskSource;
+use surrealdb::engine::any;
+
+/// Apply every migration in `dir` to a throwaway in-memory database and
+/// snapshot the resulting schema.
+pub async fn golden_schema(dir: &Path) -> Result<SchemaSnapshot> {
+    let db = any::connect("mem://").await?;
+    db.use_ns("test").use_db("test").await?;
+    let runner = MigrationRunner::new(&db, DiskSource::new(dir));
+    runner.up().await?;
+    runner.schema_snapshot().await
+}
+
+/// A Rust integration test applying the migrations in `migrations` to an
+/// in-memory database and comparing its schema with the snapshot at
+/// `golden`. Relative paths are resolved against the crate root.
+pub fn test_source(migrations: &str, golden: &str) -> String {
+    format!(
+        r#"//! Generated by `smg export-test`. Run it again after adding migrations
+//! to refresh the golden schema, or rerun this test with
+//! `SMG_UPDATE_SNAPSHOTS=1`.
+//!
+//! Needs `surreal-migraine` (with the `testing` feature) and `tokio` (with
+//! `macros` and `rt-multi-thread`) as dev-dependencies.
+
+use std::path::Path;
+use surreal_migraine::testing::TestDb;
+use surreal_migraine::types::DiskSource;
+
+const MIGRATIONS: &str = {migrations:?};
+const GOLDEN_SCHEMA: &str = {golden:?};
+
+#[tokio::test]
+async fn migrations_apply_and_match_the_golden_schema() {{
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let db = TestDb::migrated(DiskSource::new(root.join(MIGRATIONS)))
+        .await
+        .expect("migrations apply cleanly");
+    db.assert_schema_snapshot(root.join(GOLDEN_SCHEMA)).await;
+}}
+"#
+    )
+}
+
+/// `path` relative to `root` when it lies under it, for embedding in the
+/// generated test.
+pub fn relative(path: &Path, root: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
@@ -4,6 +4,28 @@ use eyre::{Result, eyre};
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use surreal_migraine::types::{DiskSource, Migration, MigrationSource};
+
+/// Locate an existing migrations directory without creating one.
+/// Uses `dir_override` when given, otherwise `./migrations` or the current
+/// directory if it is itself named `migrations`.
+pub fn find_migrations_dir(dir_override: Option<PathBuf>) -> Result<PathBuf> {
+    let dir = match dir_override {
+        Some(d) => d,
+        None => {
+            let cwd = std::env::current_dir()?;
+            match cwd.file_name().and_then(|s| s.to_str()) {
+                Some(name) if name.eq_ignore_ascii_case("migrations") => cwd,
+                _ => cwd.join("migrations"),
+            }
+        }
+    };
+    if !dir.is_dir() {
+        return Err(eyre!("migrations directory {} not found", dir.display()));
+    }
+    tracing::debug!(dir = %dir.display(), "using migrations dir");
+    Ok(dir)
+}
 
 /// Detect an existing `migrations` directory or create one.
 /// If `dir_override` is Some(path) that path is used (created if needed).
@@ -177,3 +199,13 @@ pub fn create_temporal_paired_migration(dir: &Path, name: &str) -> Result<PathBu
     let _ = f_down.write_all(header.as_bytes());
     Ok(path)
 }
+
+/// The migration in `dir` named `name`, with or without its `.surql`
+/// extension.
+pub fn find_migration(dir: &Path, name: &str) -> Result<Migration> {
+    DiskSource::new(dir)
+        .list()?
+        .into_iter()
+        .find(|m| m.name == name || m.name.strip_suffix(".surql") == Some(name))
+        .ok_or_else(|| eyre!("no migration named {name}"))
+}
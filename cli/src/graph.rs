@@ -0,0 +1,152 @@
+use std::fmt::Write;
+use surreal_migraine::types::{Migration, MigrationKind};
+use surreal_migraine::validate::refers_to;
+
+/// A migration, or a dependency no migration matches.
+struct Node {
+    name: String,
+    repeatable: bool,
+    missing: bool,
+}
+
+/// How two migrations are connected; edges point from the migration that
+/// runs first.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Link {
+    /// Consecutive versioned migrations, applied in this order.
+    Order,
+    /// A `depends-on` header of the target names the source.
+    Depends,
+    /// A dependency listed after the migration needing it, or part of a
+    /// cycle: the odd orderings worth a look.
+    Backwards,
+}
+
+struct Graph {
+    nodes: Vec<Node>,
+    edges: Vec<(usize, usize, Link)>,
+}
+
+/// The ordering and dependency graph of `migrations`, listed in the order
+/// they apply.
+fn graph(migrations: &[Migration]) -> Graph {
+    let mut nodes = migrations
+        .iter()
+        .map(|m| Node {
+            name: m.name.clone(),
+            repeatable: m.kind == MigrationKind::Repeatable,
+            missing: false,
+        })
+        .collect::<Vec<_>>();
+    let mut edges = Vec::new();
+    for (index, migration) in migrations.iter().enumerate() {
+        for dependency in &migration.metadata.depends {
+            let from = match migrations.iter().position(|m| refers_to(m, dependency)) {
+                Some(from) => from,
+                None => match nodes
+                    .iter()
+                    .position(|n| n.missing && n.name == *dependency)
+                {
+                    Some(from) => from,
+                    None => {
+                        nodes.push(Node {
+                            name: dependency.clone(),
+                            repeatable: false,
+                            missing: true,
+                        });
+                        nodes.len() - 1
+                    }
+                },
+            };
+            let link = if from >= index && from < migrations.len() {
+                Link::Backwards
+            } else {
+                Link::Depends
+            };
+            edges.push((from, index, link));
+        }
+    }
+
+    let versioned = (0..migrations.len())
+        .filter(|&i| migrations[i].kind != MigrationKind::Repeatable)
+        .collect::<Vec<_>>();
+    for pair in versioned.windows(2) {
+        if !edges
+            .iter()
+            .any(|&(from, to, _)| from == pair[0] && to == pair[1])
+        {
+            edges.push((pair[0], pair[1], Link::Order));
+        }
+    }
+    Graph { nodes, edges }
+}
+
+/// The graph of `migrations` in Graphviz DOT. Solid edges are declared
+/// dependencies, dashed ones the apply order, red ones dependencies
+/// pointing backwards; repeatable migrations are rounded and unknown
+/// dependencies dashed red.
+pub fn dot(migrations: &[Migration]) -> String {
+    let graph = graph(migrations);
+    let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+
+    let mut out = String::from("digraph migrations {\n    rankdir=LR;\n    node [shape=box];\n");
+    for node in &graph.nodes {
+        let style = match (node.repeatable, node.missing) {
+            (_, true) => " [style=dashed, color=red]",
+            (true, _) => " [style=rounded]",
+            _ => "",
+        };
+        let _ = writeln!(out, "    {}{style};", quote(&node.name));
+    }
+    for (from, to, link) in graph.edges {
+        let style = match link {
+            Link::Order => " [style=dashed]",
+            Link::Depends => "",
+            Link::Backwards => " [color=red]",
+        };
+        let _ = writeln!(
+            out,
+            "    {} -> {}{style};",
+            quote(&graph.nodes[from].name),
+            quote(&graph.nodes[to].name)
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// The graph of `migrations` as a Mermaid flowchart, styled like [`dot`].
+pub fn mermaid(migrations: &[Migration]) -> String {
+    let graph = graph(migrations);
+
+    let mut out = String::from("flowchart LR\n");
+    for (index, node) in graph.nodes.iter().enumerate() {
+        let label = node.name.replace('"', "#quot;");
+        let shape = if node.repeatable {
+            format!("([\"{label}\"])")
+        } else {
+            format!("[\"{label}\"]")
+        };
+        let class = if node.missing { ":::missing" } else { "" };
+        let _ = writeln!(out, "    m{index}{shape}{class}");
+    }
+    let mut backwards = Vec::new();
+    for (position, (from, to, link)) in graph.edges.into_iter().enumerate() {
+        let arrow = match link {
+            Link::Order => "-.->",
+            Link::Depends => "-->",
+            Link::Backwards => {
+                backwards.push(position.to_string());
+                "-->"
+            }
+        };
+        let _ = writeln!(out, "    m{from} {arrow} m{to}");
+    }
+    if graph.nodes.iter().any(|n| n.missing) {
+        out.push_str("    classDef missing stroke:red,stroke-dasharray:4\n");
+    }
+    if !backwards.is_empty() {
+        let _ = writeln!(out, "    linkStyle {} stroke:red", backwards.join(","));
+    }
+    out
+}
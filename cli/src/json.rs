@@ -0,0 +1,95 @@
+use crate::status::rfc3339;
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use surreal_migraine::statements::StatementStats;
+use surreal_migraine::types::{IgnoredEntry, Migration, MigrationStatus, RunDirection, RunReport};
+
+/// `smg status`: every migration with its state and checksums, plus
+/// statement counts for pending migrations with `--detailed`.
+pub fn status(
+    statuses: &[MigrationStatus],
+    ignored: &[IgnoredEntry],
+    stats: Option<&BTreeMap<String, StatementStats>>,
+) -> Value {
+    let migrations = statuses
+        .iter()
+        .map(|s| {
+            let mut value = json!({
+                "name": s.name,
+                "kind": s.kind,
+                "applied": s.applied,
+                "baseline": s.baseline,
+                "applied_at": s.applied_at.as_ref().map(rfc3339),
+                "applied_seq": s.applied_seq,
+                "batch": s.batch,
+                "checksum": s.checksum,
+                "applied_checksum": s.applied_checksum,
+                "checksum_matches": s.checksum_matches(),
+            });
+            if let Some(stats) = stats {
+                value["statements"] = json!(stats.get(&s.name));
+            }
+            value
+        })
+        .collect::<Vec<_>>();
+    json!({ "migrations": migrations, "ignored": ignored })
+}
+
+/// `smg list` without a database: discovered migrations in apply order.
+pub fn list(migrations: &[Migration], ignored: &[IgnoredEntry]) -> Value {
+    let migrations = migrations
+        .iter()
+        .map(|m| json!({ "name": m.name, "kind": m.kind }))
+        .collect::<Vec<_>>();
+    json!({ "migrations": migrations, "ignored": ignored })
+}
+
+/// `smg list --pending` / `--applied`: the matching migrations.
+pub fn filtered(statuses: &[MigrationStatus]) -> Value {
+    let migrations = statuses
+        .iter()
+        .map(|s| json!({ "name": s.name, "kind": s.kind, "applied": s.applied }))
+        .collect::<Vec<_>>();
+    json!({ "migrations": migrations })
+}
+
+/// `smg runs list`: every persisted run, oldest first.
+pub fn runs(runs: &[RunReport]) -> Value {
+    json!({ "runs": runs.iter().map(run_report).collect::<Vec<_>>() })
+}
+
+/// `smg runs show`: a persisted run with its outcomes.
+pub fn run_report(report: &RunReport) -> Value {
+    json!({
+        "id": crate::runs::id(report),
+        "direction": report.direction,
+        "finished_at": report.finished_at.as_ref().map(rfc3339),
+        "fingerprint": report.fingerprint,
+        "batch": report.batch,
+        "change_ref": report.change_ref,
+        "duration_ms": report.duration_ms,
+        "error": report.error,
+        "migrations": report.outcomes,
+    })
+}
+
+/// `smg up` / `smg down`: what the run did. `report` is `None` when
+/// nothing ran.
+pub fn run(direction: RunDirection, report: Option<&RunReport>) -> Value {
+    match report {
+        Some(report) => json!({
+            "direction": report.direction,
+            "batch": report.batch,
+            "duration_ms": report.duration_ms,
+            "error": report.error,
+            "migrations": report.outcomes,
+        }),
+        None => json!({
+            "direction": direction,
+            "batch": null,
+            "duration_ms": 0,
+            "error": null,
+            "migrations": [],
+        }),
+    }
+}
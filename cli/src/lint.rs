@@ -0,0 +1,57 @@
+use crate::cache::Cache;
+use eyre::Result;
+use std::fmt::Write;
+use surreal_migraine::lint::{ALLOW, Finding, Rule, scan_secrets};
+use surreal_migraine::types::{DiskSource, MigrationSource};
+
+/// Lint every migration's scripts like `MigrationSource::lint`, scanning
+/// scripts in parallel and reusing `cache`d results for unchanged ones.
+pub fn lint(
+    source: &DiskSource,
+    cache: &mut Cache<Vec<(usize, Rule, String)>>,
+) -> Result<Vec<Finding>> {
+    let mut scripts = Vec::new();
+    for migration in source.list()? {
+        scripts.push((migration.name.clone(), false, source.get_up(&migration)?));
+        if let Some(down) = source.get_down(&migration)? {
+            scripts.push((migration.name, true, down));
+        }
+    }
+
+    let contents = scripts.iter().map(|(.., c)| c.as_str()).collect::<Vec<_>>();
+    let results = cache.check(&contents, scan_secrets);
+    let mut findings = Vec::new();
+    for ((migration, down, _), found) in scripts.iter().zip(results) {
+        findings.extend(found.into_iter().map(|(line, rule, message)| Finding {
+            migration: migration.clone(),
+            down: *down,
+            line,
+            rule,
+            message,
+        }));
+    }
+    Ok(findings)
+}
+
+/// One line per finding, then how to fix or silence them.
+pub fn render(findings: &[Finding]) -> String {
+    let mut out = String::new();
+    for finding in findings {
+        let script = if finding.down { " (down)" } else { "" };
+        let _ = writeln!(
+            out,
+            "{}{script} line {}: {}: {}",
+            finding.migration,
+            finding.line,
+            finding.rule.as_str(),
+            finding.message
+        );
+    }
+    if !findings.is_empty() {
+        let _ = writeln!(
+            out,
+            "\nmove secrets into {{{{placeholders}}}} supplied at run time, or end a line with `{ALLOW}` if it is a false positive"
+        );
+    }
+    out
+}
@@ -1,11 +1,41 @@
+mod cache;
 mod cli;
+mod config;
 mod consts;
+mod convert;
+mod db;
+mod diff;
+mod export;
 mod fs;
+mod graph;
+mod json;
+mod lint;
 mod name;
+#[cfg(feature = "otel")]
+mod otel;
+mod progress;
+mod rehearse;
+mod rename;
+mod runs;
+mod skeleton;
+mod squash;
+mod stats;
+mod status;
+mod upgrade;
+mod validate;
+mod watch;
 
+use cache::Cache;
 use clap::Parser;
-use cli::{Args, Commands};
+use cli::{Args, Commands, Format, RunsCommand, StatsCommand};
 use eyre::Result;
+use std::path::{Path, PathBuf};
+use surreal_migraine::checksum::Sha256Hasher;
+use surreal_migraine::lockfile::{LOCK_FILE, LockFile};
+use surreal_migraine::types::{DiskSource, MigrationSource};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 
 fn main() -> Result<()> {
     color_eyre::install()?;
@@ -14,6 +44,34 @@ fn main() -> Result<()> {
 
     let verbose = match &args.command {
         Commands::Add(a) => a.verbose,
+        Commands::Stats(StatsCommand::Project(s)) => s.verbose,
+        Commands::Runs(RunsCommand::List(r)) => r.verbose,
+        Commands::Runs(RunsCommand::Show(r)) => r.verbose,
+        Commands::Up(u) => u.verbose,
+        Commands::Down(d) => d.verbose,
+        Commands::Status(s) => s.verbose,
+        Commands::Tag(t) => t.verbose,
+        Commands::List(l) => l.verbose,
+        Commands::Redo(r) => r.verbose,
+        Commands::Env(e) => e.verbose,
+        Commands::Fresh(f) => f.verbose,
+        Commands::Seed(s) => s.verbose,
+        Commands::Rehearse(r) => r.verbose,
+        Commands::Lint(l) => l.verbose,
+        Commands::Validate(v) => v.verbose,
+        Commands::Graph(g) => g.verbose,
+        Commands::Squash(s) => s.verbose,
+        Commands::UpgradeAdvise(u) => u.verbose,
+        Commands::ExportTest(e) => e.verbose,
+        Commands::Diff(d) => d.verbose,
+        Commands::Drift(d) => d.verbose,
+        Commands::GenerateDown(g) => g.verbose,
+        Commands::Convert(c) => c.verbose,
+        Commands::Rename(r) => r.verbose,
+        Commands::CheckConflicts(c) => c.verbose,
+        Commands::Lock(l) => l.verbose,
+        Commands::Check(c) => c.verbose,
+        Commands::Watch(w) => w.verbose,
     };
 
     let env_filter = if std::env::var("RUST_LOG").is_ok() {
@@ -27,15 +85,34 @@ fn main() -> Result<()> {
         tracing_subscriber::EnvFilter::new(level)
     };
 
-    tracing_subscriber::fmt().with_env_filter(env_filter).init();
+    // Keep stdout clean for JSON consumers.
+    let fmt = if args.format == Format::Json {
+        tracing_subscriber::fmt::layer()
+            .with_writer(std::io::stderr)
+            .boxed()
+    } else {
+        tracing_subscriber::fmt::layer()
+            .with_writer(|| progress::LogWriter)
+            .boxed()
+    };
+    let subscriber = tracing_subscriber::registry().with(env_filter).with(fmt);
+    #[cfg(feature = "otel")]
+    let exporter = otel::Exporter::from_env()?;
+    #[cfg(feature = "otel")]
+    let subscriber = subscriber.with(exporter.as_ref().map(otel::Exporter::layer));
+    subscriber.init();
+
+    let project = config::Project::load(args.profile)?;
 
     match args.command {
         Commands::Add(a) => {
-            let dir = fs::detect_or_create_migrations_dir(a.dir)?;
+            let dir = fs::detect_or_create_migrations_dir(project.dir(a.dir))?;
+            let temporal =
+                a.temporal || (!a.numeric && project.naming() == config::Naming::Temporal);
             // Paired folder (with up/down) is the default. Use --single to
             // create a single .surql file instead, preserving temporal or numeric mode.
             if a.single {
-                if a.temporal {
+                if temporal {
                     let path = fs::create_temporal_migration(&dir, &a.name)?;
                     tracing::info!("created {}", path.display());
                 } else {
@@ -43,7 +120,7 @@ fn main() -> Result<()> {
                     tracing::info!("created {}", path.display());
                 }
             } else {
-                let path = if a.temporal {
+                let path = if temporal {
                     fs::create_temporal_paired_migration(&dir, &a.name)?
                 } else {
                     fs::create_numeric_paired_migration(&dir, &a.name)?
@@ -51,6 +128,254 @@ fn main() -> Result<()> {
                 tracing::info!("created paired migration {}", path.display());
             }
         }
+        Commands::Stats(StatsCommand::Project(s)) => {
+            let dir = fs::find_migrations_dir(project.dir(s.dir))?;
+            let stats = stats::ProjectStats::collect(&dir)?;
+            print!("{}", stats.render(s.top));
+        }
+        Commands::Up(u) => {
+            let dir = fs::find_migrations_dir(project.dir(u.dir.clone()))?;
+            db::block_on(db::up(&u, &project, &dir, args.format))??;
+        }
+        Commands::Down(d) => {
+            let dir = fs::find_migrations_dir(project.dir(d.dir.clone()))?;
+            db::block_on(db::down(&d, &project, &dir, args.format))??;
+        }
+        Commands::Status(s) => {
+            let dir = fs::find_migrations_dir(project.dir(s.dir.clone()))?;
+            print!(
+                "{}",
+                db::block_on(db::status(&s, &project, &dir, args.format))??
+            );
+        }
+        Commands::List(l) => {
+            let dir = fs::find_migrations_dir(project.dir(l.dir.clone()))?;
+            print!(
+                "{}",
+                db::block_on(db::list(&l, &project, &dir, args.format))??
+            );
+        }
+        Commands::Redo(r) => {
+            let dir = fs::find_migrations_dir(project.dir(r.dir.clone()))?;
+            print!("{}", db::block_on(db::redo(&r, &project, &dir))??);
+        }
+        Commands::Fresh(f) => {
+            let dir = fs::find_migrations_dir(project.dir(f.dir.clone()))?;
+            db::block_on(db::fresh(&f, &project, &dir))??;
+        }
+        Commands::Seed(s) => {
+            let dir = fs::find_migrations_dir(project.dir(s.dir.clone()))?;
+            db::block_on(db::seed(&s, &project, &dir))??;
+        }
+        Commands::Rehearse(r) => {
+            let dir = fs::find_migrations_dir(project.dir(r.dir.clone()))?;
+            let findings = db::block_on(db::rehearse(&r, &project, &dir))??;
+            print!("{}", rehearse::render(&findings));
+            if !findings.is_empty() {
+                eyre::bail!("{} statement(s) fail when re-run", findings.len());
+            }
+            tracing::info!("every pending migration can be re-run");
+        }
+        Commands::Lint(l) => {
+            let dir = fs::find_migrations_dir(project.dir(l.dir))?;
+            let mut cache = Cache::load(
+                (!l.no_cache).then(|| project.cache_dir(&dir)).as_deref(),
+                "lint",
+            );
+            let findings = lint::lint(&DiskSource::new(dir), &mut cache)?;
+            cache.save()?;
+            print!("{}", lint::render(&findings));
+            if !findings.is_empty() {
+                eyre::bail!("{} lint finding(s)", findings.len());
+            }
+            tracing::info!("no lint findings");
+        }
+        Commands::Validate(v) => {
+            let dir = fs::find_migrations_dir(project.dir(v.dir))?;
+            let mut cache = Cache::load(
+                (!v.no_cache).then(|| project.cache_dir(&dir)).as_deref(),
+                "validate",
+            );
+            let problems = validate::validate(&dir, v.gaps, &mut cache)?;
+            cache.save()?;
+            print!("{}", validate::render(&problems));
+            if !problems.is_empty() {
+                eyre::bail!("{} problem(s) found", problems.len());
+            }
+            tracing::info!("migrations are valid");
+        }
+        Commands::Graph(g) => {
+            let dir = fs::find_migrations_dir(project.dir(g.dir))?;
+            let migrations = DiskSource::new(dir).list()?;
+            if g.mermaid {
+                print!("{}", graph::mermaid(&migrations));
+            } else {
+                print!("{}", graph::dot(&migrations));
+            }
+        }
+        Commands::Squash(s) => {
+            let dir = fs::find_migrations_dir(project.dir(s.dir))?;
+            squash::squash(&dir, s.from.as_deref(), &s.to, &s.name)?;
+        }
+        Commands::UpgradeAdvise(u) => {
+            let dir = fs::find_migrations_dir(project.dir(u.dir))?;
+            let advice = surreal_migraine::upgrade::advise(&DiskSource::new(dir), u.to)?;
+            print!("{}", upgrade::render(&advice));
+            if !advice.is_empty() {
+                eyre::bail!(
+                    "{} use(s) of syntax SurrealDB {} no longer accepts",
+                    advice.len(),
+                    u.to
+                );
+            }
+            tracing::info!("migrations are ready for SurrealDB {}", u.to);
+        }
+        Commands::ExportTest(e) => {
+            let dir = fs::find_migrations_dir(project.dir(e.dir))?;
+            let schema = db::block_on(export::golden_schema(&dir))??;
+            let parent = |path: &PathBuf| {
+                path.parent()
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."))
+                    .to_path_buf()
+            };
+            std::fs::create_dir_all(parent(&e.golden))?;
+            std::fs::create_dir_all(parent(&e.out))?;
+            schema.write(&e.golden)?;
+
+            // Paths in the test are relative to the crate it lands in.
+            let out_dir = std::fs::canonicalize(parent(&e.out))?;
+            let root = out_dir
+                .ancestors()
+                .find(|dir| dir.join("Cargo.toml").is_file())
+                .unwrap_or(&out_dir);
+            let test = export::test_source(
+                &export::relative(&std::fs::canonicalize(&dir)?, root),
+                &export::relative(&std::fs::canonicalize(&e.golden)?, root),
+            );
+            std::fs::write(&e.out, test)?;
+            tracing::info!("wrote {} and {}", e.out.display(), e.golden.display());
+        }
+        Commands::Diff(d) => {
+            let (current, desired) = db::block_on(db::diff(&d, &project))??;
+            let changes = current.changes_to(&desired);
+            for change in &changes {
+                tracing::info!("{change}");
+            }
+            let up = current.migration_to(&desired);
+            let down = desired.migration_to(&current);
+            match &d.name {
+                _ if changes.is_empty() => tracing::info!("schema is up to date"),
+                None => print!("{up}"),
+                Some(name) => {
+                    let dir = fs::detect_or_create_migrations_dir(project.dir(d.dir))?;
+                    let path = if d.temporal || project.naming() == config::Naming::Temporal {
+                        fs::create_temporal_paired_migration(&dir, name)?
+                    } else {
+                        fs::create_numeric_paired_migration(&dir, name)?
+                    };
+                    diff::append(&path, &up, &down)?;
+                    tracing::info!("created {}", path.display());
+                }
+            }
+        }
+        Commands::Drift(d) => {
+            let dir = fs::find_migrations_dir(project.dir(d.dir.clone()))?;
+            let changes = db::block_on(db::drift(&d, &project, &dir))??;
+            for change in &changes {
+                println!("{change}");
+                tracing::debug!("{}", change.sql);
+            }
+            if !changes.is_empty() {
+                eyre::bail!(
+                    "{} schema object(s) changed outside migrations",
+                    changes.len()
+                );
+            }
+            tracing::info!("schema matches the applied migrations");
+        }
+        Commands::CheckConflicts(c) => {
+            let dir = fs::find_migrations_dir(project.dir(c.dir.clone()))?;
+            let (conflicts, renames) = db::block_on(db::check_conflicts(&c, &project, &dir))??;
+            for conflict in &conflicts {
+                println!("{conflict}");
+            }
+            if !renames.is_empty() {
+                println!("\nto fix, rename (with the same --ns/--db, so applied records follow):");
+                for rename in &renames {
+                    println!("  smg rename {} {}", rename.from, rename.to);
+                }
+            }
+            if !conflicts.is_empty() {
+                eyre::bail!("{} migration conflict(s)", conflicts.len());
+            }
+            tracing::info!("no conflicts");
+        }
+        Commands::Lock(l) => {
+            let dir = fs::find_migrations_dir(project.dir(l.dir))?;
+            let lock = LockFile::generate(&DiskSource::new(&dir), &Sha256Hasher)?;
+            let path = dir.join(LOCK_FILE);
+            lock.write(&path)?;
+            tracing::info!(
+                "locked {} migration(s) in {}",
+                lock.entries.len(),
+                path.display()
+            );
+        }
+        Commands::Check(c) => {
+            let dir = fs::find_migrations_dir(project.dir(c.dir.clone()))?;
+            let problems = db::block_on(db::check(&c, &project, &dir))??;
+            for problem in &problems {
+                println!("{problem}");
+            }
+            if !problems.is_empty() {
+                eyre::bail!("{} check(s) failed", problems.len());
+            }
+            tracing::info!("migrations are consistent");
+        }
+        Commands::Watch(w) => {
+            let dir = fs::find_migrations_dir(project.dir(w.dir.clone()))?;
+            db::block_on(watch::watch(&w, &project, &dir))??;
+        }
+        Commands::GenerateDown(g) => {
+            let dir = fs::find_migrations_dir(project.dir(g.dir))?;
+            let path = skeleton::generate_down(&dir, &g.name, g.force)?;
+            tracing::info!("wrote {}; review it before applying", path.display());
+        }
+        Commands::Convert(c) => {
+            let dir = fs::find_migrations_dir(project.dir(c.dir.clone()))?;
+            let path = db::block_on(db::convert(&c, &project, &dir))??;
+            tracing::info!("converted {} to {}", c.name, path.display());
+        }
+        Commands::Rename(r) => {
+            let dir = fs::find_migrations_dir(project.dir(r.dir.clone()))?;
+            let to = db::block_on(db::rename(&r, &project, &dir))??;
+            tracing::info!("renamed {} to {to}", r.from);
+        }
+        Commands::Env(e) => {
+            print!(
+                "{}",
+                config::Effective::resolve(&e.connect, e.dir, &project).render()
+            );
+        }
+        Commands::Runs(RunsCommand::List(r)) => {
+            let dir = fs::find_migrations_dir(project.dir(r.dir.clone()))?;
+            print!(
+                "{}",
+                db::block_on(db::runs(&r.connect, &project, &dir, args.format))??
+            );
+        }
+        Commands::Runs(RunsCommand::Show(r)) => {
+            let dir = fs::find_migrations_dir(project.dir(r.dir.clone()))?;
+            print!(
+                "{}",
+                db::block_on(db::show_run(&r, &project, &dir, args.format))??
+            );
+        }
+        Commands::Tag(t) => {
+            let dir = fs::find_migrations_dir(project.dir(t.dir.clone()))?;
+            db::block_on(db::tag(&t, &project, &dir))??;
+        }
     }
 
     Ok(())
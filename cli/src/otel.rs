@@ -0,0 +1,52 @@
+use eyre::Result;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+use tracing_opentelemetry::OpenTelemetryLayer;
+
+/// Setting either variable turns on span export.
+const ENDPOINT_VARS: [&str; 2] = [
+    "OTEL_EXPORTER_OTLP_ENDPOINT",
+    "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+];
+
+/// Exports spans over OTLP/HTTP, flushing them when dropped at exit.
+pub struct Exporter(SdkTracerProvider);
+
+impl Exporter {
+    /// The exporter configured by the standard `OTEL_*` variables, when an
+    /// OTLP endpoint is set.
+    pub fn from_env() -> Result<Option<Self>> {
+        if !ENDPOINT_VARS.iter().any(|v| std::env::var_os(v).is_some()) {
+            return Ok(None);
+        }
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .build()?;
+        let mut resource = Resource::builder();
+        if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+            resource = resource.with_service_name("smg");
+        }
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(resource.build())
+            .build();
+        Ok(Some(Self(provider)))
+    }
+
+    /// A layer sending the spans of a subscriber to this exporter.
+    pub fn layer<S>(&self) -> OpenTelemetryLayer<S, SdkTracer>
+    where
+        S: tracing::Subscriber + for<'l> tracing_subscriber::registry::LookupSpan<'l>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.0.tracer("smg"))
+    }
+}
+
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        if let Err(e) = self.0.shutdown() {
+            eprintln!("failed to export traces: {e}");
+        }
+    }
+}
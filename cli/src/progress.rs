@@ -0,0 +1,73 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::io::Write;
+use std::sync::{LazyLock, Mutex};
+use surreal_migraine::types::{ProgressEvent, ProgressListener, RunDirection};
+
+/// Every progress bar draws here, so log lines can be printed above them.
+static BARS: LazyLock<MultiProgress> = LazyLock::new(MultiProgress::new);
+
+/// Log writer that prints to stdout above the progress bars instead of
+/// through them.
+pub struct LogWriter;
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        BARS.suspend(|| std::io::stdout().write(buf))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stdout().flush()
+    }
+}
+
+/// A progress bar on stderr following the runs it is given events of,
+/// showing the migration (and statement) being executed. Hidden when
+/// stderr is not a terminal.
+#[derive(Default)]
+pub struct Bar(Mutex<Option<ProgressBar>>);
+
+impl ProgressListener for Bar {
+    fn on_progress(&self, event: &ProgressEvent) {
+        let mut bar = self.0.lock().unwrap();
+        match event {
+            ProgressEvent::RunStarted { direction, total } if *total > 0 => {
+                let verb = match direction {
+                    RunDirection::Down => "reverting",
+                    _ => "applying",
+                };
+                let style = ProgressStyle::with_template(&format!(
+                    "{verb} [{{elapsed_precise}}] {{bar:30}} {{pos}}/{{len}} {{wide_msg}}"
+                ))
+                .expect("valid template");
+                let new = BARS.add(ProgressBar::new(*total as u64).with_style(style));
+                *bar = Some(new);
+            }
+            ProgressEvent::MigrationStarted { name, .. } => {
+                if let Some(bar) = bar.as_ref() {
+                    bar.set_message(name.clone());
+                }
+            }
+            ProgressEvent::StatementStarted {
+                migration,
+                index,
+                total,
+            } => {
+                if let Some(bar) = bar.as_ref() {
+                    bar.set_message(format!("{migration} (statement {}/{total})", index + 1));
+                }
+            }
+            ProgressEvent::MigrationFinished { .. } => {
+                if let Some(bar) = bar.as_ref() {
+                    bar.inc(1);
+                }
+            }
+            ProgressEvent::RunFinished { .. } => {
+                if let Some(bar) = bar.take() {
+                    bar.finish_and_clear();
+                    BARS.remove(&bar);
+                }
+            }
+            _ => {}
+        }
+    }
+}
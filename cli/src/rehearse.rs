@@ -0,0 +1,79 @@
+use eyre::{Result, eyre};
+use std::collections::HashSet;
+use std::fmt::Write;
+use surreal_migraine::statements;
+use surreal_migraine::types::{DiskSource, MigrationSource};
+use surrealdb::Surreal;
+use surrealdb::engine::any::{self, Any};
+
+/// A statement that failed when its migration was applied a second time.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub migration: String,
+    pub line: usize,
+    pub statement: String,
+    pub error: String,
+}
+
+/// Apply every migration to a throwaway in-memory database, running each
+/// one in `pending` twice, and collect the statements that fail the second
+/// time. `None` treats every migration as pending.
+pub async fn rehearse(
+    source: &DiskSource,
+    pending: Option<&HashSet<String>>,
+) -> Result<Vec<Finding>> {
+    let shadow = any::connect("mem://").await?;
+    shadow.use_ns("rehearse").use_db("rehearse").await?;
+
+    let mut findings = Vec::new();
+    for migration in source.list()? {
+        let up = source.get_up(&migration)?;
+        if let Err(e) = run(&shadow, &up).await {
+            eyre::bail!("{} fails on a fresh shadow database: {e}", migration.name);
+        }
+        if pending.is_some_and(|p| !p.contains(&migration.name)) {
+            continue;
+        }
+
+        tracing::debug!(migration = %migration.name, "applying again");
+        for statement in statements::split(&up) {
+            if let Err(e) = run(&shadow, statement.text).await {
+                findings.push(Finding {
+                    migration: migration.name.clone(),
+                    line: statement.line,
+                    statement: statement.text.to_string(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    Ok(findings)
+}
+
+async fn run(db: &Surreal<Any>, sql: &str) -> Result<()> {
+    let mut response = db.query(sql).await?;
+    match response.take_errors().into_iter().min_by_key(|(i, _)| *i) {
+        Some((_, e)) => Err(eyre!(e.to_string())),
+        None => Ok(()),
+    }
+}
+
+/// Report findings grouped by migration, with a hint on how to fix them.
+pub fn render(findings: &[Finding]) -> String {
+    let mut out = String::new();
+    for finding in findings {
+        let first_line = finding.statement.lines().next().unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "{} line {}: {first_line}\n  fails when re-run: {}",
+            finding.migration, finding.line, finding.error
+        );
+    }
+    if !findings.is_empty() {
+        let _ = writeln!(
+            out,
+            "\nmake these statements idempotent, e.g. DEFINE ... IF NOT EXISTS or DEFINE ... OVERWRITE"
+        );
+    }
+    out
+}
@@ -0,0 +1,40 @@
+use eyre::Result;
+use std::path::Path;
+use surreal_migraine::types::{Migration, MigrationKind, REPEATABLE_PREFIX};
+
+/// The entry name `new` gives `migration`: `.surql` is added to or dropped
+/// from it to match the migration's layout. Fails when discovery would not
+/// pick the new name up as the same kind of migration.
+pub fn target_name(migration: &Migration, new: &str) -> Result<String> {
+    let stem = new.strip_suffix(".surql").unwrap_or(new);
+    if stem.is_empty() || stem.contains(['/', '\\']) || stem.starts_with('.') {
+        eyre::bail!("invalid migration name {new:?}");
+    }
+    let versioned = stem.chars().next().is_some_and(|c| c.is_ascii_digit());
+    match migration.kind {
+        MigrationKind::File | MigrationKind::Paired if !versioned => {
+            eyre::bail!(
+                "{new} must start with a version prefix, like {}",
+                migration.name
+            )
+        }
+        MigrationKind::Repeatable if !stem.starts_with(REPEATABLE_PREFIX) => {
+            eyre::bail!(
+                "{new} must start with {REPEATABLE_PREFIX}, like {}",
+                migration.name
+            )
+        }
+        MigrationKind::Paired => Ok(stem.to_string()),
+        MigrationKind::File | MigrationKind::Repeatable => Ok(format!("{stem}.surql")),
+    }
+}
+
+/// Rename the entry `from` in `dir` to `to`, refusing to replace one.
+pub fn rename_entry(dir: &Path, from: &str, to: &str) -> Result<()> {
+    let target = dir.join(to);
+    if target.exists() {
+        eyre::bail!("{} already exists", target.display());
+    }
+    std::fs::rename(dir.join(from), target)?;
+    Ok(())
+}
@@ -0,0 +1,115 @@
+use crate::status::format_datetime;
+use std::fmt::Write;
+use surreal_migraine::types::RunReport;
+
+/// The key of a persisted run, as `smg runs show` takes it.
+pub fn id(run: &RunReport) -> String {
+    run.id.as_ref().map_or("-".to_string(), |id| {
+        id.to_string()
+            .trim_start_matches("migration_runs:")
+            .trim_start_matches('⟨')
+            .trim_end_matches('⟩')
+            .to_string()
+    })
+}
+
+/// Render `smg runs list`: one aligned row per run, oldest first.
+pub fn render_list(runs: &[RunReport]) -> String {
+    let rows = runs
+        .iter()
+        .map(|run| {
+            vec![
+                id(run),
+                format!("{:?}", run.direction).to_lowercase(),
+                run.finished_at
+                    .as_ref()
+                    .map_or("-".to_string(), format_datetime),
+                run.outcomes.len().to_string(),
+                format!("{}ms", run.duration_ms),
+                if run.error.is_some() { "failed" } else { "ok" }.to_string(),
+            ]
+        })
+        .collect::<Vec<_>>();
+    table(
+        &[
+            "RUN",
+            "DIRECTION",
+            "FINISHED AT",
+            "MIGRATIONS",
+            "DURATION",
+            "RESULT",
+        ],
+        &rows,
+    )
+}
+
+/// Render `smg runs show`: the run's details, then one row per migration
+/// it executed.
+pub fn render_run(run: &RunReport) -> String {
+    let mut fields = vec![
+        ("run", id(run)),
+        ("direction", format!("{:?}", run.direction).to_lowercase()),
+        (
+            "finished at",
+            run.finished_at
+                .as_ref()
+                .map_or("-".to_string(), format_datetime),
+        ),
+        ("duration", format!("{}ms", run.duration_ms)),
+        ("fingerprint", run.fingerprint.clone()),
+    ];
+    if let Some(batch) = run.batch {
+        fields.push(("batch", batch.to_string()));
+    }
+    if let Some(change_ref) = &run.change_ref {
+        fields.push(("change ref", change_ref.clone()));
+    }
+    if let Some(error) = &run.error {
+        fields.push(("error", error.clone()));
+    }
+
+    let mut out = String::new();
+    for (name, value) in fields {
+        let _ = writeln!(out, "{name:<12} {value}");
+    }
+    if !run.outcomes.is_empty() {
+        let rows = run
+            .outcomes
+            .iter()
+            .map(|o| {
+                vec![
+                    o.name.clone(),
+                    format!("{:?}", o.status).to_lowercase(),
+                    format!("{}ms", o.duration_ms),
+                    o.error.clone().unwrap_or_default(),
+                ]
+            })
+            .collect::<Vec<_>>();
+        out.push('\n');
+        out.push_str(&table(&["MIGRATION", "STATUS", "DURATION", "ERROR"], &rows));
+    }
+    out
+}
+
+/// `header` and `rows` in aligned columns.
+fn table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let header = header.iter().map(|h| h.to_string()).collect::<Vec<_>>();
+    let mut widths = header.iter().map(String::len).collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    out
+}
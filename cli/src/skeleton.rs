@@ -0,0 +1,44 @@
+use eyre::Result;
+use std::path::{Path, PathBuf};
+use surreal_migraine::skeleton::down_skeleton;
+use surreal_migraine::statements;
+use surreal_migraine::types::{DiskSource, MigrationKind, MigrationSource};
+
+/// Write a down-script skeleton (see `surreal_migraine::skeleton`) for the
+/// paired migration `name` in `dir`, below the down script's comment
+/// header. Refuses to replace a down script with statements in it unless
+/// `force` is set.
+pub fn generate_down(dir: &Path, name: &str, force: bool) -> Result<PathBuf> {
+    let source = DiskSource::new(dir);
+    let migration = crate::fs::find_migration(dir, name)?;
+    if migration.kind != MigrationKind::Paired {
+        eyre::bail!(
+            "{} is a single-file migration; only paired migrations have a down script",
+            migration.name
+        );
+    }
+
+    let path = dir.join(&migration.name).join("down.surql");
+    let existing = source.get_down(&migration)?.unwrap_or_default();
+    if !force && !statements::split(&existing).is_empty() {
+        eyre::bail!(
+            "{} already has statements; pass --force to replace it",
+            path.display()
+        );
+    }
+
+    let skeleton = down_skeleton(&source.get_up(&migration)?);
+    if skeleton.is_empty() {
+        eyre::bail!(
+            "{}: the up script defines nothing to reverse",
+            migration.name
+        );
+    }
+    let header = existing
+        .lines()
+        .take_while(|line| line.trim_start().starts_with("--"))
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    std::fs::write(&path, format!("{header}{skeleton}"))?;
+    Ok(path)
+}
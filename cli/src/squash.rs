@@ -0,0 +1,44 @@
+use crate::name::sanitize_name;
+use eyre::Result;
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use surreal_migraine::types::DiskSource;
+
+/// Where squashed-away migrations are moved, relative to the migrations
+/// directory. Hidden entries are skipped by discovery.
+pub const ARCHIVE_DIR: &str = ".archive";
+
+/// Combine the versioned migrations from `from` (the first one by default)
+/// through `to` into one migration (see `surreal_migraine::squash`), move
+/// the originals to [`ARCHIVE_DIR`] and write the manifest next to them.
+pub fn squash(dir: &Path, from: Option<&str>, to: &str, name: &str) -> Result<PathBuf> {
+    let sanitized = sanitize_name(name);
+    if sanitized.is_empty() {
+        eyre::bail!("sanitized name is empty");
+    }
+
+    let start = from.map_or(Bound::Unbounded, Bound::Included);
+    let squashed = surreal_migraine::squash::squash(
+        &DiskSource::new(dir),
+        (start, Bound::Included(to)),
+        &sanitized,
+    )?;
+    let path = squashed.write(dir)?;
+
+    let archive = dir.join(ARCHIVE_DIR);
+    std::fs::create_dir_all(&archive)?;
+    for replaced in &squashed.manifest.replaces {
+        std::fs::rename(dir.join(&replaced.name), archive.join(&replaced.name))?;
+    }
+    std::fs::write(
+        archive.join(format!("{}.manifest.json", squashed.name)),
+        serde_json::to_string_pretty(&squashed.manifest)?,
+    )?;
+    tracing::info!(
+        "squashed {} migrations into {}; originals moved to {}",
+        squashed.manifest.replaces.len(),
+        path.display(),
+        archive.display()
+    );
+    Ok(path)
+}
@@ -0,0 +1,165 @@
+use eyre::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+use surreal_migraine::types::{DiskSource, MigrationKind, MigrationSource};
+
+/// Summary of a migrations directory, as printed by `smg stats project`.
+#[derive(Debug, Default)]
+pub struct ProjectStats {
+    pub files: usize,
+    pub paired: usize,
+    pub repeatable: usize,
+    /// Versioned migrations that have a down script.
+    pub with_down: usize,
+    pub total_bytes: usize,
+    /// `(name, bytes)` of every migration's up script, largest first.
+    pub sizes: Vec<(String, usize)>,
+    /// Migrations per `-- author:` value; `None` counts unattributed ones.
+    pub authors: BTreeMap<Option<String>, usize>,
+    /// Migrations added per month (`YYYY-MM`), from git history. Empty when
+    /// the directory is not in a git repository.
+    pub growth: BTreeMap<String, usize>,
+}
+
+impl ProjectStats {
+    /// Collect statistics for the migrations in `dir`.
+    pub fn collect(dir: &Path) -> Result<Self> {
+        let source = DiskSource::new(dir);
+        let mut stats = Self::default();
+
+        for migration in source.list()? {
+            let up = source.get_up(&migration)?;
+            match migration.kind {
+                MigrationKind::File => stats.files += 1,
+                MigrationKind::Paired => stats.paired += 1,
+                MigrationKind::Repeatable => stats.repeatable += 1,
+            }
+            if migration.kind != MigrationKind::Repeatable && source.get_down(&migration)?.is_some()
+            {
+                stats.with_down += 1;
+            }
+            stats.total_bytes += up.len();
+            *stats
+                .authors
+                .entry(migration.metadata.author.clone())
+                .or_default() += 1;
+            stats.sizes.push((migration.name, up.len()));
+        }
+        stats
+            .sizes
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        stats.growth = git_growth(dir);
+
+        Ok(stats)
+    }
+
+    pub fn total(&self) -> usize {
+        self.files + self.paired + self.repeatable
+    }
+
+    /// Percentage of versioned migrations with a down script.
+    pub fn down_coverage(&self) -> f64 {
+        let versioned = self.files + self.paired;
+        if versioned == 0 {
+            return 100.0;
+        }
+        self.with_down as f64 * 100.0 / versioned as f64
+    }
+
+    /// Render a plain-text report listing the `top` largest migrations.
+    pub fn render(&self, top: usize) -> String {
+        let mut out = String::new();
+        let total = self.total();
+        let average = self.total_bytes.checked_div(total).unwrap_or(0);
+
+        let _ = writeln!(out, "migrations:    {total}");
+        let _ = writeln!(
+            out,
+            "  file: {}, paired: {}, repeatable: {}",
+            self.files, self.paired, self.repeatable
+        );
+        let _ = writeln!(out, "down coverage: {:.1}%", self.down_coverage());
+        let _ = writeln!(out, "average size:  {average} bytes");
+
+        if total > 0 {
+            let _ = writeln!(out, "\nlargest:");
+            for (name, bytes) in self.sizes.iter().take(top) {
+                let _ = writeln!(out, "  {bytes:>8} bytes  {name}");
+            }
+
+            let _ = writeln!(out, "\nauthors:");
+            let mut authors = self.authors.iter().collect::<Vec<_>>();
+            authors.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            for (author, count) in authors {
+                let _ = writeln!(
+                    out,
+                    "  {count:>4}  {}",
+                    author.as_deref().unwrap_or("(unknown)")
+                );
+            }
+        }
+
+        if !self.growth.is_empty() {
+            let _ = writeln!(out, "\ngrowth:");
+            let mut running = 0;
+            for (month, added) in &self.growth {
+                running += added;
+                let _ = writeln!(out, "  {month}  +{added:<4} {running}");
+            }
+        }
+
+        out
+    }
+}
+
+/// Count migrations added per month according to `git log`.
+fn git_growth(dir: &Path) -> BTreeMap<String, usize> {
+    let output = Command::new("git")
+        .args([
+            "log",
+            "--diff-filter=A",
+            "--reverse",
+            "--date=format:%Y-%m",
+            "--format=@%ad",
+            "--name-only",
+            "--relative",
+            "--",
+            ".",
+        ])
+        .current_dir(dir)
+        .output();
+    let output = match output {
+        Ok(o) if o.status.success() => o,
+        Ok(o) => {
+            tracing::debug!(stderr = %String::from_utf8_lossy(&o.stderr), "git log failed");
+            return BTreeMap::new();
+        }
+        Err(e) => {
+            tracing::debug!("git unavailable: {e}");
+            return BTreeMap::new();
+        }
+    };
+
+    let mut growth = BTreeMap::new();
+    let mut month = None;
+    let mut counted = std::collections::HashSet::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(m) = line.strip_prefix('@') {
+            month = Some(m.to_string());
+            continue;
+        }
+        // Paired migrations add two files; count each top-level entry once.
+        if !line.ends_with(".surql") {
+            continue;
+        }
+        let entry = line.split('/').next().unwrap_or(line).to_string();
+        if let Some(month) = &month
+            && counted.insert(entry)
+        {
+            *growth.entry(month.clone()).or_default() += 1;
+        }
+    }
+    growth
+}
@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use surreal_migraine::statements::StatementStats;
+use surreal_migraine::types::{IgnoredEntry, MigrationStatus};
+use surrealdb::Datetime;
+
+/// Render `smg status` output: one aligned row per migration, followed by
+/// skipped entries when `ignored` is non-empty.
+///
+/// With `stats` (`--detailed`), pending migrations also show their
+/// statement counts, and a summary line totals them.
+pub fn render(
+    statuses: &[MigrationStatus],
+    ignored: &[IgnoredEntry],
+    stats: Option<&BTreeMap<String, StatementStats>>,
+) -> String {
+    let rows = statuses
+        .iter()
+        .map(|s| {
+            let state = if s.baseline {
+                "baseline"
+            } else if s.applied {
+                "applied"
+            } else {
+                "pending"
+            };
+            let batch = s.batch.map_or("-".to_string(), |b| b.to_string());
+            let applied_at = s
+                .applied_at
+                .as_ref()
+                .map_or("-".to_string(), format_datetime);
+            let checksum = match s.checksum_matches() {
+                Some(true) => "ok",
+                Some(false) => "changed",
+                None => "-",
+            };
+            let mut row = [s.name.as_str(), state, &batch, &applied_at, checksum]
+                .map(str::to_string)
+                .to_vec();
+            if let Some(stats) = stats {
+                row.push(
+                    stats
+                        .get(&s.name)
+                        .map_or("-".to_string(), |s| s.to_string()),
+                );
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+
+    let mut header = ["MIGRATION", "STATUS", "BATCH", "APPLIED AT", "CHECKSUM"]
+        .map(str::to_string)
+        .to_vec();
+    if stats.is_some() {
+        header.push("STATEMENTS".to_string());
+    }
+    let mut widths = header.iter().map(String::len).collect::<Vec<_>>();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut out = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+
+    if let Some(stats) = stats.filter(|s| !s.is_empty()) {
+        let mut total = StatementStats::default();
+        for migration in stats.values() {
+            total.merge(migration);
+        }
+        let _ = writeln!(
+            out,
+            "\npending: {total} ({} schema, {} data)",
+            total.schema(),
+            total.data()
+        );
+    }
+
+    if !ignored.is_empty() {
+        let _ = writeln!(out, "\nignored:");
+        for entry in ignored {
+            let _ = writeln!(out, "  {}: {}", entry.name, entry.reason);
+        }
+    }
+    out
+}
+
+/// `2024-05-01 12:00:00 UTC` from the database's RFC 3339 timestamp.
+pub fn format_datetime(datetime: &Datetime) -> String {
+    let raw = rfc3339(datetime);
+    match chrono::DateTime::parse_from_rfc3339(&raw) {
+        Ok(parsed) => parsed.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        Err(_) => raw,
+    }
+}
+
+/// The RFC 3339 timestamp of `datetime`.
+pub fn rfc3339(datetime: &Datetime) -> String {
+    // Display renders a SurrealQL literal: d'2024-05-01T12:00:00.123Z'
+    let raw = datetime.to_string();
+    raw.trim_start_matches('d')
+        .trim_matches(['\'', '"'])
+        .to_string()
+}
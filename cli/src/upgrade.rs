@@ -0,0 +1,21 @@
+use std::fmt::Write;
+use surreal_migraine::upgrade::Advice;
+
+/// One line per use, with the version that changed it and the replacement.
+pub fn render(advice: &[Advice]) -> String {
+    let mut out = String::new();
+    for advice in advice {
+        let script = if advice.down { " (down)" } else { "" };
+        let _ = writeln!(
+            out,
+            "{}{script} line {}: `{}`: {} in {}; use `{}`",
+            advice.migration,
+            advice.line,
+            advice.rule.pattern,
+            advice.rule.message,
+            advice.rule.since,
+            advice.rule.fix
+        );
+    }
+    out
+}
@@ -0,0 +1,139 @@
+use crate::cache::Cache;
+use eyre::Result;
+use std::fmt::Write;
+use std::path::Path;
+use surreal_migraine::placeholders;
+use surreal_migraine::types::{DiskSource, MigrationKind, MigrationSource};
+use surreal_migraine::validate::{DependencyProblem, numeric_prefix};
+
+/// Something wrong with a migration directory entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// The entry's name, or the script within it (`002_posts/down.surql`).
+    pub entry: String,
+    pub message: String,
+}
+
+/// Check the migrations in `dir` without a database: naming, duplicate
+/// prefixes (and gaps, with `check_gaps`), complete paired folders, UTF-8
+/// content, SurrealQL syntax, and `depends-on` headers.
+///
+/// Scripts containing `{{placeholders}}` are not syntax-checked, as they
+/// only become SurrealQL once the values are substituted. The others are
+/// parsed in parallel, reusing `cache`d syntax errors for unchanged ones.
+pub fn validate(
+    dir: &Path,
+    check_gaps: bool,
+    cache: &mut Cache<Option<String>>,
+) -> Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+    let mut problem = |entry: &str, message: String| {
+        problems.push(Problem {
+            entry: entry.to_string(),
+            message,
+        })
+    };
+
+    for entry in std::fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        if name.to_str().is_none() {
+            problem(
+                &name.to_string_lossy(),
+                "name is not valid UTF-8, so it is skipped".to_string(),
+            );
+        }
+    }
+
+    let source = DiskSource::new(dir);
+    for ignored in source.ignored()? {
+        problem(&ignored.name, ignored.reason);
+    }
+
+    let migrations = source.list()?;
+    let mut parse = Vec::new();
+    for migration in &migrations {
+        let name = migration.name.as_str();
+        if migration.kind != MigrationKind::Repeatable && numeric_prefix(name).is_none() {
+            problem(name, "name is not `<version>_<description>`".to_string());
+        }
+
+        let scripts = match migration.kind {
+            MigrationKind::File | MigrationKind::Repeatable => {
+                if !name.ends_with(".surql") {
+                    problem(name, "file does not end in `.surql`".to_string());
+                }
+                vec![(name.to_string(), dir.join(name))]
+            }
+            MigrationKind::Paired => ["up.surql", "down.surql"]
+                .into_iter()
+                .map(|file| (format!("{name}/{file}"), dir.join(name).join(file)))
+                .collect(),
+        };
+        for (script, path) in scripts {
+            if !path.is_file() {
+                problem(&script, "missing".to_string());
+                continue;
+            }
+            let Ok(content) = String::from_utf8(std::fs::read(&path)?) else {
+                problem(&script, "not valid UTF-8".to_string());
+                continue;
+            };
+            if !placeholders::names(&content).is_empty() {
+                tracing::debug!(script, "skipping syntax check of templated script");
+                continue;
+            }
+            parse.push((script, content));
+        }
+    }
+
+    let contents = parse.iter().map(|(_, c)| c.as_str()).collect::<Vec<_>>();
+    let errors = cache.check(&contents, |content| {
+        surrealdb::sql::parse(content).err().map(|e| e.to_string())
+    });
+    for ((script, _), error) in parse.iter().zip(errors) {
+        if let Some(error) = error {
+            problem(script, format!("syntax error: {error}"));
+        }
+    }
+
+    let report = source.validate(check_gaps)?;
+    for duplicate in &report.duplicates {
+        problem(
+            &duplicate.names.join(", "),
+            format!("share the version prefix {}", duplicate.prefix),
+        );
+    }
+    for gap in &report.gaps {
+        problem(
+            &gap.to_string(),
+            "no migration has this version".to_string(),
+        );
+    }
+    for dependency in &report.dependencies {
+        match dependency {
+            DependencyProblem::Unknown { name, dependency } => problem(
+                name,
+                format!("depends on {dependency}, which does not exist"),
+            ),
+            DependencyProblem::Later { name, dependency } => problem(
+                name,
+                format!("depends on {dependency}, which comes after it"),
+            ),
+            DependencyProblem::Cycle { names } => problem(
+                &names.join(", "),
+                "depend on each other in a cycle".to_string(),
+            ),
+        }
+    }
+
+    Ok(problems)
+}
+
+/// One line per problem.
+pub fn render(problems: &[Problem]) -> String {
+    let mut out = String::new();
+    for problem in problems {
+        let _ = writeln!(out, "{}: {}", problem.entry, problem.message);
+    }
+    out
+}
@@ -0,0 +1,111 @@
+use crate::cli::WatchArgs;
+use crate::config::Project;
+use crate::db;
+use eyre::Result;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use surreal_migraine::MigrationRunner;
+use surreal_migraine::metadata::NO_TRANSACTION;
+use surreal_migraine::types::{DiskSource, MigrationSource, RunDirection};
+use surrealdb::engine::any::Any;
+
+/// `smg watch`: apply pending migrations on start and again whenever a
+/// migration is added or edited, until Ctrl-C.
+pub async fn watch(args: &WatchArgs, project: &Project, dir: &Path) -> Result<()> {
+    let db = db::connect(&args.connect, project).await?;
+    let runner =
+        db::runner(&db, DiskSource::new(dir), project)?.with_cancel_token(db::cancel_on_ctrl_c());
+
+    let root = std::fs::canonicalize(dir)?;
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let watched = root.clone();
+    let mut watcher =
+        notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) => {
+                if let Some(path) = relevant(&watched, &event) {
+                    let _ = tx.send(path);
+                }
+            }
+            Err(e) => tracing::warn!("watch error: {e}"),
+        })?;
+    watcher.watch(&root, RecursiveMode::Recursive)?;
+    tracing::info!("watching {} for changes (Ctrl-C to stop)", dir.display());
+
+    let debounce = Duration::from_millis(args.debounce);
+    let mut interrupted = std::pin::pin!(tokio::signal::ctrl_c());
+    apply(&runner, dir).await;
+    loop {
+        let path = tokio::select! {
+            path = rx.recv() => path,
+            _ = &mut interrupted => None,
+        };
+        let Some(path) = path else {
+            break;
+        };
+        tracing::debug!("{} changed", path.display());
+        // Wait for the burst of events an editor's save produces to end.
+        while let Ok(Some(path)) = tokio::time::timeout(debounce, rx.recv()).await {
+            tracing::debug!("{} changed", path.display());
+        }
+        tracing::info!("migrations changed; applying pending migrations");
+        apply(&runner, dir).await;
+    }
+    tracing::info!("stopped watching");
+    Ok(())
+}
+
+/// Apply pending migrations, logging a failure instead of returning it so
+/// the next change gets another try.
+async fn apply(runner: &MigrationRunner<'_, Any, DiskSource>, dir: &Path) {
+    let result = async {
+        clear_rolled_back(runner, dir).await?;
+        runner.up().await
+    };
+    match result.await {
+        Ok(_) => tracing::info!("database is up to date"),
+        Err(e) => {
+            tracing::error!("{e:#}");
+            tracing::info!("fix the migration and save it to try again");
+        }
+    }
+}
+
+/// Clear the dirty marker a failed up migration left when it ran in a
+/// transaction: none of it was applied, so its edited version can run.
+/// Other failures keep blocking runs, as outside watch mode.
+async fn clear_rolled_back(
+    runner: &MigrationRunner<'_, Any, DiskSource>,
+    dir: &Path,
+) -> Result<()> {
+    let Some(state) = runner.dirty().await? else {
+        return Ok(());
+    };
+    let transactional = DiskSource::new(dir)
+        .list()?
+        .iter()
+        .find(|m| m.name == state.migration)
+        .is_some_and(|m| !m.metadata.flags.iter().any(|f| f == NO_TRANSACTION));
+    if state.direction == RunDirection::Up && transactional {
+        runner.repair().await?;
+    }
+    Ok(())
+}
+
+/// The path an event touched, unless it only read files or touched hidden
+/// entries (editor swap files, `.smg-cache`, `.archive`).
+fn relevant(root: &Path, event: &Event) -> Option<PathBuf> {
+    if matches!(event.kind, EventKind::Access(_)) {
+        return None;
+    }
+    event
+        .paths
+        .iter()
+        .find(|path| {
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            !relative
+                .components()
+                .any(|c| c.as_os_str().to_string_lossy().starts_with('.'))
+        })
+        .cloned()
+}
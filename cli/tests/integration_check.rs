@@ -0,0 +1,51 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn check_frozen_gates_on_lock_pending_and_edits() {
+    let dir = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    let smg = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args(args)
+            .args(["--engine", "surrealkv", "--path"])
+            .arg(data.path().join("db"))
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+
+    smg(&["check", "--frozen"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("migrations.lock does not exist"))
+        .stdout(predicate::str::contains("001_users.surql is pending"))
+        .stderr(predicate::str::contains("2 check(s) failed"));
+    // Pending migrations alone are fine without --frozen.
+    smg(&["check"]).assert().success();
+
+    cargo_bin_cmd!("smg")
+        .args(["lock", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .success();
+    smg(&["up"]).assert().success();
+    smg(&["check", "--frozen"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("migrations are consistent"));
+
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE users;").unwrap();
+    smg(&["check"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "001_users.surql changed since it was locked",
+        ))
+        .stdout(predicate::str::contains(
+            "001_users.surql changed since it was applied",
+        ));
+}
@@ -0,0 +1,218 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+const CONFIG: &str = r#"
+dir = "db/migrations"
+naming = "temporal"
+table = "schema_history"
+bookkeeping = "ops/app_history"
+default_env = "dev"
+
+[env.dev]
+url = "mem://"
+ns = "app"
+db = "dev"
+
+[env.prod]
+url = "wss://db.example.com"
+ns = "app"
+db = "main"
+user = "deploy"
+pass = "hunter2"
+"#;
+
+fn project() -> tempfile::TempDir {
+    let root = tempdir().unwrap();
+    fs::write(root.path().join("smg.toml"), CONFIG).unwrap();
+    fs::create_dir_all(root.path().join("db/migrations")).unwrap();
+    root
+}
+
+#[test]
+fn env_reports_values_from_the_selected_profile() {
+    let root = project();
+
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .args(["env", "--env", "prod", "--db", "replica"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("smg.toml"))
+        .stdout(predicate::str::is_match(r"env\s+prod\s+flag\n").unwrap())
+        .stdout(predicate::str::is_match(r"url\s+wss://db.example.com\s+smg.toml\n").unwrap())
+        .stdout(predicate::str::is_match(r"database\s+replica\s+flag\n").unwrap())
+        .stdout(predicate::str::is_match(r"password\s+\*\*\*\*\s+smg.toml\n").unwrap())
+        .stdout(predicate::str::is_match(r"table\s+schema_history\s+smg.toml\n").unwrap())
+        .stdout(predicate::str::is_match(r"bookkeeping\s+ops/app_history\s+smg.toml\n").unwrap())
+        .stdout(predicate::str::is_match(r"naming\s+temporal\s+smg.toml\n").unwrap())
+        .stdout(predicate::str::contains("hunter2").not());
+}
+
+#[test]
+fn environment_variables_sit_between_flags_and_the_profile() {
+    let root = project();
+
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .env("SURREAL_URL", "ws://ci-db:8000")
+        .env("SURREAL_DB", "ci")
+        .env("SURREAL_PASS", "s3cret")
+        .args(["env", "--env", "prod", "--db", "replica"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(r"url\s+ws://ci-db:8000\s+env\n").unwrap())
+        .stdout(predicate::str::is_match(r"namespace\s+app\s+smg.toml\n").unwrap())
+        .stdout(predicate::str::is_match(r"database\s+replica\s+flag\n").unwrap())
+        .stdout(predicate::str::is_match(r"password\s+\*\*\*\*\s+env\n").unwrap())
+        .stdout(predicate::str::contains("s3cret").not());
+}
+
+#[test]
+fn up_uses_the_default_profile_and_configured_dir() {
+    let root = project();
+    fs::write(
+        root.path().join("db/migrations/001_users.surql"),
+        "DEFINE TABLE user;",
+    )
+    .unwrap();
+    let nested = root.path().join("src/app");
+    fs::create_dir_all(&nested).unwrap();
+
+    // smg.toml is found from a subdirectory of the project.
+    cargo_bin_cmd!("smg")
+        .current_dir(&nested)
+        .arg("up")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Applied migration: 001_users.surql",
+        ));
+}
+
+#[test]
+fn unknown_profile_is_an_error() {
+    let root = project();
+
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .args(["status", "--env", "staging"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "no profile [env.staging] (defined: dev, prod)",
+        ));
+}
+
+#[test]
+fn protected_profile_requires_a_change_ref() {
+    let root = project();
+    let config = root.path().join("smg.toml");
+    let mut text = fs::read_to_string(&config).unwrap();
+    text.push_str("\n[env.audited]\nurl = \"mem://\"\nns = \"app\"\ndb = \"main\"\nrequire_change_ref = true\n");
+    fs::write(&config, text).unwrap();
+    fs::write(
+        root.path().join("db/migrations/001_users.surql"),
+        "DEFINE TABLE user;",
+    )
+    .unwrap();
+
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .env_remove("SMG_CHANGE_REF")
+        .args(["up", "--env", "audited"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "profile audited requires a change reference",
+        ));
+
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .args(["up", "--env", "audited", "--change-ref", "JIRA-123"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Applied migration: 001_users.surql",
+        ));
+
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .env("SMG_CHANGE_REF", "JIRA-123")
+        .args(["up", "--env", "audited"])
+        .assert()
+        .success();
+
+    fs::create_dir(root.path().join("seeds")).unwrap();
+    for args in [&["fresh", "--yes"][..], &["seed"]] {
+        cargo_bin_cmd!("smg")
+            .current_dir(root.path())
+            .env_remove("SMG_CHANGE_REF")
+            .args(args)
+            .args(["--env", "audited"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "profile audited requires a change reference",
+            ));
+    }
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .args([
+            "fresh",
+            "--yes",
+            "--env",
+            "audited",
+            "--change-ref",
+            "JIRA-123",
+        ])
+        .assert()
+        .success();
+}
+
+#[test]
+fn add_follows_the_configured_naming() {
+    let root = project();
+
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .args(["add", "create_users"])
+        .assert()
+        .success();
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .args(["add", "create_posts", "--numeric"])
+        .assert()
+        .success();
+
+    let mut names = fs::read_dir(root.path().join("db/migrations"))
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names.len(), 2, "{names:?}");
+    assert!(
+        names.iter().any(|n| n.ends_with("_create_posts")),
+        "{names:?}"
+    );
+    let temporal = names.iter().find(|n| n.ends_with("_create_users")).unwrap();
+    let prefix = temporal.split('_').next().unwrap();
+    assert!(prefix.len() >= 12, "{temporal}");
+}
+
+#[test]
+fn malformed_bookkeeping_location_is_an_error() {
+    let root = project();
+    let config = CONFIG.replace("ops/app_history", "ops");
+    fs::write(root.path().join("smg.toml"), config).unwrap();
+
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .arg("status")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "bookkeeping = \"ops\" in smg.toml must be `<namespace>/<database>`",
+        ));
+}
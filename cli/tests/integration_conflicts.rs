@@ -0,0 +1,61 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn shared_versions_are_found_without_a_database() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(dir.path().join("002_posts.surql"), "DEFINE TABLE post;").unwrap();
+    fs::write(dir.path().join("002_tags.surql"), "DEFINE TABLE tag;").unwrap();
+
+    cargo_bin_cmd!("smg")
+        .env_remove("SURREAL_NS")
+        .env_remove("SURREAL_DB")
+        .args(["check-conflicts", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "002_posts.surql, 002_tags.surql share the version prefix 002",
+        ))
+        .stdout(predicate::str::contains(
+            "smg rename 002_tags.surql 003_tags.surql",
+        ))
+        .stderr(predicate::str::contains("1 migration conflict(s)"));
+}
+
+#[test]
+fn merged_migrations_behind_the_history_are_renumbered() {
+    let dir = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    let smg = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args(args)
+            .args(["--engine", "surrealkv", "--path"])
+            .arg(data.path().join("db"))
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(dir.path().join("003_posts.surql"), "DEFINE TABLE post;").unwrap();
+    smg(&["up"]).assert().success();
+    smg(&["check-conflicts"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("no conflicts"));
+
+    // Another branch added 002 before 003 was merged.
+    fs::write(dir.path().join("002_tags.surql"), "DEFINE TABLE tag;").unwrap();
+    smg(&["check-conflicts"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "002_tags.surql is pending but 003_posts.surql is already applied",
+        ))
+        .stdout(predicate::str::contains(
+            "smg rename 002_tags.surql 004_tags.surql",
+        ));
+}
@@ -0,0 +1,95 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn convert(dir: &std::path::Path, name: &str) -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("smg");
+    cmd.env_remove("SURREAL_NS")
+        .env_remove("SURREAL_DB")
+        .args(["convert", name, "--dir"])
+        .arg(dir);
+    cmd
+}
+
+#[test]
+fn convert_round_trips_between_layouts() {
+    let dir = tempdir().unwrap();
+    let up = "-- migration: users\n-- no-transaction\nDEFINE TABLE user;\n";
+    fs::write(dir.path().join("001_users.surql"), up).unwrap();
+
+    convert(dir.path(), "001_users").assert().success();
+    let folder = dir.path().join("001_users");
+    assert!(!dir.path().join("001_users.surql").exists());
+    assert_eq!(fs::read_to_string(folder.join("up.surql")).unwrap(), up);
+    assert_eq!(
+        fs::read_to_string(folder.join("down.surql")).unwrap(),
+        "-- migration: users\n-- no-transaction\n"
+    );
+
+    convert(dir.path(), "001_users").assert().success();
+    assert!(!folder.exists());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("001_users.surql")).unwrap(),
+        up
+    );
+}
+
+#[test]
+fn convert_keeps_down_scripts_unless_forced() {
+    let dir = tempdir().unwrap();
+    let folder = dir.path().join("001_users");
+    fs::create_dir(&folder).unwrap();
+    fs::write(folder.join("up.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(folder.join("down.surql"), "REMOVE TABLE user;").unwrap();
+
+    convert(dir.path(), "001_users")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --force to drop it"));
+    assert!(folder.join("down.surql").exists());
+
+    convert(dir.path(), "001_users")
+        .arg("--force")
+        .assert()
+        .success();
+    assert!(dir.path().join("001_users.surql").exists());
+}
+
+#[test]
+fn convert_moves_the_applied_record_along() {
+    let dir = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    let smg = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args(args)
+            .args(["--engine", "surrealkv", "--path"])
+            .arg(data.path().join("db"))
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+    smg(&["up"]).assert().success();
+
+    smg(&["convert", "001_users"])
+        .write_stdin("n\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("aborted"));
+    assert!(dir.path().join("001_users.surql").exists());
+
+    smg(&["convert", "001_users", "--yes"]).assert().success();
+    assert!(dir.path().join("001_users/up.surql").exists());
+    smg(&["up"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Applied migration").not());
+
+    smg(&["convert", "001_users", "--yes"]).assert().success();
+    assert!(dir.path().join("001_users.surql").exists());
+    smg(&["up"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Applied migration").not());
+}
@@ -0,0 +1,91 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn diff_writes_a_migration_reconciling_the_database() {
+    let dir = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    fs::write(
+        dir.path().join("001_users.surql"),
+        "DEFINE TABLE user SCHEMAFULL;\nDEFINE FIELD name ON user TYPE string;",
+    )
+    .unwrap();
+    let desired = data.path().join("schema.surql");
+    fs::write(
+        &desired,
+        "DEFINE TABLE user SCHEMAFULL;
+DEFINE FIELD name ON user TYPE string;
+DEFINE FIELD email ON user TYPE string;
+DEFINE INDEX unique_email ON user FIELDS email UNIQUE;",
+    )
+    .unwrap();
+    let smg = |command: &str| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args([command, "--engine", "surrealkv", "--path"])
+            .arg(data.path().join("db"))
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+    smg("up").assert().success();
+
+    smg("diff")
+        .arg("--schema")
+        .arg(&desired)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "DEFINE FIELD email ON user TYPE string",
+        ))
+        .stdout(predicate::str::contains(
+            "DEFINE INDEX unique_email ON user",
+        ));
+
+    smg("diff")
+        .arg("--schema")
+        .arg(&desired)
+        .args(["--name", "add_email"])
+        .assert()
+        .success();
+    let down = fs::read_to_string(dir.path().join("002_add_email/down.surql")).unwrap();
+    assert!(
+        down.contains("REMOVE INDEX unique_email ON user;"),
+        "{down}"
+    );
+    assert!(down.contains("REMOVE FIELD email ON user;"), "{down}");
+
+    smg("up").assert().success();
+    smg("diff")
+        .arg("--schema")
+        .arg(&desired)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("schema is up to date"))
+        .stdout(predicate::str::contains("DEFINE").not());
+}
+
+#[test]
+fn diff_starts_from_a_snapshot_file() {
+    let dir = tempdir().unwrap();
+    let snapshot = dir.path().join("schema.json");
+    fs::write(
+        &snapshot,
+        r#"{"tables": {"legacy": {"definition": "DEFINE TABLE legacy TYPE ANY SCHEMALESS PERMISSIONS NONE"}}}"#,
+    )
+    .unwrap();
+    let desired = dir.path().join("desired.surql");
+    fs::write(&desired, "DEFINE FUNCTION fn::one() { RETURN 1; };").unwrap();
+
+    cargo_bin_cmd!("smg")
+        .arg("diff")
+        .arg("--from-snapshot")
+        .arg(&snapshot)
+        .arg("--schema")
+        .arg(&desired)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("DEFINE FUNCTION fn::one()"))
+        .stdout(predicate::str::contains("REMOVE TABLE legacy;"));
+}
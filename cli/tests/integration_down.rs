@@ -0,0 +1,132 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn down(dir: &std::path::Path) -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("smg");
+    cmd.args([
+        "down",
+        "--url",
+        "mem://",
+        "--ns",
+        "test",
+        "--db",
+        "test",
+        "--dir",
+        dir.to_str().unwrap(),
+    ]);
+    cmd
+}
+
+#[test]
+fn down_with_nothing_applied_is_a_no_op() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    down(dir.path())
+        .args(["--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nothing to revert"));
+}
+
+#[test]
+fn down_to_unapplied_migration_fails() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    down(dir.path())
+        .args(["--to", "001_users.surql"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not applied"));
+}
+
+#[test]
+fn down_targets_are_exclusive() {
+    let dir = tempdir().unwrap();
+
+    down(dir.path())
+        .args(["--steps", "2", "--all"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn down_prints_an_empty_run_as_json() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    let output = down(dir.path())
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        run,
+        serde_json::json!({
+            "direction": "down",
+            "batch": null,
+            "duration_ms": 0,
+            "error": null,
+            "migrations": [],
+        })
+    );
+}
+
+#[test]
+fn down_last_batch_reverts_what_the_last_up_applied() {
+    let dir = tempdir().unwrap();
+    let migrations = dir.path().join("migrations");
+    for name in ["001_users", "002_posts", "003_tags"] {
+        let table = &name[4..];
+        fs::create_dir_all(migrations.join(name)).unwrap();
+        fs::write(
+            migrations.join(name).join("up.surql"),
+            format!("DEFINE TABLE {table};"),
+        )
+        .unwrap();
+        fs::write(
+            migrations.join(name).join("down.surql"),
+            format!("REMOVE TABLE {table};"),
+        )
+        .unwrap();
+    }
+    let later = dir.path().join("later");
+    fs::create_dir(&later).unwrap();
+    for name in ["002_posts", "003_tags"] {
+        fs::rename(migrations.join(name), later.join(name)).unwrap();
+    }
+    let data = dir.path().join("data");
+    let smg = |command: &str| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args([command, "--engine", "surrealkv", "--path"])
+            .arg(&data)
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(&migrations);
+        cmd
+    };
+
+    smg("up").assert().success();
+    for name in ["002_posts", "003_tags"] {
+        fs::rename(later.join(name), migrations.join(name)).unwrap();
+    }
+    smg("up").assert().success();
+
+    smg("down")
+        .arg("--last-batch")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Reverted migration: 003_tags"))
+        .stdout(predicate::str::contains("Reverted migration: 002_posts"))
+        .stdout(predicate::str::contains("001_users").not());
+    smg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("001_users  applied  1"))
+        .stdout(predicate::str::contains("002_posts  pending"));
+}
@@ -0,0 +1,50 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn drift_reports_objects_missing_from_the_migrations() {
+    let dir = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    fs::write(
+        dir.path().join("001_users.surql"),
+        "DEFINE TABLE user SCHEMAFULL;\nDEFINE FIELD name ON user TYPE string;",
+    )
+    .unwrap();
+    let smg = |command: &str| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args([command, "--engine", "surrealkv", "--path"])
+            .arg(data.path().join("db"))
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+
+    smg("up").assert().success();
+    smg("drift")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "schema matches the applied migrations",
+        ));
+
+    // A hotfix applied and then dropped from the migrations directory.
+    let hotfix = dir.path().join("002_hotfix.surql");
+    fs::write(
+        &hotfix,
+        "DEFINE INDEX by_name ON user FIELDS name;\nDEFINE TABLE audit;",
+    )
+    .unwrap();
+    smg("up").assert().success();
+    fs::remove_file(&hotfix).unwrap();
+
+    smg("drift")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("added table audit"))
+        .stdout(predicate::str::contains("added index user.by_name"))
+        .stderr(predicate::str::contains(
+            "2 schema object(s) changed outside migrations",
+        ));
+}
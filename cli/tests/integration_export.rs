@@ -0,0 +1,44 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn export_test_writes_a_test_and_golden_schema() {
+    let root = tempdir().unwrap();
+    fs::write(
+        root.path().join("Cargo.toml"),
+        "[package]\nname = \"app\"\n",
+    )
+    .unwrap();
+    let migrations = root.path().join("db/migrations");
+    fs::create_dir_all(&migrations).unwrap();
+    fs::write(
+        migrations.join("001_users.surql"),
+        "DEFINE TABLE user SCHEMAFULL;\nDEFINE FIELD name ON user TYPE string;",
+    )
+    .unwrap();
+
+    cargo_bin_cmd!("smg")
+        .current_dir(root.path())
+        .args(["export-test", "--dir", "db/migrations"])
+        .assert()
+        .success();
+
+    let test = fs::read_to_string(root.path().join("tests/migrations.rs")).unwrap();
+    assert!(
+        test.contains("const MIGRATIONS: &str = \"db/migrations\";"),
+        "{test}"
+    );
+    assert!(
+        test.contains("const GOLDEN_SCHEMA: &str = \"tests/schema.json\";"),
+        "{test}"
+    );
+    assert!(test.contains("async fn migrations_apply_and_match_the_golden_schema()"));
+
+    let golden = fs::read_to_string(root.path().join("tests/schema.json")).unwrap();
+    assert!(
+        golden.contains("DEFINE FIELD name ON user TYPE string"),
+        "{golden}"
+    );
+    assert!(!golden.contains("\"migrations\""), "{golden}");
+}
@@ -0,0 +1,41 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn fresh(dir: &std::path::Path, db: &str) -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("smg");
+    cmd.args(["fresh", "--url", "mem://", "--ns", "test", "--db", db])
+        .args(["--dir", dir.to_str().unwrap()]);
+    cmd
+}
+
+#[test]
+fn fresh_requires_yes() {
+    let dir = tempdir().unwrap();
+
+    fresh(dir.path(), "ci")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("pass --yes to confirm"));
+}
+
+#[test]
+fn fresh_refuses_production_looking_targets_unless_forced() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    fresh(dir.path(), "app_prod")
+        .arg("--yes")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("database app_prod"));
+
+    fresh(dir.path(), "app_prod")
+        .args(["--yes", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Applied migration: 001_users.surql",
+        ));
+}
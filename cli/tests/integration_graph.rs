@@ -0,0 +1,53 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn graph_prints_order_and_dependencies() {
+    let dir = tempdir().unwrap();
+    let path = dir.path();
+    let write = |name: &str, content: &str| fs::write(path.join(name), content).unwrap();
+    write("001_users.surql", "DEFINE TABLE user;");
+    write(
+        "002_posts.surql",
+        "-- depends-on: 001_users, 003_tags\nDEFINE TABLE post;",
+    );
+    write(
+        "003_tags.surql",
+        "-- depends-on: 009_gone\nDEFINE TABLE tag;",
+    );
+    write("R_views.surql", "DEFINE TABLE view;");
+
+    cargo_bin_cmd!("smg")
+        .args(["graph", "--dir", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("digraph migrations {"))
+        .stdout(predicate::str::contains(
+            "\"R_views.surql\" [style=rounded];",
+        ))
+        .stdout(predicate::str::contains(
+            "\"009_gone\" [style=dashed, color=red];",
+        ))
+        .stdout(predicate::str::contains(
+            "\"001_users.surql\" -> \"002_posts.surql\";",
+        ))
+        .stdout(predicate::str::contains(
+            "\"003_tags.surql\" -> \"002_posts.surql\" [color=red];",
+        ))
+        .stdout(predicate::str::contains(
+            "\"002_posts.surql\" -> \"003_tags.surql\" [style=dashed];",
+        ));
+
+    cargo_bin_cmd!("smg")
+        .args(["graph", "--mermaid", "--dir", path.to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("flowchart LR\n"))
+        .stdout(predicate::str::contains("m3([\"R_views.surql\"])"))
+        .stdout(predicate::str::contains("m4[\"009_gone\"]:::missing"))
+        .stdout(predicate::str::contains("m2 --> m1"))
+        .stdout(predicate::str::contains("m1 -.-> m2"))
+        .stdout(predicate::str::contains("linkStyle 1 stroke:red"));
+}
@@ -0,0 +1,89 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+const CONFIG: &str = r#"
+dir = "migrations"
+
+[env.dev]
+url = "mem://"
+ns = "app"
+db = "dev"
+
+[hooks]
+before_all = [{ run = "echo \"$SMG_HOOK $SMG_DIRECTION\" >> hooks.log" }]
+before_each = [{ run = "echo \"$SMG_HOOK $SMG_MIGRATION\" >> hooks.log" }]
+after_all = [
+    { run = "echo \"$SMG_HOOK $SMG_OUTCOME\" >> hooks.log" },
+    { surql = "hooks/after.surql" },
+]
+"#;
+
+#[cfg(unix)]
+#[test]
+fn up_runs_the_configured_hooks() {
+    let root = tempdir().unwrap();
+    let path = root.path();
+    fs::write(path.join("smg.toml"), CONFIG).unwrap();
+    fs::create_dir_all(path.join("migrations")).unwrap();
+    fs::create_dir_all(path.join("hooks")).unwrap();
+    fs::write(
+        path.join("migrations/001_users.surql"),
+        "DEFINE TABLE user;",
+    )
+    .unwrap();
+    fs::write(
+        path.join("migrations/002_posts.surql"),
+        "DEFINE TABLE post;",
+    )
+    .unwrap();
+    fs::write(path.join("hooks/after.surql"), "RETURN $outcome;").unwrap();
+
+    cargo_bin_cmd!("smg")
+        .current_dir(path)
+        .args(["up", "--env", "dev"])
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(path.join("hooks.log")).unwrap(),
+        "before-all up\n\
+         before-each 001_users.surql\n\
+         before-each 002_posts.surql\n\
+         after-all succeeded\n"
+    );
+
+    fs::write(
+        path.join("hooks/after.surql"),
+        "THROW 'change feeds not resumed';",
+    )
+    .unwrap();
+    cargo_bin_cmd!("smg")
+        .current_dir(path)
+        .args(["up", "--env", "dev"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("after-all hook failed"))
+        .stderr(predicate::str::contains("change feeds not resumed"));
+}
+
+#[test]
+fn hooks_need_a_command_or_a_script() {
+    let root = tempdir().unwrap();
+    let path = root.path();
+    fs::write(
+        path.join("smg.toml"),
+        "[env.dev]\nurl = \"mem://\"\nns = \"app\"\ndb = \"dev\"\n\n[hooks]\nafter_each = [{}]\n",
+    )
+    .unwrap();
+    fs::create_dir_all(path.join("migrations")).unwrap();
+
+    cargo_bin_cmd!("smg")
+        .current_dir(path)
+        .args(["up", "--env", "dev"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "after-each hooks in smg.toml need exactly one of `run` and `surql`",
+        ));
+}
@@ -0,0 +1,70 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn lint_reports_secrets_without_printing_them() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(
+        dir.path().join("002_admin.surql"),
+        "DEFINE USER admin ON ROOT PASSWORD 'hunter2' ROLES OWNER;",
+    )
+    .unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args(["lint", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "002_admin.surql line 1: secret: password or token set to a literal",
+        ))
+        .stdout(predicate::str::contains("hunter2").not())
+        .stderr(predicate::str::contains("1 lint finding(s)"));
+}
+
+#[test]
+fn lint_passes_clean_migrations() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("001_admin.surql"),
+        "DEFINE USER admin ON ROOT PASSWORD '{{ admin_password }}' ROLES OWNER;",
+    )
+    .unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args(["lint", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("no lint findings"));
+}
+
+#[test]
+fn lint_reuses_cached_results_for_unchanged_scripts() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    let lint = || {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args(["lint", "--dir", dir.path().to_str().unwrap()]);
+        cmd
+    };
+    lint().assert().success();
+
+    // Plant a finding for the unchanged script; only a cached run sees it.
+    let cache = dir.path().join(".smg-cache/lint.json");
+    let mut file: serde_json::Value = serde_json::from_slice(&fs::read(&cache).unwrap()).unwrap();
+    for entry in file["entries"].as_object_mut().unwrap().values_mut() {
+        *entry = serde_json::json!([[1, "secret", "planted"]]);
+    }
+    fs::write(&cache, file.to_string()).unwrap();
+
+    lint().assert().failure().stdout(predicate::str::contains(
+        "001_users.surql line 1: secret: planted",
+    ));
+    lint().arg("--no-cache").assert().success();
+    assert_eq!(
+        fs::read_to_string(dir.path().join(".smg-cache/.gitignore")).unwrap(),
+        "*\n"
+    );
+}
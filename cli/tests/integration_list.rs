@@ -0,0 +1,90 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn migrations() -> tempfile::TempDir {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::create_dir(dir.path().join("002_posts")).unwrap();
+    fs::write(dir.path().join("002_posts/up.surql"), "DEFINE TABLE post;").unwrap();
+    fs::write(dir.path().join("init.surql"), "DEFINE TABLE tag;").unwrap();
+    dir
+}
+
+#[test]
+fn list_all_works_offline_and_explains_skipped_entries() {
+    let dir = migrations();
+
+    cargo_bin_cmd!("smg")
+        .args(["list", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "file        001_users.surql\npaired      002_posts\n",
+        ))
+        .stdout(predicate::str::contains(
+            "init.surql: file name does not start with a digit",
+        ));
+}
+
+#[test]
+fn list_pending_compares_against_the_database() {
+    let dir = migrations();
+
+    cargo_bin_cmd!("smg")
+        .args([
+            "list",
+            "--pending",
+            "--url",
+            "mem://",
+            "--ns",
+            "test",
+            "--db",
+            "test",
+        ])
+        .args(["--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("001_users.surql\n002_posts\n"));
+}
+
+#[test]
+fn list_applied_without_database_fails() {
+    let dir = migrations();
+
+    cargo_bin_cmd!("smg")
+        .args(["list", "--applied", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "a database is required: pass --ns and --db",
+        ));
+}
+
+#[test]
+fn list_prints_json() {
+    let dir = migrations();
+
+    let output = cargo_bin_cmd!("smg")
+        .args([
+            "list",
+            "--format",
+            "json",
+            "--dir",
+            dir.path().to_str().unwrap(),
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        list["migrations"],
+        serde_json::json!([
+            { "name": "001_users.surql", "kind": "file" },
+            { "name": "002_posts", "kind": "paired" },
+        ])
+    );
+    assert_eq!(list["ignored"][0]["name"], "init.surql");
+}
@@ -0,0 +1,42 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn up_locked_refuses_migrations_changed_since_smg_lock() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    let smg = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args(args)
+            .args(["--url", "mem://", "--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+
+    cargo_bin_cmd!("smg")
+        .args(["lock", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("locked 1 migration(s)"));
+    let lock = fs::read_to_string(dir.path().join("migrations.lock")).unwrap();
+    assert!(lock.contains("\n001_users.surql "), "{lock}");
+
+    cargo_bin_cmd!("smg")
+        .args(["validate", "--no-cache", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .success();
+    smg(&["up", "--locked"]).assert().success();
+
+    fs::write(dir.path().join("002_posts.surql"), "DEFINE TABLE post;").unwrap();
+    smg(&["up", "--locked"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "002_posts.surql is not in migrations.lock",
+        ));
+    smg(&["up"]).assert().success();
+}
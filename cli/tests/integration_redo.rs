@@ -0,0 +1,45 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn redo(dir: &std::path::Path) -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("smg");
+    cmd.args([
+        "redo",
+        "--url",
+        "mem://",
+        "--ns",
+        "test",
+        "--db",
+        "test",
+        "--dir",
+        dir.to_str().unwrap(),
+    ]);
+    cmd
+}
+
+#[test]
+fn redo_with_nothing_applied_fails() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    redo(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no applied migrations to redo"));
+}
+
+#[test]
+fn redo_dry_run_checks_the_named_migration_is_applied() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    redo(dir.path())
+        .args(["--dry-run", "001_users.surql"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "migration 001_users.surql is not applied",
+        ));
+}
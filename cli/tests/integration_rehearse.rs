@@ -0,0 +1,40 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn rehearse_reports_statements_that_fail_on_rerun() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("001_users.surql"),
+        "DEFINE TABLE IF NOT EXISTS user;\nDEFINE INDEX email ON user FIELDS email UNIQUE;\n",
+    )
+    .unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args(["rehearse", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "001_users.surql line 2: DEFINE INDEX email ON user FIELDS email UNIQUE",
+        ))
+        .stdout(predicate::str::contains("DEFINE TABLE").not())
+        .stderr(predicate::str::contains("1 statement(s) fail when re-run"));
+}
+
+#[test]
+fn rehearse_passes_idempotent_migrations() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("001_users.surql"),
+        "DEFINE TABLE IF NOT EXISTS user;\nDEFINE FIELD OVERWRITE email ON user TYPE string;\n",
+    )
+    .unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args(["rehearse", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("can be re-run"));
+}
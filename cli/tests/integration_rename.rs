@@ -0,0 +1,63 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn rename_moves_the_applied_record_along() {
+    let dir = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    let smg = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args(args)
+            .args(["--engine", "surrealkv", "--path"])
+            .arg(data.path().join("db"))
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+    smg(&["up"]).assert().success();
+
+    smg(&["rename", "001_users", "001_accounts"])
+        .write_stdin("n\n")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("aborted"));
+    assert!(dir.path().join("001_users.surql").exists());
+
+    smg(&["rename", "001_users", "001_accounts", "--yes"])
+        .assert()
+        .success();
+    assert!(dir.path().join("001_accounts.surql").exists());
+    assert!(!dir.path().join("001_users.surql").exists());
+
+    smg(&["up"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Applied migration").not());
+}
+
+#[test]
+fn rename_without_a_database_only_renames_the_migration() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("001_users")).unwrap();
+    fs::write(dir.path().join("001_users/up.surql"), "DEFINE TABLE user;").unwrap();
+
+    cargo_bin_cmd!("smg")
+        .env_remove("SURREAL_NS")
+        .env_remove("SURREAL_DB")
+        .args(["rename", "001_users", "001_accounts.surql", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("still record that name"));
+    assert!(dir.path().join("001_accounts/up.surql").exists());
+
+    cargo_bin_cmd!("smg")
+        .args(["rename", "001_accounts", "accounts", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must start with a version prefix"));
+}
@@ -0,0 +1,67 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn runs_lists_and_shows_past_runs() {
+    let dir = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(dir.path().join("002_posts.surql"), "DEFINE TABLE post;").unwrap();
+    let smg = |args: &[&str]| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args(args)
+            .args(["--engine", "surrealkv", "--path"])
+            .arg(data.path().join("db"))
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+
+    smg(&["up"]).assert().success();
+    smg(&["runs", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("DIRECTION"))
+        .stdout(predicate::str::contains("up"))
+        .stdout(predicate::str::contains("ok"));
+
+    let output = smg(&["runs", "list", "--format", "json"]).output().unwrap();
+    assert!(output.status.success());
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let runs = list["runs"].as_array().unwrap();
+    assert_eq!(runs.len(), 1);
+    let id = runs[0]["id"].as_str().unwrap().to_string();
+    let fingerprint = runs[0]["fingerprint"].as_str().unwrap().to_string();
+    assert!(!fingerprint.is_empty());
+
+    smg(&["runs", "show", &id])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&fingerprint))
+        .stdout(predicate::str::contains("001_users.surql"))
+        .stdout(predicate::str::contains("002_posts.surql"));
+
+    let output = smg(&["runs", "show", &id, "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(run["id"], id.as_str());
+    assert_eq!(run["direction"], "up");
+    assert_eq!(run["fingerprint"], fingerprint.as_str());
+    assert_eq!(run["error"], serde_json::Value::Null);
+    let names = run["migrations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["001_users.surql", "002_posts.surql"]);
+
+    smg(&["runs", "show", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no run nope is recorded"));
+}
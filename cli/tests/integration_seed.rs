@@ -0,0 +1,47 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn seed_scales_generated_data() {
+    let dir = tempdir().unwrap();
+    let path = dir.path();
+    fs::create_dir_all(path.join("migrations")).unwrap();
+    fs::create_dir_all(path.join("seeds")).unwrap();
+    fs::write(
+        path.join("migrations/001_users.surql"),
+        "DEFINE TABLE user;",
+    )
+    .unwrap();
+    fs::write(
+        path.join("seeds/001_users.surql"),
+        "FOR $i IN 1..=({{ seed.scale }} * 2) { CREATE user; };",
+    )
+    .unwrap();
+    fs::write(
+        path.join("seeds/002_check.surql"),
+        "IF count(SELECT * FROM user) != 20 { THROW 'expected 20 users'; };",
+    )
+    .unwrap();
+    let seed = |scale: &str| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.current_dir(path).args([
+            "seed", "--url", "mem://", "--ns", "test", "--db", "test", "--scale", scale,
+        ]);
+        cmd
+    };
+
+    seed("10x")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("seeded the database at scale 10x"));
+    seed("1x")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected 20 users"));
+    seed("lots")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a scale factor"));
+}
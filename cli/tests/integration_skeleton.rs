@@ -0,0 +1,48 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn generate_down_writes_a_skeleton_below_the_header() {
+    let dir = tempdir().unwrap();
+    let migration = dir.path().join("001_users");
+    fs::create_dir(&migration).unwrap();
+    fs::write(
+        migration.join("up.surql"),
+        "-- migration: users\nDEFINE TABLE user;\nDEFINE INDEX by_name ON post FIELDS name;",
+    )
+    .unwrap();
+    fs::write(migration.join("down.surql"), "-- migration: users\n").unwrap();
+    let generate = || {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args(["generate-down", "001_users", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+
+    generate().assert().success();
+    assert_eq!(
+        fs::read_to_string(migration.join("down.surql")).unwrap(),
+        "-- migration: users\nREMOVE INDEX by_name ON post;\nREMOVE TABLE user;\n"
+    );
+
+    generate()
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already has statements"));
+    generate().arg("--force").assert().success();
+}
+
+#[test]
+fn generate_down_refuses_single_file_migrations() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args(["generate-down", "001_users", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is a single-file migration"));
+}
@@ -0,0 +1,129 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn paired(dir: &std::path::Path, name: &str, up: &str, down: &str) {
+    let folder = dir.join(name);
+    fs::create_dir(&folder).unwrap();
+    fs::write(folder.join("up.surql"), up).unwrap();
+    fs::write(folder.join("down.surql"), down).unwrap();
+}
+
+#[test]
+fn squash_combines_a_range_and_archives_the_originals() {
+    let dir = tempdir().unwrap();
+    paired(
+        dir.path(),
+        "001_users",
+        "-- migration: users\nDEFINE TABLE user;",
+        "REMOVE TABLE user;",
+    );
+    paired(
+        dir.path(),
+        "002_posts",
+        "DEFINE TABLE post;",
+        "REMOVE TABLE post;",
+    );
+    paired(
+        dir.path(),
+        "003_tags",
+        "DEFINE TABLE tag;",
+        "REMOVE TABLE tag;",
+    );
+
+    cargo_bin_cmd!("smg")
+        .args(["squash", "--to", "002_posts", "--name", "baseline", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .success();
+
+    let squashed = dir.path().join("002_baseline");
+    let up = fs::read_to_string(squashed.join("up.surql")).unwrap();
+    assert!(up.contains("-- replaces: 001_users, 002_posts\n"), "{up}");
+    assert!(
+        up.find("DEFINE TABLE user;").unwrap() < up.find("DEFINE TABLE post;").unwrap(),
+        "{up}"
+    );
+    let down = fs::read_to_string(squashed.join("down.surql")).unwrap();
+    assert!(
+        down.find("REMOVE TABLE post;").unwrap() < down.find("REMOVE TABLE user;").unwrap(),
+        "{down}"
+    );
+    assert!(dir.path().join(".archive/001_users/up.surql").is_file());
+    assert!(dir.path().join(".archive/002_posts/up.surql").is_file());
+    assert!(dir.path().join("003_tags").is_dir());
+    let manifest =
+        fs::read_to_string(dir.path().join(".archive/002_baseline.manifest.json")).unwrap();
+    assert!(
+        manifest.contains("\"squashed\": \"002_baseline\""),
+        "{manifest}"
+    );
+
+    cargo_bin_cmd!("smg")
+        .args(["list", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("002_baseline"))
+        .stdout(predicate::str::contains("001_users").not());
+}
+
+#[test]
+fn squashed_migration_is_recorded_on_a_migrated_database() {
+    let dir = tempdir().unwrap();
+    let data = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(dir.path().join("002_posts.surql"), "DEFINE TABLE post;").unwrap();
+    let smg = |command: &str| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args([command, "--engine", "surrealkv", "--path"])
+            .arg(data.path().join("db"))
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+    smg("up").assert().success();
+
+    cargo_bin_cmd!("smg")
+        .args([
+            "squash",
+            "--from",
+            "001_users",
+            "--to",
+            "002_posts",
+            "--dir",
+        ])
+        .arg(dir.path())
+        .assert()
+        .success();
+    assert!(dir.path().join("002_squashed.surql").is_file());
+
+    smg("up")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Recorded squashed migration: 002_squashed.surql",
+        ))
+        .stdout(predicate::str::contains("Applied migration").not());
+    smg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("002_squashed.surql"))
+        .stdout(predicate::str::contains("001_users").not());
+}
+
+#[test]
+fn squash_needs_at_least_two_migrations() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args(["squash", "--to", "001_users", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the range must span at least two migrations",
+        ));
+}
@@ -0,0 +1,64 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use std::process::Command;
+use tempfile::tempdir;
+
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+#[test]
+fn project_stats_summarize_tree() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir(root.join("001_users")).unwrap();
+    fs::write(
+        root.join("001_users/up.surql"),
+        "-- author: alice\nDEFINE TABLE user;",
+    )
+    .unwrap();
+    fs::write(root.join("001_users/down.surql"), "REMOVE TABLE user;").unwrap();
+    fs::write(
+        root.join("002_posts.surql"),
+        "-- author: bob\nDEFINE TABLE post; DEFINE FIELD title ON post TYPE string;",
+    )
+    .unwrap();
+    fs::write(root.join("R_views.surql"), "DEFINE TABLE v;").unwrap();
+
+    git(root, &["init", "-q"]);
+    git(root, &["add", "."]);
+    git(root, &["commit", "-q", "-m", "init"]);
+
+    let mut cmd = cargo_bin_cmd!("smg");
+    cmd.args(["stats", "project", "--dir", root.to_str().unwrap()]);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("migrations:    3"))
+        .stdout(predicate::str::contains(
+            "file: 1, paired: 1, repeatable: 1",
+        ))
+        .stdout(predicate::str::contains("down coverage: 50.0%"))
+        .stdout(predicate::str::contains("alice"))
+        .stdout(predicate::str::contains("+3"));
+}
+
+#[test]
+fn project_stats_require_existing_dir() {
+    let dir = tempdir().unwrap();
+    let mut cmd = cargo_bin_cmd!("smg");
+    cmd.args([
+        "stats",
+        "project",
+        "--dir",
+        dir.path().join("missing").to_str().unwrap(),
+    ]);
+    cmd.assert().failure();
+    assert!(!dir.path().join("missing").exists());
+}
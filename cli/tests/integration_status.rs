@@ -0,0 +1,112 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn status(dir: &std::path::Path) -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("smg");
+    cmd.args([
+        "status",
+        "--url",
+        "mem://",
+        "--ns",
+        "test",
+        "--db",
+        "test",
+        "--dir",
+        dir.to_str().unwrap(),
+    ]);
+    cmd
+}
+
+#[test]
+fn status_lists_pending_migrations_as_a_table() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(dir.path().join("002_posts.surql"), "DEFINE TABLE post;").unwrap();
+
+    status(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "MIGRATION        STATUS   BATCH  APPLIED AT  CHECKSUM",
+        ))
+        .stdout(predicate::str::contains(
+            "001_users.surql  pending  -      -           -",
+        ))
+        .stdout(predicate::str::contains("ignored").not());
+}
+
+#[test]
+fn status_verbose_lists_ignored_entries() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(dir.path().join("init.surql"), "DEFINE TABLE post;").unwrap();
+
+    status(dir.path())
+        .arg("-v")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "init.surql: file name does not start with a digit",
+        ));
+}
+
+#[test]
+fn status_since_unknown_tag_fails() {
+    let dir = tempdir().unwrap();
+
+    status(dir.path())
+        .args(["--since-tag", "v1.0.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown tag: v1.0.0"));
+}
+
+#[test]
+fn status_prints_json() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    let output = status(dir.path())
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let migration = &status["migrations"][0];
+    assert_eq!(migration["name"], "001_users.surql");
+    assert_eq!(migration["kind"], "file");
+    assert_eq!(migration["applied"], false);
+    assert!(migration["applied_at"].is_null());
+    assert!(migration["checksum"].is_string());
+}
+
+#[test]
+fn status_detailed_counts_pending_statements() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("001_users.surql"),
+        "DEFINE TABLE user;\nDEFINE FIELD name ON user TYPE string;",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("002_backfill.surql"),
+        "UPDATE user SET name = 'anon' WHERE name = NONE;\nRELATE user:a->follows->user:b;",
+    )
+    .unwrap();
+
+    status(dir.path())
+        .arg("--detailed")
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::is_match(r"001_users.surql\s+pending\s+-\s+-\s+-\s+2 define\n")
+                .unwrap(),
+        )
+        .stdout(predicate::str::contains("1 update, 1 relate"))
+        .stdout(predicate::str::contains(
+            "pending: 2 define, 1 update, 1 relate (2 schema, 2 data)",
+        ));
+}
@@ -0,0 +1,161 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+fn up(dir: &std::path::Path) -> assert_cmd::Command {
+    let mut cmd = cargo_bin_cmd!("smg");
+    cmd.args([
+        "up",
+        "--url",
+        "mem://",
+        "--ns",
+        "test",
+        "--db",
+        "test",
+        "--dir",
+        dir.to_str().unwrap(),
+    ]);
+    cmd
+}
+
+#[test]
+fn up_applies_pending_migrations() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    up(dir.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Applied migration: 001_users.surql",
+        ));
+}
+
+#[test]
+fn up_reports_failing_migration() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_bad.surql"), "THROW 'boom';").unwrap();
+
+    up(dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("boom"));
+}
+
+#[test]
+fn up_fails_a_migration_exceeding_its_timeout() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_slow.surql"), "SLEEP 5s;").unwrap();
+
+    up(dir.path())
+        .args(["--migration-timeout", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "001_slow.surql: migration timeout of 1s exceeded",
+        ));
+}
+
+#[test]
+fn up_retries_a_refused_connection() {
+    let dir = tempdir().unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args([
+            "up",
+            "--url",
+            "ws://127.0.0.1:1",
+            "--ns",
+            "test",
+            "--db",
+            "test",
+        ])
+        .args(["--retries", "2", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("retrying in 200ms"))
+        .stdout(predicate::str::contains("retrying in 400ms"));
+}
+
+#[test]
+fn up_reads_the_connection_from_environment_variables() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    cargo_bin_cmd!("smg")
+        .env("SURREAL_URL", "mem://")
+        .env("SURREAL_NS", "test")
+        .env("SURREAL_DB", "test")
+        .args(["up", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Applied migration: 001_users.surql",
+        ));
+}
+
+#[test]
+fn up_prints_the_run_as_json() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(dir.path().join("002_bad.surql"), "THROW 'boom';").unwrap();
+
+    let output = up(dir.path()).args(["--format", "json"]).output().unwrap();
+    assert!(!output.status.success());
+
+    let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(run["direction"], "up");
+    assert!(run["error"].as_str().unwrap().contains("boom"));
+    let migrations = run["migrations"].as_array().unwrap();
+    assert_eq!(migrations[0]["name"], "001_users.surql");
+    assert_eq!(migrations[0]["status"], "applied");
+    assert!(migrations[0]["duration_ms"].is_u64());
+    assert_eq!(migrations[1]["status"], "failed");
+    assert!(migrations[1]["error"].as_str().unwrap().contains("boom"));
+}
+
+#[test]
+fn up_migrates_an_embedded_file_database() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    let data = dir.path().join("data");
+    let smg = |command: &str| {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args([command, "--engine", "surrealkv", "--path"])
+            .arg(&data)
+            .args(["--ns", "app", "--db", "app", "--dir"])
+            .arg(dir.path());
+        cmd
+    };
+
+    smg("up")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Applied migration: 001_users.surql",
+        ));
+    // The second process opens the same files and sees the applied record.
+    smg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("001_users.surql  applied"));
+}
+
+#[test]
+fn up_applies_migrations_in_parallel() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(dir.path().join("002_posts.surql"), "DEFINE TABLE post;").unwrap();
+    fs::write(
+        dir.path().join("003_authors.surql"),
+        "-- depends: 001_users, 002_posts\nDEFINE FIELD author ON post TYPE record<user>;",
+    )
+    .unwrap();
+
+    up(dir.path())
+        .args(["--parallel", "4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("applied 3 migration(s)"));
+}
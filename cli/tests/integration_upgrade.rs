@@ -0,0 +1,34 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn upgrade_advise_reports_files_needing_changes() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("001_search.surql"),
+        "DEFINE ANALYZER simple TOKENIZERS blank;\nDEFINE INDEX body ON post FIELDS body SEARCH ANALYZER simple BM25;",
+    )
+    .unwrap();
+    fs::write(dir.path().join("002_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args(["upgrade-advise", "--to", "3.0", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "001_search.surql line 2: `SEARCH ANALYZER`: search indexes were renamed to full-text indexes in 3.0; use `FULLTEXT ANALYZER`",
+        ))
+        .stdout(predicate::str::contains("002_users").not())
+        .stderr(predicate::str::contains(
+            "1 use(s) of syntax SurrealDB 3.0 no longer accepts",
+        ));
+
+    cargo_bin_cmd!("smg")
+        .args(["upgrade-advise", "--to", "2.2", "--dir"])
+        .arg(dir.path())
+        .assert()
+        .success();
+}
@@ -0,0 +1,127 @@
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn validate_reports_every_problem_without_a_database() {
+    let dir = tempdir().unwrap();
+    let path = dir.path();
+    fs::write(path.join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(path.join("002_posts.surql"), "DEFINE TABLE post;").unwrap();
+    fs::write(path.join("002_tags.surql"), "DEFINE TABLE tag;").unwrap();
+    fs::write(path.join("003_typo.surql"), "DEFINE TABEL oops;").unwrap();
+    fs::write(path.join("004_latin1.surql"), b"DEFINE TABLE caf\xe9;").unwrap();
+    fs::create_dir(path.join("005_pair")).unwrap();
+    fs::write(path.join("005_pair/up.surql"), "DEFINE TABLE pair;").unwrap();
+    fs::write(path.join("006_notes.sql"), "DEFINE TABLE note;").unwrap();
+    fs::write(
+        path.join("007_seed.surql"),
+        "CREATE user SET name = '{{ admin }}';",
+    )
+    .unwrap();
+    fs::write(path.join("init.surql"), "DEFINE TABLE init;").unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args(["validate", "--dir", path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "init.surql: file name does not start with a digit",
+        ))
+        .stdout(predicate::str::contains("003_typo.surql: syntax error"))
+        .stdout(predicate::str::contains(
+            "004_latin1.surql: not valid UTF-8",
+        ))
+        .stdout(predicate::str::contains("005_pair/down.surql: missing"))
+        .stdout(predicate::str::contains(
+            "006_notes.sql: file does not end in `.surql`",
+        ))
+        .stdout(predicate::str::contains(
+            "002_posts.surql, 002_tags.surql: share the version prefix 002",
+        ))
+        .stdout(predicate::str::contains("001_users").not())
+        .stdout(predicate::str::contains("007_seed").not())
+        .stderr(predicate::str::contains("6 problem(s) found"));
+}
+
+#[test]
+fn validate_passes_clean_migrations_and_checks_gaps_on_request() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(
+        dir.path().join("003_posts.surql"),
+        "DEFINE TABLE post;\nDEFINE FUNCTION fn::greet($name: string) { RETURN 'Hi ' + $name; };",
+    )
+    .unwrap();
+
+    cargo_bin_cmd!("smg")
+        .args(["validate", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("migrations are valid"));
+
+    cargo_bin_cmd!("smg")
+        .args(["validate", "--gaps", "--dir", dir.path().to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("2: no migration has this version"));
+}
+
+#[test]
+fn validate_reports_unsatisfiable_dependencies() {
+    let dir = tempdir().unwrap();
+    let path = dir.path();
+    let write = |name: &str, content: &str| fs::write(path.join(name), content).unwrap();
+    write("001_users.surql", "DEFINE TABLE user;");
+    write(
+        "002_posts.surql",
+        "-- depends-on: 001_users, 003_tags\nDEFINE TABLE post;",
+    );
+    write(
+        "003_tags.surql",
+        "-- depends-on: 009_gone\nDEFINE TABLE tag;",
+    );
+    write("004_a.surql", "-- depends-on: 005_b\nDEFINE TABLE a;");
+    write("005_b.surql", "-- depends-on: 004_a\nDEFINE TABLE b;");
+
+    cargo_bin_cmd!("smg")
+        .args(["validate", "--dir", path.to_str().unwrap()])
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains(
+            "004_a.surql, 005_b.surql: depend on each other in a cycle",
+        ))
+        .stdout(predicate::str::contains(
+            "002_posts.surql: depends on 003_tags, which comes after it",
+        ))
+        .stdout(predicate::str::contains(
+            "003_tags.surql: depends on 009_gone, which does not exist",
+        ))
+        .stderr(predicate::str::contains("3 problem(s) found"));
+}
+
+#[test]
+fn validate_rechecks_scripts_that_changed_since_the_cached_run() {
+    let dir = tempdir().unwrap();
+    let script = dir.path().join("001_users.surql");
+    fs::write(&script, "DEFINE TABLE user").unwrap();
+    fs::write(dir.path().join("002_posts.surql"), "DEFINE TABLEE post;").unwrap();
+    let validate = || {
+        let mut cmd = cargo_bin_cmd!("smg");
+        cmd.args(["validate", "--dir", dir.path().to_str().unwrap()]);
+        cmd
+    };
+
+    validate()
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("002_posts.surql: syntax error"));
+    assert!(dir.path().join(".smg-cache/validate.json").is_file());
+
+    fs::write(dir.path().join("002_posts.surql"), "DEFINE TABLE post;").unwrap();
+    validate()
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("migrations are valid"));
+}
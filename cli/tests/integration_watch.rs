@@ -0,0 +1,73 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{Receiver, channel};
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+/// Start `smg watch` on a throwaway database, returning the process and
+/// its stdout lines.
+fn watch(dir: &std::path::Path) -> (Child, Receiver<String>) {
+    let mut child = Command::new(assert_cmd::cargo::cargo_bin!("smg"))
+        .args([
+            "watch",
+            "--url",
+            "mem://",
+            "--ns",
+            "test",
+            "--db",
+            "test",
+            "--debounce",
+            "100",
+            "--dir",
+            dir.to_str().unwrap(),
+        ])
+        .env("NO_COLOR", "1")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    (child, rx)
+}
+
+/// Read lines until one contains `text`, failing after a timeout.
+fn wait_for(lines: &Receiver<String>, text: &str) {
+    let deadline = Instant::now() + Duration::from_secs(30);
+    let mut seen = Vec::new();
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        match lines.recv_timeout(left) {
+            Ok(line) if line.contains(text) => return,
+            Ok(line) => seen.push(line),
+            Err(_) => break,
+        }
+    }
+    panic!("no line containing {text:?}; got:\n{}", seen.join("\n"));
+}
+
+#[test]
+fn watch_applies_new_migrations_and_survives_failures() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+
+    let (mut child, lines) = watch(dir.path());
+    wait_for(&lines, "watching");
+    wait_for(&lines, "Applied migration: 001_users.surql");
+
+    std::fs::write(dir.path().join("002_bad.surql"), "THROW 'boom';").unwrap();
+    wait_for(&lines, "boom");
+    wait_for(&lines, "save it to try again");
+
+    std::fs::write(dir.path().join("002_bad.surql"), "DEFINE TABLE post;").unwrap();
+    wait_for(&lines, "Applied migration: 002_bad.surql");
+    wait_for(&lines, "database is up to date");
+
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
@@ -23,12 +23,13 @@ fn main() -> Result<()> {
         let kind = match m.kind {
             MigrationKind::File => "file (up-only)",
             MigrationKind::Paired => "paired (up/down)",
+            MigrationKind::Repeatable => "repeatable (re-applied on change)",
         };
         println!("- {}: {}", m.name, kind);
     }
 
     // Inspect the first migration's contents (if any).
-    if let Some(first) = migrations.get(0) {
+    if let Some(first) = migrations.first() {
         let up = src.get_up(first)?;
         println!(
             "\nFirst migration (`{}`) up.sql length: {} bytes",
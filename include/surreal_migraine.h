@@ -0,0 +1,29 @@
+/* C interface to surreal-migraine. Build with `--features capi`. */
+#ifndef SURREAL_MIGRAINE_H
+#define SURREAL_MIGRAINE_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Apply pending migrations. Returns NULL on success or an error message
+ * that must be released with migraine_free_string(). `username` and
+ * `password` may be NULL. */
+char *migraine_run_up(const char *url, const char *namespace_, const char *database,
+                      const char *username, const char *password,
+                      const char *migrations_dir);
+
+/* Return {"migrations": [...]} or {"error": "..."} as JSON. Never NULL;
+ * release with migraine_free_string(). */
+char *migraine_status_json(const char *url, const char *namespace_, const char *database,
+                           const char *username, const char *password,
+                           const char *migrations_dir);
+
+/* Release a string returned by this library. NULL is ignored. */
+void migraine_free_string(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* SURREAL_MIGRAINE_H */
@@ -0,0 +1,174 @@
+//! Procedural macros for `surreal-migraine`. Use them through the
+//! library's `macros` feature rather than depending on this crate.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use syn::{LitStr, parse_macro_input};
+
+/// Embed a migrations directory into the binary as an
+/// `EmbeddedSource<'static>`, checking it at compile time.
+///
+/// The path is relative to the crate's `Cargo.toml`. Compilation fails,
+/// listing every problem, when an entry is not a migration, a name lacks a
+/// version prefix, a paired folder misses `up.surql` or `down.surql`, a
+/// script is not UTF-8 or not valid SurrealQL (scripts with
+/// `{{placeholders}}` are not parsed), or two migrations share a version.
+/// Hidden entries and `migrations.lock` are skipped.
+///
+/// ```rust,ignore
+/// use surreal_migraine::embed_migrations;
+/// use surreal_migraine::types::EmbeddedSource;
+///
+/// static MIGRATIONS: EmbeddedSource<'static> = embed_migrations!("migrations");
+/// ```
+///
+/// Scripts are embedded with `include_bytes!`, so editing one rebuilds the
+/// crate; adding or removing a migration needs a `cargo clean -p` or a
+/// touched source file until Cargo can track directories for macros.
+#[proc_macro]
+pub fn embed_migrations(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    let root = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let dir = root.join(literal.value());
+
+    let embedded = match embed(&dir) {
+        Ok(embedded) => embedded,
+        Err(problems) => {
+            let message = format!(
+                "{} problem(s) in {}:\n  {}",
+                problems.len(),
+                dir.display(),
+                problems.join("\n  ")
+            );
+            return syn::Error::new(literal.span(), message)
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    quote! {{
+        static DIR: ::surreal_migraine::__macros::Dir<'static> = #embedded;
+        ::surreal_migraine::types::EmbeddedSource::new(&DIR)
+    }}
+    .into()
+}
+
+/// A `Dir` expression holding the migrations in `dir`, or every problem
+/// found in them.
+fn embed(dir: &Path) -> Result<TokenStream2, Vec<String>> {
+    let entries = std::fs::read_dir(dir)
+        .and_then(|entries| sorted(entries.map(|e| e.map(|e| e.path()))))
+        .map_err(|e| vec![format!("cannot read the directory: {e}")])?;
+
+    let mut problems = Vec::new();
+    let mut versions = BTreeMap::<u64, Vec<String>>::new();
+    let mut tokens = Vec::new();
+    for path in entries {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            problems.push(format!("{}: name is not valid UTF-8", path.display()));
+            continue;
+        };
+        if name.starts_with('.') || name == "migrations.lock" {
+            continue;
+        }
+        let versioned = name.starts_with(|c: char| c.is_ascii_digit());
+        if path.is_dir() {
+            if !versioned {
+                problems.push(format!(
+                    "{name}: directory name does not start with a digit"
+                ));
+                continue;
+            }
+            let mut files = Vec::new();
+            for script in ["up.surql", "down.surql"] {
+                match check(&path.join(script)) {
+                    Ok(file) => files.push(file(&format!("{name}/{script}"))),
+                    Err(problem) => problems.push(format!("{name}/{script}: {problem}")),
+                }
+            }
+            tokens.push(quote! {
+                ::surreal_migraine::__macros::DirEntry::Dir(
+                    ::surreal_migraine::__macros::Dir::new(#name, &[#(#files),*])
+                )
+            });
+        } else {
+            if !versioned && !name.starts_with("R_") {
+                problems.push(format!(
+                    "{name}: file name does not start with a digit or `R_`"
+                ));
+                continue;
+            }
+            match check(&path) {
+                Ok(file) => tokens.push(file(name)),
+                Err(problem) => problems.push(format!("{name}: {problem}")),
+            }
+        }
+        if versioned {
+            match version(name) {
+                Some(version) => versions.entry(version).or_default().push(name.to_string()),
+                None => problems.push(format!("{name}: name is not `<version>_<description>`")),
+            }
+        }
+    }
+
+    for (version, names) in versions {
+        if names.len() > 1 {
+            problems.push(format!(
+                "{}: share the version prefix {version}",
+                names.join(", ")
+            ));
+        }
+    }
+    if !problems.is_empty() {
+        return Err(problems);
+    }
+    Ok(quote! {
+        ::surreal_migraine::__macros::Dir::new("", &[#(#tokens),*])
+    })
+}
+
+/// Check the script at `path`, returning a builder for its `DirEntry`
+/// under a given relative name.
+fn check(path: &Path) -> Result<impl Fn(&str) -> TokenStream2, String> {
+    if !path.is_file() {
+        return Err("missing".to_string());
+    }
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let content = String::from_utf8(bytes).map_err(|_| "not valid UTF-8".to_string())?;
+    if !content.contains("{{") {
+        surrealdb::sql::parse(&content).map_err(|e| format!("syntax error: {e}"))?;
+    }
+
+    let absolute = path
+        .canonicalize()
+        .map_err(|e| e.to_string())?
+        .to_string_lossy()
+        .into_owned();
+    Ok(move |name: &str| {
+        quote! {
+            ::surreal_migraine::__macros::DirEntry::File(
+                ::surreal_migraine::__macros::File::new(#name, include_bytes!(#absolute))
+            )
+        }
+    })
+}
+
+/// The numeric version of a versioned name (`003_users` gives `3`).
+fn version(name: &str) -> Option<u64> {
+    let (digits, _) = name.split_once('_')?;
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+fn sorted(paths: impl Iterator<Item = std::io::Result<PathBuf>>) -> std::io::Result<Vec<PathBuf>> {
+    let mut paths = paths.collect::<std::io::Result<Vec<_>>>()?;
+    paths.sort();
+    Ok(paths)
+}
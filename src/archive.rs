@@ -0,0 +1,168 @@
+use crate::types::{IgnoredEntry, Listing, Migration, MigrationSource};
+use eyre::{Result, eyre};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+/// Migrations read from a `.tar`, `.tar.gz` or `.zip` bundle, e.g. a
+/// versioned release artifact, without unpacking it to disk.
+///
+/// The bundle holds a migrations directory as on disk, either at its root
+/// or inside a single top-level folder (as `tar czf bundle.tgz migrations`
+/// produces). The format is detected from the content, not the file name.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use surreal_migraine::archive::ArchiveSource;
+///
+/// let source = ArchiveSource::open("artifacts/migrations-1.4.0.tar.gz")?;
+/// MigrationRunner::new(&db, source).up().await?;
+///
+/// static BUNDLE: &[u8] = include_bytes!("../migrations.zip");
+/// let source = ArchiveSource::from_bytes(BUNDLE)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ArchiveSource {
+    listing: Listing,
+}
+
+impl ArchiveSource {
+    /// Read the bundle at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes =
+            std::fs::read(path).map_err(|e| eyre!("cannot read {}: {e}", path.display()))?;
+        Self::from_bytes(&bytes).map_err(|e| eyre!("{}: {e}", path.display()))
+    }
+
+    /// Read a bundle held in memory.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let files = if bytes.starts_with(b"PK\x03\x04") {
+            zip_files(bytes)?
+        } else if bytes.starts_with(&[0x1f, 0x8b]) {
+            tar_files(flate2::read::GzDecoder::new(bytes))?
+        } else {
+            tar_files(bytes)?
+        };
+        let files = strip_root(files);
+
+        let mut entries = files
+            .keys()
+            .map(|path| match path.split_once('/') {
+                Some((dir, _)) => (dir.to_string(), true),
+                None => (path.clone(), false),
+            })
+            .collect::<Vec<_>>();
+        entries.sort();
+        entries.dedup();
+
+        let text = |path: &str| -> Result<Option<String>> {
+            files
+                .get(path)
+                .map(|bytes| {
+                    String::from_utf8(bytes.clone()).map_err(|_| eyre!("{path} is not valid UTF-8"))
+                })
+                .transpose()
+        };
+        let mut scripts = BTreeMap::new();
+        for (name, is_dir) in &entries {
+            let (up, down) = if *is_dir {
+                (
+                    text(&format!("{name}/up.surql"))?,
+                    text(&format!("{name}/down.surql"))?,
+                )
+            } else {
+                (text(name)?, None)
+            };
+            if let Some(up) = up {
+                scripts.insert(name.clone(), (up, down));
+            }
+        }
+        Ok(Self {
+            listing: Listing::new(entries, scripts),
+        })
+    }
+}
+
+/// The regular files of a tar archive, keyed by path.
+fn tar_files(reader: impl Read) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut files = BTreeMap::new();
+    for entry in archive
+        .entries()
+        .map_err(|e| eyre!("not a tar archive: {e}"))?
+    {
+        let mut entry = entry.map_err(|e| eyre!("corrupt tar archive: {e}"))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry.path()?.to_string_lossy().into_owned();
+        let mut bytes = Vec::new();
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|e| eyre!("cannot read {path}: {e}"))?;
+        files.insert(normalize(&path), bytes);
+    }
+    Ok(files)
+}
+
+/// The files of a zip archive, keyed by path.
+fn zip_files(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| eyre!("corrupt zip archive: {e}"))?;
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| eyre!("corrupt zip archive: {e}"))?;
+        if file.is_dir() {
+            continue;
+        }
+        let path = file.name().to_string();
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)
+            .map_err(|e| eyre!("cannot read {path}: {e}"))?;
+        files.insert(normalize(&path), bytes);
+    }
+    Ok(files)
+}
+
+fn normalize(path: &str) -> String {
+    path.trim_start_matches("./").to_string()
+}
+
+/// Drop the single top-level folder every file is in, unless that folder
+/// is itself a migration.
+fn strip_root(files: BTreeMap<String, Vec<u8>>) -> BTreeMap<String, Vec<u8>> {
+    let root = |path: &str| path.split_once('/').map(|(root, _)| root.to_string());
+    let Some(Some(first)) = files.keys().next().map(|path| root(path)) else {
+        return files;
+    };
+    let shared = files.keys().all(|path| root(path).as_ref() == Some(&first));
+    if !shared || first.starts_with(|c: char| c.is_ascii_digit()) {
+        return files;
+    }
+    files
+        .into_iter()
+        .map(|(path, bytes)| (path[first.len() + 1..].to_string(), bytes))
+        .collect()
+}
+
+impl MigrationSource for ArchiveSource {
+    fn list(&self) -> Result<Vec<Migration>> {
+        self.listing.list()
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        self.listing.get_up(migration)
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        self.listing.get_down(migration)
+    }
+
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        self.listing.ignored()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::placeholders;
+use crate::types::{DiskSource, MigrationKind, MigrationSource};
+use crate::validate::numeric_prefix;
+use eyre::{Result, eyre};
+use std::path::Path;
+
+/// Validate the migrations directory `dir` from a `build.rs`, so broken
+/// migrations fail `cargo build` instead of a deploy.
+///
+/// Checks what `smg validate` checks: entries that are not migrations,
+/// `<version>_<description>` naming, paired folders missing a script,
+/// non-UTF-8 scripts, SurrealQL syntax (scripts with `{{placeholders}}`
+/// are not parsed), versions shared by two migrations and unsatisfiable
+/// `depends-on` headers. Prints
+/// `cargo:rerun-if-changed` for `dir`, so the check runs again whenever a
+/// migration changes. The error lists every problem found.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // build.rs
+/// fn main() -> eyre::Result<()> {
+///     surreal_migraine::build::validate_migrations("migrations")
+/// }
+/// ```
+pub fn validate_migrations(dir: impl AsRef<Path>) -> Result<()> {
+    let dir = dir.as_ref();
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    let problems = problems(dir)?;
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(eyre!(
+        "{} problem(s) in {}:\n  {}",
+        problems.len(),
+        dir.display(),
+        problems.join("\n  ")
+    ))
+}
+
+/// One `<entry>: <message>` line per problem in `dir`.
+fn problems(dir: &Path) -> Result<Vec<String>> {
+    if !dir.is_dir() {
+        eyre::bail!("migrations directory {} does not exist", dir.display());
+    }
+    let source = DiskSource::new(dir);
+    let mut problems = source
+        .ignored()?
+        .into_iter()
+        .map(|ignored| format!("{}: {}", ignored.name, ignored.reason))
+        .collect::<Vec<_>>();
+
+    for migration in source.list()? {
+        let name = migration.name.as_str();
+        if migration.kind != MigrationKind::Repeatable && numeric_prefix(name).is_none() {
+            problems.push(format!("{name}: name is not `<version>_<description>`"));
+        }
+
+        let scripts = match migration.kind {
+            MigrationKind::File | MigrationKind::Repeatable => {
+                vec![(name.to_string(), dir.join(name))]
+            }
+            MigrationKind::Paired => ["up.surql", "down.surql"]
+                .into_iter()
+                .map(|file| (format!("{name}/{file}"), dir.join(name).join(file)))
+                .collect(),
+        };
+        for (script, path) in scripts {
+            if !path.is_file() {
+                problems.push(format!("{script}: missing"));
+                continue;
+            }
+            let Ok(content) = String::from_utf8(std::fs::read(&path)?) else {
+                problems.push(format!("{script}: not valid UTF-8"));
+                continue;
+            };
+            if !placeholders::names(&content).is_empty() {
+                continue;
+            }
+            if let Err(e) = surrealdb::sql::parse(&content) {
+                problems.push(format!("{script}: syntax error: {e}"));
+            }
+        }
+    }
+
+    let report = source.validate(false)?;
+    for duplicate in report.duplicates {
+        problems.push(format!(
+            "{}: share the version prefix {}",
+            duplicate.names.join(", "),
+            duplicate.prefix
+        ));
+    }
+    problems.extend(report.dependencies.iter().map(|p| p.to_string()));
+    Ok(problems)
+}
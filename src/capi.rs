@@ -0,0 +1,162 @@
+//! C ABI for embedding the runner in non-Rust tooling.
+//!
+//! Build a shared or static library with:
+//!
+//! ```text
+//! cargo rustc -p surreal-migraine --release --features capi --crate-type cdylib
+//! cargo rustc -p surreal-migraine --release --features capi --crate-type staticlib
+//! ```
+//!
+//! The matching declarations live in `include/surreal_migraine.h`. Every
+//! string returned by this module is owned by the caller and must be
+//! released with [`migraine_free_string`].
+use crate::MigrationRunner;
+use crate::connect::{ConnectOptions, connect};
+use crate::types::DiskSource;
+use eyre::{Result, eyre};
+use serde_json::json;
+use std::ffi::{CStr, CString, c_char};
+use std::future::Future;
+
+/// Read an optional UTF-8 string argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn opt_arg(ptr: *const c_char, name: &str) -> Result<Option<String>> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    // SAFETY: the caller guarantees `ptr` is a valid NUL-terminated string.
+    let s = unsafe { CStr::from_ptr(ptr) }
+        .to_str()
+        .map_err(|_| eyre!("`{name}` is not valid UTF-8"))?;
+    Ok(Some(s.to_owned()))
+}
+
+/// Read a required UTF-8 string argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string.
+unsafe fn arg(ptr: *const c_char, name: &str) -> Result<String> {
+    unsafe { opt_arg(ptr, name) }?.ok_or_else(|| eyre!("`{name}` must not be null"))
+}
+
+/// Collect the shared connection arguments.
+///
+/// # Safety
+///
+/// Every pointer must be null or point to a NUL-terminated string.
+unsafe fn options(
+    url: *const c_char,
+    namespace: *const c_char,
+    database: *const c_char,
+    username: *const c_char,
+    password: *const c_char,
+) -> Result<ConnectOptions> {
+    unsafe {
+        Ok(ConnectOptions {
+            url: arg(url, "url")?,
+            namespace: arg(namespace, "namespace")?,
+            database: arg(database, "database")?,
+            username: opt_arg(username, "username")?,
+            password: opt_arg(password, "password")?,
+        })
+    }
+}
+
+fn block_on<T>(f: impl Future<Output = Result<T>>) -> Result<T> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(f)
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    CString::new(s.replace('\0', ""))
+        .unwrap_or_default()
+        .into_raw()
+}
+
+/// Apply all pending migrations from `migrations_dir`.
+///
+/// Returns null on success, or an error message on failure.
+///
+/// # Safety
+///
+/// `url`, `namespace`, `database` and `migrations_dir` must point to
+/// NUL-terminated strings. `username` and `password` may be null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn migraine_run_up(
+    url: *const c_char,
+    namespace: *const c_char,
+    database: *const c_char,
+    username: *const c_char,
+    password: *const c_char,
+    migrations_dir: *const c_char,
+) -> *mut c_char {
+    let result = unsafe { options(url, namespace, database, username, password) }
+        .and_then(|opts| Ok((opts, unsafe { arg(migrations_dir, "migrations_dir") }?)))
+        .and_then(|(opts, dir)| {
+            block_on(async move {
+                let db = connect(&opts).await?;
+                MigrationRunner::new(&db, DiskSource::new(dir)).up().await
+            })
+        });
+
+    match result {
+        Ok(_) => std::ptr::null_mut(),
+        Err(e) => into_c_string(format!("{e:#}")),
+    }
+}
+
+/// Describe every migration in `migrations_dir` as JSON.
+///
+/// Returns `{"migrations": [...]}` on success or `{"error": "..."}` on
+/// failure. The result is never null.
+///
+/// # Safety
+///
+/// `url`, `namespace`, `database` and `migrations_dir` must point to
+/// NUL-terminated strings. `username` and `password` may be null.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn migraine_status_json(
+    url: *const c_char,
+    namespace: *const c_char,
+    database: *const c_char,
+    username: *const c_char,
+    password: *const c_char,
+    migrations_dir: *const c_char,
+) -> *mut c_char {
+    let result = unsafe { options(url, namespace, database, username, password) }
+        .and_then(|opts| Ok((opts, unsafe { arg(migrations_dir, "migrations_dir") }?)))
+        .and_then(|(opts, dir)| {
+            block_on(async move {
+                let db = connect(&opts).await?;
+                MigrationRunner::new(&db, DiskSource::new(dir))
+                    .status()
+                    .await
+            })
+        });
+
+    let value = match result {
+        Ok(statuses) => json!({ "migrations": statuses }),
+        Err(e) => json!({ "error": format!("{e:#}") }),
+    };
+    into_c_string(value.to_string())
+}
+
+/// Release a string returned by this library. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `s` must be null or a pointer previously returned by this library that
+/// has not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn migraine_free_string(s: *mut c_char) {
+    if !s.is_null() {
+        // SAFETY: `s` was produced by `CString::into_raw` in this module.
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
@@ -0,0 +1,131 @@
+use crate::metadata::MigrationMetadata;
+use crate::types::{Migration, MigrationKind, MigrationSource};
+use eyre::{Result, eyre};
+use serde::{Deserialize, Serialize};
+use surrealdb::{Connection, Surreal};
+
+/// Migrations stored as records of a SurrealDB table, so a control-plane
+/// service can keep one catalog database and apply its migrations to many
+/// tenant databases. Read once by [`CatalogSource::fetch`] and served from
+/// memory afterwards.
+///
+/// Each record holds a migration's `name`, `kind` (`file`, `paired` or
+/// `repeatable`), `up` and `down` scripts and its `position` in apply
+/// order. [`CatalogSource::publish`] writes a catalog from any other
+/// source.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use surreal_migraine::catalog::CatalogSource;
+/// use surreal_migraine::types::DiskSource;
+///
+/// // In the release pipeline:
+/// CatalogSource::publish(&catalog, "migration_catalog", &DiskSource::new("migrations")).await?;
+///
+/// // In the control plane, for every tenant:
+/// let source = CatalogSource::fetch(&catalog, "migration_catalog").await?;
+/// MigrationRunner::new(&tenant, source).up().await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CatalogSource {
+    entries: Vec<CatalogEntry>,
+}
+
+/// A migration record in a catalog table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CatalogEntry {
+    name: String,
+    kind: MigrationKind,
+    up: String,
+    down: Option<String>,
+    position: usize,
+}
+
+impl CatalogSource {
+    /// Read every migration in the catalog `table` of `db`.
+    pub async fn fetch<C: Connection>(db: &Surreal<C>, table: &str) -> Result<Self> {
+        let mut response = db
+            .query(
+                "SELECT name, kind, up, down, position FROM type::table($table) ORDER BY position",
+            )
+            .bind(("table", table.to_string()))
+            .await
+            .map_err(|e| eyre!(e.to_string()))?;
+        let entries: Vec<serde_json::Value> = response
+            .take(0)
+            .map_err(|e| eyre!("cannot read the catalog {table}: {e}"))?;
+        let entries = entries
+            .into_iter()
+            .map(serde_json::from_value)
+            .collect::<serde_json::Result<_>>()
+            .map_err(|e| eyre!("malformed record in the catalog {table}: {e}"))?;
+        Ok(Self { entries })
+    }
+
+    /// Replace the contents of the catalog `table` of `db` with the
+    /// migrations of `source`, in one transaction.
+    pub async fn publish<C: Connection, S: MigrationSource + ?Sized>(
+        db: &Surreal<C>,
+        table: &str,
+        source: &S,
+    ) -> Result<()> {
+        let records = source
+            .list()?
+            .into_iter()
+            .enumerate()
+            .map(|(position, migration)| {
+                let entry = CatalogEntry {
+                    up: source.get_up(&migration)?,
+                    down: source.get_down(&migration)?,
+                    name: migration.name,
+                    kind: migration.kind,
+                    position,
+                };
+                Ok(serde_json::to_value(entry)?)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        db.query(
+            "BEGIN TRANSACTION;
+             DELETE $table;
+             INSERT INTO $table $records RETURN NONE;
+             COMMIT TRANSACTION;",
+        )
+        .bind(("table", surrealdb::sql::Table::from(table)))
+        .bind(("records", records))
+        .await
+        .map_err(|e| eyre!(e.to_string()))?
+        .check()
+        .map_err(|e| eyre!(e.to_string()))?;
+        Ok(())
+    }
+
+    fn get(&self, migration: &Migration) -> Result<&CatalogEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == migration.name)
+            .ok_or_else(|| eyre!("migration `{}` not found", migration.name))
+    }
+}
+
+impl MigrationSource for CatalogSource {
+    fn list(&self) -> Result<Vec<Migration>> {
+        Ok(self
+            .entries
+            .iter()
+            .map(|entry| Migration {
+                name: entry.name.clone(),
+                kind: entry.kind.clone(),
+                metadata: MigrationMetadata::parse(&entry.up),
+            })
+            .collect())
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        Ok(self.get(migration)?.up.clone())
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        Ok(self.get(migration)?.down.clone())
+    }
+}
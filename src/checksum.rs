@@ -0,0 +1,125 @@
+use sha2::{Digest, Sha256, Sha512};
+
+/// Name of the algorithm used by `checksum` and by the runner unless
+/// configured otherwise.
+pub const DEFAULT_ALGORITHM: &str = "sha256";
+
+/// A checksum algorithm for migration content.
+///
+/// The runner records `algorithm()` next to every checksum, so records
+/// written with one algorithm are still verified correctly after switching
+/// to another. Implement this trait to plug in a mandated backend, such as
+/// a FIPS-validated library.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::checksum::{Hasher, Sha512Hasher};
+///
+/// assert_eq!(Sha512Hasher.algorithm(), "sha512");
+/// assert_eq!(Sha512Hasher.checksum("DEFINE TABLE user;").len(), 128);
+/// ```
+pub trait Hasher: Send + Sync {
+    /// Stable name recorded with each checksum, e.g. `sha256`.
+    fn algorithm(&self) -> &str;
+
+    /// Hash `content`.
+    fn digest(&self, content: &[u8]) -> Vec<u8>;
+
+    /// Hex-encoded digest of `content`.
+    fn checksum(&self, content: &str) -> String {
+        to_hex(&self.digest(content.as_bytes()))
+    }
+}
+
+/// SHA-256, the default.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256Hasher;
+
+impl Hasher for Sha256Hasher {
+    fn algorithm(&self) -> &str {
+        DEFAULT_ALGORITHM
+    }
+
+    fn digest(&self, content: &[u8]) -> Vec<u8> {
+        Sha256::digest(content).to_vec()
+    }
+}
+
+/// SHA-512.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha512Hasher;
+
+impl Hasher for Sha512Hasher {
+    fn algorithm(&self) -> &str {
+        "sha512"
+    }
+
+    fn digest(&self, content: &[u8]) -> Vec<u8> {
+        Sha512::digest(content).to_vec()
+    }
+}
+
+/// BLAKE3, available with the `blake3` feature.
+#[cfg(feature = "blake3")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Blake3Hasher;
+
+#[cfg(feature = "blake3")]
+impl Hasher for Blake3Hasher {
+    fn algorithm(&self) -> &str {
+        "blake3"
+    }
+
+    fn digest(&self, content: &[u8]) -> Vec<u8> {
+        blake3::hash(content).as_bytes().to_vec()
+    }
+}
+
+/// Look up a built-in hasher by its recorded algorithm name.
+pub fn builtin(algorithm: &str) -> Option<Box<dyn Hasher>> {
+    match algorithm {
+        DEFAULT_ALGORITHM => Some(Box::new(Sha256Hasher)),
+        "sha512" => Some(Box::new(Sha512Hasher)),
+        #[cfg(feature = "blake3")]
+        "blake3" => Some(Box::new(Blake3Hasher)),
+        _ => None,
+    }
+}
+
+/// Compute the hex-encoded SHA-256 checksum of a migration's content.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::checksum::checksum;
+///
+/// let sum = checksum("DEFINE TABLE user;");
+/// assert_eq!(sum.len(), 64);
+/// ```
+pub fn checksum(content: &str) -> String {
+    to_hex(&Sha256::digest(content.as_bytes()))
+}
+
+/// Compute a fingerprint identifying an ordered set of migrations.
+///
+/// Each item is a `(name, content)` pair. The fingerprint changes whenever
+/// a migration is added, removed, reordered, or edited, which makes it
+/// suitable for identifying the exact plan a run executed.
+pub fn fingerprint<'a, I>(items: I) -> String
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    let mut hasher = Sha256::new();
+    for (name, content) in items {
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update(checksum(content).as_bytes());
+        hasher.update([b'\n']);
+    }
+    to_hex(&hasher.finalize())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,87 @@
+use crate::retry::RetryPolicy;
+use eyre::{Result, eyre};
+use surrealdb::Surreal;
+use surrealdb::engine::any::{self, Any};
+use surrealdb::opt::auth::Root;
+
+/// Connection settings for a SurrealDB endpoint.
+///
+/// `url` accepts any scheme supported by the enabled SurrealDB engines,
+/// for example `ws://localhost:8000` or `http://localhost:8000`.
+/// Credentials are optional; when both are set the connection signs in as
+/// a root user before selecting the namespace and database.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    /// Endpoint URL (`ws://`, `wss://`, `http://`, `https://`).
+    pub url: String,
+    /// Namespace to select after connecting.
+    pub namespace: String,
+    /// Database to select after connecting.
+    pub database: String,
+    /// Root username.
+    pub username: Option<String>,
+    /// Root password.
+    pub password: Option<String>,
+}
+
+/// Connect to the endpoint described by `options` and select its
+/// namespace and database.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use surreal_migraine::connect::{ConnectOptions, connect};
+///
+/// let db = connect(&ConnectOptions {
+///     url: "ws://localhost:8000".into(),
+///     namespace: "app".into(),
+///     database: "app".into(),
+///     username: Some("root".into()),
+///     password: Some("root".into()),
+/// })
+/// .await?;
+/// ```
+pub async fn connect(options: &ConnectOptions) -> Result<Surreal<Any>> {
+    let db = any::connect(options.url.as_str())
+        .await
+        .map_err(|e| eyre!("failed to connect to {}: {e}", options.url))?;
+
+    if let (Some(username), Some(password)) = (&options.username, &options.password) {
+        db.signin(Root { username, password })
+            .await
+            .map_err(|e| eyre!("failed to sign in as {username}: {e}"))?;
+    }
+
+    db.use_ns(&options.namespace)
+        .use_db(&options.database)
+        .await
+        .map_err(|e| eyre!(e.to_string()))?;
+
+    tracing::debug!(url = %options.url, ns = %options.namespace, db = %options.database, "connected");
+    Ok(db)
+}
+
+/// Like [`connect`], retrying transient failures (a refused or reset
+/// connection, a timeout) according to `policy`.
+///
+/// Useful when the database may still be starting, as when a deploy starts
+/// the application and SurrealDB at the same time.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use surreal_migraine::connect::connect_with_retry;
+/// use surreal_migraine::retry::RetryPolicy;
+///
+/// let db = connect_with_retry(&options, &RetryPolicy::new(10)).await?;
+/// ```
+pub async fn connect_with_retry(
+    options: &ConnectOptions,
+    policy: &RetryPolicy,
+) -> Result<Surreal<Any>> {
+    policy
+        .run(&format!("connecting to {}", options.url), || {
+            connect(options)
+        })
+        .await
+}
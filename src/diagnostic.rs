@@ -0,0 +1,88 @@
+use crate::statements::Statement;
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use std::fmt;
+
+/// A statement that failed while running a migration in
+/// `ExecutionMode::PerStatement`.
+///
+/// The error carries the whole migration script and a span over the
+/// failing statement, so rendering it with a `miette` report handler shows
+/// the offending SQL underlined in context. It converts into an
+/// `eyre::Report`; recover it with `downcast_ref::<StatementError>()`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use surreal_migraine::diagnostic::StatementError;
+///
+/// if let Err(report) = runner.up().await
+///     && let Some(diagnostic) = report.downcast_ref::<StatementError>()
+/// {
+///     eprintln!("{:?}", miette::Report::new(diagnostic.clone()));
+/// }
+/// ```
+#[derive(Debug, Clone, Diagnostic)]
+#[diagnostic(code(surreal_migraine::statement_failed))]
+pub struct StatementError {
+    /// The migration the statement belongs to.
+    pub migration: String,
+    /// 1-based position of the statement within the migration.
+    pub index: usize,
+    /// Number of statements in the migration.
+    pub total: usize,
+    /// 1-based line on which the statement starts.
+    pub line: usize,
+    /// The database error.
+    pub error: String,
+    /// The migration script.
+    #[source_code]
+    pub source: NamedSource<String>,
+    /// The failing statement within `source`.
+    #[label("{error}")]
+    pub span: SourceSpan,
+    /// What to do next.
+    #[help]
+    pub help: Option<String>,
+}
+
+impl StatementError {
+    /// Describe the failure of `statement`, the `index`-th of `total`
+    /// statements in migration `migration` whose script is `script`.
+    pub fn new(
+        migration: &str,
+        script: &str,
+        statement: &Statement<'_>,
+        index: usize,
+        total: usize,
+        error: String,
+    ) -> Self {
+        let help = (index > 1).then(|| {
+            format!(
+                "statements 1-{} already ran; fix this statement and undo or skip the earlier ones before retrying",
+                index - 1
+            )
+        });
+        Self {
+            migration: migration.to_owned(),
+            index,
+            total,
+            line: statement.line,
+            error,
+            source: NamedSource::new(migration, script.to_owned()),
+            span: (statement.offset, statement.text.len()).into(),
+            help,
+        }
+    }
+}
+
+impl fmt::Display for StatementError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: statement {}/{} (line {}) failed: {}",
+            self.migration, self.index, self.total, self.line, self.error
+        )
+    }
+}
+
+impl std::error::Error for StatementError {}
@@ -0,0 +1,21 @@
+use similar::TextDiff;
+
+/// Render a unified diff between the SQL recorded when `name` was applied
+/// and its current content.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::diff::unified;
+///
+/// let diff = unified("001_init", "DEFINE TABLE user;\n", "DEFINE TABLE users;\n");
+/// assert!(diff.contains("-DEFINE TABLE user;"));
+/// assert!(diff.contains("+DEFINE TABLE users;"));
+/// ```
+pub fn unified(name: &str, applied: &str, current: &str) -> String {
+    TextDiff::from_lines(applied, current)
+        .unified_diff()
+        .context_radius(3)
+        .header(&format!("{name} (applied)"), &format!("{name} (current)"))
+        .to_string()
+}
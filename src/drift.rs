@@ -0,0 +1,126 @@
+use eyre::{Result, eyre};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A difference between the recorded migration history and the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    /// The migration the drift concerns.
+    pub name: String,
+    /// What drifted.
+    pub kind: DriftKind,
+}
+
+/// The kinds of drift `up()` detects before applying anything.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DriftKind {
+    /// An applied migration was edited; carries a unified diff when the
+    /// applied SQL was recorded.
+    ChecksumMismatch {
+        /// Unified diff from the applied to the current SQL.
+        diff: Option<String>,
+    },
+    /// An applied migration no longer exists in the source.
+    MissingSource,
+}
+
+/// How to handle one `Drift` item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    /// Leave history as it is and continue.
+    Accept,
+    /// Rewrite history to match the source: re-record the checksum of an
+    /// edited migration, or forget a migration missing from the source.
+    Repair,
+    /// Stop the run.
+    Abort,
+}
+
+impl std::str::FromStr for Resolution {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "accept" => Ok(Self::Accept),
+            "repair" => Ok(Self::Repair),
+            "abort" => Ok(Self::Abort),
+            other => Err(eyre!(
+                "unknown resolution {other:?}; expected accept, repair or abort"
+            )),
+        }
+    }
+}
+
+/// Decides how each drift item found by `up()` is handled.
+///
+/// Implemented for closures, so an interactive prompt can be plugged in
+/// directly, and by `ResolutionFile` for automation.
+pub trait DriftResolver {
+    /// Choose a resolution for `drift`.
+    fn resolve(&self, drift: &Drift) -> Resolution;
+}
+
+impl<F: Fn(&Drift) -> Resolution> DriftResolver for F {
+    fn resolve(&self, drift: &Drift) -> Resolution {
+        self(drift)
+    }
+}
+
+/// Pre-recorded resolutions, one per migration name.
+///
+/// The format is one `<migration> = <accept|repair|abort>` entry per line;
+/// blank lines and lines starting with `#` are ignored. Drift on migrations
+/// without an entry resolves to `Abort`.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::drift::{Drift, DriftKind, DriftResolver, Resolution, ResolutionFile};
+///
+/// let file = ResolutionFile::parse("# reviewed in PR 42\n003_users = repair\n").unwrap();
+/// let drift = Drift { name: "003_users".into(), kind: DriftKind::MissingSource };
+/// assert_eq!(file.resolve(&drift), Resolution::Repair);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResolutionFile {
+    entries: HashMap<String, Resolution>,
+}
+
+impl ResolutionFile {
+    /// Parse resolution entries from `content`.
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut entries = HashMap::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, resolution) = line.split_once('=').ok_or_else(|| {
+                eyre!("line {}: expected `<migration> = <resolution>`", index + 1)
+            })?;
+            let resolution = resolution
+                .trim()
+                .parse()
+                .map_err(|e| eyre!("line {}: {e}", index + 1))?;
+            entries.insert(name.trim().to_owned(), resolution);
+        }
+        Ok(Self { entries })
+    }
+
+    /// Read and parse the resolution file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| eyre!("failed to read {}: {e}", path.display()))?;
+        Self::parse(&content)
+    }
+}
+
+impl DriftResolver for ResolutionFile {
+    fn resolve(&self, drift: &Drift) -> Resolution {
+        self.entries
+            .get(&drift.name)
+            .copied()
+            .unwrap_or(Resolution::Abort)
+    }
+}
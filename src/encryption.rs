@@ -0,0 +1,178 @@
+use crate::metadata::{MigrationMetadata, SENSITIVE};
+use crate::types::{IgnoredEntry, Migration, MigrationSource};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use eyre::{Result, eyre};
+
+/// Environment variable [`EncryptedSource::from_env`] reads the key from,
+/// base64-encoded.
+pub const KEY_VAR: &str = "SMG_ENCRYPTION_KEY";
+
+/// First line of an encrypted script. The base64-encoded nonce and
+/// ciphertext follow on the next line.
+pub const HEADER: &str = "-- smg:encrypted aes-256-gcm";
+
+/// Length of the nonce prefixed to the ciphertext.
+const NONCE_LEN: usize = 12;
+
+/// Wraps another source and decrypts the scripts [`encrypt`] produced, so
+/// migrations holding secrets (seed credentials, API keys) can be committed
+/// encrypted. Scripts that do not start with [`HEADER`] are passed through,
+/// so only the sensitive migrations need to be encrypted.
+///
+/// Checksums and metadata are those of the decrypted script, and encrypted
+/// migrations carry the [`SENSITIVE`] flag so the runner never records
+/// their decrypted content. Listing decrypts the encrypted up scripts to
+/// read their headers, so a missing or wrong key fails `list()` unless
+/// every up script is plaintext.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::encryption::{EncryptedSource, encrypt};
+/// use surreal_migraine::types::{InMemorySource, MigrationSource};
+///
+/// let key = [7u8; 32];
+/// let inner = InMemorySource::new().with_migration(
+///     "001_seed",
+///     encrypt("CREATE user:admin SET password = 'hunter2';", &key),
+///     None,
+/// );
+/// let source = EncryptedSource::new(inner, &key);
+/// let migration = &source.list().unwrap()[0];
+/// assert_eq!(
+///     source.get_up(migration).unwrap(),
+///     "CREATE user:admin SET password = 'hunter2';"
+/// );
+/// ```
+#[derive(Clone)]
+pub struct EncryptedSource<S> {
+    inner: S,
+    key: Option<Key<Aes256Gcm>>,
+}
+
+impl<S: std::fmt::Debug> std::fmt::Debug for EncryptedSource<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedSource")
+            .field("inner", &self.inner)
+            .field("key", &self.key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl<S: MigrationSource> EncryptedSource<S> {
+    /// Decrypt the scripts of `inner` with `key`.
+    pub fn new(inner: S, key: &[u8; 32]) -> Self {
+        Self {
+            inner,
+            key: Some((*key).into()),
+        }
+    }
+
+    /// Decrypt the scripts of `inner` with the key in [`KEY_VAR`], if set.
+    pub fn from_env(inner: S) -> Result<Self> {
+        let key = match std::env::var(KEY_VAR) {
+            Ok(encoded) => Some(parse_key(&encoded)?.into()),
+            Err(_) => None,
+        };
+        Ok(Self { inner, key })
+    }
+
+    fn decrypt(&self, migration: &Migration, script: String) -> Result<String> {
+        let Some(encoded) = script.strip_prefix(HEADER) else {
+            return Ok(script);
+        };
+        let key = self
+            .key
+            .as_ref()
+            .ok_or_else(|| eyre!("{} is encrypted; set {KEY_VAR} to apply it", migration.name))?;
+        let bytes = STANDARD
+            .decode(encoded.trim())
+            .map_err(|e| eyre!("{} is not valid encrypted content: {e}", migration.name))?;
+        if bytes.len() < NONCE_LEN {
+            eyre::bail!("{} is not valid encrypted content", migration.name);
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plaintext = Aes256Gcm::new(key)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                eyre!(
+                    "cannot decrypt {}: wrong key or corrupt content",
+                    migration.name
+                )
+            })?;
+        String::from_utf8(plaintext)
+            .map_err(|_| eyre!("{} decrypts to invalid UTF-8", migration.name))
+    }
+}
+
+/// Encrypt `script` with `key` into the format [`EncryptedSource`]
+/// decrypts, using a random nonce.
+pub fn encrypt(script: &str, key: &[u8; 32]) -> String {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = Aes256Gcm::new(&(*key).into())
+        .encrypt(&nonce, script.as_bytes())
+        .expect("AES-GCM encryption of an in-memory buffer does not fail");
+    let mut bytes = nonce.to_vec();
+    bytes.extend(ciphertext);
+    format!("{HEADER}\n{}\n", STANDARD.encode(bytes))
+}
+
+/// A new random key, base64-encoded as [`KEY_VAR`] expects.
+pub fn generate_key() -> String {
+    STANDARD.encode(Aes256Gcm::generate_key(&mut OsRng))
+}
+
+/// Decode a base64-encoded 256-bit key.
+pub fn parse_key(encoded: &str) -> Result<[u8; 32]> {
+    let bytes = STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| eyre!("{KEY_VAR} is not valid base64: {e}"))?;
+    bytes
+        .try_into()
+        .map_err(|bytes: Vec<u8>| eyre!("{KEY_VAR} must be 32 bytes, got {}", bytes.len()))
+}
+
+impl<S: MigrationSource> MigrationSource for EncryptedSource<S> {
+    fn list(&self) -> Result<Vec<Migration>> {
+        self.inner
+            .list()?
+            .into_iter()
+            .map(|mut migration| {
+                let up = self.inner.get_up(&migration)?;
+                let up_encrypted = up.starts_with(HEADER);
+                if up_encrypted {
+                    // The inner source parsed the header of the ciphertext.
+                    migration.metadata = MigrationMetadata::parse(&self.decrypt(&migration, up)?);
+                }
+                // A down script that cannot be read is left for `get_down()`
+                // to report, as without the wrapper.
+                let down_encrypted = matches!(
+                    self.inner.get_down(&migration),
+                    Ok(Some(down)) if down.starts_with(HEADER)
+                );
+                if (up_encrypted || down_encrypted) && !migration.metadata.sensitive() {
+                    migration.metadata.flags.push(SENSITIVE.to_string());
+                }
+                Ok(migration)
+            })
+            .collect()
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        self.decrypt(migration, self.inner.get_up(migration)?)
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        self.inner
+            .get_down(migration)?
+            .map(|down| self.decrypt(migration, down))
+            .transpose()
+    }
+
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        self.inner.ignored()
+    }
+}
@@ -0,0 +1,170 @@
+use crate::types::{IgnoredEntry, Listing, Migration, MigrationSource};
+use eyre::{Result, eyre};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+/// Migrations read from a directory of a git repository at a given ref (a
+/// tag, branch or commit), without checking it out. Everything is read
+/// once, when the source is created, and served from memory afterwards.
+///
+/// [`GitSource::fetch`] fetches the ref from a remote into a local bare
+/// repository first; [`GitSource::open`] reads a repository already on
+/// disk. Both run the `git` executable, which must be on `PATH`, with the
+/// user's git configuration and credentials.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use surreal_migraine::git::GitSource;
+///
+/// let source = GitSource::fetch(
+///     "https://github.com/acme/app.git",
+///     "v1.4.0",
+///     "migrations",
+///     "/var/cache/smg/app.git",
+/// )?;
+/// tracing::info!(commit = source.commit(), "applying migrations");
+/// MigrationRunner::new(&db, source).up().await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct GitSource {
+    commit: String,
+    listing: Listing,
+}
+
+impl GitSource {
+    /// Fetch `reference` from the repository at `url` into the bare
+    /// repository `cache` (created if missing, reused afterwards) and read
+    /// the migrations in its `dir`.
+    pub fn fetch(url: &str, reference: &str, dir: &str, cache: impl AsRef<Path>) -> Result<Self> {
+        option_like("url", url)?;
+        option_like("reference", reference)?;
+        let cache = cache.as_ref();
+        if !cache.join("HEAD").is_file() {
+            std::fs::create_dir_all(cache)?;
+            git(cache, &["init", "--quiet", "--bare"])?;
+        }
+        git(
+            cache,
+            &[
+                "fetch", "--quiet", "--force", "--depth", "1", "--", url, reference,
+            ],
+        )?;
+        Self::open(cache, "FETCH_HEAD", dir)
+    }
+
+    /// Read the migrations in `dir` of the repository at `repo` as of
+    /// `reference`.
+    pub fn open(repo: impl AsRef<Path>, reference: &str, dir: &str) -> Result<Self> {
+        option_like("reference", reference)?;
+        let repo = repo.as_ref();
+        let commit = git(
+            repo,
+            &[
+                "rev-parse",
+                "--verify",
+                "--end-of-options",
+                &format!("{reference}^{{commit}}"),
+            ],
+        )?
+        .trim()
+        .to_string();
+        let dir = dir.trim_matches('/');
+        let tree = if dir.is_empty() {
+            commit.clone()
+        } else {
+            format!("{commit}:{dir}")
+        };
+
+        let entries = tree_entries(repo, &tree)?;
+        let mut scripts = BTreeMap::new();
+        for (name, is_dir) in &entries {
+            let path = format!("{tree}/{name}");
+            if *is_dir {
+                let files = tree_entries(repo, &path)?;
+                let read = |file: &str| -> Result<Option<String>> {
+                    if !files.iter().any(|(name, is_dir)| name == file && !is_dir) {
+                        return Ok(None);
+                    }
+                    git(repo, &["cat-file", "blob", &format!("{path}/{file}")]).map(Some)
+                };
+                if let Some(up) = read("up.surql")? {
+                    scripts.insert(name.clone(), (up, read("down.surql")?));
+                }
+            } else {
+                let up = git(repo, &["cat-file", "blob", &path])?;
+                scripts.insert(name.clone(), (up, None));
+            }
+        }
+
+        Ok(Self {
+            commit,
+            listing: Listing::new(entries, scripts),
+        })
+    }
+
+    /// The commit the migrations were read from.
+    pub fn commit(&self) -> &str {
+        &self.commit
+    }
+}
+
+/// Refuse a `what` that git would take for an option, such as a reference
+/// of `--upload-pack=…`.
+fn option_like(what: &str, value: &str) -> Result<()> {
+    if value.starts_with('-') {
+        eyre::bail!("invalid git {what} {value:?}: it must not start with `-`");
+    }
+    Ok(())
+}
+
+/// The `(name, is_dir)` entries of the tree `tree`, sorted by name.
+fn tree_entries(repo: &Path, tree: &str) -> Result<Vec<(String, bool)>> {
+    let listing = git(repo, &["ls-tree", "-z", tree])?;
+    let mut entries = listing
+        .split('\0')
+        .filter_map(|line| {
+            let (info, name) = line.split_once('\t')?;
+            Some((name.to_string(), info.split(' ').nth(1) == Some("tree")))
+        })
+        .collect::<Vec<_>>();
+    entries.sort();
+    Ok(entries)
+}
+
+/// Run `git` with `args` in `repo`, returning its standard output.
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()
+        .map_err(|e| eyre!("cannot run git: {e}"))?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    String::from_utf8(output.stdout).map_err(|_| eyre!("git {} output is not UTF-8", args[0]))
+}
+
+impl MigrationSource for GitSource {
+    fn list(&self) -> Result<Vec<Migration>> {
+        self.listing.list()
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        self.listing.get_up(migration)
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        self.listing.get_down(migration)
+    }
+
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        self.listing.ignored()
+    }
+}
@@ -0,0 +1,77 @@
+//! Scripts and commands run around migration runs.
+//!
+//! Hooks registered with `MigrationRunner::with_hook` run before and after
+//! every `up()` and `down` run that has migrations to execute, and before
+//! and after each migration of it, e.g. to pause change feeds during a
+//! deploy and warm caches after it. A hook that fails fails the run, but
+//! never marks a migration dirty.
+//!
+//! SurrealQL hooks run in the migrated database with these parameters, and
+//! shell commands get them as environment variables:
+//!
+//! - `$hook` / `SMG_HOOK`: `before-all`, `after-all`, `before-each` or
+//!   `after-each`
+//! - `$direction` / `SMG_DIRECTION`: `up` or `down`
+//! - `$migration` / `SMG_MIGRATION`: the migration, for the `each` hooks
+//! - `$outcome` / `SMG_OUTCOME`: `succeeded` or `failed`, for `after-all`
+
+use eyre::{Result, eyre};
+
+/// When a hook runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookPoint {
+    /// Before the first migration of a run.
+    BeforeAll,
+    /// After the last migration of a run, whether or not the run failed,
+    /// once the `BeforeAll` hooks succeeded.
+    AfterAll,
+    /// Before each migration.
+    BeforeEach,
+    /// After each migration that succeeded.
+    AfterEach,
+}
+
+impl std::fmt::Display for HookPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HookPoint::BeforeAll => "before-all",
+            HookPoint::AfterAll => "after-all",
+            HookPoint::BeforeEach => "before-each",
+            HookPoint::AfterEach => "after-each",
+        })
+    }
+}
+
+/// What a hook runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Hook {
+    /// A SurrealQL script, run in the migrated database.
+    Sql(String),
+    /// A shell command, run with `sh -c` (`cmd /C` on Windows) from the
+    /// current directory, inheriting its output.
+    Command(String),
+}
+
+/// Run `command` with the `env` variables set, failing when it exits
+/// unsuccessfully.
+pub(crate) async fn run_command(command: &str, env: Vec<(&'static str, String)>) -> Result<()> {
+    let line = command.to_owned();
+    let status = tokio::task::spawn_blocking(move || {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        std::process::Command::new(shell)
+            .arg(flag)
+            .arg(&line)
+            .envs(env)
+            .status()
+    })
+    .await?
+    .map_err(|e| eyre!("cannot run `{command}`: {e}"))?;
+    if !status.success() {
+        eyre::bail!("`{command}` exited with {status}");
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use crate::checksum;
+use crate::lockfile::{LOCK_FILE, LockFile};
+use crate::metadata::MigrationMetadata;
+use crate::types::{Migration, MigrationKind, MigrationSource, REPEATABLE_PREFIX};
+use eyre::{Result, eyre};
+
+/// Migrations published over HTTP(S), e.g. as build artifacts on an
+/// internal server, fetched once by [`HttpSource::fetch`] and served from
+/// memory afterwards.
+///
+/// The base URL must serve the directory's `migrations.lock` (see
+/// `lockfile::LockFile`, written by `smg lock`) as the manifest, next to
+/// the migrations laid out as on disk: `<name>.surql` for single-file and
+/// repeatable migrations, `<name>/up.surql` and optionally
+/// `<name>/down.surql` for paired ones. Every up script must match its
+/// checksum in the manifest, so a partial or tampered upload fails the
+/// fetch instead of the run.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use surreal_migraine::http::HttpSource;
+///
+/// let source = HttpSource::fetch(
+///     "https://artifacts.example.com/app/1.4.0/migrations/",
+///     Some("Bearer s3cr3t"),
+/// )
+/// .await?;
+/// MigrationRunner::new(&db, source).up().await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpSource {
+    /// Migrations in manifest order, with their up and down scripts.
+    migrations: Vec<(Migration, String, Option<String>)>,
+}
+
+impl HttpSource {
+    /// Fetch the manifest and every migration under `base_url`, sending
+    /// `authorization`, when given, as the `Authorization` header.
+    pub async fn fetch(base_url: &str, authorization: Option<&str>) -> Result<Self> {
+        let base = format!("{}/", base_url.trim_end_matches('/'));
+        let client = reqwest::Client::new();
+        let get = |path: String| {
+            let mut request = client.get(format!("{base}{path}"));
+            if let Some(value) = authorization {
+                request = request.header(reqwest::header::AUTHORIZATION, value);
+            }
+            async move {
+                let response = request.send().await.map_err(|e| eyre!("GET {path}: {e}"))?;
+                if response.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Ok(None);
+                }
+                let response = response
+                    .error_for_status()
+                    .map_err(|e| eyre!("GET {path}: {e}"))?;
+                let text = response
+                    .text()
+                    .await
+                    .map_err(|e| eyre!("GET {path}: {e}"))?;
+                Ok::<_, eyre::Report>(Some(text))
+            }
+        };
+
+        let manifest = get(LOCK_FILE.to_string())
+            .await?
+            .ok_or_else(|| eyre!("{base}{LOCK_FILE} not found"))?;
+        let lock = LockFile::parse(&manifest)?;
+        let hasher = checksum::builtin(&lock.algorithm)
+            .ok_or_else(|| eyre!("unknown checksum algorithm {}", lock.algorithm))?;
+
+        let mut migrations = Vec::new();
+        for entry in lock.entries {
+            let name = entry.name;
+            let kind = if name.starts_with(REPEATABLE_PREFIX) {
+                MigrationKind::Repeatable
+            } else if name.ends_with(".surql") {
+                MigrationKind::File
+            } else {
+                MigrationKind::Paired
+            };
+            let (up, down) = match kind {
+                MigrationKind::Paired => (
+                    get(format!("{name}/up.surql")).await?,
+                    get(format!("{name}/down.surql")).await?,
+                ),
+                _ => (get(name.clone()).await?, None),
+            };
+            let up = up.ok_or_else(|| eyre!("{name} is in the manifest but not found"))?;
+            if hasher.checksum(&up) != entry.checksum {
+                eyre::bail!("{name} does not match its checksum in the manifest");
+            }
+            let migration = Migration {
+                name,
+                kind,
+                metadata: MigrationMetadata::parse(&up),
+            };
+            migrations.push((migration, up, down));
+        }
+        Ok(Self { migrations })
+    }
+
+    fn get(&self, migration: &Migration) -> Result<&(Migration, String, Option<String>)> {
+        self.migrations
+            .iter()
+            .find(|(m, _, _)| m.name == migration.name)
+            .ok_or_else(|| eyre!("migration `{}` not found", migration.name))
+    }
+}
+
+impl MigrationSource for HttpSource {
+    fn list(&self) -> Result<Vec<Migration>> {
+        Ok(self.migrations.iter().map(|(m, _, _)| m.clone()).collect())
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        Ok(self.get(migration)?.1.clone())
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        Ok(self.get(migration)?.2.clone())
+    }
+}
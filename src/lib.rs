@@ -1,10 +1,91 @@
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "runner")]
+pub mod build;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "runner")]
+pub mod catalog;
+pub mod checksum;
+#[cfg(feature = "remote")]
+pub mod connect;
+#[cfg(feature = "miette")]
+pub mod diagnostic;
+pub mod diff;
+pub mod drift;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(feature = "git")]
+pub mod git;
+#[cfg(feature = "runner")]
+pub mod hooks;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod lint;
+pub mod lockfile;
+pub mod metadata;
+#[cfg(feature = "object-store")]
+pub mod object_store;
+pub mod ordering;
+pub mod placeholders;
+pub mod plan;
+#[cfg(feature = "remote")]
+pub mod regions;
+#[cfg(feature = "runner")]
+pub mod retry;
+#[cfg(feature = "runner")]
+pub mod schema;
+pub mod skeleton;
+pub mod squash;
+pub mod statements;
+#[cfg(feature = "runner")]
+mod telemetry;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod types;
+pub mod upgrade;
+pub mod validate;
 
+#[cfg(feature = "runner")]
 mod migrations_impl {
-    use crate::types::{MigrationRecord, MigrationSource};
+    use crate::checksum::{self, Hasher, Sha256Hasher, fingerprint};
+    use crate::diff;
+    use crate::drift::{Drift, DriftKind, DriftResolver, Resolution};
+    use crate::hooks::{self, Hook, HookPoint};
+    use crate::lockfile::LockFile;
+    use crate::metadata::MigrationMetadata;
+    use crate::ordering::{self, Ordering};
+    use crate::placeholders::{self, DB_PARAM_PREFIX, SEED_SCALE};
+    use crate::plan::{PlannedMigration, ReleasePlan};
+    use crate::retry::RetryPolicy;
+    use crate::statements::{self, StatementKind};
+    use crate::telemetry;
+    use crate::types::{
+        CancelToken, ChecksumMismatch, DirtyState, ExecutionMode, LockOptions, Migration,
+        MigrationEvent, MigrationKind, MigrationOutcome, MigrationRecord, MigrationReport,
+        MigrationSource, MigrationStatus, OutOfOrderPolicy, OutcomeStatus, ProgressEvent,
+        ProgressListener, RunDirection, RunReport, Tag, Tenant, TenantFailurePolicy, TenantFilter,
+        TenantOutcome, UsePolicy,
+    };
     use eyre::{Result, eyre};
+    use futures_util::stream::{FuturesUnordered, StreamExt};
     use serde_json::json;
-    use surrealdb::Surreal;
+    use std::collections::{BTreeSet, HashMap, HashSet};
+    use std::future::Future;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+    use surrealdb::{RecordId, Surreal};
+    use tokio::sync::OnceCell;
+    use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+    use tracing::{Instrument, Span};
+
+    tokio::task_local! {
+        /// Tenant database the running `in_tenant()` work migrates instead
+        /// of the client's session, selected per query with `USE`. Scoped to
+        /// that future, so dropping it or running something else alongside
+        /// never leaves another call pointed at the tenant.
+        static TENANT: Tenant;
+    }
 
     /// A simple migration runner for SurrealDB.
     ///
@@ -39,6 +120,81 @@ mod migrations_impl {
         pub db: &'a Surreal<E>,
         /// Migration discovery/source implementation (filesystem, embedded, etc.).
         pub source: S,
+        /// Advisory lock settings; `None` disables locking.
+        lock: Option<LockOptions>,
+        /// How migration scripts are submitted to the database.
+        execution: ExecutionMode,
+        /// What to do with pending migrations that sort before applied ones.
+        out_of_order: OutOfOrderPolicy,
+        /// Tables this runner may touch; `None` allows every table.
+        owned_tables: Option<HashSet<String>>,
+        /// What to do with `USE` statements in scripts.
+        use_policy: UsePolicy,
+        /// Whether `up()` refuses to run when applied migrations were edited.
+        verify_checksums: bool,
+        /// Whether `fresh()` may wipe the database.
+        allow_fresh: bool,
+        /// Last migration covered by the existing schema; it and everything
+        /// before it are recorded without executing.
+        baseline: Option<String>,
+        /// Decides how drift found by `up()` is handled.
+        resolver: Option<Box<dyn DriftResolver + Send + Sync + 'a>>,
+        /// Values for `{{name}}` placeholders; `None` disables substitution.
+        variables: Option<HashMap<String, String>>,
+        /// Algorithm used for new checksums.
+        hasher: Box<dyn Hasher + 'a>,
+        /// Active environment; migrations limited to others are skipped.
+        environment: Option<String>,
+        /// Whether a failed migration's down script runs to clean up.
+        cleanup_on_failure: bool,
+        /// Custom order for the source's migrations.
+        ordering: Option<Box<dyn Ordering + 'a>>,
+        /// Cancels the run when triggered.
+        cancel: Option<CancelToken>,
+        /// How long a single migration may execute.
+        migration_timeout: Option<Duration>,
+        /// How long a whole run may execute.
+        run_timeout: Option<Duration>,
+        /// When the current run's `run_timeout` expires.
+        deadline: Mutex<Option<Instant>>,
+        /// Batch of the migrations the current run applies, assigned when
+        /// it records the first one.
+        batch: Mutex<Option<u64>>,
+        /// How many migrations `up()` may execute at once.
+        parallelism: usize,
+        /// Serializes recording applied migrations, which numbers them.
+        recording: tokio::sync::Mutex<()>,
+        /// Retries of read-only queries and idempotent migrations.
+        retry: Option<RetryPolicy>,
+        /// Value of the `{{ seed.scale }}` placeholder.
+        seed_scale: u32,
+        /// Scripts and commands run around runs and migrations, in the
+        /// order they were added.
+        hooks: Vec<(HookPoint, Hook)>,
+        /// Receives progress events of runs.
+        progress: Option<Box<dyn ProgressListener + Send + Sync + 'a>>,
+        /// Values of `{{ db.param.* }}` placeholders, keyed by placeholder name.
+        db_params: Mutex<HashMap<String, String>>,
+        /// Warnings raised by the current run, for its `MigrationReport`.
+        warnings: Mutex<Vec<String>>,
+        /// Receives the events of the run started by `up_with_events()`.
+        events: Mutex<Option<UnboundedSender<MigrationEvent>>>,
+        /// Table recording applied migrations.
+        table: String,
+        /// Namespace and database holding the bookkeeping tables; `None`
+        /// keeps them in the migrated database.
+        bookkeeping: Option<(String, String)>,
+        /// Change-management reference recorded with every run.
+        change_ref: Option<String>,
+        /// Id of the report the current run persisted, once it has.
+        last_run: Mutex<Option<RecordId>>,
+        /// Lock file the source must match before migrations are applied.
+        lock_file: Option<LockFile>,
+        /// Tenant databases seen by `ensure_tenant()`, each set once it has
+        /// been brought up to date.
+        tenants: Mutex<HashMap<Tenant, Arc<OnceCell<()>>>>,
+        /// Serializes tenant runs, which share the runner's per-run state.
+        tenant_runs: tokio::sync::Mutex<()>,
     }
 
     impl<'a, E: surrealdb::Connection, S: MigrationSource> MigrationRunner<'a, E, S> {
@@ -57,199 +213,3593 @@ mod migrations_impl {
         /// let runner = MigrationRunner::new(&db, src);
         /// ```
         pub fn new(db: &'a Surreal<E>, source: S) -> Self {
-            Self { db, source }
+            Self {
+                db,
+                source,
+                lock: None,
+                execution: ExecutionMode::default(),
+                out_of_order: OutOfOrderPolicy::default(),
+                owned_tables: None,
+                use_policy: UsePolicy::default(),
+                verify_checksums: false,
+                allow_fresh: false,
+                baseline: None,
+                resolver: None,
+                variables: None,
+                hasher: Box::new(Sha256Hasher),
+                environment: None,
+                cleanup_on_failure: false,
+                ordering: None,
+                cancel: None,
+                migration_timeout: None,
+                run_timeout: None,
+                deadline: Mutex::new(None),
+                batch: Mutex::new(None),
+                parallelism: 1,
+                recording: tokio::sync::Mutex::const_new(()),
+                retry: None,
+                seed_scale: 1,
+                hooks: Vec::new(),
+                progress: None,
+                db_params: Mutex::new(HashMap::new()),
+                warnings: Mutex::new(Vec::new()),
+                events: Mutex::new(None),
+                table: "migrations".to_string(),
+                bookkeeping: None,
+                change_ref: None,
+                last_run: Mutex::new(None),
+                lock_file: None,
+                tenants: Mutex::new(HashMap::new()),
+                tenant_runs: tokio::sync::Mutex::const_new(()),
+            }
         }
 
-        /// Run all pending migrations discovered by the configured
-        /// `MigrationSource`.
+        /// Record applied migrations in `table` instead of `migrations`.
         ///
-        /// This method ensures the `migrations` table exists, discovers
-        /// available migrations, filters out ones already recorded in the
-        /// database, and executes each migration inside a transaction. On
-        /// success each migration is recorded in the `migrations` table.
+        /// Useful when `migrations` is taken by the application's own
+        /// schema. The run, tag, state and lock tables keep their names.
+        /// The name must be a plain identifier (letters, digits and `_`).
         ///
-        /// # Example
+        /// # Examples
         ///
         /// ```rust,ignore
-        /// # async fn run_example(runner: &MigrationRunner<'_, _, _>) -> eyre::Result<()> {
-        /// runner.up().await?;
-        /// # Ok(())
-        /// # }
+        /// let runner = MigrationRunner::new(&db, src).with_table("schema_history");
         /// ```
-        pub async fn up(&self) -> Result<()> {
-            self.ensure_migrations_table_exists().await?;
+        pub fn with_table(mut self, table: impl Into<String>) -> Self {
+            self.table = table.into();
+            self
+        }
 
-            let migrations = self.source.list()?;
+        /// Keep the bookkeeping tables (applied migrations, runs, tags, the
+        /// dirty marker and the lock) in namespace `ns`, database `db`
+        /// instead of the migrated database.
+        ///
+        /// The session stays on the migrated database: each bookkeeping
+        /// query switches with a `USE` that only lasts for that query. This
+        /// keeps application databases free of tooling tables. Give every
+        /// migrated database its own bookkeeping database (or table, see
+        /// `with_table()`), as runs, tags and the lock are shared by all
+        /// runners using one.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// let runner = MigrationRunner::new(&db, src).with_bookkeeping("ops", "migrations_app_x");
+        /// ```
+        pub fn with_bookkeeping(mut self, ns: impl Into<String>, db: impl Into<String>) -> Self {
+            self.bookkeeping = Some((ns.into(), db.into()));
+            self
+        }
 
-            let applied = self.get_applied_migrations().await?;
+        /// Record `change_ref` (a change-management ticket such as
+        /// `JIRA-123`) with every run, so `runs()` shows which approved
+        /// change applied or reverted each migration.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// let runner = MigrationRunner::new(&db, src).with_change_ref("CHG-4711");
+        /// ```
+        pub fn with_change_ref(mut self, change_ref: impl Into<String>) -> Self {
+            self.change_ref = Some(change_ref.into());
+            self
+        }
 
-            let migrations_to_run: Vec<_> = migrations
-                .into_iter()
-                .filter(|m| !applied.contains(&m.name))
-                .collect();
+        /// Stop runs when `token` is cancelled.
+        ///
+        /// No further migrations start once the token fires, and the query
+        /// in flight is abandoned. SurrealDB cannot cancel a running query
+        /// over WebSocket, so the server may still finish that statement;
+        /// over HTTP the dropped request aborts it. Either way the
+        /// interrupted migration is marked failed and the database dirty,
+        /// and cleanup scripts are not run.
+        pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+            self.cancel = Some(token);
+            self
+        }
 
-            for migration in migrations_to_run {
-                // If the migration is a directory, look for `up.surql` inside it.
-                let content = self.source.get_up(&migration)?;
+        /// Fail a migration that executes for longer than `timeout`, e.g.
+        /// because the connection hung or a statement scans a huge table.
+        ///
+        /// The migration is abandoned like a cancelled one: it is marked
+        /// failed and the database dirty, the server may still finish the
+        /// query, and cleanup scripts are not run.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// let runner = MigrationRunner::new(&db, src)
+        ///     .with_migration_timeout(Duration::from_secs(60))
+        ///     .with_run_timeout(Duration::from_secs(600));
+        /// ```
+        pub fn with_migration_timeout(mut self, timeout: Duration) -> Self {
+            self.migration_timeout = Some(timeout);
+            self
+        }
 
-                let tx_sql = format!("BEGIN TRANSACTION;\n{content}\nCOMMIT TRANSACTION;");
-                let mut response = self
-                    .db
-                    .query(&tx_sql)
-                    .await
-                    .map_err(|e| eyre!(e.to_string()))?;
+        /// Fail a run that executes for longer than `timeout` in total.
+        ///
+        /// No migration starts after the time is up, and the one in flight
+        /// is abandoned as with `with_migration_timeout()`.
+        pub fn with_run_timeout(mut self, timeout: Duration) -> Self {
+            self.run_timeout = Some(timeout);
+            self
+        }
 
-                let errors = response.take_errors();
-                if !errors.is_empty() {
-                    let remaining = errors
-                        .values()
-                        .map(|e| e.to_string())
-                        .filter(|s| {
-                            !s.contains("The query was not executed due to a failed transaction")
-                        })
-                        .collect::<Vec<_>>();
+        /// Let `up()` execute up to `n` migrations at once.
+        ///
+        /// Pending migrations then run as soon as the migrations their
+        /// `-- depends:` header names are applied, so the declared
+        /// dependencies are the only ordering left: a migration without one
+        /// may run before, after or alongside any other. A dependency must be
+        /// applied or planned in the same run; `up()` rejects unknown ones
+        /// before running anything. Unlike in ordered mode, a dependency
+        /// planned after the migration naming it is fine here. A failure lets
+        /// the migrations in flight finish but starts no new ones. `n` of 1
+        /// (the default) applies migrations one by one in order.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// let runner = MigrationRunner::new(&db, src).with_parallelism(8);
+        /// ```
+        pub fn with_parallelism(mut self, n: usize) -> Self {
+            self.parallelism = n.max(1);
+            self
+        }
 
-                    if !remaining.is_empty() {
-                        let first = &remaining[0];
-                        eyre::bail!(first.to_owned());
-                    }
-                }
-                self.record_migration(&migration.name).await?;
-                tracing::info!("Applied migration: {}", migration.name);
-            }
+        /// Retry operations that fail with a transient connection error
+        /// (see `retry::is_transient()`) according to `policy`.
+        ///
+        /// Retries cover the read-only bookkeeping queries (reading the
+        /// applied migrations and the dirty marker, creating the
+        /// bookkeeping tables if missing) and scripts whose header carries
+        /// the `-- idempotent` flag. Other migrations are never retried, as
+        /// a script cut off midway may have partly run.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// use surreal_migraine::retry::RetryPolicy;
+        ///
+        /// let runner = MigrationRunner::new(&db, src).with_retry(RetryPolicy::new(5));
+        /// ```
+        pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+            self.retry = Some(policy);
+            self
+        }
 
-            Ok(())
+        /// Replace `{{ seed.scale }}` in seeds with `scale` (at least 1)
+        /// instead of 1, so generator-based seeds run by `seed()` produce
+        /// `scale` times their data. Migrations run by `up()` and `down()`
+        /// do not see it.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// // seeds/001_users.surql:
+        /// //   FOR $i IN 1..({{ seed.scale }} * 100) { CREATE user; };
+        /// let runner = MigrationRunner::new(&db, src).with_seed_scale(10);
+        /// runner.seed(&DiskSource::new("seeds")).await?;
+        /// ```
+        pub fn with_seed_scale(mut self, scale: u32) -> Self {
+            self.seed_scale = scale.max(1);
+            self
         }
 
-        /// Revert applied migrations in reverse discovery order.
+        /// Run `hook` at `point` of every `up()` and `down` run with
+        /// migrations to execute; hooks at the same point run in the order
+        /// they were added. See the `hooks` module for what they are given.
         ///
-        /// For `Paired` migrations this runs the embedded `down.surql`. For
-        /// up-only file migrations the runner attempts basic heuristics to
-        /// locate a sibling down script. After a successful revert the
-        /// migration record is removed from the `migrations` table.
+        /// # Examples
         ///
-        /// # Example
+        /// ```rust,ignore
+        /// use surreal_migraine::hooks::{Hook, HookPoint};
+        ///
+        /// let runner = MigrationRunner::new(&db, src)
+        ///     .with_hook(HookPoint::BeforeAll, Hook::Command("./scripts/pause-feeds.sh".into()))
+        ///     .with_hook(HookPoint::AfterAll, Hook::Command("./scripts/resume-feeds.sh".into()));
+        /// ```
+        pub fn with_hook(mut self, point: HookPoint, hook: Hook) -> Self {
+            self.hooks.push((point, hook));
+            self
+        }
+
+        /// Report the progress of `up()`, `fresh()` and the `down` runs to
+        /// `listener`: when a run starts and ends, when each migration starts
+        /// and finishes, and, with `ExecutionMode::PerStatement`, before each
+        /// statement.
+        ///
+        /// # Examples
         ///
         /// ```rust,ignore
-        /// # async fn revert_example(runner: &MigrationRunner<'_, _, _>) -> eyre::Result<()> {
-        /// runner.down().await?;
-        /// # Ok(())
-        /// # }
+        /// use surreal_migraine::types::ProgressEvent;
+        ///
+        /// let runner = MigrationRunner::new(&db, src).with_progress(|event: &ProgressEvent| {
+        ///     if let ProgressEvent::MigrationStarted { name, index, total } = event {
+        ///         println!("[{}/{total}] {name}", index + 1);
+        ///     }
+        /// });
         /// ```
-        pub async fn down(&self) -> Result<()> {
-            self.ensure_migrations_table_exists().await?;
+        pub fn with_progress(mut self, listener: impl ProgressListener + Send + Sync + 'a) -> Self {
+            self.progress = Some(Box::new(listener));
+            self
+        }
 
-            let migrations = self.source.list()?;
-            let mut applied = self.get_applied_migrations().await?;
+        /// Order migrations with `ordering` instead of the order the source
+        /// lists them in.
+        ///
+        /// Everything that walks migrations in order follows it: `up()`,
+        /// `down()`, `plan()`, `status()` and the out-of-order check.
+        pub fn with_ordering(mut self, ordering: impl Ordering + 'a) -> Self {
+            self.ordering = Some(Box::new(ordering));
+            self
+        }
 
-            // Preserve discovery order, but revert in reverse (last discovered first)
-            let name_to_entry = migrations
-                .into_iter()
-                .map(|m| (m.name.clone(), m))
-                .collect::<std::collections::HashMap<_, _>>();
+        /// Run a failed migration's own down script to clean up after it.
+        ///
+        /// A transaction rolls back a failed script on its own, but
+        /// `-- no-transaction` scripts and `ExecutionMode::PerStatement` leave
+        /// behind whatever ran before the failure. With this enabled, `up()`
+        /// executes the migration's down script (if it has one) right after
+        /// the failure. When the cleanup succeeds the run still fails but the
+        /// database is not marked dirty; when it fails, both errors are
+        /// reported and the dirty marker is set as usual.
+        pub fn with_cleanup_on_failure(mut self, enabled: bool) -> Self {
+            self.cleanup_on_failure = enabled;
+            self
+        }
 
-            // Only consider applied migrations and sort them by discovery order
-            applied.retain(|n| name_to_entry.contains_key(n));
+        /// Run only migrations meant for `environment`.
+        ///
+        /// Migrations whose header carries `-- env: ...` are skipped unless
+        /// the list includes `environment`; migrations without an `env` key
+        /// run everywhere. Without an active environment every migration
+        /// runs, so set it explicitly wherever dev-only migrations must not
+        /// apply.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// // Skips migrations headed `-- env: dev, test`.
+        /// let runner = MigrationRunner::new(&db, src).with_environment("prod");
+        /// ```
+        pub fn with_environment(mut self, environment: impl Into<String>) -> Self {
+            self.environment = Some(environment.into());
+            self
+        }
 
-            // Reverse to get most-recent-first
-            applied.reverse();
+        /// Compute checksums of newly applied migrations with `hasher`
+        /// instead of SHA-256.
+        ///
+        /// Each record stores the algorithm it was written with, and existing
+        /// records keep being verified with their own algorithm as long as it
+        /// is built in (see `checksum::builtin`) or matches `hasher`. Resolve
+        /// drift with `Resolution::Repair` to rewrite old records.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// use surreal_migraine::checksum::Sha512Hasher;
+        ///
+        /// let runner = MigrationRunner::new(&db, src).with_hasher(Sha512Hasher);
+        /// ```
+        pub fn with_hasher(mut self, hasher: impl Hasher + 'a) -> Self {
+            self.hasher = Box::new(hasher);
+            self
+        }
 
-            for name in applied {
-                if let Some(migration) = name_to_entry.get(&name) {
-                    let down_content = self.source.get_down(migration)?;
+        /// Substitute `{{name}}` placeholders in migration SQL with `variables`
+        /// before executing it.
+        ///
+        /// Use this for environment-specific values such as bucket names or
+        /// access scopes in `DEFINE` statements. Checksums are computed over
+        /// the SQL as written, so every environment records the same
+        /// checksum. A placeholder without a value fails the migration. See
+        /// `placeholders::substitute` for the syntax.
+        ///
+        /// Placeholders named `db.param.<name>` need no variable: they take
+        /// the value of `DEFINE PARAM $<name>` in the target database.
+        /// `up()` reads them all before applying anything, so every
+        /// migration in a run sees the same values and a missing param
+        /// fails the run up front; a param defined by a migration in the
+        /// same run is not visible to it.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// let runner = MigrationRunner::new(&db, src)
+        ///     .with_variables([("env", "staging"), ("bucket", "assets-staging")]);
+        /// ```
+        pub fn with_variables<I, K, V>(mut self, variables: I) -> Self
+        where
+            I: IntoIterator<Item = (K, V)>,
+            K: Into<String>,
+            V: Into<String>,
+        {
+            self.variables
+                .get_or_insert_with(HashMap::new)
+                .extend(variables.into_iter().map(|(k, v)| (k.into(), v.into())));
+            self
+        }
 
-                    if let Some(content) = down_content {
-                        let tx_sql = format!("BEGIN TRANSACTION;\n{content}\nCOMMIT TRANSACTION;");
-                        let mut response = self
-                            .db
-                            .query(&tx_sql)
-                            .await
-                            .map_err(|e| eyre!(e.to_string()))?;
+        /// Consult `resolver` for each drift item `up()` finds before applying
+        /// anything.
+        ///
+        /// Drift is an applied migration that was edited or that no longer
+        /// exists in the source. Each item is resolved as `Accept` (continue),
+        /// `Repair` (rewrite history to match the source) or `Abort` (stop).
+        /// Without a resolver, drift is ignored unless checksum verification
+        /// is enabled, in which case edits fail the run.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// use surreal_migraine::drift::ResolutionFile;
+        ///
+        /// let runner = MigrationRunner::new(&db, src)
+        ///     .with_drift_resolver(ResolutionFile::load("resolutions.txt")?);
+        /// ```
+        pub fn with_drift_resolver(
+            mut self,
+            resolver: impl DriftResolver + Send + Sync + 'a,
+        ) -> Self {
+            self.resolver = Some(Box::new(resolver));
+            self
+        }
 
-                        let errors = response.take_errors();
-                        if !errors.is_empty() {
-                            let remaining = errors
-                                .values()
-                                .map(|e| e.to_string())
-                                .filter(|s| {
-                                    !s.contains(
-                                        "The query was not executed due to a failed transaction",
-                                    )
-                                })
-                                .collect::<Vec<_>>();
-
-                            if !remaining.is_empty() {
-                                let first = &remaining[0];
-                                eyre::bail!(first.to_owned());
-                            }
-                        }
-                        self.remove_migration_record(&migration.name).await?;
-                        tracing::info!("Reverted migration: {}", migration.name);
-                    } else {
-                        tracing::warn!(migration = %migration.name, "no down script found; skipping");
-                    }
-                }
-            }
+        /// Treat `name` and every versioned migration discovered before it as
+        /// already reflected in the database.
+        ///
+        /// This is the adoption path for a legacy database whose schema
+        /// predates the migration history: on the first `up()` against a
+        /// database that already has tables, functions, params or analyzers
+        /// but no migration records, those migrations are recorded as
+        /// baseline instead of being executed, and migrations after `name`
+        /// apply normally. A database with no schema yet, or one `fresh()`
+        /// wiped, runs every migration, as does one migrated before. Set it
+        /// for the adoption run and drop it afterwards.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// let runner = MigrationRunner::new(&db, src).with_baseline("004_legacy_schema");
+        /// runner.up().await?; // records 001-004 as baseline, applies 005+
+        /// ```
+        pub fn with_baseline(mut self, name: impl Into<String>) -> Self {
+            self.baseline = Some(name.into());
+            self
+        }
 
-            Ok(())
+        /// Allow `fresh()` to wipe the database.
+        ///
+        /// `fresh()` refuses to run unless this is set, so a runner pointed at
+        /// production by mistake cannot drop its data. Only enable it for
+        /// development and CI databases.
+        pub fn with_fresh_allowed(mut self, allowed: bool) -> Self {
+            self.allow_fresh = allowed;
+            self
         }
 
-        /// Remove a migration record from the `migrations` table.
-        async fn remove_migration_record(&self, name: &str) -> Result<()> {
-            let sql = "DELETE FROM migrations WHERE name = $name;";
-            let _ = self
-                .db
-                .query(sql)
-                .bind(("name", name.to_owned()))
-                .await
-                .map_err(|e| eyre!(e.to_string()))?;
-            Ok(())
+        /// Make `up()` fail before applying anything when an applied
+        /// migration's content no longer matches its recorded checksum.
+        ///
+        /// The error includes a unified diff for every mismatch whose applied
+        /// SQL was recorded. See `verify()` to inspect mismatches directly.
+        pub fn with_checksum_verification(mut self, enabled: bool) -> Self {
+            self.verify_checksums = enabled;
+            self
         }
 
-        /// Ensure the `migrations` table exists.
-        async fn ensure_migrations_table_exists(&self) -> Result<()> {
-            let sql = "DEFINE TABLE IF NOT EXISTS migrations PERMISSIONS NONE;";
-            self.db.query(sql).await.map_err(|e| eyre!(e.to_string()))?;
-            Ok(())
+        /// Restrict the runner to migrations that only touch `tables`.
+        ///
+        /// Use this when several services share a database and each owns a
+        /// subset of its tables. Before executing anything, `up()` and
+        /// `down()` refuse to run if a planned script defines, removes or
+        /// writes to a table outside the set.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// let runner = MigrationRunner::new(&db, src).with_owned_tables(["user", "session"]);
+        /// ```
+        pub fn with_owned_tables<I, T>(mut self, tables: I) -> Self
+        where
+            I: IntoIterator<Item = T>,
+            T: Into<String>,
+        {
+            self.owned_tables = Some(tables.into_iter().map(Into::into).collect());
+            self
         }
 
-        /// Retrieve applied migration names from the `migrations` table.
+        /// Choose what happens to `USE NS`/`USE DB` statements in scripts.
+        /// Defaults to `UsePolicy::Reject`: `up()`, `down()` and `plan()`
+        /// fail before executing anything.
         ///
-        /// Pages results in batches to avoid loading very large tables into memory.
-        async fn get_applied_migrations(&self) -> Result<Vec<String>> {
-            let migrations: Vec<MigrationRecord> = match self.db.select("migrations").await {
-                Ok(r) => r,
-                Err(e) => {
-                    tracing::debug!("failed to select migrations: {}", e.to_string());
-                    return Ok(Vec::new());
-                }
-            };
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// use crate::types::UsePolicy;
+        ///
+        /// let runner = MigrationRunner::new(&db, src).with_use_policy(UsePolicy::Strip);
+        /// ```
+        pub fn with_use_policy(mut self, policy: UsePolicy) -> Self {
+            self.use_policy = policy;
+            self
+        }
 
-            let mut migration_strings = Vec::new();
+        /// Choose how `up()` treats pending migrations that sort before an
+        /// already-applied migration, which usually means two branches added
+        /// migrations concurrently. Defaults to `OutOfOrderPolicy::Warn`.
+        pub fn with_out_of_order(mut self, policy: OutOfOrderPolicy) -> Self {
+            self.out_of_order = policy;
+            self
+        }
 
-            for record in migrations {
-                let name = record.name;
-                if !name.is_empty() {
-                    migration_strings.push(name);
-                }
-            }
+        /// Choose how migration scripts are submitted to the database.
+        ///
+        /// `ExecutionMode::PerStatement` executes statements one by one,
+        /// logging progress and reporting the failing statement with its line
+        /// number instead of a single opaque transaction error.
+        pub fn with_execution_mode(mut self, mode: ExecutionMode) -> Self {
+            self.execution = mode;
+            self
+        }
 
-            Ok(migration_strings)
+        /// Hold an advisory lock in the `migration_lock` table while `up()` or
+        /// `down()` runs.
+        ///
+        /// While the lock is held its heartbeat is refreshed every
+        /// `options.heartbeat_interval`. Another runner that finds the lock
+        /// fails fast if the heartbeat is fresh, and takes the lock over if the
+        /// heartbeat is older than `options.stale_after` (the holder crashed).
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// use crate::types::LockOptions;
+        ///
+        /// let runner = MigrationRunner::new(&db, src).with_lock(LockOptions::default());
+        /// ```
+        pub fn with_lock(mut self, options: LockOptions) -> Self {
+            self.lock = Some(options);
+            self
         }
 
-        /// Record a migration as applied by creating a record in `migrations`.
-        async fn record_migration(&self, name: &str) -> Result<()> {
-            let content = json!({ "name": name });
-            let _ = self
-                .db
-                .query("CREATE migrations CONTENT $content")
-                .bind(("content", content))
-                .await
-                .map_err(|e| eyre!(e.to_string()))?;
+        /// Refuse to apply migrations unless the source matches `lock`,
+        /// a `migrations.lock` listing every migration and its checksum (see
+        /// `lockfile::LockFile`). Catches migrations edited, added or
+        /// removed without regenerating the lock file.
+        ///
+        /// # Examples
+        ///
+        /// ```rust,ignore
+        /// use surreal_migraine::lockfile::{LOCK_FILE, LockFile};
+        ///
+        /// let lock = LockFile::read(Path::new("migrations").join(LOCK_FILE))?;
+        /// let runner = MigrationRunner::new(&db, src).with_lock_file(lock);
+        /// ```
+        pub fn with_lock_file(mut self, lock: LockFile) -> Self {
+            self.lock_file = Some(lock);
+            self
+        }
+
+        /// Run all pending migrations discovered by the configured
+        /// `MigrationSource`.
+        ///
+        /// This method ensures the `migrations` table exists, discovers
+        /// available migrations, filters out ones already recorded in the
+        /// database, and executes each migration inside a transaction (unless
+        /// its header carries the `-- no-transaction` directive). On
+        /// success each migration is recorded in the `migrations` table along
+        /// with its checksum. Repeatable migrations run after all versioned
+        /// ones, and only when their checksum differs from the recorded one. A
+        /// `RunReport` describing the run is persisted whether it succeeds or
+        /// fails, and a `MigrationReport` of what it did is returned. A failing
+        /// migration also leaves a `DirtyState` marker that blocks further runs
+        /// until `repair()` is called.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// # async fn run_example(runner: &MigrationRunner<'_, _, _>) -> eyre::Result<()> {
+        /// let report = runner.up().await?;
+        /// println!("applied {} migration(s)", report.applied().count());
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn up(&self) -> Result<MigrationReport> {
+            self.start_run();
+            self.ensure_migrations_table_exists().await?;
+            self.locked(async {
+                self.ensure_clean().await?;
+                self.apply_pending().await
+            })
+            .await
+        }
+
+        /// Like `up()`, returning the run as a future together with a channel
+        /// of its events, for UIs that pull live progress rather than
+        /// implementing a `ProgressListener`.
+        ///
+        /// Nothing happens until the future is polled; drive it alongside the
+        /// receiver, which closes when the run ends. Wrap the receiver in
+        /// `tokio_stream::wrappers::UnboundedReceiverStream` for a `Stream`.
+        /// Run one of these at a time per runner.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// let (run, mut events) = runner.up_with_events();
+        /// let ui = async {
+        ///     while let Some(event) = events.recv().await {
+        ///         println!("{event:?}");
+        ///     }
+        /// };
+        /// let (report, ()) = tokio::join!(run, ui);
+        /// report?;
+        /// ```
+        pub fn up_with_events(
+            &self,
+        ) -> (
+            impl Future<Output = Result<MigrationReport>>,
+            UnboundedReceiver<MigrationEvent>,
+        ) {
+            let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+            let run = async move {
+                *self.events.lock().unwrap() = Some(tx);
+                let result = self.up().await;
+                // Dropping the sender closes the channel.
+                self.events.lock().unwrap().take();
+                result
+            };
+            (run, rx)
+        }
+
+        /// Apply pending migrations to the tenant database `ns`/`db` the
+        /// first time this runner sees it.
+        ///
+        /// For apps that create tenants at runtime: call it before serving a
+        /// tenant's request, with the runner's source holding the
+        /// tenant-template migrations. The first call per tenant runs `up()`;
+        /// later calls return at once, so migrations added while the process
+        /// runs reach known tenants only after a restart. Concurrent first
+        /// requests for a new tenant migrate it once while requests for
+        /// tenants already up to date go through; add `with_lock()` when
+        /// several processes may touch the same tenant.
+        ///
+        /// The runner selects the tenant in each of its own queries and
+        /// leaves the client's session alone, so other tasks sharing the
+        /// client keep their namespace and database. Fails when
+        /// `with_bookkeeping()` is set, since each tenant keeps its own
+        /// bookkeeping tables.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// let runner = MigrationRunner::new(&db, EmbeddedSource::new(&TENANT_MIGRATIONS));
+        /// runner.ensure_tenant("tenants", &tenant_id).await?;
+        /// // The tenant database is up to date; query it with its own session.
+        /// ```
+        pub async fn ensure_tenant(&self, ns: &str, db: &str) -> Result<()> {
+            let ensured = self
+                .tenants
+                .lock()
+                .unwrap()
+                .entry(Tenant::new(ns, db))
+                .or_default()
+                .clone();
+            if ensured.initialized() {
+                return Ok(());
+            }
+            ensured
+                .get_or_try_init(|| async {
+                    self.check_tenants()?;
+                    self.in_tenant(&Tenant::new(ns, db), self.up()).await?;
+                    tracing::info!(
+                        namespace = ns,
+                        database = db,
+                        "tenant database is up to date"
+                    );
+                    Ok::<_, eyre::Report>(())
+                })
+                .await?;
             Ok(())
         }
-    }
-}
 
-pub use include_dir::{Dir, include_dir};
+        /// List the tenant databases on the server that pass `filter`,
+        /// sorted by namespace and database.
+        ///
+        /// Reads the namespaces with `INFO FOR ROOT` and their databases
+        /// with `INFO FOR NS`, so the client needs root access. Feed the
+        /// result to `up_for_tenants()` to pick up new tenants on every
+        /// deploy. Names containing a backtick are skipped.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// use surreal_migraine::types::{TenantFailurePolicy, TenantFilter};
+        ///
+        /// let filter = TenantFilter::new().with_include("tenants").with_exclude("tenants/demo_*");
+        /// let tenants = runner.discover_tenants(&filter).await?;
+        /// let outcomes = runner.up_for_tenants(tenants, TenantFailurePolicy::Continue).await;
+        /// ```
+        pub async fn discover_tenants(&self, filter: &TenantFilter) -> Result<Vec<Tenant>> {
+            let mut tenants = Vec::new();
+            for namespace in self.info_keys("INFO FOR ROOT", "namespaces").await? {
+                let databases = self
+                    .info_keys(&format!("USE NS `{namespace}`;\nINFO FOR NS"), "databases")
+                    .await?;
+                tenants.extend(
+                    databases
+                        .into_iter()
+                        .map(|database| Tenant::new(&namespace, database))
+                        .filter(|tenant| filter.matches(tenant)),
+                );
+            }
+            tenants.sort();
+            Ok(tenants)
+        }
+
+        /// Names listed under `key` in the result of the `INFO` statement
+        /// ending `sql`, leaving out those that cannot be quoted.
+        async fn info_keys(&self, sql: &str, key: &str) -> Result<Vec<String>> {
+            let mut response = self.db.query(sql).await.map_err(|e| eyre!(e.to_string()))?;
+            let last = response.num_statements() - 1;
+            let info: Option<serde_json::Value> =
+                response.take(last).map_err(|e| eyre!(e.to_string()))?;
+            let names = info
+                .as_ref()
+                .and_then(|info| info.get(key))
+                .and_then(serde_json::Value::as_object)
+                .map(|names| names.keys().cloned().collect::<Vec<_>>())
+                .unwrap_or_default();
+            Ok(names
+                .into_iter()
+                .filter(|name| {
+                    let quotable = !name.contains('`');
+                    if !quotable {
+                        tracing::warn!("skipping tenant {name:?}: its name contains a backtick");
+                    }
+                    quotable
+                })
+                .collect())
+        }
+
+        /// Apply pending migrations to each of `tenants` in turn.
+        ///
+        /// For one schema shared by many tenant databases. Returns an
+        /// outcome per tenant attempted, in order. With
+        /// `TenantFailurePolicy::FailFast` the first failing tenant ends the
+        /// run and later tenants are left out; with `Continue` every tenant
+        /// is attempted. Check `TenantOutcome::is_success()` for failures.
+        ///
+        /// As with `ensure_tenant()`, the client's session is left alone, and
+        /// only this call's queries go to the tenant being migrated: a
+        /// concurrent `up()` stays in the client's database, and dropping
+        /// the future part-way leaves nothing pointed at the tenant. Each
+        /// tenant keeps its own bookkeeping tables, so this fails up front
+        /// when `with_bookkeeping()` is set.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// use surreal_migraine::types::{Tenant, TenantFailurePolicy};
+        ///
+        /// let tenants = ["acme", "globex"].map(|db| Tenant::new("tenants", db));
+        /// for outcome in runner.up_for_tenants(tenants, TenantFailurePolicy::Continue).await? {
+        ///     if let Some(error) = &outcome.error {
+        ///         eprintln!("{}: {error}", outcome.tenant);
+        ///     }
+        /// }
+        /// ```
+        pub async fn up_for_tenants(
+            &self,
+            tenants: impl IntoIterator<Item = Tenant>,
+            on_failure: TenantFailurePolicy,
+        ) -> Result<Vec<TenantOutcome>> {
+            self.check_tenants()?;
+            let mut outcomes = Vec::new();
+            for tenant in tenants {
+                tracing::info!(%tenant, "migrating tenant");
+                let result = self.in_tenant(&tenant, self.up()).await;
+                let (report, error) = match result {
+                    Ok(report) => (Some(report), None),
+                    Err(e) => {
+                        tracing::error!(%tenant, "tenant failed: {e:#}");
+                        (None, Some(format!("{e:#}")))
+                    }
+                };
+                let failed = error.is_some();
+                outcomes.push(TenantOutcome {
+                    tenant,
+                    report,
+                    error,
+                });
+                if failed && on_failure == TenantFailurePolicy::FailFast {
+                    break;
+                }
+            }
+            Ok(outcomes)
+        }
+
+        /// Fail if tenants cannot be migrated with this configuration.
+        fn check_tenants(&self) -> Result<()> {
+            if self.bookkeeping.is_some() {
+                eyre::bail!(
+                    "tenant databases keep their own bookkeeping tables; \
+                     remove with_bookkeeping() to migrate tenants"
+                );
+            }
+            Ok(())
+        }
+
+        /// Run `work` against the database of `tenant` instead of the
+        /// client's, after any other tenant run of this runner.
+        async fn in_tenant<T>(
+            &self,
+            tenant: &Tenant,
+            work: impl Future<Output = Result<T>>,
+        ) -> Result<T> {
+            for name in [&tenant.namespace, &tenant.database] {
+                if name.is_empty() || name.contains('`') {
+                    eyre::bail!("invalid tenant namespace or database name {name:?}");
+                }
+            }
+            let _run = self.tenant_runs.lock().await;
+            self.db_params.lock().unwrap().clear();
+            let result = TENANT.scope(tenant.clone(), work).await;
+            self.db_params.lock().unwrap().clear();
+            result
+        }
+
+        /// The `USE` statement selecting the tenant database of the current
+        /// run, if any.
+        fn tenant_use(&self) -> Option<String> {
+            TENANT
+                .try_with(|t| format!("USE NS `{}` DB `{}`;", t.namespace, t.database))
+                .ok()
+        }
+
+        /// A query in the migrated database: the tenant of the current run,
+        /// if any, else the client's. Take results with `target_at()`.
+        fn target(&self, sql: impl AsRef<str>) -> surrealdb::method::Query<'a, E> {
+            match self.tenant_use() {
+                Some(scope) => self.db.query(format!("{scope}\n{}", sql.as_ref())),
+                None => self.db.query(sql.as_ref().to_owned()),
+            }
+        }
+
+        /// Index of the `index`-th statement of a `target()` query,
+        /// skipping its `USE`.
+        fn target_at(&self, index: usize) -> usize {
+            index + usize::from(TENANT.try_with(|_| ()).is_ok())
+        }
+
+        /// Wipe the database and apply every migration from scratch.
+        ///
+        /// All tables (including migration history, tags and the dirty
+        /// marker), functions, params and analyzers in the current database
+        /// are removed before `up()` runs, so every migration executes, even
+        /// those a `with_baseline()` covers. Intended for development and CI
+        /// databases; it fails unless enabled with `with_fresh_allowed(true)`.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// MigrationRunner::new(&db, src)
+        ///     .with_fresh_allowed(cfg!(debug_assertions))
+        ///     .fresh()
+        ///     .await?;
+        /// ```
+        pub async fn fresh(&self) -> Result<MigrationReport> {
+            if !self.allow_fresh {
+                eyre::bail!(
+                    "fresh() drops every table in the database; enable it with with_fresh_allowed(true)"
+                );
+            }
+            self.start_run();
+
+            self.ensure_migrations_table_exists().await?;
+            self.locked(async {
+                self.wipe().await?;
+                self.ensure_migrations_table_exists().await?;
+                self.apply_pending().await
+            })
+            .await
+        }
+
+        /// The tables, functions, params and analyzers of the database, as
+        /// `(kind, name)` pairs with the kind as `INFO FOR DB` names it.
+        async fn database_objects(&self) -> Result<Vec<(&'static str, String)>> {
+            let mut response = self
+                .target("INFO FOR DB")
+                .await
+                .map_err(|e| eyre!(e.to_string()))?;
+            let info: Option<serde_json::Value> = response
+                .take(self.target_at(0))
+                .map_err(|e| eyre!(e.to_string()))?;
+            let info = info.unwrap_or_default();
+            let mut objects = Vec::new();
+            for kind in ["tables", "functions", "params", "analyzers"] {
+                if let Some(names) = info.get(kind).and_then(|v| v.as_object()) {
+                    objects.extend(names.keys().map(|name| (kind, name.clone())));
+                }
+            }
+            Ok(objects)
+        }
+
+        /// Remove every table, function, param and analyzer except the run
+        /// lock.
+        async fn wipe(&self) -> Result<()> {
+            let mut sql = String::new();
+            for (kind, name) in self.database_objects().await? {
+                match kind {
+                    "tables" if name == "migration_lock" => {}
+                    "tables" => sql.push_str(&format!("REMOVE TABLE `{name}`;\n")),
+                    "functions" => sql.push_str(&format!("REMOVE FUNCTION fn::{name};\n")),
+                    "params" => sql.push_str(&format!("REMOVE PARAM ${name};\n")),
+                    _ => sql.push_str(&format!("REMOVE ANALYZER {name};\n")),
+                }
+            }
+
+            tracing::warn!("wiping database before fresh migration run");
+            self.execute_script(&sql, true, None).await?;
+
+            if self.bookkeeping.is_some() {
+                self.bookkeeping(format!(
+                    "DELETE {}; DELETE migration_runs; DELETE migration_tags; DELETE migration_state;",
+                    self.table()?
+                ))?
+                .await
+                .map_err(|e| eyre!(e.to_string()))?;
+            }
+            Ok(())
+        }
+
+        /// Run the data-population scripts in `seeds`.
+        ///
+        /// Seeds live apart from schema migrations (typically in a `seeds/`
+        /// directory read with `DiskSource`) and are not recorded in the
+        /// `migrations` table, so every call runs all of them again; write
+        /// them to be re-runnable, e.g. with `UPSERT` or `INSERT IGNORE`.
+        /// They run in listing order with the runner's execution mode,
+        /// variables and environment filter, and the run is reported in
+        /// `runs()` with direction `Seed`. A failing seed stops the run
+        /// without marking the database dirty. `{{ seed.scale }}` in a seed
+        /// is replaced with the factor set by `with_seed_scale()`, unless
+        /// `with_variables()` gives `seed.scale` a value of its own.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// runner.up().await?;
+        /// runner.seed(&DiskSource::new("seeds")).await?;
+        /// ```
+        pub async fn seed<T: MigrationSource>(&self, seeds: &T) -> Result<()> {
+            self.start_run();
+            self.ensure_migrations_table_exists().await?;
+            self.locked(async {
+                let mut plan = Vec::new();
+                for seed in seeds.list()? {
+                    if let Some(environment) = &self.environment
+                        && !seed.metadata.applies_to(environment)
+                    {
+                        continue;
+                    }
+                    let content = seeds.get_up(&seed)?;
+                    plan.push((seed, content));
+                }
+
+                let fingerprint =
+                    fingerprint(plan.iter().map(|(m, c)| (m.name.as_str(), c.as_str())));
+                let mut report = RunReport::new(RunDirection::Seed, fingerprint);
+                let started = Instant::now();
+
+                let mut result = Ok(());
+                for (seed, content) in &plan {
+                    result = self
+                        .step(&mut report, &seed.name, OutcomeStatus::Applied, async {
+                            let scale = HashMap::from([(
+                                SEED_SCALE.to_owned(),
+                                self.seed_scale.to_string(),
+                            )]);
+                            self.execute_with(seed, content, seed.metadata.transactional(), scale)
+                                .await
+                        })
+                        .await;
+                    if result.is_err() {
+                        break;
+                    }
+                    tracing::info!("Ran seed: {}", seed.name);
+                }
+
+                report.duration_ms = elapsed_ms(started);
+                report.error = result.as_ref().err().map(|e| e.to_string());
+                let persisted = self.record_run(report).await;
+                recorded(result, persisted)
+            })
+            .await
+        }
+
+        /// Record migrations as applied without executing their SQL.
+        ///
+        /// Use this to baseline a database whose schema already exists: the
+        /// named migrations are recorded with their current checksum, so
+        /// `up()` skips them. Names must exist in the source; migrations that
+        /// are already applied are left untouched.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// runner.mark_applied(&["001_init", "002_users"]).await?;
+        /// ```
+        pub async fn mark_applied(&self, names: &[&str]) -> Result<()> {
+            self.ensure_migrations_table_exists().await?;
+            *self.batch.lock().unwrap() = None;
+            self.locked(async {
+                let migrations = self.migrations()?;
+                let applied = self.get_applied_migrations().await?;
+                let applied = applied
+                    .iter()
+                    .map(|r| r.name.as_str())
+                    .collect::<HashSet<_>>();
+
+                let mut targets = Vec::new();
+                for name in names {
+                    let migration = migrations
+                        .iter()
+                        .find(|m| m.name == *name)
+                        .ok_or_else(|| eyre!("unknown migration: {name}"))?;
+                    targets.push(migration);
+                }
+
+                for migration in targets {
+                    if applied.contains(migration.name.as_str()) {
+                        tracing::debug!(migration = %migration.name, "already applied; not marking");
+                        continue;
+                    }
+                    let content = self.source.get_up(migration)?;
+                    self.record_migration(migration, &content, false).await?;
+                    tracing::info!("Marked migration as applied: {}", migration.name);
+                }
+                Ok(())
+            })
+            .await
+        }
+
+        /// Move the applied record of migration `from` to the name `to`,
+        /// after the migration was renamed in the source, so it does not look
+        /// pending. Returns whether `from` was applied.
+        ///
+        /// The checksum and applied SQL are kept, and tags and the dirty
+        /// marker pointing at `from` move along with it; past run reports
+        /// still name `from`. Fails when `to` is already recorded.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// std::fs::rename("migrations/003_users.surql", "migrations/003_accounts.surql")?;
+        /// runner.rename_record("003_users.surql", "003_accounts.surql").await?;
+        /// ```
+        pub async fn rename_record(&self, from: &str, to: &str) -> Result<bool> {
+            if to.is_empty() {
+                eyre::bail!("cannot rename {from} to an empty name");
+            }
+            self.ensure_migrations_table_exists().await?;
+            self.locked(async {
+                let applied = self.get_applied_migrations().await?;
+                if applied.iter().any(|r| r.name == to) {
+                    eyre::bail!("a migration named {to} is already recorded");
+                }
+                if !applied.iter().any(|r| r.name == from) {
+                    return Ok(false);
+                }
+
+                let sql = format!(
+                    "BEGIN TRANSACTION;
+                    UPDATE {} SET name = $to WHERE name = $from;
+                    UPDATE migration_tags SET migration = $to WHERE migration = $from;
+                    UPDATE migration_state:dirty SET migration = $to WHERE migration = $from;
+                    COMMIT TRANSACTION;",
+                    self.table()?
+                );
+                self.bookkeeping(sql)?
+                    .bind(("from", from.to_string()))
+                    .bind(("to", to.to_string()))
+                    .await
+                    .map_err(|e| eyre!(e.to_string()))?
+                    .check()
+                    .map_err(|e| eyre!(e.to_string()))?;
+                tracing::info!("Renamed applied migration record: {from} -> {to}");
+                Ok(true)
+            })
+            .await
+        }
+
+        /// Build a release plan for the pending migrations together with the
+        /// ordered rollback that undoes it.
+        ///
+        /// The plan goes through the same checks as `up()` (ordering,
+        /// checksum verification, table ownership) without executing
+        /// anything. Write it out with `ReleasePlan::write()` so the rollback
+        /// procedure can be reviewed before the deploy.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// let plan = runner.plan().await?;
+        /// plan.write("release/v2.3.0")?; // fails if a migration has no down script
+        /// ```
+        pub async fn plan(&self) -> Result<ReleasePlan> {
+            self.ensure_migrations_table_exists().await?;
+            let pending = self.pending().await?;
+
+            let mut plan = ReleasePlan {
+                fingerprint: fingerprint(
+                    pending.iter().map(|(m, c)| (m.name.as_str(), c.as_str())),
+                ),
+                ..Default::default()
+            };
+            for (migration, _) in pending.iter().rev() {
+                if migration.kind == MigrationKind::Repeatable {
+                    continue;
+                }
+                match self.source.get_down(migration)? {
+                    Some(sql) => plan.rollback.push(PlannedMigration {
+                        name: migration.name.clone(),
+                        sql,
+                    }),
+                    None => plan.irreversible.insert(0, migration.name.clone()),
+                }
+            }
+            plan.up = pending
+                .into_iter()
+                .map(|(migration, sql)| PlannedMigration {
+                    name: migration.name,
+                    sql,
+                })
+                .collect();
+            Ok(plan)
+        }
+
+        /// Discovered migrations that apply to the active environment.
+        fn migrations(&self) -> Result<Vec<Migration>> {
+            let mut migrations = self.source.list()?;
+            if let Some(ordering) = &self.ordering {
+                ordering::sort(&mut migrations, ordering.as_ref());
+            }
+            if let Some(environment) = &self.environment {
+                migrations.retain(|m| {
+                    let applies = m.metadata.applies_to(environment);
+                    if !applies {
+                        tracing::debug!(migration = %m.name, environment, "skipped for environment");
+                    }
+                    applies
+                });
+            }
+            Ok(migrations)
+        }
+
+        /// The migrations `up()` would run, in order, with their content.
+        async fn pending(&self) -> Result<Vec<(Migration, String)>> {
+            let migrations = self.migrations()?;
+
+            let applied = self.get_applied_migrations().await?;
+            let applied_checksums = applied
+                .iter()
+                .map(|r| (r.name.as_str(), r.checksum.as_deref()))
+                .collect::<HashMap<_, _>>();
+
+            // Versioned migrations run first, in discovery order; repeatable
+            // migrations follow whenever their content changed.
+            let (repeatable, mut versioned): (Vec<_>, Vec<_>) = migrations
+                .into_iter()
+                .partition(|m| m.kind == MigrationKind::Repeatable);
+
+            // Migrations covered by the baseline are recorded, never run.
+            let baselined = self
+                .adopted_baseline(&versioned, &applied)
+                .await?
+                .iter()
+                .map(|m| m.name.clone())
+                .collect::<HashSet<_>>();
+            versioned.retain(|m| !baselined.contains(&m.name));
+            // So are squashed migrations replacing only applied ones.
+            versioned.retain(|m| {
+                m.metadata.replaces.is_empty()
+                    || !m
+                        .metadata
+                        .replaces
+                        .iter()
+                        .all(|r| applied_checksums.contains_key(r.as_str()))
+            });
+
+            self.check_order(&versioned, &applied_checksums)?;
+
+            if self.verify_checksums && self.resolver.is_none() {
+                let mismatches = self.mismatches(&versioned, &applied)?;
+                if !mismatches.is_empty() {
+                    eyre::bail!(mismatch_error(&mismatches));
+                }
+            }
+
+            let mut plan = Vec::new();
+            for migration in versioned
+                .into_iter()
+                .filter(|m| !applied_checksums.contains_key(m.name.as_str()))
+            {
+                // If the migration is a directory, look for `up.surql` inside it.
+                let content = self.source.get_up(&migration)?;
+                plan.push((migration, content));
+            }
+            let records = applied
+                .iter()
+                .map(|r| (r.name.as_str(), r))
+                .collect::<HashMap<_, _>>();
+            for migration in repeatable {
+                let content = self.source.get_up(&migration)?;
+                let unchanged = match records.get(migration.name.as_str()) {
+                    Some(record) => {
+                        record.checksum.as_deref()
+                            == Some(self.checksum_for(record, &content)?.as_str())
+                    }
+                    None => false,
+                };
+                if !unchanged {
+                    plan.push((migration, content));
+                }
+            }
+
+            self.check_dependencies(&plan, &applied, &baselined)?;
+            self.check_scripts(plan.iter().map(|(m, c)| (m, c.as_str())))?;
+            Ok(plan)
+        }
+
+        /// Refuse a `plan` whose migrations depend on migrations that are
+        /// neither applied (or `baselined`) nor planned, or, when applying
+        /// in order, planned only after them.
+        fn check_dependencies(
+            &self,
+            plan: &[(Migration, String)],
+            applied: &[MigrationRecord],
+            baselined: &HashSet<String>,
+        ) -> Result<()> {
+            let migrations = plan.iter().map(|(m, _)| m.clone()).collect::<Vec<_>>();
+            let satisfied = applied
+                .iter()
+                .map(|r| r.name.as_str())
+                .chain(baselined.iter().map(String::as_str))
+                .collect::<Vec<_>>();
+            let problems =
+                crate::validate::dependencies(&migrations, &satisfied, self.parallelism == 1);
+            if !problems.is_empty() {
+                eyre::bail!(
+                    "migration dependencies cannot be satisfied:\n  {}",
+                    problems
+                        .iter()
+                        .map(|p| p.to_string())
+                        .collect::<Vec<_>>()
+                        .join("\n  ")
+                );
+            }
+            Ok(())
+        }
+
+        /// Apply pending versioned migrations and changed repeatables. The
+        /// caller holds the lock.
+        async fn apply_pending(&self) -> Result<MigrationReport> {
+            self.check_lock_file()?;
+            self.record_baseline().await?;
+            self.record_squashed().await?;
+            self.resolve_drift().await?;
+            let plan = self.pending().await?;
+            let skipped = self.excluded()?;
+            self.event(MigrationEvent::Discovered {
+                pending: plan.iter().map(|(m, _)| m.name.clone()).collect(),
+            });
+            for name in &skipped {
+                self.event(MigrationEvent::Skipped {
+                    name: name.clone(),
+                    reason: "limited to other environments".to_owned(),
+                });
+            }
+            self.db_params.lock().unwrap().clear();
+            self.load_db_params(plan.iter().map(|(_, c)| c.as_str()))
+                .await?;
+
+            let fingerprint = fingerprint(plan.iter().map(|(m, c)| (m.name.as_str(), c.as_str())));
+            let mut report = RunReport::new(RunDirection::Up, fingerprint);
+            let started = Instant::now();
+
+            let total = plan.len();
+            if total > 0 {
+                self.run_hooks(HookPoint::BeforeAll, RunDirection::Up, None, None)
+                    .await?;
+            }
+            self.progress(ProgressEvent::RunStarted {
+                direction: RunDirection::Up,
+                total,
+            });
+            let run_span = telemetry::run(RunDirection::Up, total);
+            let mut result = if self.parallelism > 1 {
+                self.apply_graph(&plan, &run_span, &mut report).await
+            } else {
+                self.apply_in_order(&plan, &run_span, &mut report).await
+            };
+            if total > 0 {
+                result = self.after_all(RunDirection::Up, result).await;
+            }
+            self.progress(ProgressEvent::RunFinished {
+                direction: RunDirection::Up,
+                succeeded: result.is_ok(),
+            });
+            telemetry::finish_run(
+                &run_span,
+                RunDirection::Up,
+                result.is_ok(),
+                elapsed_ms(started),
+            );
+
+            let outcomes = report.outcomes.clone();
+            let persisted = self.finish_run(report, started, &result).await;
+            recorded(result, persisted)?;
+            Ok(self.report(RunDirection::Up, outcomes, skipped, started))
+        }
+
+        /// Apply the migrations of `plan` one after another, stopping at the
+        /// first failure.
+        async fn apply_in_order(
+            &self,
+            plan: &[(Migration, String)],
+            run_span: &Span,
+            report: &mut RunReport,
+        ) -> Result<()> {
+            for (index, (migration, content)) in plan.iter().enumerate() {
+                self.check_cancelled()?;
+                let (outcome, applied) = self
+                    .apply_hooked(run_span, index, plan.len(), migration, content)
+                    .await;
+                let failed = outcome
+                    .as_ref()
+                    .is_some_and(|o| o.status == OutcomeStatus::Failed);
+                report.outcomes.extend(outcome);
+                match applied {
+                    Err(e) if failed => return Err(self.clean_up(report, migration, e).await),
+                    Err(e) => return Err(e),
+                    Ok(()) => {}
+                }
+            }
+            Ok(())
+        }
+
+        /// Apply the migrations of `plan` up to `parallelism` at a time,
+        /// each once the migrations it depends on are applied. `pending()`
+        /// rules out cycles, so every migration gets its turn.
+        async fn apply_graph(
+            &self,
+            plan: &[(Migration, String)],
+            run_span: &Span,
+            report: &mut RunReport,
+        ) -> Result<()> {
+            let total = plan.len();
+            let depends = plan
+                .iter()
+                .map(|(migration, _)| {
+                    migration
+                        .metadata
+                        .depends
+                        .iter()
+                        .filter_map(|name| {
+                            plan.iter()
+                                .position(|(m, _)| crate::validate::refers_to(m, name))
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let mut started = vec![false; total];
+            let mut applied = vec![false; total];
+            let mut running = FuturesUnordered::new();
+            let mut failed = None;
+            let mut stopped = None;
+            loop {
+                while failed.is_none() && stopped.is_none() && running.len() < self.parallelism {
+                    let Some(next) =
+                        (0..total).find(|&i| !started[i] && depends[i].iter().all(|&d| applied[d]))
+                    else {
+                        break;
+                    };
+                    if let Err(e) = self.check_cancelled() {
+                        stopped = Some(e);
+                        break;
+                    }
+                    started[next] = true;
+                    let (migration, content) = &plan[next];
+                    running.push(async move {
+                        let result = self
+                            .apply_hooked(run_span, next, total, migration, content)
+                            .await;
+                        (next, result)
+                    });
+                }
+                let Some((index, (outcome, result))) = running.next().await else {
+                    break;
+                };
+                let migration_failed = outcome
+                    .as_ref()
+                    .is_some_and(|o| o.status == OutcomeStatus::Failed);
+                report.outcomes.extend(outcome);
+                match result {
+                    Ok(()) => applied[index] = true,
+                    Err(e) if failed.is_none() => failed = Some((index, e, migration_failed)),
+                    Err(e) => tracing::error!("{e:#}"),
+                }
+            }
+
+            match failed {
+                Some((index, e, true)) => {
+                    return Err(self.clean_up(report, &plan[index].0, e).await);
+                }
+                Some((_, e, false)) => return Err(e),
+                None => {}
+            }
+            match stopped {
+                Some(e) => Err(e),
+                None => Ok(()),
+            }
+        }
+
+        /// `apply_one` between the before-each and after-each hooks. The
+        /// outcome is missing when a before-each hook failed; a failed hook
+        /// leaves the migration as it was.
+        async fn apply_hooked(
+            &self,
+            run_span: &Span,
+            index: usize,
+            total: usize,
+            migration: &Migration,
+            content: &str,
+        ) -> (Option<MigrationOutcome>, Result<()>) {
+            let name = Some(migration.name.as_str());
+            if let Err(e) = self
+                .run_hooks(HookPoint::BeforeEach, RunDirection::Up, name, None)
+                .await
+            {
+                return (None, Err(e));
+            }
+            let (outcome, applied) = self
+                .apply_one(run_span, index, total, migration, content)
+                .await;
+            let applied = match applied {
+                Ok(()) => {
+                    self.run_hooks(HookPoint::AfterEach, RunDirection::Up, name, None)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            (Some(outcome), applied)
+        }
+
+        /// Apply `migration`, the `index`-th of the `total` migrations of
+        /// the run traced by `run_span`, reporting its progress. Returns its
+        /// outcome and the error it failed with.
+        async fn apply_one(
+            &self,
+            run_span: &Span,
+            index: usize,
+            total: usize,
+            migration: &Migration,
+            content: &str,
+        ) -> (MigrationOutcome, Result<()>) {
+            self.progress(ProgressEvent::MigrationStarted {
+                name: migration.name.clone(),
+                index,
+                total,
+            });
+            self.event(MigrationEvent::Started {
+                name: migration.name.clone(),
+            });
+            let migration_started = Instant::now();
+            let span = telemetry::migration(run_span, RunDirection::Up, migration, content);
+            let applied = async {
+                self.execute(migration, content, migration.metadata.transactional())
+                    .await?;
+                self.record_migration(migration, content, false).await
+            }
+            .instrument(span.clone())
+            .await;
+            let duration_ms = elapsed_ms(migration_started);
+            let status = match applied {
+                Ok(()) => OutcomeStatus::Applied,
+                Err(_) => OutcomeStatus::Failed,
+            };
+            telemetry::finish_migration(&span, RunDirection::Up, Some(status), duration_ms);
+            self.progress(ProgressEvent::MigrationFinished {
+                name: migration.name.clone(),
+                index,
+                total,
+                status: Some(status),
+                duration_ms,
+            });
+
+            self.event(match &applied {
+                Ok(()) => MigrationEvent::Applied {
+                    name: migration.name.clone(),
+                    duration_ms,
+                },
+                Err(e) => MigrationEvent::Failed {
+                    name: migration.name.clone(),
+                    error: e.to_string(),
+                },
+            });
+            if applied.is_ok() {
+                tracing::info!("Applied migration: {}", migration.name);
+            }
+
+            let outcome = MigrationOutcome {
+                name: migration.name.clone(),
+                status,
+                duration_ms,
+                error: applied.as_ref().err().map(|e| e.to_string()),
+            };
+            (outcome, applied)
+        }
+
+        /// Revert applied migrations, most recently applied first.
+        ///
+        /// For `Paired` migrations this runs the embedded `down.surql`. For
+        /// up-only file migrations the runner attempts basic heuristics to
+        /// locate a sibling down script. After a successful revert the
+        /// migration record is removed from the `migrations` table.
+        /// Repeatable migrations have no down script and are left in place.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// # async fn revert_example(runner: &MigrationRunner<'_, _, _>) -> eyre::Result<()> {
+        /// runner.down().await?;
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn down(&self) -> Result<MigrationReport> {
+            self.revert(Revert::All).await
+        }
+
+        /// Revert the migrations of the most recent batch: the versioned
+        /// migrations the last `up()` applied, undoing a deploy. A run that
+        /// only re-applied repeatables does not count.
+        ///
+        /// Fails when the applied migrations were recorded before batches
+        /// were tracked.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// let report = runner.down_last_batch().await?;
+        /// println!("reverted {} migration(s)", report.reverted().count());
+        /// ```
+        pub async fn down_last_batch(&self) -> Result<MigrationReport> {
+            self.revert(Revert::LastBatch).await
+        }
+
+        /// Revert every migration applied after `target`, leaving `target`
+        /// itself applied.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// runner.down_to("003_add_index").await?;
+        /// ```
+        pub async fn down_to(&self, target: &str) -> Result<MigrationReport> {
+            self.revert(Revert::Keep(target)).await
+        }
+
+        /// Revert to the migration the tag `name` points at.
+        ///
+        /// See `tag()`.
+        pub async fn down_to_tag(&self, name: &str) -> Result<MigrationReport> {
+            let tag = self.get_tag(name).await?;
+            self.revert(Revert::Keep(&tag.migration)).await
+        }
+
+        /// Revert applied migrations newest first, as far as `target` says.
+        async fn revert(&self, target: Revert<'_>) -> Result<MigrationReport> {
+            self.start_run();
+            self.ensure_migrations_table_exists().await?;
+            self.locked(async {
+                self.ensure_clean().await?;
+                self.revert_locked(target).await
+            })
+            .await
+        }
+
+        /// Revert applied migrations, newest first, as far as `target` says.
+        /// The caller holds the lock.
+        async fn revert_locked(&self, target: Revert<'_>) -> Result<MigrationReport> {
+            let migrations = self.migrations()?;
+            let records = self.get_applied_migrations().await?;
+            let applied = records
+                .iter()
+                .map(|r| r.name.as_str())
+                .collect::<HashSet<_>>();
+
+            if let Revert::Keep(stop) = target
+                && !applied.contains(stop)
+            {
+                eyre::bail!("migration {stop} is not applied");
+            }
+            let batches = records
+                .iter()
+                .map(|r| (r.name.as_str(), r.batch))
+                .collect::<HashMap<_, _>>();
+            // Repeatables are never reverted, so a run that only re-applied
+            // one does not count as the last batch.
+            let order = applied_order(&migrations, &records);
+            let last_batch = order
+                .iter()
+                .filter_map(|m| batches.get(m.name.as_str()).copied().flatten())
+                .max();
+            if target == Revert::LastBatch && last_batch.is_none() && !order.is_empty() {
+                eyre::bail!("the applied migrations predate batch tracking; revert them by name");
+            }
+
+            // Revert newest applied first, which differs from discovery order
+            // when migrations were applied out of order.
+            let mut plan = Vec::new();
+            for migration in order.into_iter().rev().take_while(|m| match target {
+                Revert::All => true,
+                Revert::Keep(stop) => m.name != stop,
+                Revert::LastBatch => batches.get(m.name.as_str()) == Some(&last_batch),
+            }) {
+                let down_content = self.source.get_down(migration)?;
+                plan.push((migration, down_content));
+            }
+
+            self.check_scripts(
+                plan.iter()
+                    .filter_map(|(m, c)| c.as_deref().map(|c| (*m, c))),
+            )?;
+
+            let fingerprint = fingerprint(
+                plan.iter()
+                    .filter_map(|(m, c)| c.as_deref().map(|c| (m.name.as_str(), c))),
+            );
+            let mut report = RunReport::new(RunDirection::Down, fingerprint);
+            let started = Instant::now();
+
+            let total = plan.len();
+            if total > 0 {
+                self.run_hooks(HookPoint::BeforeAll, RunDirection::Down, None, None)
+                    .await?;
+            }
+            self.progress(ProgressEvent::RunStarted {
+                direction: RunDirection::Down,
+                total,
+            });
+            let run_span = telemetry::run(RunDirection::Down, total);
+            let mut skipped = Vec::new();
+            let mut result = Ok(());
+            for (index, (migration, down_content)) in plan.into_iter().enumerate() {
+                if let Err(e) = self.check_cancelled() {
+                    result = Err(e);
+                    break;
+                }
+                self.progress(ProgressEvent::MigrationStarted {
+                    name: migration.name.clone(),
+                    index,
+                    total,
+                });
+                if let Some(content) = down_content {
+                    let name = Some(migration.name.as_str());
+                    if let Err(e) = self
+                        .run_hooks(HookPoint::BeforeEach, RunDirection::Down, name, None)
+                        .await
+                    {
+                        result = Err(e);
+                        break;
+                    }
+                    let migration_started = Instant::now();
+                    let transactional = MigrationMetadata::parse(&content).transactional();
+                    let span =
+                        telemetry::migration(&run_span, RunDirection::Down, migration, &content);
+                    let reverted = async {
+                        self.execute(migration, &content, transactional).await?;
+                        self.remove_migration_record(&migration.name).await
+                    }
+                    .instrument(span.clone())
+                    .await;
+                    let duration_ms = elapsed_ms(migration_started);
+                    let status = if reverted.is_ok() {
+                        OutcomeStatus::Reverted
+                    } else {
+                        OutcomeStatus::Failed
+                    };
+                    telemetry::finish_migration(
+                        &span,
+                        RunDirection::Down,
+                        Some(status),
+                        duration_ms,
+                    );
+                    self.progress(ProgressEvent::MigrationFinished {
+                        name: migration.name.clone(),
+                        index,
+                        total,
+                        status: Some(status),
+                        duration_ms,
+                    });
+
+                    if let Err(e) = reverted {
+                        report.outcomes.push(MigrationOutcome {
+                            name: migration.name.clone(),
+                            status: OutcomeStatus::Failed,
+                            duration_ms,
+                            error: Some(e.to_string()),
+                        });
+                        result = Err(e);
+                        break;
+                    }
+
+                    report.outcomes.push(MigrationOutcome {
+                        name: migration.name.clone(),
+                        status: OutcomeStatus::Reverted,
+                        duration_ms,
+                        error: None,
+                    });
+                    tracing::info!("Reverted migration: {}", migration.name);
+                    if let Err(e) = self
+                        .run_hooks(HookPoint::AfterEach, RunDirection::Down, name, None)
+                        .await
+                    {
+                        result = Err(e);
+                        break;
+                    }
+                } else {
+                    tracing::warn!(migration = %migration.name, "no down script found; skipping");
+                    self.warn(format!(
+                        "{} has no down script and was left applied",
+                        migration.name
+                    ));
+                    skipped.push(migration.name.clone());
+                    let span = telemetry::migration(&run_span, RunDirection::Down, migration, "");
+                    telemetry::finish_migration(&span, RunDirection::Down, None, 0);
+                    self.progress(ProgressEvent::MigrationFinished {
+                        name: migration.name.clone(),
+                        index,
+                        total,
+                        status: None,
+                        duration_ms: 0,
+                    });
+                }
+            }
+            if total > 0 {
+                result = self.after_all(RunDirection::Down, result).await;
+            }
+            self.progress(ProgressEvent::RunFinished {
+                direction: RunDirection::Down,
+                succeeded: result.is_ok(),
+            });
+            telemetry::finish_run(
+                &run_span,
+                RunDirection::Down,
+                result.is_ok(),
+                elapsed_ms(started),
+            );
+
+            let outcomes = report.outcomes.clone();
+            let persisted = self.finish_run(report, started, &result).await;
+            recorded(result, persisted)?;
+            Ok(self.report(RunDirection::Down, outcomes, skipped, started))
+        }
+
+        /// Revert the most recently applied migration and apply it again.
+        ///
+        /// This is the usual loop while iterating on a new migration: edit the
+        /// file, call `redo()`, inspect the result. The migration must have a
+        /// down script.
+        ///
+        /// "Most recent" is the versioned migration applied last, which is not
+        /// the last discovered one when migrations ran out of order.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// runner.redo().await?;
+        /// ```
+        pub async fn redo(&self) -> Result<()> {
+            self.redo_target(None).await
+        }
+
+        /// Revert the applied migration `name` and apply it again.
+        ///
+        /// Unlike `redo()` this may target any applied migration; later
+        /// migrations are left untouched, so only use it when they do not
+        /// depend on what `name` changes.
+        pub async fn redo_named(&self, name: &str) -> Result<()> {
+            self.redo_target(Some(name)).await
+        }
+
+        async fn redo_target(&self, name: Option<&str>) -> Result<()> {
+            self.start_run();
+            self.ensure_migrations_table_exists().await?;
+            self.locked(async {
+                self.ensure_clean().await?;
+
+                let migrations = self.migrations()?;
+                let records = self.get_applied_migrations().await?;
+                let mut candidates = applied_order(&migrations, &records).into_iter().cloned();
+                let migration = match name {
+                    Some(name) => candidates
+                        .find(|m| m.name == name)
+                        .ok_or_else(|| eyre!("migration {name} is not applied"))?,
+                    None => candidates
+                        .next_back()
+                        .ok_or_else(|| eyre!("no applied migrations to redo"))?,
+                };
+
+                let down = self
+                    .source
+                    .get_down(&migration)?
+                    .ok_or_else(|| eyre!("migration {} has no down script", migration.name))?;
+                let up = self.source.get_up(&migration)?;
+                self.check_scripts(
+                    [(&migration, down.as_str()), (&migration, up.as_str())].into_iter(),
+                )?;
+
+                let fingerprint = fingerprint([
+                    (migration.name.as_str(), down.as_str()),
+                    (migration.name.as_str(), up.as_str()),
+                ]);
+                let mut report = RunReport::new(RunDirection::Redo, fingerprint);
+                let started = Instant::now();
+
+                let transactional = MigrationMetadata::parse(&down).transactional();
+                let mut result = self
+                    .step(
+                        &mut report,
+                        &migration.name,
+                        OutcomeStatus::Reverted,
+                        async {
+                            self.execute(&migration, &down, transactional).await?;
+                            self.remove_migration_record(&migration.name).await
+                        },
+                    )
+                    .await;
+                if result.is_ok() {
+                    result = self
+                        .step(
+                            &mut report,
+                            &migration.name,
+                            OutcomeStatus::Applied,
+                            async {
+                                self.execute(&migration, &up, migration.metadata.transactional())
+                                    .await?;
+                                self.record_migration(&migration, &up, false).await
+                            },
+                        )
+                        .await;
+                }
+
+                let persisted = self.finish_run(report, started, &result).await;
+                let result = recorded(result, persisted);
+                if result.is_ok() {
+                    tracing::info!("Redid migration: {}", migration.name);
+                }
+                result
+            })
+            .await
+        }
+
+        /// Record a named checkpoint, such as a release version, pointing at
+        /// the most recently applied migration: the one applied last, as in
+        /// `redo()`, not the last one discovered.
+        ///
+        /// Tags are unique; tagging twice with the same name fails. Use
+        /// `down_to_tag()` to roll back to a tag and `status_since_tag()` to
+        /// see what changed after it.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// let tag = runner.tag("v2.3.0").await?;
+        /// println!("v2.3.0 -> {}", tag.migration);
+        /// ```
+        pub async fn tag(&self, name: &str) -> Result<Tag> {
+            self.ensure_migrations_table_exists().await?;
+            let migrations = self.migrations()?;
+            let records = self.get_applied_migrations().await?;
+            let migration = applied_order(&migrations, &records)
+                .pop()
+                .ok_or_else(|| eyre!("no applied migrations to tag"))?;
+
+            let tag = Tag {
+                name: name.to_owned(),
+                migration: migration.name.clone(),
+                created_at: None,
+            };
+            let created: Option<Tag> = self
+                .bookkeeping("CREATE ONLY type::thing('migration_tags', $name) CONTENT $tag")?
+                .bind(("name", name.to_owned()))
+                .bind(("tag", tag))
+                .await
+                .map_err(|e| eyre!("failed to create tag {name}: {e}"))?
+                .take(self.at(0))
+                .map_err(|e| eyre!("failed to create tag {name}: {e}"))?;
+            created.ok_or_else(|| eyre!("failed to create tag {name}"))
+        }
+
+        /// All recorded tags, oldest first.
+        pub async fn tags(&self) -> Result<Vec<Tag>> {
+            self.ensure_migrations_table_exists().await?;
+            let mut response = self
+                .bookkeeping("SELECT * FROM migration_tags ORDER BY created_at ASC")?
+                .await
+                .map_err(|e| eyre!(e.to_string()))?;
+            response.take(self.at(0)).map_err(|e| eyre!(e.to_string()))
+        }
+
+        /// Status of every migration discovered after the one tag `name`
+        /// points at.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// for m in runner.status_since_tag("v2.2.0").await? {
+        ///     println!("{} applied={}", m.name, m.applied);
+        /// }
+        /// ```
+        pub async fn status_since_tag(&self, name: &str) -> Result<Vec<MigrationStatus>> {
+            let tag = self.get_tag(name).await?;
+            let statuses = self.status().await?;
+            let start = statuses
+                .iter()
+                .position(|s| s.name == tag.migration)
+                .ok_or_else(|| {
+                    eyre!(
+                        "tag {name} points at {}, which no longer exists",
+                        tag.migration
+                    )
+                })?;
+            Ok(statuses.into_iter().skip(start + 1).collect())
+        }
+
+        async fn get_tag(&self, name: &str) -> Result<Tag> {
+            self.ensure_migrations_table_exists().await?;
+            let tag: Option<Tag> = self
+                .bookkeeping("SELECT * FROM ONLY type::thing('migration_tags', $name)")?
+                .bind(("name", name.to_owned()))
+                .await
+                .map_err(|e| eyre!(e.to_string()))?
+                .take(self.at(0))
+                .map_err(|e| eyre!(e.to_string()))?;
+            tag.ok_or_else(|| eyre!("unknown tag: {name}"))
+        }
+
+        /// The dirty marker left by a failed migration, if any.
+        pub async fn dirty(&self) -> Result<Option<DirtyState>> {
+            self.ensure_migrations_table_exists().await?;
+            self.retrying("reading the dirty marker", || async {
+                self.bookkeeping("SELECT * FROM ONLY migration_state:dirty")?
+                    .await
+                    .map_err(|e| eyre!(e.to_string()))?
+                    .take(self.at(0))
+                    .map_err(|e| eyre!(e.to_string()))
+            })
+            .await
+        }
+
+        /// Clear the dirty marker so `up()` and `down()` can run again.
+        ///
+        /// Call this once the partial effects of the failed migration have
+        /// been fixed or undone. Returns the marker that was cleared.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// if let Some(state) = runner.repair().await? {
+        ///     println!("cleared failure of {}", state.migration);
+        /// }
+        /// ```
+        pub async fn repair(&self) -> Result<Option<DirtyState>> {
+            self.ensure_migrations_table_exists().await?;
+            let cleared: Option<DirtyState> = self
+                .bookkeeping("DELETE ONLY migration_state:dirty RETURN BEFORE")?
+                .await
+                .map_err(|e| eyre!(e.to_string()))?
+                .take(self.at(0))
+                .map_err(|e| eyre!(e.to_string()))?;
+            if let Some(state) = &cleared {
+                tracing::info!(migration = %state.migration, "cleared dirty state");
+            }
+            Ok(cleared)
+        }
+
+        /// Compare every applied versioned migration against its current
+        /// content.
+        ///
+        /// Returns one `ChecksumMismatch` per edited migration, with a unified
+        /// diff when the applied SQL was recorded. Repeatable migrations are
+        /// expected to change and are not reported.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// for mismatch in runner.verify().await? {
+        ///     eprintln!("{} changed since it was applied", mismatch.name);
+        ///     if let Some(diff) = &mismatch.diff {
+        ///         eprintln!("{diff}");
+        ///     }
+        /// }
+        /// ```
+        pub async fn verify(&self) -> Result<Vec<ChecksumMismatch>> {
+            self.ensure_migrations_table_exists().await?;
+            let applied = self.get_applied_migrations().await?;
+            let versioned = self
+                .migrations()?
+                .into_iter()
+                .filter(|m| m.kind != MigrationKind::Repeatable)
+                .collect::<Vec<_>>();
+            self.mismatches(&versioned, &applied)
+        }
+
+        /// Migrations sharing a version prefix or out of order with the
+        /// order this database applied them in, with renames that resolve
+        /// them. See `validate::conflicts`.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// let (conflicts, renames) = runner.check_conflicts().await?;
+        /// for rename in renames {
+        ///     eprintln!("smg rename {} {}", rename.from, rename.to);
+        /// }
+        /// ```
+        pub async fn check_conflicts(
+            &self,
+        ) -> Result<(
+            Vec<crate::validate::Conflict>,
+            Vec<crate::validate::Renumber>,
+        )> {
+            self.ensure_migrations_table_exists().await?;
+            let migrations = self.migrations()?;
+            let records = self.get_applied_migrations().await?;
+            let applied = applied_order(&migrations, &records)
+                .into_iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>();
+            Ok(crate::validate::conflicts(&migrations, &applied))
+        }
+
+        /// Snapshot the schema of the migrated database (see
+        /// `schema::SchemaSnapshot`), leaving out the runner's own tables.
+        pub async fn schema_snapshot(&self) -> Result<crate::schema::SchemaSnapshot> {
+            let table = self.table()?;
+            Ok(crate::schema::SchemaSnapshot::capture(self.db)
+                .await?
+                .without_tables([
+                    table,
+                    "migration_runs",
+                    "migration_tags",
+                    "migration_state",
+                    "migration_lock",
+                ]))
+        }
+
+        /// Compare the live schema with the one the applied migrations
+        /// produce, to catch objects changed out of band (e.g. a hotfix run
+        /// by hand in production).
+        ///
+        /// The applied migrations are replayed, in the order they were
+        /// applied, on `scratch`, which must be an empty database such as a
+        /// `mem://` one. Each returned change turns the replayed schema into
+        /// the live one: "added index user.email" means the index exists
+        /// only in the live database. Empty when the schemas match.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// let scratch = surrealdb::engine::any::connect("mem://").await?;
+        /// scratch.use_ns("drift").use_db("drift").await?;
+        /// for change in runner.verify_schema(&scratch).await? {
+        ///     eprintln!("{change}: {}", change.sql);
+        /// }
+        /// ```
+        pub async fn verify_schema(
+            &self,
+            scratch: &Surreal<E>,
+        ) -> Result<Vec<crate::schema::SchemaChange>> {
+            self.ensure_migrations_table_exists().await?;
+            if crate::schema::SchemaSnapshot::capture(scratch).await?
+                != crate::schema::SchemaSnapshot::default()
+            {
+                eyre::bail!("the scratch database to replay migrations on is not empty");
+            }
+
+            let migrations = self.migrations()?;
+            let records = self.get_applied_migrations().await?;
+            let applied = records
+                .iter()
+                .map(|r| r.name.as_str())
+                .collect::<HashSet<_>>();
+            let mut replayed = applied_order(&migrations, &records)
+                .into_iter()
+                .cloned()
+                .collect::<Vec<_>>();
+            replayed.extend(
+                migrations
+                    .iter()
+                    .filter(|m| m.kind == MigrationKind::Repeatable)
+                    .filter(|m| applied.contains(m.name.as_str()))
+                    .cloned(),
+            );
+            tracing::debug!(migrations = replayed.len(), "replaying applied migrations");
+
+            // `{{ db.param.* }}` placeholders take the live database's values.
+            let scripts = replayed
+                .iter()
+                .map(|m| self.source.get_up(m))
+                .collect::<Result<Vec<_>>>()?;
+            self.db_params.lock().unwrap().clear();
+            self.load_db_params(scripts.iter().map(String::as_str))
+                .await?;
+            let db_params = self.db_params.lock().unwrap().clone();
+            let variables = match &self.variables {
+                Some(variables) => Some(variables.clone().into_iter().chain(db_params).collect()),
+                None if db_params.is_empty() => None,
+                None => Some(db_params),
+            };
+
+            let mut replay = MigrationRunner::new(
+                scratch,
+                Replay {
+                    source: &self.source,
+                    migrations: replayed,
+                },
+            )
+            .with_execution_mode(self.execution)
+            .with_use_policy(self.use_policy);
+            replay.variables = variables;
+            replay.environment = self.environment.clone();
+            replay.up().await?;
+
+            let expected = replay.schema_snapshot().await?;
+            Ok(expected.changes_to(&self.schema_snapshot().await?))
+        }
+
+        /// Report, for every discovered migration, whether it has been applied
+        /// and whether its content still matches the applied checksum.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// # async fn status_example(runner: &MigrationRunner<'_, _, _>) -> eyre::Result<()> {
+        /// for m in runner.status().await? {
+        ///     println!("{} applied={} matches={:?}", m.name, m.applied, m.checksum_matches());
+        /// }
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn status(&self) -> Result<Vec<MigrationStatus>> {
+            self.ensure_migrations_table_exists().await?;
+
+            let applied = self.get_applied_migrations().await?;
+            let applied = applied
+                .into_iter()
+                .map(|r| (r.name.clone(), r))
+                .collect::<HashMap<_, _>>();
+
+            let mut statuses = Vec::new();
+            for migration in self.migrations()? {
+                let content = self.source.get_up(&migration)?;
+                let record = applied.get(&migration.name);
+                statuses.push(MigrationStatus {
+                    applied: record.is_some(),
+                    applied_checksum: record.and_then(|r| r.checksum.clone()),
+                    baseline: record.is_some_and(|r| r.baseline),
+                    applied_at: record.and_then(|r| r.applied_at.clone()),
+                    applied_seq: record.and_then(|r| r.applied_seq),
+                    batch: record.and_then(|r| r.batch),
+                    checksum: match record {
+                        Some(record) => self.checksum_for(record, &content)?,
+                        None => self.hasher.checksum(&content),
+                    },
+                    name: migration.name,
+                    kind: migration.kind,
+                });
+            }
+
+            Ok(statuses)
+        }
+
+        /// List the persisted reports of past `up()`/`down()` runs, oldest first.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// # async fn runs_example(runner: &MigrationRunner<'_, _, _>) -> eyre::Result<()> {
+        /// for run in runner.runs().await? {
+        ///     println!("{:?}: {} migration(s)", run.direction, run.outcomes.len());
+        /// }
+        /// # Ok(())
+        /// # }
+        /// ```
+        pub async fn runs(&self) -> Result<Vec<RunReport>> {
+            let mut response = self
+                .bookkeeping("SELECT * FROM migration_runs ORDER BY finished_at ASC;")?
+                .await
+                .map_err(|e| eyre!(e.to_string()))?;
+            let runs: Vec<RunReport> = response
+                .take(self.at(0))
+                .map_err(|e| eyre!(e.to_string()))?;
+            Ok(runs)
+        }
+
+        /// Fetch a single persisted run report by id.
+        ///
+        /// `id` may be either the record key or the full record id
+        /// (`migration_runs:<key>`). Returns `Ok(None)` if no such run exists.
+        pub async fn run(&self, id: &str) -> Result<Option<RunReport>> {
+            let key = id.strip_prefix("migration_runs:").unwrap_or(id);
+            let key = key.trim_start_matches('⟨').trim_end_matches('⟩');
+            let run: Option<RunReport> = self
+                .bookkeeping("SELECT * FROM ONLY type::thing('migration_runs', $key)")?
+                .bind(("key", key.to_owned()))
+                .await
+                .map_err(|e| eyre!(e.to_string()))?
+                .take(self.at(0))
+                .map_err(|e| eyre!(e.to_string()))?;
+            Ok(run)
+        }
+
+        /// Fetch the report of the latest run of this runner, whether it
+        /// succeeded or failed.
+        ///
+        /// Unlike taking the newest entry of `runs()`, this is never a run
+        /// another process recorded meanwhile. Returns `Ok(None)` before the
+        /// first run, and when the latest run ended before its report was
+        /// persisted.
+        ///
+        /// # Example
+        ///
+        /// ```rust,ignore
+        /// let result = runner.up().await;
+        /// if let Some(run) = runner.last_run().await? {
+        ///     println!("{:?}: {:?}", run.id, run.error);
+        /// }
+        /// ```
+        pub async fn last_run(&self) -> Result<Option<RunReport>> {
+            let id = self.last_run.lock().unwrap().clone();
+            match id {
+                Some(id) => self.run(&id.to_string()).await,
+                None => Ok(None),
+            }
+        }
+
+        /// Apply the out-of-order policy to the pending migrations in `versioned`.
+        fn check_order(
+            &self,
+            versioned: &[Migration],
+            applied: &HashMap<&str, Option<&str>>,
+        ) -> Result<()> {
+            let Some(last_applied) = versioned
+                .iter()
+                .rposition(|m| applied.contains_key(m.name.as_str()))
+            else {
+                return Ok(());
+            };
+
+            let out_of_order = versioned[..last_applied]
+                .iter()
+                .filter(|m| !applied.contains_key(m.name.as_str()))
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>();
+            if out_of_order.is_empty() {
+                return Ok(());
+            }
+
+            let latest = &versioned[last_applied].name;
+            match self.out_of_order {
+                OutOfOrderPolicy::Error => eyre::bail!(
+                    "pending migrations sort before already-applied `{latest}`: {}",
+                    out_of_order.join(", ")
+                ),
+                OutOfOrderPolicy::Warn => {
+                    for name in out_of_order {
+                        tracing::warn!(migration = %name, latest = %latest, "applying migration out of order");
+                        self.warn(format!("{name} applied out of order, after {latest}"));
+                    }
+                }
+                OutOfOrderPolicy::Allow => {
+                    tracing::debug!(
+                        count = out_of_order.len(),
+                        "applying migrations out of order"
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        /// Detect drift and apply the configured resolver's decisions.
+        async fn resolve_drift(&self) -> Result<()> {
+            let Some(resolver) = &self.resolver else {
+                return Ok(());
+            };
+
+            let migrations = self.migrations()?;
+            let applied = self.get_applied_migrations().await?;
+            let versioned = migrations
+                .iter()
+                .filter(|m| m.kind != MigrationKind::Repeatable)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            let mut drift = self
+                .mismatches(&versioned, &applied)?
+                .into_iter()
+                .map(|m| Drift {
+                    name: m.name,
+                    kind: DriftKind::ChecksumMismatch { diff: m.diff },
+                })
+                .collect::<Vec<_>>();
+            let known = migrations
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<HashSet<_>>();
+            drift.extend(
+                applied
+                    .iter()
+                    .filter(|r| !known.contains(r.name.as_str()))
+                    .map(|r| Drift {
+                        name: r.name.clone(),
+                        kind: DriftKind::MissingSource,
+                    }),
+            );
+
+            let mut repairs = Vec::new();
+            for item in drift {
+                match resolver.resolve(&item) {
+                    Resolution::Accept => {
+                        tracing::warn!(migration = %item.name, "accepted drift");
+                        self.warn(format!("accepted drift in {}", item.name));
+                    }
+                    Resolution::Repair => repairs.push(item),
+                    Resolution::Abort => {
+                        eyre::bail!("aborted on drift in migration {}", item.name);
+                    }
+                }
+            }
+
+            for item in repairs {
+                match item.kind {
+                    DriftKind::ChecksumMismatch { .. } => {
+                        let migration = versioned
+                            .iter()
+                            .find(|m| m.name == item.name)
+                            .expect("mismatch refers to a discovered migration");
+                        let content = self.source.get_up(migration)?;
+                        self.bookkeeping(format!(
+                                "UPDATE {} SET checksum = $checksum, algorithm = $algorithm, content = $content WHERE name = $name",
+                                self.table()?
+                            ))?
+                            .bind(("checksum", self.hasher.checksum(&content)))
+                            .bind(("algorithm", self.hasher.algorithm().to_owned()))
+                            .bind(("content", content))
+                            .bind(("name", item.name.clone()))
+                            .await
+                            .map_err(|e| eyre!(e.to_string()))?;
+                    }
+                    DriftKind::MissingSource => {
+                        self.remove_migration_record(&item.name).await?;
+                    }
+                }
+                tracing::info!(migration = %item.name, "repaired drift");
+            }
+            Ok(())
+        }
+
+        /// Collect applied migrations whose current content differs from the
+        /// recorded checksum.
+        fn mismatches(
+            &self,
+            migrations: &[Migration],
+            applied: &[MigrationRecord],
+        ) -> Result<Vec<ChecksumMismatch>> {
+            let applied = applied
+                .iter()
+                .map(|r| (r.name.as_str(), r))
+                .collect::<HashMap<_, _>>();
+
+            let mut mismatches = Vec::new();
+            for migration in migrations {
+                let Some(record) = applied.get(migration.name.as_str()) else {
+                    continue;
+                };
+                let Some(applied_checksum) = &record.checksum else {
+                    continue;
+                };
+                let content = self.source.get_up(migration)?;
+                let current = self.checksum_for(record, &content)?;
+                if *applied_checksum != current {
+                    mismatches.push(ChecksumMismatch {
+                        name: migration.name.clone(),
+                        applied_checksum: applied_checksum.clone(),
+                        checksum: current,
+                        diff: record
+                            .content
+                            .as_deref()
+                            .filter(|_| !migration.metadata.sensitive())
+                            .map(|old| diff::unified(&migration.name, old, &content)),
+                    });
+                }
+            }
+            Ok(mismatches)
+        }
+
+        /// Checksum `content` with the algorithm `record` was written with.
+        fn checksum_for(&self, record: &MigrationRecord, content: &str) -> Result<String> {
+            let algorithm = record
+                .algorithm
+                .as_deref()
+                .unwrap_or(checksum::DEFAULT_ALGORITHM);
+            if algorithm == self.hasher.algorithm() {
+                return Ok(self.hasher.checksum(content));
+            }
+            checksum::builtin(algorithm)
+                .map(|hasher| hasher.checksum(content))
+                .ok_or_else(|| {
+                    eyre!(
+                        "migration {} was recorded with unsupported checksum algorithm {algorithm}",
+                        record.name
+                    )
+                })
+        }
+
+        /// Fail unless the source matches the lock file, when one is set.
+        fn check_lock_file(&self) -> Result<()> {
+            let Some(lock) = &self.lock_file else {
+                return Ok(());
+            };
+            let mismatches = lock.verify(&self.source)?;
+            if !mismatches.is_empty() {
+                eyre::bail!(
+                    "migrations do not match {}: {}; run `smg lock` after reviewing the change",
+                    crate::lockfile::LOCK_FILE,
+                    mismatches
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                );
+            }
+            Ok(())
+        }
+
+        /// Refuse planned scripts that touch tables outside `owned_tables`
+        /// or, under `UsePolicy::Reject`, contain `USE` statements.
+        fn check_scripts<'m>(
+            &self,
+            plan: impl Iterator<Item = (&'m Migration, &'m str)>,
+        ) -> Result<()> {
+            let plan = plan.collect::<Vec<_>>();
+            if self.use_policy == UsePolicy::Reject {
+                let mut violations = Vec::new();
+                for (migration, content) in &plan {
+                    let lines = statements::split(content)
+                        .iter()
+                        .filter(|s| s.kind() == StatementKind::Use)
+                        .map(|s| s.line.to_string())
+                        .collect::<Vec<_>>();
+                    if !lines.is_empty() {
+                        violations.push(format!("{} (line {})", migration.name, lines.join(", ")));
+                    }
+                }
+                if !violations.is_empty() {
+                    eyre::bail!(
+                        "migrations must not switch namespace or database with USE: {}",
+                        violations.join("; ")
+                    );
+                }
+            }
+
+            let Some(owned) = &self.owned_tables else {
+                return Ok(());
+            };
+
+            let mut violations = Vec::new();
+            for (migration, content) in plan {
+                let mut foreign = statements::split(content)
+                    .iter()
+                    .flat_map(|s| s.tables())
+                    .filter(|t| !owned.contains(t))
+                    .collect::<Vec<_>>();
+                foreign.sort();
+                foreign.dedup();
+                if !foreign.is_empty() {
+                    violations.push(format!("{} ({})", migration.name, foreign.join(", ")));
+                }
+            }
+
+            if !violations.is_empty() {
+                eyre::bail!(
+                    "migrations touch tables outside the owned set: {}",
+                    violations.join("; ")
+                );
+            }
+            Ok(())
+        }
+
+        /// Execute `content`, one of `migration`'s scripts, according to the
+        /// configured `ExecutionMode`. It runs in the namespace and database
+        /// the up script's header declares, so a down script undoes the up
+        /// script where it ran.
+        async fn execute(
+            &self,
+            migration: &Migration,
+            content: &str,
+            transactional: bool,
+        ) -> Result<()> {
+            self.execute_with(migration, content, transactional, HashMap::new())
+                .await
+        }
+
+        /// `execute()` with `defaults` as the values of placeholders that
+        /// neither the runner's variables nor the database give one.
+        async fn execute_with(
+            &self,
+            migration: &Migration,
+            content: &str,
+            transactional: bool,
+            defaults: HashMap<String, String>,
+        ) -> Result<()> {
+            let name = migration.name.as_str();
+            let stripped;
+            let content = if self.use_policy == UsePolicy::Strip
+                && statements::split(content)
+                    .iter()
+                    .any(|s| s.kind() == StatementKind::Use)
+            {
+                tracing::warn!(migration = %name, "removing USE statements before executing");
+                self.warn(format!("removed USE statements from {name}"));
+                stripped = strip_use(content);
+                stripped.as_str()
+            } else {
+                content
+            };
+
+            let uses_db_params = placeholders::names(content)
+                .iter()
+                .any(|n| n.starts_with(DB_PARAM_PREFIX));
+            if uses_db_params {
+                self.load_db_params([content])
+                    .await
+                    .map_err(|e| eyre!("{name}: {e}"))?;
+            }
+
+            let uses_defaults = placeholders::names(content)
+                .iter()
+                .any(|n| defaults.contains_key(n));
+            let substituted;
+            let content = if self.variables.is_some() || uses_db_params || uses_defaults {
+                let mut vars = defaults;
+                vars.extend(self.variables.clone().unwrap_or_default());
+                vars.extend(self.db_params.lock().unwrap().clone());
+                substituted =
+                    placeholders::substitute(content, &vars).map_err(|e| eyre!("{name}: {e}"))?;
+                substituted.as_str()
+            } else {
+                content
+            };
+            let metadata = MigrationMetadata::parse(content);
+            let tenant = TENANT.try_with(Tenant::clone).ok();
+            let scope =
+                scope(&migration.metadata, tenant.as_ref()).map_err(|e| eyre!("{name}: {e}"))?;
+            let scope = scope.as_deref();
+            let run = || async {
+                match self.execution {
+                    ExecutionMode::Script => {
+                        self.execute_script(content, transactional, scope).await
+                    }
+                    ExecutionMode::PerStatement => {
+                        self.execute_statements(name, content, scope).await
+                    }
+                }
+            };
+            let work = async {
+                if metadata.idempotent() {
+                    self.retrying(name, run).await
+                } else {
+                    run().await
+                }
+            };
+            let work = async {
+                let Some(token) = &self.cancel else {
+                    return work.await;
+                };
+                tokio::select! {
+                    result = work => result,
+                    () = token.cancelled() => Err(eyre!("{name}: cancelled while running")),
+                }
+            };
+            let Some((limit, timeout)) = self.time_limit() else {
+                return work.await;
+            };
+            tokio::time::timeout(limit, work).await.unwrap_or_else(|_| {
+                let which = match timeout {
+                    Timeout::Migration => "migration",
+                    Timeout::Run => "run",
+                };
+                Err(TimedOut(format!(
+                    "{name}: {which} timeout of {:?} exceeded",
+                    self.limit_of(timeout)
+                ))
+                .into())
+            })
+        }
+
+        /// Run `op`, retrying transient failures by the retry policy.
+        async fn retrying<T, F, Fut>(&self, what: &str, mut op: F) -> Result<T>
+        where
+            F: FnMut() -> Fut,
+            Fut: Future<Output = Result<T>>,
+        {
+            match &self.retry {
+                Some(policy) => policy.run(what, op).await,
+                None => op().await,
+            }
+        }
+
+        /// How long the next migration may execute: the migration timeout,
+        /// capped by what is left of the run timeout.
+        fn time_limit(&self) -> Option<(Duration, Timeout)> {
+            let left = self
+                .deadline
+                .lock()
+                .unwrap()
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+            match (self.migration_timeout, left) {
+                (Some(migration), Some(left)) if left < migration => Some((left, Timeout::Run)),
+                (Some(migration), _) => Some((migration, Timeout::Migration)),
+                (None, Some(left)) => Some((left, Timeout::Run)),
+                (None, None) => None,
+            }
+        }
+
+        /// The configured duration of `timeout`.
+        fn limit_of(&self, timeout: Timeout) -> Duration {
+            match timeout {
+                Timeout::Migration => self.migration_timeout,
+                Timeout::Run => self.run_timeout,
+            }
+            .unwrap_or_default()
+        }
+
+        /// Read the `DEFINE PARAM` values behind the `{{ db.param.* }}`
+        /// placeholders in `scripts` that are not loaded yet.
+        async fn load_db_params<'c>(
+            &self,
+            scripts: impl IntoIterator<Item = &'c str>,
+        ) -> Result<()> {
+            let wanted = scripts
+                .into_iter()
+                .flat_map(placeholders::names)
+                .filter(|n| n.starts_with(DB_PARAM_PREFIX))
+                .collect::<BTreeSet<_>>();
+
+            for placeholder in wanted {
+                // Explicit variables win, e.g. the live values `verify_schema()`
+                // replays with.
+                let given = self
+                    .variables
+                    .as_ref()
+                    .is_some_and(|v| v.contains_key(&placeholder));
+                if given || self.db_params.lock().unwrap().contains_key(&placeholder) {
+                    continue;
+                }
+                let param = &placeholder[DB_PARAM_PREFIX.len()..];
+                if param.is_empty()
+                    || !param
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+                {
+                    eyre::bail!("{{{{ {placeholder} }}}}: invalid param name");
+                }
+
+                let mut response = self
+                    .target(format!("RETURN ${param};"))
+                    .await
+                    .map_err(|e| eyre!(e.to_string()))?;
+                let value: Option<serde_json::Value> = response
+                    .take(self.target_at(0))
+                    .map_err(|e| eyre!(e.to_string()))?;
+                let value = match value {
+                    None | Some(serde_json::Value::Null) => {
+                        eyre::bail!("{{{{ {placeholder} }}}}: the database has no param ${param}")
+                    }
+                    Some(serde_json::Value::String(s)) => s,
+                    Some(other) => other.to_string(),
+                };
+                tracing::debug!(param, "resolved database param");
+                self.db_params.lock().unwrap().insert(placeholder, value);
+            }
+            Ok(())
+        }
+
+        /// Pass `event` to the progress listener, if there is one.
+        fn progress(&self, event: ProgressEvent) {
+            if let Some(listener) = &self.progress {
+                listener.on_progress(&event);
+            }
+        }
+
+        /// Send `event` to the receiver of `up_with_events()`, if there is one.
+        fn event(&self, event: MigrationEvent) {
+            if let Some(events) = &*self.events.lock().unwrap() {
+                let _ = events.send(event);
+            }
+        }
+
+        /// Keep `message` for the `MigrationReport` of the current run.
+        fn warn(&self, message: String) {
+            self.warnings.lock().unwrap().push(message);
+        }
+
+        /// The `MigrationReport` of a successful run started at `started`.
+        fn report(
+            &self,
+            direction: RunDirection,
+            outcomes: Vec<MigrationOutcome>,
+            skipped: Vec<String>,
+            started: Instant,
+        ) -> MigrationReport {
+            MigrationReport {
+                direction,
+                outcomes,
+                skipped,
+                batch: *self.batch.lock().unwrap(),
+                duration_ms: elapsed_ms(started),
+                warnings: std::mem::take(&mut *self.warnings.lock().unwrap()),
+            }
+        }
+
+        /// Names of the source's migrations limited to other environments.
+        fn excluded(&self) -> Result<Vec<String>> {
+            let Some(environment) = &self.environment else {
+                return Ok(Vec::new());
+            };
+            Ok(self
+                .source
+                .list()?
+                .into_iter()
+                .filter(|m| !m.metadata.applies_to(environment))
+                .map(|m| m.name)
+                .collect())
+        }
+
+        /// Run the hooks registered for `point` of a run in `direction`, in
+        /// order, stopping at the first failure. `migration` is set for the
+        /// `each` hooks and `succeeded` for `AfterAll`.
+        async fn run_hooks(
+            &self,
+            point: HookPoint,
+            direction: RunDirection,
+            migration: Option<&str>,
+            succeeded: Option<bool>,
+        ) -> Result<()> {
+            let direction = match direction {
+                RunDirection::Up => "up",
+                RunDirection::Down => "down",
+                RunDirection::Redo => "redo",
+                RunDirection::Seed => "seed",
+            };
+            let outcome = succeeded.map(|ok| if ok { "succeeded" } else { "failed" });
+            for (_, hook) in self.hooks.iter().filter(|(p, _)| *p == point) {
+                let ran = match hook {
+                    Hook::Sql(sql) => self
+                        .target(sql)
+                        .bind(("hook", point.to_string()))
+                        .bind(("direction", direction))
+                        .bind(("migration", migration.map(str::to_owned)))
+                        .bind(("outcome", outcome))
+                        .await
+                        .map_err(|e| eyre!(e.to_string()))
+                        .and_then(|r| r.check().map(drop).map_err(|e| eyre!(e.to_string()))),
+                    Hook::Command(command) => {
+                        let mut env = vec![
+                            ("SMG_HOOK", point.to_string()),
+                            ("SMG_DIRECTION", direction.to_owned()),
+                        ];
+                        env.extend(migration.map(|m| ("SMG_MIGRATION", m.to_owned())));
+                        env.extend(outcome.map(|o| ("SMG_OUTCOME", o.to_owned())));
+                        hooks::run_command(command, env).await
+                    }
+                };
+                ran.map_err(|e| match migration {
+                    Some(name) => eyre!("{point} hook for {name} failed: {e}"),
+                    None => eyre!("{point} hook failed: {e}"),
+                })?;
+            }
+            Ok(())
+        }
+
+        /// Run the after-all hooks of a run in `direction` that ended with
+        /// `result`. A failing hook fails a successful run; after a failed
+        /// run it is only logged, so the run's own error is reported.
+        async fn after_all(&self, direction: RunDirection, result: Result<()>) -> Result<()> {
+            let hooked = self
+                .run_hooks(HookPoint::AfterAll, direction, None, Some(result.is_ok()))
+                .await;
+            match (result, hooked) {
+                (Ok(()), hooked) => hooked,
+                (Err(e), Err(hook)) => {
+                    tracing::error!("{hook:#}");
+                    Err(e)
+                }
+                (Err(e), Ok(())) => Err(e),
+            }
+        }
+
+        /// Fail if the run has been cancelled or used up its run timeout.
+        fn check_cancelled(&self) -> Result<()> {
+            if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+                eyre::bail!("run cancelled");
+            }
+            if self
+                .deadline
+                .lock()
+                .unwrap()
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                eyre::bail!("run timeout of {:?} exceeded", self.limit_of(Timeout::Run));
+            }
+            Ok(())
+        }
+
+        /// Reset the state of the previous run and start the run timeout.
+        fn start_run(&self) {
+            self.last_run.lock().unwrap().take();
+            self.warnings.lock().unwrap().clear();
+            *self.batch.lock().unwrap() = None;
+            *self.deadline.lock().unwrap() = self.run_timeout.map(|t| Instant::now() + t);
+        }
+
+        /// Execute each statement of `content` separately, naming the
+        /// statement that failed.
+        ///
+        /// With the `miette` feature the error is a `StatementError` carrying
+        /// the script and a span over the failing statement.
+        async fn execute_statements(
+            &self,
+            name: &str,
+            content: &str,
+            scope: Option<&str>,
+        ) -> Result<()> {
+            let statements = statements::split(content);
+            let total = statements.len();
+            for (index, statement) in statements.iter().enumerate() {
+                self.progress(ProgressEvent::StatementStarted {
+                    migration: name.to_owned(),
+                    index,
+                    total,
+                });
+                tracing::debug!(
+                    line = statement.line,
+                    "executing statement {}/{total}",
+                    index + 1
+                );
+                if let Err(e) = self.execute_script(statement.text, false, scope).await {
+                    #[cfg(feature = "miette")]
+                    return Err(crate::diagnostic::StatementError::new(
+                        name,
+                        content,
+                        statement,
+                        index + 1,
+                        total,
+                        e.to_string(),
+                    )
+                    .into());
+
+                    #[cfg(not(feature = "miette"))]
+                    return Err(eyre!(
+                        "{name}: statement {}/{total} (line {}) failed: {e}\n  {}",
+                        index + 1,
+                        statement.line,
+                        statement.text
+                    ));
+                }
+            }
+            Ok(())
+        }
+
+        /// Execute `content` as one query, surfacing the first statement error.
+        ///
+        /// The script is wrapped in a transaction unless `transactional` is
+        /// `false` (the `-- no-transaction` directive), and preceded by the
+        /// `scope` statement switching to its namespace and database.
+        async fn execute_script(
+            &self,
+            content: &str,
+            transactional: bool,
+            scope: Option<&str>,
+        ) -> Result<()> {
+            let mut sql = if transactional {
+                format!("BEGIN TRANSACTION;\n{content}\nCOMMIT TRANSACTION;")
+            } else {
+                content.to_string()
+            };
+            if let Some(scope) = scope.map(str::to_owned).or_else(|| self.tenant_use()) {
+                sql.insert_str(0, &format!("{scope}\n"));
+            }
+            let mut response = self
+                .db
+                .query(&sql)
+                .await
+                .map_err(|e| eyre!(e.to_string()))?;
+
+            let errors = response.take_errors();
+            if !errors.is_empty() {
+                let remaining = errors
+                    .values()
+                    .map(|e| e.to_string())
+                    .filter(|s| {
+                        !s.contains("The query was not executed due to a failed transaction")
+                    })
+                    .collect::<Vec<_>>();
+
+                if !remaining.is_empty() {
+                    let first = &remaining[0];
+                    eyre::bail!(first.to_owned());
+                }
+            }
+            Ok(())
+        }
+
+        /// Run `work` while holding the advisory lock, if one is configured.
+        async fn locked<T>(&self, work: impl Future<Output = Result<T>>) -> Result<T> {
+            let Some(options) = &self.lock else {
+                return work.await;
+            };
+
+            let owner = lock_owner();
+            self.acquire_lock(&owner, options).await?;
+
+            // Another runner may take over a lock whose heartbeat went
+            // stale; stop then rather than run migrations side by side.
+            let heartbeat = async {
+                loop {
+                    tokio::time::sleep(options.heartbeat_interval).await;
+                    match self.heartbeat(&owner).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            return Err(eyre!(
+                                "another runner took over the migration lock; stopped this run"
+                            ));
+                        }
+                        Err(e) => {
+                            tracing::warn!("failed to refresh migration lock heartbeat: {e}");
+                        }
+                    }
+                }
+            };
+
+            let result = tokio::select! {
+                result = work => result,
+                lost = heartbeat => lost,
+            };
+
+            match (self.release_lock(&owner).await, result) {
+                (Err(e), Ok(_)) => Err(e),
+                (Err(e), Err(error)) => {
+                    tracing::warn!("failed to release the migration lock: {e}");
+                    Err(error)
+                }
+                (Ok(()), result) => result,
+            }
+        }
+
+        /// Take the advisory lock for `owner`, failing if another runner holds
+        /// it with a fresh heartbeat.
+        async fn acquire_lock(&self, owner: &str, options: &LockOptions) -> Result<()> {
+            let sql = "BEGIN TRANSACTION;
+                LET $lock = (SELECT * FROM ONLY migration_lock:runner);
+                IF $lock != NONE AND $lock.owner != $owner AND $lock.heartbeat > time::now() - <duration> $stale {
+                    THROW 'migrations are locked by ' + $lock.owner;
+                };
+                UPSERT migration_lock:runner CONTENT {
+                    owner: $owner,
+                    acquired_at: time::now(),
+                    heartbeat: time::now(),
+                    previous_owner: $lock.owner,
+                };
+                COMMIT TRANSACTION;";
+            let stale = format!("{}ms", options.stale_after.as_millis());
+            let mut response = self
+                .bookkeeping(sql)?
+                .bind(("owner", owner.to_owned()))
+                .bind(("stale", stale))
+                .await
+                .map_err(|e| eyre!(e.to_string()))?;
+
+            let errors = response.take_errors();
+            if let Some(e) = errors
+                .values()
+                .map(|e| e.to_string())
+                .find(|s| !s.contains("The query was not executed due to a failed transaction"))
+            {
+                eyre::bail!(e);
+            }
+
+            let previous: Option<String> = response
+                .take((self.at(4), "previous_owner"))
+                .map_err(|e| eyre!(e.to_string()))?;
+            if let Some(previous) = previous.filter(|p| p != owner) {
+                tracing::warn!(previous = %previous, "took over stale migration lock");
+                self.warn(format!("took over the stale migration lock of {previous}"));
+            }
+            tracing::debug!(owner = %owner, "acquired migration lock");
+            Ok(())
+        }
+
+        /// Refresh the heartbeat of the lock held by `owner`, returning
+        /// whether `owner` still holds it.
+        async fn heartbeat(&self, owner: &str) -> Result<bool> {
+            let updated: Vec<String> = self
+                .bookkeeping(
+                    "UPDATE migration_lock:runner SET heartbeat = time::now() \
+                     WHERE owner = $owner RETURN VALUE owner;",
+                )?
+                .bind(("owner", owner.to_owned()))
+                .await
+                .map_err(|e| eyre!(e.to_string()))?
+                .take(self.at(0))
+                .map_err(|e| eyre!(e.to_string()))?;
+            Ok(!updated.is_empty())
+        }
+
+        /// Release the lock held by `owner`.
+        async fn release_lock(&self, owner: &str) -> Result<()> {
+            self.bookkeeping("DELETE migration_lock:runner WHERE owner = $owner;")?
+                .bind(("owner", owner.to_owned()))
+                .await
+                .map_err(|e| eyre!(e.to_string()))?;
+            tracing::debug!(owner = %owner, "released migration lock");
+            Ok(())
+        }
+
+        /// Complete `report` with the run's duration and error, then persist it.
+        async fn finish_run(
+            &self,
+            mut report: RunReport,
+            started: Instant,
+            result: &Result<()>,
+        ) -> Result<()> {
+            report.duration_ms = elapsed_ms(started);
+            report.error = result.as_ref().err().map(|e| e.to_string());
+
+            // A failure followed by a successful cleanup leaves nothing
+            // behind. Siblings still running in a parallel run finish after
+            // the failure, so look for it rather than at the last outcome.
+            let failed = report.outcomes.iter().enumerate().find_map(|(i, o)| {
+                let cleaned = report.outcomes[i + 1..]
+                    .iter()
+                    .any(|later| later.name == o.name && later.status == OutcomeStatus::Reverted);
+                (o.status == OutcomeStatus::Failed && !cleaned).then_some(o)
+            });
+            if let Some(failed) = failed {
+                let state = DirtyState {
+                    migration: failed.name.clone(),
+                    direction: report.direction,
+                    error: failed.error.clone().unwrap_or_default(),
+                    failed_at: None,
+                };
+                self.bookkeeping("UPSERT migration_state:dirty CONTENT $state")?
+                    .bind(("state", state))
+                    .await
+                    .map_err(|e| eyre!(e.to_string()))?;
+            }
+
+            self.record_run(report).await
+        }
+
+        /// Undo a migration that failed with `error` by running its down
+        /// script, if cleanup is enabled and it has one. Returns the error to
+        /// report for the run.
+        async fn clean_up(
+            &self,
+            report: &mut RunReport,
+            migration: &Migration,
+            error: eyre::Report,
+        ) -> eyre::Report {
+            if !self.cleanup_on_failure || migration.kind == MigrationKind::Repeatable {
+                return error;
+            }
+            // The abandoned query may still be running on the server.
+            if self.check_cancelled().is_err() || error.downcast_ref::<TimedOut>().is_some() {
+                return error;
+            }
+            let down = match self.source.get_down(migration) {
+                Ok(Some(down)) => down,
+                Ok(None) => return error,
+                Err(e) => return eyre!("{error}; loading the down script for cleanup failed: {e}"),
+            };
+
+            tracing::warn!(migration = %migration.name, "running down script to clean up");
+            let transactional = MigrationMetadata::parse(&down).transactional();
+            let cleaned = self
+                .step(report, &migration.name, OutcomeStatus::Reverted, async {
+                    self.execute(migration, &down, transactional).await
+                })
+                .await;
+            match cleaned {
+                Ok(()) => error.wrap_err(format!(
+                    "migration {} failed and was cleaned up with its down script",
+                    migration.name
+                )),
+                Err(e) => eyre!("{error}; cleanup with the down script also failed: {e}"),
+            }
+        }
+
+        /// Run `work` for `name`, recording its outcome in `report` as
+        /// `status` on success or `Failed` otherwise.
+        async fn step(
+            &self,
+            report: &mut RunReport,
+            name: &str,
+            status: OutcomeStatus,
+            work: impl Future<Output = Result<()>>,
+        ) -> Result<()> {
+            let started = Instant::now();
+            let result = work.await;
+            report.outcomes.push(MigrationOutcome {
+                name: name.to_owned(),
+                status: if result.is_ok() {
+                    status
+                } else {
+                    OutcomeStatus::Failed
+                },
+                duration_ms: elapsed_ms(started),
+                error: result.as_ref().err().map(|e| e.to_string()),
+            });
+            result
+        }
+
+        /// Refuse to continue while a dirty marker exists.
+        async fn ensure_clean(&self) -> Result<()> {
+            if let Some(state) = self.dirty().await? {
+                eyre::bail!(
+                    "database is dirty: migration {} failed ({}); fix it and call repair()",
+                    state.migration,
+                    state.error
+                );
+            }
+            Ok(())
+        }
+
+        /// Persist a run report in the `migration_runs` table.
+        async fn record_run(&self, mut report: RunReport) -> Result<()> {
+            report.change_ref = self.change_ref.clone();
+            report.batch = *self.batch.lock().unwrap();
+            let created: Vec<RunReport> = self
+                .bookkeeping("CREATE migration_runs CONTENT $content")?
+                .bind(("content", report))
+                .await
+                .map_err(|e| eyre!(e.to_string()))?
+                .check()
+                .map_err(|e| eyre!(e.to_string()))?
+                .take(self.at(0))
+                .map_err(|e| eyre!(e.to_string()))?;
+            *self.last_run.lock().unwrap() = created.into_iter().next().and_then(|run| run.id);
+            Ok(())
+        }
+
+        /// Remove a migration record from the migrations table.
+        async fn remove_migration_record(&self, name: &str) -> Result<()> {
+            let sql = format!("DELETE FROM {} WHERE name = $name;", self.table()?);
+            let _ = self
+                .bookkeeping(sql)?
+                .bind(("name", name.to_owned()))
+                .await
+                .map_err(|e| eyre!(e.to_string()))?;
+            Ok(())
+        }
+
+        /// Start a query against the bookkeeping tables.
+        ///
+        /// With `with_bookkeeping()` the query first switches to the
+        /// bookkeeping database; take its results at `self.at(index)`.
+        fn bookkeeping(&self, sql: impl AsRef<str>) -> Result<surrealdb::method::Query<'a, E>> {
+            let sql = sql.as_ref();
+            self.check_bookkeeping()?;
+            let Some((ns, db)) = &self.bookkeeping else {
+                return Ok(self.target(sql));
+            };
+            Ok(self.db.query(format!("USE NS `{ns}` DB `{db}`;\n{sql}")))
+        }
+
+        /// Fail if the bookkeeping namespace or database cannot be quoted.
+        fn check_bookkeeping(&self) -> Result<()> {
+            for name in self.bookkeeping.iter().flat_map(|(ns, db)| [ns, db]) {
+                if name.is_empty() || name.contains('`') {
+                    eyre::bail!("invalid bookkeeping namespace or database name {name:?}");
+                }
+            }
+            Ok(())
+        }
+
+        /// Index of the `index`-th statement of a `bookkeeping()` query,
+        /// skipping its `USE`.
+        fn at(&self, index: usize) -> usize {
+            if self.bookkeeping.is_some() {
+                index + 1
+            } else {
+                self.target_at(index)
+            }
+        }
+
+        /// The migrations table name, checked to be a plain identifier
+        /// before it is spliced into a query.
+        fn table(&self) -> Result<&str> {
+            let table = self.table.as_str();
+            if table.is_empty()
+                || table.starts_with(|c: char| c.is_ascii_digit())
+                || !table
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+            {
+                eyre::bail!("invalid migrations table name {table:?}");
+            }
+            Ok(table)
+        }
+
+        /// Ensure the migrations and `migration_runs` tables exist.
+        async fn ensure_migrations_table_exists(&self) -> Result<()> {
+            let table = self.table()?;
+            let sql = format!(
+                "DEFINE TABLE IF NOT EXISTS {table} PERMISSIONS NONE;
+                DEFINE FIELD IF NOT EXISTS applied_at ON {table} TYPE option<datetime> DEFAULT time::now();
+                DEFINE FIELD IF NOT EXISTS applied_seq ON {table} TYPE option<int>;
+                DEFINE FIELD IF NOT EXISTS batch ON {table} TYPE option<int>;
+                DEFINE TABLE IF NOT EXISTS migration_runs PERMISSIONS NONE;
+                DEFINE FIELD IF NOT EXISTS finished_at ON migration_runs TYPE datetime DEFAULT time::now();
+                DEFINE TABLE IF NOT EXISTS migration_tags PERMISSIONS NONE;
+                DEFINE FIELD IF NOT EXISTS created_at ON migration_tags TYPE datetime DEFAULT time::now();
+                DEFINE TABLE IF NOT EXISTS migration_state PERMISSIONS NONE;
+                DEFINE FIELD IF NOT EXISTS failed_at ON migration_state TYPE datetime DEFAULT time::now();"
+            );
+            self.retrying("creating the bookkeeping tables", || async {
+                self.bookkeeping(&sql)?
+                    .await
+                    .map_err(|e| eyre!(e.to_string()))?;
+                Ok(())
+            })
+            .await
+        }
+
+        /// Retrieve applied migration records from the migrations table.
+        async fn get_applied_migrations(&self) -> Result<Vec<MigrationRecord>> {
+            let sql = format!("SELECT * FROM {}", self.table()?);
+            self.check_bookkeeping()?;
+            let selected = self
+                .retrying("reading the applied migrations", || async {
+                    self.bookkeeping(&sql)?
+                        .await
+                        .map_err(|e| eyre!(e.to_string()))?
+                        .take(self.at(0))
+                        .map_err(|e| eyre!(e.to_string()))
+                })
+                .await;
+            let migrations: Vec<MigrationRecord> = match selected {
+                Ok(r) => r,
+                Err(e) => {
+                    tracing::debug!("failed to select migrations: {e}");
+                    return Ok(Vec::new());
+                }
+            };
+
+            Ok(migrations
+                .into_iter()
+                .filter(|record| !record.name.is_empty())
+                .collect())
+        }
+
+        /// The versioned migrations at or before the configured baseline.
+        fn baselined<'m>(&self, versioned: &'m [Migration]) -> Result<&'m [Migration]> {
+            let Some(baseline) = &self.baseline else {
+                return Ok(&[]);
+            };
+            let end = versioned
+                .iter()
+                .position(|m| &m.name == baseline)
+                .ok_or_else(|| eyre!("baseline migration {baseline} not found"))?;
+            Ok(&versioned[..=end])
+        }
+
+        /// The versioned migrations the baseline covers in this database:
+        /// those of `baselined()` when the database is being adopted (it has
+        /// no migration records but a schema) or was adopted before (it has
+        /// baseline records), none otherwise.
+        async fn adopted_baseline<'m>(
+            &self,
+            versioned: &'m [Migration],
+            applied: &[MigrationRecord],
+        ) -> Result<&'m [Migration]> {
+            if self.baseline.is_none() {
+                return Ok(&[]);
+            }
+            let adopting = applied.is_empty() && !self.database_is_empty().await?;
+            if adopting || applied.iter().any(|r| r.baseline) {
+                self.baselined(versioned)
+            } else {
+                Ok(&[])
+            }
+        }
+
+        /// Whether the database holds nothing `wipe()` would remove besides
+        /// the bookkeeping tables.
+        async fn database_is_empty(&self) -> Result<bool> {
+            let table = self.table()?;
+            Ok(self.database_objects().await?.iter().all(|(kind, name)| {
+                *kind == "tables"
+                    && (name == table
+                        || [
+                            "migration_lock",
+                            "migration_runs",
+                            "migration_tags",
+                            "migration_state",
+                        ]
+                        .contains(&name.as_str()))
+            }))
+        }
+
+        /// Record unapplied migrations covered by the baseline, when it
+        /// applies to this database.
+        async fn record_baseline(&self) -> Result<()> {
+            if self.baseline.is_none() {
+                return Ok(());
+            }
+            let versioned = self
+                .migrations()?
+                .into_iter()
+                .filter(|m| m.kind != MigrationKind::Repeatable)
+                .collect::<Vec<_>>();
+            let records = self.get_applied_migrations().await?;
+            let covered = self.adopted_baseline(&versioned, &records).await?;
+            let applied = records
+                .iter()
+                .map(|r| r.name.as_str())
+                .collect::<HashSet<_>>();
+
+            for migration in covered {
+                if !applied.contains(migration.name.as_str()) {
+                    let content = self.source.get_up(migration)?;
+                    self.record_migration(migration, &content, true).await?;
+                    tracing::info!("Recorded baseline migration: {}", migration.name);
+                }
+            }
+            Ok(())
+        }
+
+        /// Record squashed migrations whose replaced migrations are all
+        /// applied, taking over their records and place in the apply order,
+        /// without running them.
+        ///
+        /// A squashed migration replacing only some applied migrations is an
+        /// error: running it would repeat them, and skipping it would lose
+        /// the rest.
+        async fn record_squashed(&self) -> Result<()> {
+            let applied = self.get_applied_migrations().await?;
+            let records = applied
+                .iter()
+                .map(|r| (r.name.as_str(), r))
+                .collect::<HashMap<_, _>>();
+
+            for migration in self.migrations()? {
+                let replaces = &migration.metadata.replaces;
+                if replaces.is_empty() || records.contains_key(migration.name.as_str()) {
+                    continue;
+                }
+                let replaced = replaces
+                    .iter()
+                    .filter_map(|name| records.get(name.as_str()))
+                    .collect::<Vec<_>>();
+                if replaced.is_empty() {
+                    continue;
+                }
+                if replaced.len() < replaces.len() {
+                    let missing = replaces
+                        .iter()
+                        .filter(|name| !records.contains_key(name.as_str()))
+                        .map(String::as_str)
+                        .collect::<Vec<_>>();
+                    eyre::bail!(
+                        "{} squashes migrations that are only partly applied; not applied: {}",
+                        migration.name,
+                        missing.join(", ")
+                    );
+                }
+
+                let content = self.source.get_up(&migration)?;
+                let applied_seq = replaced.iter().filter_map(|r| r.applied_seq).max();
+                let batch = replaced.iter().filter_map(|r| r.batch).max();
+                let table = self.table()?;
+                self.bookkeeping(format!(
+                    "BEGIN TRANSACTION;
+                    DELETE FROM {table} WHERE name IN $replaced;
+                    CREATE {table} CONTENT $content;
+                    COMMIT TRANSACTION;"
+                ))?
+                .bind(("replaced", replaces.clone()))
+                .bind((
+                    "content",
+                    self.record_content(&migration, &content, true, applied_seq, batch),
+                ))
+                .await
+                .map_err(|e| eyre!(e.to_string()))?
+                .check()
+                .map_err(|e| eyre!(e.to_string()))?;
+                tracing::info!(
+                    "Recorded squashed migration: {} (replaces {})",
+                    migration.name,
+                    replaces.join(", ")
+                );
+            }
+            Ok(())
+        }
+
+        /// Record a migration as applied by creating a record in the
+        /// migrations table.
+        ///
+        /// Repeatable migrations replace their previous record so the stored
+        /// checksum always reflects the last applied content.
+        ///
+        /// `baseline` marks migrations recorded by `with_baseline()` rather than
+        /// executed.
+        async fn record_migration(
+            &self,
+            migration: &Migration,
+            up: &str,
+            baseline: bool,
+        ) -> Result<()> {
+            let _recording = self.recording.lock().await;
+            if migration.kind == MigrationKind::Repeatable {
+                self.remove_migration_record(&migration.name).await?;
+            }
+            let applied = self.get_applied_migrations().await?;
+            let applied_seq = applied
+                .iter()
+                .filter_map(|r| r.applied_seq)
+                .max()
+                .map_or(1, |seq| seq + 1);
+            let batch = *self.batch.lock().unwrap().get_or_insert_with(|| {
+                applied
+                    .iter()
+                    .filter_map(|r| r.batch)
+                    .max()
+                    .map_or(1, |batch| batch + 1)
+            });
+            let content =
+                self.record_content(migration, up, baseline, Some(applied_seq), Some(batch));
+            let _ = self
+                .bookkeeping(format!("CREATE {} CONTENT $content", self.table()?))?
+                .bind(("content", content))
+                .await
+                .map_err(|e| eyre!(e.to_string()))?;
+            Ok(())
+        }
+
+        /// The migrations table record for `migration` with script `up`.
+        fn record_content(
+            &self,
+            migration: &Migration,
+            up: &str,
+            baseline: bool,
+            applied_seq: Option<u64>,
+            batch: Option<u64>,
+        ) -> serde_json::Value {
+            json!({
+                "name": migration.name,
+                "applied_seq": applied_seq,
+                "batch": batch,
+                "checksum": self.hasher.checksum(up),
+                "algorithm": self.hasher.algorithm(),
+                "content": (!migration.metadata.sensitive()).then_some(up),
+                "baseline": baseline,
+            })
+        }
+    }
+
+    /// The `USE` statement switching to the namespace and database a
+    /// migration declared in the header of its up script, if it declared any,
+    /// with the rest taken from the `tenant` being migrated.
+    fn scope(metadata: &MigrationMetadata, tenant: Option<&Tenant>) -> Result<Option<String>> {
+        if metadata.namespace.is_none() && metadata.database.is_none() {
+            return Ok(None);
+        }
+        let mut scope = String::new();
+        for (keyword, name) in [
+            (
+                "NS",
+                metadata.namespace.as_ref().or(tenant.map(|t| &t.namespace)),
+            ),
+            (
+                "DB",
+                metadata.database.as_ref().or(tenant.map(|t| &t.database)),
+            ),
+        ] {
+            let Some(name) = name else {
+                continue;
+            };
+            if name.is_empty() || name.contains('`') {
+                eyre::bail!("invalid {} name {name:?}", keyword.to_lowercase());
+            }
+            scope.push_str(&format!(" {keyword} `{name}`"));
+        }
+        Ok((!scope.is_empty()).then(|| format!("USE{scope};")))
+    }
+
+    /// How far `revert()` goes.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Revert<'t> {
+        /// Every applied migration.
+        All,
+        /// Everything applied after this migration, which is kept.
+        Keep(&'t str),
+        /// The migrations of the most recent batch.
+        LastBatch,
+    }
+
+    /// Which timeout stopped a migration.
+    #[derive(Debug, Clone, Copy)]
+    enum Timeout {
+        Migration,
+        Run,
+    }
+
+    /// A migration abandoned because a timeout expired.
+    #[derive(Debug)]
+    struct TimedOut(String);
+
+    impl std::fmt::Display for TimedOut {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str(&self.0)
+        }
+    }
+
+    impl std::error::Error for TimedOut {}
+
+    /// Identify this process as a lock owner.
+    fn lock_owner() -> String {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default();
+        format!("pid-{}-{nanos:x}", std::process::id())
+    }
+
+    /// Describe checksum mismatches, including diffs when available.
+    fn mismatch_error(mismatches: &[ChecksumMismatch]) -> String {
+        let mut message = String::from("applied migrations were modified:");
+        for mismatch in mismatches {
+            message.push_str(&format!(
+                "\n{}: applied {} but now {}",
+                mismatch.name, mismatch.applied_checksum, mismatch.checksum
+            ));
+            match &mismatch.diff {
+                Some(diff) => message.push_str(&format!("\n{diff}")),
+                None => message.push_str(" (applied SQL was not recorded)"),
+            }
+        }
+        message
+    }
+
+    /// A fixed list of migrations from another source, for replaying
+    /// exactly those in `verify_schema()`.
+    struct Replay<'s, S> {
+        source: &'s S,
+        migrations: Vec<Migration>,
+    }
+
+    impl<S: MigrationSource> MigrationSource for Replay<'_, S> {
+        fn list(&self) -> Result<Vec<Migration>> {
+            Ok(self.migrations.clone())
+        }
+
+        fn get_up(&self, migration: &Migration) -> Result<String> {
+            self.source.get_up(migration)
+        }
+
+        fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+            self.source.get_down(migration)
+        }
+    }
+
+    /// `content` without its `USE` statements and their terminating `;`.
+    fn strip_use(content: &str) -> String {
+        let mut out = String::with_capacity(content.len());
+        let mut from = 0;
+        for statement in statements::split(content)
+            .iter()
+            .filter(|s| s.kind() == StatementKind::Use)
+        {
+            out.push_str(&content[from..statement.offset]);
+            from = statement.offset + statement.text.len();
+            let rest = content[from..].trim_start();
+            if rest.starts_with(';') {
+                from = content.len() - rest.len() + 1;
+            }
+        }
+        out.push_str(&content[from..]);
+        out
+    }
+
+    /// The applied versioned migrations in the order they were applied.
+    ///
+    /// Records without an `applied_seq` predate it and come first, in
+    /// discovery order.
+    fn applied_order<'m>(
+        migrations: &'m [Migration],
+        records: &[MigrationRecord],
+    ) -> Vec<&'m Migration> {
+        let seqs = records
+            .iter()
+            .map(|r| (r.name.as_str(), r.applied_seq))
+            .collect::<HashMap<_, _>>();
+        let mut applied = migrations
+            .iter()
+            .filter(|m| m.kind != MigrationKind::Repeatable)
+            .filter_map(|m| seqs.get(m.name.as_str()).map(|seq| (*seq, m)))
+            .collect::<Vec<_>>();
+        applied.sort_by_key(|(seq, _)| *seq);
+        applied.into_iter().map(|(_, m)| m).collect()
+    }
+
+    /// The outcome of a run whose report was persisted with `persisted`.
+    /// The run's own error wins over a failure to record it, which is only
+    /// logged then.
+    fn recorded(result: Result<()>, persisted: Result<()>) -> Result<()> {
+        match (result, persisted) {
+            (Err(error), Err(e)) => {
+                tracing::warn!("failed to record the run: {e:#}");
+                Err(error)
+            }
+            (result, persisted) => result.and(persisted),
+        }
+    }
+
+    /// Milliseconds elapsed since `started`, saturating at `u64::MAX`.
+    fn elapsed_ms(started: Instant) -> u64 {
+        u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX)
+    }
+}
+
+pub use include_dir::{Dir, include_dir};
+#[cfg(feature = "macros")]
+pub use surreal_migraine_macros::embed_migrations;
+
+/// Paths used by the code `embed_migrations!` expands to.
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __macros {
+    pub use include_dir::{Dir, DirEntry, File};
+}
+
+#[cfg(feature = "runner")]
 pub use migrations_impl::*;
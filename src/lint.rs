@@ -0,0 +1,321 @@
+use crate::types::{Migration, MigrationSource};
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+
+/// Trailing comment that silences lint findings on its line, for literals
+/// that look secret but are not (test fixtures, public keys, ...).
+pub const ALLOW: &str = "-- lint: allow";
+
+/// What a lint finding is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// A credential, token or key written into the SQL.
+    Secret,
+    /// An email address or payment card number written into the SQL.
+    PersonalData,
+}
+
+impl Rule {
+    /// The rule's name as shown in reports.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Rule::Secret => "secret",
+            Rule::PersonalData => "personal-data",
+        }
+    }
+}
+
+/// A problem found in a migration script.
+///
+/// The offending text is never included, so findings are safe to print in
+/// CI logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    /// The migration the script belongs to.
+    pub migration: String,
+    /// Whether the finding is in the down script.
+    pub down: bool,
+    /// 1-based line of the finding.
+    pub line: usize,
+    pub rule: Rule,
+    /// What was found, e.g. `AWS access key ID`.
+    pub message: String,
+}
+
+/// Lint the up and down scripts of every migration in `source`.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::lint::{Rule, lint};
+/// use surreal_migraine::types::InMemorySource;
+///
+/// let src = InMemorySource::new().with_migration(
+///     "001_admin",
+///     "DEFINE USER admin ON ROOT PASSWORD 'hunter2' ROLES OWNER;",
+///     None,
+/// );
+/// let findings = lint(&src).unwrap();
+/// assert_eq!(findings[0].rule, Rule::Secret);
+/// assert_eq!(findings[0].line, 1);
+/// ```
+pub fn lint<S: MigrationSource + ?Sized>(source: &S) -> Result<Vec<Finding>> {
+    let mut findings = Vec::new();
+    for migration in source.list()? {
+        findings.extend(lint_script(&migration, &source.get_up(&migration)?, false));
+        if let Some(down) = source.get_down(&migration)? {
+            findings.extend(lint_script(&migration, &down, true));
+        }
+    }
+    Ok(findings)
+}
+
+fn lint_script(migration: &Migration, content: &str, down: bool) -> Vec<Finding> {
+    scan_secrets(content)
+        .into_iter()
+        .map(|(line, rule, message)| Finding {
+            migration: migration.name.clone(),
+            down,
+            line,
+            rule,
+            message,
+        })
+        .collect()
+}
+
+/// Find likely secrets and personal data in `content`, as
+/// `(line, rule, message)`.
+///
+/// Known token formats (cloud keys, VCS and chat tokens, JWTs, private
+/// keys), literal passwords (`DEFINE USER ... PASSWORD '...'`, or a
+/// `password`, `secret`, `token` or `api_key` field set to a literal), long
+/// random-looking string literals, email addresses and payment card numbers
+/// are reported. `{{placeholders}}` are never flagged, so moving the value
+/// into one is the usual fix. Lines ending in `-- lint: allow` are skipped.
+pub fn scan_secrets(content: &str) -> Vec<(usize, Rule, String)> {
+    let mut findings = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim_end().ends_with(ALLOW) {
+            continue;
+        }
+        let line_no = index + 1;
+        let mut found = |rule, message: &str| {
+            if !findings.iter().any(|(l, r, m): &(usize, Rule, String)| {
+                *l == line_no && *r == rule && m == message
+            }) {
+                findings.push((line_no, rule, message.to_string()));
+            }
+        };
+
+        for word in words(line) {
+            if let Some(kind) = token_kind(word) {
+                found(Rule::Secret, kind);
+            } else if is_email(word) {
+                found(Rule::PersonalData, "email address");
+            } else if is_card_number(word) {
+                found(Rule::PersonalData, "payment card number");
+            }
+        }
+        if line.contains("-----BEGIN") && line.contains("PRIVATE KEY-----") {
+            found(Rule::Secret, "private key");
+        }
+        if literal_password(line) {
+            found(Rule::Secret, "password or token set to a literal");
+        }
+        for literal in literals(line) {
+            if token_kind(literal).is_none() && is_high_entropy(literal) {
+                found(Rule::Secret, "random-looking string literal");
+            }
+        }
+    }
+    findings
+}
+
+/// Runs of characters that can make up a token, email or card number.
+fn words(line: &str) -> impl Iterator<Item = &str> {
+    line.split(|c: char| !(c.is_ascii_alphanumeric() || "_-.@+/=".contains(c)))
+        .map(|w| w.trim_matches(|c| c == '.' || c == '-'))
+        .filter(|w| !w.is_empty())
+}
+
+/// The contents of the `'...'` and `"..."` literals on `line`.
+fn literals(line: &str) -> Vec<&str> {
+    let mut literals = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find(['\'', '"']) {
+        let quote = rest.as_bytes()[start] as char;
+        let after = &rest[start + 1..];
+        let Some(end) = after.find(quote) else {
+            break;
+        };
+        literals.push(&after[..end]);
+        rest = &after[end + 1..];
+    }
+    literals
+}
+
+/// Name of the well-known credential format `word` has, if any.
+fn token_kind(word: &str) -> Option<&'static str> {
+    let tail_len = |prefix: &str| word.len() - prefix.len();
+    let alnum_tail = |prefix: &str| {
+        word[prefix.len()..]
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+    };
+
+    for prefix in ["AKIA", "ASIA"] {
+        if word.starts_with(prefix)
+            && tail_len(prefix) == 16
+            && word[4..]
+                .bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+        {
+            return Some("AWS access key ID");
+        }
+    }
+    for prefix in ["ghp_", "gho_", "ghu_", "ghs_", "ghr_", "github_pat_"] {
+        if word.starts_with(prefix) && tail_len(prefix) >= 30 && alnum_tail(prefix) {
+            return Some("GitHub token");
+        }
+    }
+    for prefix in ["sk_live_", "rk_live_"] {
+        if word.starts_with(prefix) && tail_len(prefix) >= 16 && alnum_tail(prefix) {
+            return Some("Stripe secret key");
+        }
+    }
+    if word.starts_with("AIza") && word.len() == 39 {
+        return Some("Google API key");
+    }
+    if ["xoxb-", "xoxp-", "xoxa-", "xoxr-", "xoxs-"]
+        .iter()
+        .any(|p| word.starts_with(p))
+        && word.len() >= 24
+    {
+        return Some("Slack token");
+    }
+    if word.starts_with("eyJ") && word.split('.').filter(|s| s.len() >= 8).count() == 3 {
+        return Some("JSON web token");
+    }
+    None
+}
+
+fn is_email(word: &str) -> bool {
+    let Some((local, domain)) = word.split_once('@') else {
+        return false;
+    };
+    let Some((host, tld)) = domain.rsplit_once('.') else {
+        return false;
+    };
+    !local.is_empty()
+        && !host.is_empty()
+        && tld.len() >= 2
+        && tld.bytes().all(|b| b.is_ascii_alphabetic())
+        && !domain.contains('@')
+        && !matches!(host, "example" | "test" | "localhost")
+        && !host.ends_with(".example")
+}
+
+/// 13 to 19 digits passing the Luhn check.
+fn is_card_number(word: &str) -> bool {
+    if !(13..=19).contains(&word.len()) || !word.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    let sum: u32 = word
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(i, b)| {
+            let d = u32::from(b - b'0');
+            match (i % 2, d * 2) {
+                (0, _) => d,
+                (_, doubled) if doubled > 9 => doubled - 9,
+                (_, doubled) => doubled,
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// A `DEFINE USER`/`DEFINE ACCESS` with a literal `PASSWORD`, or a
+/// secret-sounding field assigned a literal string.
+fn literal_password(line: &str) -> bool {
+    let lower = line.to_ascii_lowercase();
+    if let Some(at) = lower.find(" password ")
+        && literal_follows(&line[at + " password ".len()..])
+    {
+        return true;
+    }
+
+    const FIELDS: [&str; 8] = [
+        "password",
+        "passwd",
+        "secret",
+        "token",
+        "api_key",
+        "apikey",
+        "access_key",
+        "private_key",
+    ];
+    let mut rest = lower.as_str();
+    let mut offset = 0;
+    while let Some(at) = rest.find(['=', ':']) {
+        let name = rest[..at]
+            .trim_end()
+            .rsplit(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .next()
+            .unwrap_or_default();
+        let value = &line[offset + at + 1..];
+        if FIELDS.iter().any(|field| name.ends_with(field)) && literal_follows(value) {
+            return true;
+        }
+        offset += at + 1;
+        rest = &rest[at + 1..];
+    }
+    false
+}
+
+/// Whether `text` starts with a non-empty string literal that is not a
+/// `{{placeholder}}`.
+fn literal_follows(text: &str) -> bool {
+    let text = text.trim_start();
+    let Some(quote) = text.chars().next().filter(|c| *c == '\'' || *c == '"') else {
+        return false;
+    };
+    let body = &text[1..];
+    let Some(end) = body.find(quote) else {
+        return false;
+    };
+    let value = &body[..end];
+    !value.is_empty() && !value.contains("{{")
+}
+
+/// Long, space-free literals whose characters look random: at least 20
+/// characters, mixing letters and digits, with Shannon entropy of at least
+/// 4 bits per character. Hex strings such as UUIDs and checksums are left
+/// alone.
+fn is_high_entropy(literal: &str) -> bool {
+    if literal.len() < 20
+        || literal.contains(char::is_whitespace)
+        || literal.contains("{{")
+        || literal.bytes().all(|b| b.is_ascii_hexdigit() || b == b'-')
+        || !literal.bytes().any(|b| b.is_ascii_digit())
+        || !literal.bytes().any(|b| b.is_ascii_alphabetic())
+    {
+        return false;
+    }
+    let mut counts = [0u32; 256];
+    for b in literal.bytes() {
+        counts[usize::from(b)] += 1;
+    }
+    let len = literal.len() as f64;
+    let entropy: f64 = counts
+        .iter()
+        .filter(|c| **c > 0)
+        .map(|c| {
+            let p = f64::from(*c) / len;
+            -p * p.log2()
+        })
+        .sum();
+    entropy >= 4.0
+}
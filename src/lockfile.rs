@@ -0,0 +1,201 @@
+use crate::checksum::{self, Hasher};
+use crate::types::MigrationSource;
+use eyre::{Result, eyre};
+use std::fmt;
+use std::path::Path;
+
+/// File name of the lock file, kept in the migrations directory. Sources
+/// skip it during discovery.
+pub const LOCK_FILE: &str = "migrations.lock";
+
+/// Every migration's name and up-script checksum, in apply order: a
+/// reviewable record of the migrations a change ships, and a guard against
+/// editing migrations by accident.
+///
+/// The file has one `<name> <checksum>` line per migration after a header
+/// naming the checksum algorithm:
+///
+/// ```text
+/// # generated by `smg lock`; do not edit
+/// algorithm sha256
+/// 001_users.surql 5c1a...
+/// 002_posts 9e0b...
+/// ```
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::checksum::Sha256Hasher;
+/// use surreal_migraine::lockfile::{LockFile, LockMismatch};
+/// use surreal_migraine::types::InMemorySource;
+///
+/// let source = InMemorySource::new().with_migration("001_users", "DEFINE TABLE user;", None);
+/// let lock = LockFile::generate(&source, &Sha256Hasher).unwrap();
+/// assert!(lock.verify(&source).unwrap().is_empty());
+///
+/// let edited = InMemorySource::new().with_migration("001_users", "DEFINE TABLE users;", None);
+/// assert_eq!(
+///     lock.verify(&edited).unwrap(),
+///     [LockMismatch::Changed("001_users".into())]
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockFile {
+    /// The checksum algorithm, as named by `Hasher::algorithm`.
+    pub algorithm: String,
+    pub entries: Vec<LockEntry>,
+}
+
+/// A migration listed in a [`LockFile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockEntry {
+    pub name: String,
+    /// Checksum of the up script.
+    pub checksum: String,
+}
+
+/// A difference between a [`LockFile`] and the migrations it locks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockMismatch {
+    /// A migration the lock file does not list.
+    Added(String),
+    /// A listed migration that no longer exists.
+    Removed(String),
+    /// A migration whose up script no longer matches its checksum.
+    Changed(String),
+    /// A migration listed at a different position relative to the others.
+    Moved(String),
+}
+
+impl fmt::Display for LockMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockMismatch::Added(name) => write!(f, "{name} is not in {LOCK_FILE}"),
+            LockMismatch::Removed(name) => write!(f, "{name} is in {LOCK_FILE} but missing"),
+            LockMismatch::Changed(name) => write!(f, "{name} changed since it was locked"),
+            LockMismatch::Moved(name) => write!(f, "{name} moved since it was locked"),
+        }
+    }
+}
+
+impl LockFile {
+    /// Lock every migration of `source`, with checksums from `hasher`.
+    pub fn generate<S: MigrationSource + ?Sized>(source: &S, hasher: &dyn Hasher) -> Result<Self> {
+        let entries = source
+            .list()?
+            .iter()
+            .map(|migration| {
+                Ok(LockEntry {
+                    name: migration.name.clone(),
+                    checksum: hasher.checksum(&source.get_up(migration)?),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            algorithm: hasher.algorithm().to_string(),
+            entries,
+        })
+    }
+
+    /// How `source` differs from the locked migrations, in listing order.
+    /// Empty when it matches.
+    pub fn verify<S: MigrationSource + ?Sized>(&self, source: &S) -> Result<Vec<LockMismatch>> {
+        let hasher = checksum::builtin(&self.algorithm).ok_or_else(|| {
+            eyre!(
+                "{LOCK_FILE} uses the checksum algorithm {}, which is not built in",
+                self.algorithm
+            )
+        })?;
+        let current = Self::generate(source, hasher.as_ref())?;
+
+        let locked = |name: &str| self.entries.iter().find(|e| e.name == name);
+        let mut mismatches = Vec::new();
+        for entry in &current.entries {
+            match locked(&entry.name) {
+                None => mismatches.push(LockMismatch::Added(entry.name.clone())),
+                Some(lock) if lock.checksum != entry.checksum => {
+                    mismatches.push(LockMismatch::Changed(entry.name.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        for entry in &self.entries {
+            if !current.entries.iter().any(|e| e.name == entry.name) {
+                mismatches.push(LockMismatch::Removed(entry.name.clone()));
+            }
+        }
+
+        // Compare the order of the migrations both sides list.
+        let kept = |entries: &[LockEntry], other: &[LockEntry]| {
+            entries
+                .iter()
+                .filter(|e| other.iter().any(|o| o.name == e.name))
+                .map(|e| e.name.clone())
+                .collect::<Vec<_>>()
+        };
+        let before = kept(&self.entries, &current.entries);
+        let after = kept(&current.entries, &self.entries);
+        for (was, is) in before.iter().zip(&after) {
+            if was != is {
+                mismatches.push(LockMismatch::Moved(is.clone()));
+            }
+        }
+        Ok(mismatches)
+    }
+
+    /// The lock file's text.
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "# generated by `smg lock`; do not edit\nalgorithm {}\n",
+            self.algorithm
+        );
+        for entry in &self.entries {
+            out.push_str(&format!("{} {}\n", entry.name, entry.checksum));
+        }
+        out
+    }
+
+    /// Parse a lock file written by [`LockFile::render`].
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut algorithm = None;
+        let mut entries = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .rsplit_once(' ')
+                .ok_or_else(|| eyre!("{LOCK_FILE} line {}: expected `<name> <checksum>`", i + 1))?;
+            if algorithm.is_none() {
+                if key != "algorithm" {
+                    eyre::bail!("{LOCK_FILE} line {}: expected `algorithm <name>`", i + 1);
+                }
+                algorithm = Some(value.to_string());
+                continue;
+            }
+            entries.push(LockEntry {
+                name: key.trim_end().to_string(),
+                checksum: value.to_string(),
+            });
+        }
+        Ok(Self {
+            algorithm: algorithm.ok_or_else(|| eyre!("{LOCK_FILE} names no algorithm"))?,
+            entries,
+        })
+    }
+
+    /// Read the lock file at `path`.
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| eyre!("cannot read {}: {e}", path.display()))?;
+        Self::parse(&text)
+    }
+
+    /// Write the lock file to `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.render())?;
+        Ok(())
+    }
+}
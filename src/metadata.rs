@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Flag telling the runner to execute a script without wrapping it in
+/// `BEGIN TRANSACTION` / `COMMIT TRANSACTION`.
+pub const NO_TRANSACTION: &str = "no-transaction";
+
+/// Flag marking a script as safe to run again after it was cut off midway,
+/// so the runner may retry it after a transient connection error.
+pub const IDEMPOTENT: &str = "idempotent";
+
+/// Flag marking a script as holding secrets. The runner records only its
+/// checksum, not its content, and keeps it out of diffs and traces.
+/// [`EncryptedSource`](crate::encryption) sets it on encrypted scripts.
+pub const SENSITIVE: &str = "sensitive";
+
+/// Structured metadata read from the comment header of a migration.
+///
+/// The header is the run of `--` comment lines (blank lines allowed) at the
+/// top of a migration's "up" SQL. Lines of the form `-- key: value` are
+/// collected; the first statement line ends the header. Recognised keys:
+///
+/// - `description`: free-form description of the migration.
+/// - `author`: who wrote the migration.
+/// - `tags`: comma-separated list of tags.
+/// - `flags`: comma-separated list of flags.
+/// - `env`: comma-separated list of environments the migration is limited
+///   to, such as `dev, test`. Migrations without it run everywhere.
+/// - `replaces`: comma-separated list of the migrations a squashed
+///   migration consolidates.
+/// - `depends` (or `depends-on`): comma-separated list of the migrations
+///   this one needs, optionally in brackets (`[001_users, 002_posts]`).
+///   Each must be applied or run before this one; with parallel execution
+///   these are the only ordering constraints.
+/// - `namespace` (or `ns`) and `database` (or `db`): where the script
+///   runs, for migrations defining namespace- or root-level objects. The
+///   runner switches there for the migration's up and down scripts only;
+///   bookkeeping stays in the runner's database.
+///
+/// Any other key (for example the `migration` and `created` lines written by
+/// `smg add`) is kept in `extra`. Keys are case-insensitive.
+///
+/// A comment consisting of a single lower-case word such as
+/// `-- no-transaction` is a directive and is added to `flags`.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::metadata::MigrationMetadata;
+///
+/// let sql = "-- description: add users\n-- tags: core, auth\n\nDEFINE TABLE user;";
+/// let meta = MigrationMetadata::parse(sql);
+/// assert_eq!(meta.description.as_deref(), Some("add users"));
+/// assert!(meta.has_tag("auth"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MigrationMetadata {
+    /// Free-form description of the migration.
+    pub description: Option<String>,
+    /// Author of the migration.
+    pub author: Option<String>,
+    /// Tags attached to the migration.
+    pub tags: Vec<String>,
+    /// Flags that alter how the migration is handled.
+    pub flags: Vec<String>,
+    /// Environments the migration is limited to; empty means all.
+    #[serde(default)]
+    pub environments: Vec<String>,
+    /// Migrations this one consolidates. A database that applied all of
+    /// them records this migration without running it.
+    #[serde(default)]
+    pub replaces: Vec<String>,
+    /// Migrations this one depends on.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends: Vec<String>,
+    /// Namespace the script runs in instead of the runner's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// Database the script runs in instead of the runner's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    /// Header keys that are not recognised, keyed by lower-cased name.
+    pub extra: BTreeMap<String, String>,
+}
+
+impl MigrationMetadata {
+    /// Parse the comment header at the top of `content`.
+    pub fn parse(content: &str) -> Self {
+        let mut meta = Self::default();
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some(comment) = line.strip_prefix("--") else {
+                break;
+            };
+            let Some((key, value)) = comment.split_once(':') else {
+                let directive = comment.trim();
+                if !directive.is_empty()
+                    && directive
+                        .chars()
+                        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                {
+                    meta.flags.push(directive.to_string());
+                }
+                continue;
+            };
+
+            let key = key.trim().to_ascii_lowercase();
+            if key.is_empty()
+                || !key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                continue;
+            }
+            let value = value.trim();
+
+            match key.as_str() {
+                "description" => meta.description = Some(value.to_string()),
+                "author" => meta.author = Some(value.to_string()),
+                "tags" => meta.tags.extend(split_list(value)),
+                "flags" => meta.flags.extend(split_list(value)),
+                "env" => meta.environments.extend(split_list(value)),
+                "replaces" => meta.replaces.extend(split_list(value)),
+                "depends" | "depends-on" => meta.depends.extend(split_list(
+                    value.trim_start_matches('[').trim_end_matches(']'),
+                )),
+                "namespace" | "ns" => meta.namespace = Some(value.to_string()),
+                "database" | "db" => meta.database = Some(value.to_string()),
+                _ => {
+                    meta.extra.insert(key, value.to_string());
+                }
+            }
+        }
+
+        meta
+    }
+
+    /// Returns `true` if the migration carries `tag`.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Returns `true` if the migration carries `flag`.
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.iter().any(|f| f == flag)
+    }
+
+    /// Returns `true` if the migration should run in `environment`.
+    pub fn applies_to(&self, environment: &str) -> bool {
+        self.environments.is_empty() || self.environments.iter().any(|e| e == environment)
+    }
+
+    /// Returns `true` unless the script opted out of the transaction
+    /// wrapper with the [`NO_TRANSACTION`] flag.
+    pub fn transactional(&self) -> bool {
+        !self.has_flag(NO_TRANSACTION)
+    }
+
+    /// Returns `true` if the script declared itself safe to retry with the
+    /// [`IDEMPOTENT`] flag.
+    pub fn idempotent(&self) -> bool {
+        self.has_flag(IDEMPOTENT)
+    }
+
+    /// Returns `true` if the script holds secrets, per the [`SENSITIVE`]
+    /// flag.
+    pub fn sensitive(&self) -> bool {
+        self.has_flag(SENSITIVE)
+    }
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
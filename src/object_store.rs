@@ -0,0 +1,136 @@
+use crate::lockfile::{LOCK_FILE, LockFile};
+use crate::types::{IgnoredEntry, Listing, Migration, MigrationSource};
+use ::object_store::ObjectStore;
+use ::object_store::path::Path;
+use eyre::{Result, eyre};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub use ::object_store as store;
+
+/// Migrations kept in an object-store bucket (S3, GCS, Azure Blob Storage
+/// or anything else implementing `ObjectStore`), loaded once by
+/// [`ObjectStoreSource::fetch`] and served from memory afterwards.
+///
+/// The prefix is laid out like a migrations directory on disk:
+/// `<name>.surql` objects for single-file and repeatable migrations,
+/// `<name>/up.surql` and `<name>/down.surql` for paired ones. When the
+/// prefix holds a `migrations.lock` (written by `smg lock`), the fetched
+/// migrations must match it.
+///
+/// This crate enables no cloud backend of `object_store`; enable the one
+/// you use (`aws`, `gcp` or `azure`) on your own `object_store` dependency
+/// of the same version, re-exported here as [`store`].
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use object_store::aws::AmazonS3Builder;
+/// use std::sync::Arc;
+/// use surreal_migraine::object_store::ObjectStoreSource;
+///
+/// let bucket = AmazonS3Builder::from_env().with_bucket_name("releases").build()?;
+/// let source = ObjectStoreSource::fetch(Arc::new(bucket), "app/1.4.0/migrations").await?;
+/// MigrationRunner::new(&db, source).up().await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ObjectStoreSource {
+    listing: Listing,
+}
+
+impl ObjectStoreSource {
+    /// List the migrations under `prefix` in `store` and download them.
+    pub async fn fetch(store: Arc<dyn ObjectStore>, prefix: &str) -> Result<Self> {
+        let prefix = Path::from(prefix);
+        let listing = store
+            .list_with_delimiter(Some(&prefix))
+            .await
+            .map_err(|e| eyre!("cannot list {prefix}: {e}"))?;
+
+        let mut entries = listing
+            .objects
+            .iter()
+            .map(|object| (object.location.clone(), false))
+            .chain(
+                listing
+                    .common_prefixes
+                    .iter()
+                    .map(|dir| (dir.clone(), true)),
+            )
+            .filter_map(|(path, is_dir)| Some((path.filename()?.to_string(), is_dir)))
+            .collect::<Vec<_>>();
+        entries.sort();
+
+        let mut scripts = BTreeMap::new();
+        let mut lock = None;
+        for (name, is_dir) in &entries {
+            let path = prefix.child(name.as_str());
+            if name == LOCK_FILE && !is_dir {
+                lock = Some(LockFile::parse(
+                    &get(store.as_ref(), &path).await?.unwrap_or_default(),
+                )?);
+            } else if *is_dir {
+                let Some(up) = get(store.as_ref(), &path.child("up.surql")).await? else {
+                    continue;
+                };
+                let down = get(store.as_ref(), &path.child("down.surql")).await?;
+                scripts.insert(name.clone(), (up, down));
+            } else if let Some(up) = get(store.as_ref(), &path).await? {
+                scripts.insert(name.clone(), (up, None));
+            }
+        }
+
+        let source = Self {
+            listing: Listing::new(entries, scripts),
+        };
+
+        if let Some(lock) = lock {
+            let mismatches = lock.verify(&source)?;
+            if !mismatches.is_empty() {
+                eyre::bail!(
+                    "migrations under {prefix} do not match {LOCK_FILE}: {}",
+                    mismatches
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join("; ")
+                );
+            }
+        }
+        Ok(source)
+    }
+}
+
+/// The UTF-8 content of the object at `path`, or `None` if it does not
+/// exist.
+async fn get(store: &dyn ObjectStore, path: &Path) -> Result<Option<String>> {
+    let result = match store.get(path).await {
+        Ok(result) => result,
+        Err(::object_store::Error::NotFound { .. }) => return Ok(None),
+        Err(e) => return Err(eyre!("cannot read {path}: {e}")),
+    };
+    let bytes = result
+        .bytes()
+        .await
+        .map_err(|e| eyre!("cannot read {path}: {e}"))?;
+    let text = String::from_utf8(bytes.to_vec()).map_err(|_| eyre!("{path} is not valid UTF-8"))?;
+    Ok(Some(text))
+}
+
+impl MigrationSource for ObjectStoreSource {
+    fn list(&self) -> Result<Vec<Migration>> {
+        self.listing.list()
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        self.listing.get_up(migration)
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        self.listing.get_down(migration)
+    }
+
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        self.listing.ignored()
+    }
+}
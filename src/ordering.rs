@@ -0,0 +1,83 @@
+use crate::types::{Migration, MigrationKind};
+use std::cmp;
+
+/// How versioned migrations are ordered.
+///
+/// Sources list migrations by name, which suits numeric and timestamp
+/// prefixes. Layouts that sort differently, such as sprint-based
+/// `2024-W05-add_users` names, implement this trait to say which part of a
+/// name is its version and how two versions compare. The runner uses it
+/// through `MigrationRunner::with_ordering`, and `validate::validate_with`
+/// uses it to find migrations sharing a version.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::cmp;
+/// use surreal_migraine::ordering::Ordering;
+///
+/// /// `<year>-W<week>-<name>`, e.g. `2024-W05-add_users`.
+/// struct SprintOrdering;
+///
+/// impl Ordering for SprintOrdering {
+///     fn version<'n>(&self, name: &'n str) -> Option<&'n str> {
+///         name.get(..8).filter(|v| v.as_bytes()[4..6] == *b"-W")
+///     }
+///
+///     fn compare(&self, a: &str, b: &str) -> cmp::Ordering {
+///         a.cmp(b)
+///     }
+/// }
+///
+/// assert_eq!(SprintOrdering.version("2024-W05-add_users"), Some("2024-W05"));
+/// ```
+pub trait Ordering: Send + Sync {
+    /// The version part of `name`, or `None` when `name` has no version
+    /// under this scheme. Unversioned migrations sort after versioned ones.
+    fn version<'n>(&self, name: &'n str) -> Option<&'n str>;
+
+    /// Compare two versions returned by `version`. Versions comparing
+    /// `Equal` are duplicates.
+    fn compare(&self, a: &str, b: &str) -> cmp::Ordering;
+}
+
+/// Numeric prefixes such as `003_users` or `20240101120000_init`, compared
+/// as numbers. This is the scheme `validate::validate` checks.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NumericOrdering;
+
+impl Ordering for NumericOrdering {
+    fn version<'n>(&self, name: &'n str) -> Option<&'n str> {
+        let (digits, _) = name.split_once('_')?;
+        crate::validate::numeric_prefix(name).map(|_| digits)
+    }
+
+    fn compare(&self, a: &str, b: &str) -> cmp::Ordering {
+        let parse = |v: &str| v.parse::<u64>().ok();
+        parse(a).cmp(&parse(b))
+    }
+}
+
+/// Sort `migrations` by `ordering`: versioned migrations by version,
+/// then unversioned ones, then repeatables. The sort is stable, so entries
+/// that compare equal keep the order the source listed them in.
+pub fn sort(migrations: &mut [Migration], ordering: &dyn Ordering) {
+    fn rank<'m>(m: &'m Migration, ordering: &dyn Ordering) -> (bool, Option<&'m str>) {
+        match m.kind {
+            MigrationKind::Repeatable => (true, None),
+            _ => (false, ordering.version(&m.name)),
+        }
+    }
+
+    migrations.sort_by(|a, b| {
+        let (a_repeatable, a_version) = rank(a, ordering);
+        let (b_repeatable, b_version) = rank(b, ordering);
+        a_repeatable
+            .cmp(&b_repeatable)
+            .then(a_version.is_none().cmp(&b_version.is_none()))
+            .then_with(|| match (a_version, b_version) {
+                (Some(a), Some(b)) => ordering.compare(a, b),
+                _ => cmp::Ordering::Equal,
+            })
+    });
+}
@@ -0,0 +1,115 @@
+use eyre::Result;
+use std::collections::{BTreeSet, HashMap};
+
+/// Prefix of placeholders resolved from the target database: `{{ db.param.tier }}`
+/// is replaced with the value of the `DEFINE PARAM $tier`.
+pub const DB_PARAM_PREFIX: &str = "db.param.";
+
+/// Placeholder replaced in seeds with the runner's seed scale factor (`1`
+/// unless set with `MigrationRunner::with_seed_scale`), so one generator seed can
+/// produce a small dev data set and a large perf-test one:
+/// `FOR $i IN 1..({{ seed.scale }} * 100) { CREATE user; };`
+pub const SEED_SCALE: &str = "seed.scale";
+
+/// Replace `{{name}}` placeholders in `content` with values from `vars`.
+///
+/// Names may contain ASCII letters, digits, `_`, `-` and `.`, and may be
+/// padded with spaces (`{{ env }}`). Braces that do not enclose such a name
+/// are left untouched, so SurrealQL object literals are unaffected. Every
+/// placeholder must have a value; unknown names are reported together.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::collections::HashMap;
+/// use surreal_migraine::placeholders::substitute;
+///
+/// let vars = HashMap::from([("env".to_string(), "staging".to_string())]);
+/// let sql = substitute("DEFINE PARAM $env VALUE '{{ env }}';", &vars).unwrap();
+/// assert_eq!(sql, "DEFINE PARAM $env VALUE 'staging';");
+/// assert!(substitute("{{missing}}", &vars).is_err());
+/// ```
+pub fn substitute(content: &str, vars: &HashMap<String, String>) -> Result<String> {
+    let mut out = String::with_capacity(content.len());
+    let mut missing = BTreeSet::new();
+
+    for segment in segments(content) {
+        match segment {
+            Segment::Text(text) => out.push_str(text),
+            Segment::Placeholder(name) => match vars.get(name) {
+                Some(value) => out.push_str(value),
+                None => {
+                    missing.insert(name.to_owned());
+                }
+            },
+        }
+    }
+
+    if !missing.is_empty() {
+        eyre::bail!(
+            "no value for placeholders: {}",
+            missing.into_iter().collect::<Vec<_>>().join(", ")
+        );
+    }
+    Ok(out)
+}
+
+/// The distinct placeholder names used in `content`, sorted.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::placeholders::names;
+///
+/// let sql = "DEFINE TABLE t PERMISSIONS {{ scope }}; -- {{db.param.tier}} {{scope}}";
+/// assert_eq!(names(sql).into_iter().collect::<Vec<_>>(), ["db.param.tier", "scope"]);
+/// ```
+pub fn names(content: &str) -> BTreeSet<String> {
+    segments(content)
+        .into_iter()
+        .filter_map(|segment| match segment {
+            Segment::Placeholder(name) => Some(name.to_owned()),
+            Segment::Text(_) => None,
+        })
+        .collect()
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Split `content` into literal text and placeholder names.
+fn segments(content: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut rest = content;
+
+    while let Some(open) = rest.find("{{") {
+        segments.push(Segment::Text(&rest[..open]));
+        let after = &rest[open + 2..];
+        let placeholder = after
+            .find("}}")
+            .map(|close| (after[..close].trim(), close))
+            .filter(|(name, _)| is_name(name));
+
+        match placeholder {
+            Some((name, close)) => {
+                segments.push(Segment::Placeholder(name));
+                rest = &after[close + 2..];
+            }
+            None => {
+                segments.push(Segment::Text("{{"));
+                rest = after;
+            }
+        }
+    }
+    segments.push(Segment::Text(rest));
+    segments
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
+}
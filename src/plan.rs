@@ -0,0 +1,109 @@
+use crate::statements::StatementStats;
+use eyre::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// One migration in a `ReleasePlan`, with the SQL that will run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlannedMigration {
+    /// The migration's file or directory name.
+    pub name: String,
+    /// The "up" SQL in `ReleasePlan::up`, the "down" SQL in
+    /// `ReleasePlan::rollback`.
+    pub sql: String,
+}
+
+impl PlannedMigration {
+    /// How many statements of each kind `sql` contains.
+    pub fn stats(&self) -> StatementStats {
+        StatementStats::of(&self.sql)
+    }
+}
+
+/// The migrations a release will apply and the rollback that undoes them.
+///
+/// Produced by `MigrationRunner::plan()`. `rollback` lists the down scripts
+/// of the planned versioned migrations in reverse order. Repeatable
+/// migrations have no down script and are not part of the rollback.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleasePlan {
+    /// Fingerprint of the planned migrations, matching the `RunReport` of
+    /// the run that applies them.
+    pub fingerprint: String,
+    /// Migrations to apply, in order.
+    pub up: Vec<PlannedMigration>,
+    /// Down scripts undoing `up`, in the order they must run.
+    pub rollback: Vec<PlannedMigration>,
+    /// Planned versioned migrations that have no down script.
+    pub irreversible: Vec<String>,
+}
+
+impl ReleasePlan {
+    /// `true` when every planned versioned migration can be rolled back.
+    pub fn is_reversible(&self) -> bool {
+        self.irreversible.is_empty()
+    }
+
+    /// Statement counts over all of `up`, to tell a schema-only release
+    /// from a data-heavy one.
+    pub fn stats(&self) -> StatementStats {
+        let mut stats = StatementStats::default();
+        for migration in &self.up {
+            stats.merge(&migration.stats());
+        }
+        stats
+    }
+
+    /// Fail when the rollback is incomplete.
+    pub fn validate(&self) -> Result<()> {
+        if !self.is_reversible() {
+            eyre::bail!(
+                "rollback plan is incomplete; missing down scripts for: {}",
+                self.irreversible.join(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// The planned migrations as one reviewable SurrealQL script.
+    pub fn up_script(&self) -> String {
+        render(&self.fingerprint, &self.up)
+    }
+
+    /// The rollback as one reviewable SurrealQL script.
+    pub fn rollback_script(&self) -> String {
+        render(&self.fingerprint, &self.rollback)
+    }
+
+    /// Validate the plan and write `up.surql` and `rollback.surql` to `dir`,
+    /// creating it if needed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let plan = runner.plan().await?;
+    /// plan.write("release")?;
+    /// ```
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<()> {
+        self.validate()?;
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        std::fs::write(dir.join("up.surql"), self.up_script())?;
+        std::fs::write(dir.join("rollback.surql"), self.rollback_script())?;
+        tracing::info!(dir = %dir.display(), "wrote release plan");
+        Ok(())
+    }
+}
+
+fn render(fingerprint: &str, migrations: &[PlannedMigration]) -> String {
+    let mut script = format!("-- plan: {fingerprint}\n");
+    for migration in migrations {
+        script.push_str(&format!(
+            "\n-- migration: {}\n-- statements: {}\n{}\n",
+            migration.name,
+            migration.stats(),
+            migration.sql.trim_end()
+        ));
+    }
+    script
+}
@@ -0,0 +1,103 @@
+use crate::MigrationRunner;
+use crate::connect::{ConnectOptions, connect};
+use crate::types::MigrationSource;
+use eyre::{Result, eyre};
+
+/// A named SurrealDB endpoint, typically one per region or colo.
+#[derive(Debug, Clone)]
+pub struct Region {
+    /// Name used in logs and for `continue_from`.
+    pub name: String,
+    /// How to reach the region's database.
+    pub connection: ConnectOptions,
+}
+
+/// Applies migrations to several endpoints one after another.
+///
+/// Regions are migrated in the order they were added, so the first region
+/// acts as the canary: if it fails, no other region is touched. The run
+/// halts at the first failing region and the error names it, so a fixed
+/// rollout can be resumed with `with_continue_from`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use surreal_migraine::connect::ConnectOptions;
+/// use surreal_migraine::regions::RegionOrchestrator;
+/// use surreal_migraine::types::DiskSource;
+///
+/// let orchestrator = RegionOrchestrator::new()
+///     .with_region("canary", canary_options)
+///     .with_region("eu-west", eu_options)
+///     .with_region("us-east", us_options);
+/// orchestrator.up(&DiskSource::new("migrations")).await?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RegionOrchestrator {
+    regions: Vec<Region>,
+    continue_from: Option<String>,
+}
+
+impl RegionOrchestrator {
+    /// Create an orchestrator with no regions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a region to the rollout order.
+    pub fn with_region(mut self, name: impl Into<String>, connection: ConnectOptions) -> Self {
+        self.regions.push(Region {
+            name: name.into(),
+            connection,
+        });
+        self
+    }
+
+    /// Skip every region before `name`, resuming a halted rollout.
+    pub fn with_continue_from(mut self, name: impl Into<String>) -> Self {
+        self.continue_from = Some(name.into());
+        self
+    }
+
+    /// The regions that `up()` will migrate, in order.
+    pub fn planned(&self) -> Result<&[Region]> {
+        let Some(name) = &self.continue_from else {
+            return Ok(&self.regions);
+        };
+        let start = self
+            .regions
+            .iter()
+            .position(|r| &r.name == name)
+            .ok_or_else(|| eyre!("unknown region to continue from: {name}"))?;
+        Ok(&self.regions[start..])
+    }
+
+    /// Apply pending migrations from `source` to each planned region in turn.
+    ///
+    /// Returns the names of the regions that were migrated. On failure the
+    /// error identifies the region that failed; regions after it are left
+    /// untouched.
+    pub async fn up<S: MigrationSource>(&self, source: &S) -> Result<Vec<String>> {
+        let mut migrated = Vec::new();
+
+        for region in self.planned()? {
+            tracing::info!(region = %region.name, "migrating region");
+            let result = async {
+                let db = connect(&region.connection).await?;
+                MigrationRunner::new(&db, source).up().await
+            }
+            .await;
+
+            if let Err(e) = result {
+                return Err(eyre!(
+                    "region {} failed: {e}; resume with `with_continue_from(\"{}\")`",
+                    region.name,
+                    region.name
+                ));
+            }
+            migrated.push(region.name.clone());
+        }
+
+        Ok(migrated)
+    }
+}
@@ -0,0 +1,120 @@
+//! Retrying operations that failed because of a transient connection error.
+//!
+//! A database that is still starting (a common race in Kubernetes deploys)
+//! or a connection dropped by a load balancer fails an operation that would
+//! succeed moments later. [`RetryPolicy`] describes how often and how long
+//! to wait before trying again; [`is_transient`] decides which errors are
+//! worth a retry.
+
+use eyre::Result;
+use std::future::Future;
+use std::time::Duration;
+
+/// How failed operations are retried: up to `attempts` tries in total,
+/// waiting `initial_backoff` after the first failure and doubling the wait
+/// after each further one, up to `max_backoff`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+/// use surreal_migraine::retry::RetryPolicy;
+///
+/// let policy = RetryPolicy::new(5).with_initial_backoff(Duration::from_millis(100));
+/// assert_eq!(policy.backoff(1), Duration::from_millis(100));
+/// assert_eq!(policy.backoff(3), Duration::from_millis(400));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Tries in total, including the first; `1` disables retries.
+    pub attempts: u32,
+    /// Wait after the first failure.
+    pub initial_backoff: Duration,
+    /// Longest wait between two tries.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(5)
+    }
+}
+
+impl RetryPolicy {
+    /// A policy making up to `attempts` tries, starting with a 200ms wait
+    /// capped at 5s.
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+
+    /// Wait `backoff` after the first failure.
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Never wait longer than `backoff` between two tries.
+    pub fn with_max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// How long to wait after failed try number `attempt` (from 1).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Run `op` until it succeeds, fails with an error [`is_transient`]
+    /// rejects, or the attempts are used up; `what` names the operation in
+    /// the log.
+    pub async fn run<T, F, Fut>(&self, what: &str, mut op: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(e) if attempt < self.attempts && is_transient(&e) => {
+                    let wait = self.backoff(attempt);
+                    tracing::warn!("{what} failed ({e}); retrying in {wait:?}");
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Messages of errors that go away by themselves, lower-cased.
+const TRANSIENT: &[&str] = &[
+    "connection refused",
+    "connection reset",
+    "connection closed",
+    "connection was closed",
+    "connection aborted",
+    "broken pipe",
+    "timed out",
+    "temporarily unavailable",
+    "service unavailable",
+];
+
+/// Whether `error` looks like a transient connection problem (refused or
+/// reset connections, timeouts) rather than a failure of the query itself.
+///
+/// SurrealDB reports most errors as text, so this matches the messages of
+/// the error and its causes.
+pub fn is_transient(error: &eyre::Report) -> bool {
+    error.chain().any(|cause| {
+        let message = cause.to_string().to_lowercase();
+        TRANSIENT.iter().any(|t| message.contains(t))
+    })
+}
@@ -0,0 +1,397 @@
+use eyre::{Result, eyre};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use surrealdb::Surreal;
+
+/// A table's definition and the fields, indexes and events on it, each keyed
+/// by name and holding the `DEFINE ...` statement the server reports.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableSchema {
+    pub definition: String,
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+    #[serde(default)]
+    pub indexes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub events: BTreeMap<String, String>,
+}
+
+/// The schema of a database as reported by `INFO FOR DB` and
+/// `INFO FOR TABLE`, with every definition keyed by name.
+///
+/// Maps are sorted, so two snapshots of the same schema serialize to the
+/// same bytes regardless of the order things were defined in. Users and
+/// access methods are left out, as their definitions carry credentials.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaSnapshot {
+    #[serde(default)]
+    pub tables: BTreeMap<String, TableSchema>,
+    #[serde(default)]
+    pub functions: BTreeMap<String, String>,
+    #[serde(default)]
+    pub analyzers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub params: BTreeMap<String, String>,
+}
+
+impl SchemaSnapshot {
+    /// Snapshot the schema of the namespace and database `db` is using.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let snapshot = SchemaSnapshot::capture(&db).await?;
+    /// snapshot.write("schema.json")?;
+    /// ```
+    pub async fn capture<C: surrealdb::Connection>(db: &Surreal<C>) -> Result<Self> {
+        let info = query_info(db, "INFO FOR DB".to_string(), 1)
+            .await?
+            .pop()
+            .unwrap_or_default();
+        let mut snapshot = Self {
+            tables: BTreeMap::new(),
+            functions: definitions(&info, "functions"),
+            analyzers: definitions(&info, "analyzers"),
+            params: definitions(&info, "params"),
+        };
+
+        let tables = definitions(&info, "tables");
+        if tables.is_empty() {
+            return Ok(snapshot);
+        }
+        let sql = tables
+            .keys()
+            .map(|name| format!("INFO FOR TABLE `{name}`;"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let infos = query_info(db, sql, tables.len()).await?;
+        for ((name, definition), info) in tables.into_iter().zip(infos) {
+            snapshot.tables.insert(
+                name,
+                TableSchema {
+                    definition,
+                    fields: definitions(&info, "fields"),
+                    indexes: definitions(&info, "indexes"),
+                    events: definitions(&info, "events"),
+                },
+            );
+        }
+        Ok(snapshot)
+    }
+
+    /// Drop `tables` from the snapshot, e.g. migration bookkeeping.
+    pub fn without_tables<'t>(mut self, tables: impl IntoIterator<Item = &'t str>) -> Self {
+        for table in tables {
+            self.tables.remove(table);
+        }
+        self
+    }
+
+    /// The canonical serialization: pretty-printed JSON with sorted keys and
+    /// a trailing newline, suitable for committing and diffing.
+    pub fn to_canonical(&self) -> Result<String> {
+        let mut json = serde_json::to_string_pretty(self)?;
+        json.push('\n');
+        Ok(json)
+    }
+
+    /// Parse a snapshot written by [`SchemaSnapshot::to_canonical`].
+    pub fn parse(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| eyre!("invalid schema snapshot: {e}"))
+    }
+
+    /// Write the canonical snapshot to `path`.
+    pub fn write(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_canonical()?)?;
+        Ok(())
+    }
+
+    /// Read a snapshot file written by [`SchemaSnapshot::write`].
+    pub fn read(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| eyre!("cannot read {}: {e}", path.display()))?;
+        Self::parse(&json)
+    }
+}
+
+/// The kind of schema object a [`SchemaChange`] is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ObjectKind {
+    Analyzer,
+    Param,
+    Function,
+    Table,
+    Field,
+    Index,
+    Event,
+}
+
+impl ObjectKind {
+    /// The kind's name as shown in reports.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ObjectKind::Analyzer => "analyzer",
+            ObjectKind::Param => "param",
+            ObjectKind::Function => "function",
+            ObjectKind::Table => "table",
+            ObjectKind::Field => "field",
+            ObjectKind::Index => "index",
+            ObjectKind::Event => "event",
+        }
+    }
+
+    fn keyword(self) -> String {
+        self.as_str().to_ascii_uppercase()
+    }
+}
+
+/// How an object differs between two snapshots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+impl ChangeKind {
+    /// The change's name as shown in reports.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Changed => "changed",
+        }
+    }
+}
+
+/// One difference between two snapshots, with the statement that turns the
+/// first into the second.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SchemaChange {
+    pub kind: ChangeKind,
+    pub object: ObjectKind,
+    /// The object's name; fields, indexes and events as `<table>.<name>`.
+    pub name: String,
+    /// A `DEFINE` (with `OVERWRITE` for changes) or `REMOVE` statement.
+    pub sql: String,
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.kind.as_str(),
+            self.object.as_str(),
+            self.name
+        )
+    }
+}
+
+impl SchemaSnapshot {
+    /// The changes that turn this schema into `desired`.
+    ///
+    /// Definitions come first, in dependency order (analyzers, params,
+    /// functions, then each table with its fields, indexes and events),
+    /// followed by removals in the reverse order. Objects on a removed table
+    /// go with it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use surreal_migraine::schema::{SchemaSnapshot, TableSchema};
+    ///
+    /// let mut desired = SchemaSnapshot::default();
+    /// desired.tables.insert(
+    ///     "user".into(),
+    ///     TableSchema {
+    ///         definition: "DEFINE TABLE user TYPE NORMAL SCHEMAFULL".into(),
+    ///         ..Default::default()
+    ///     },
+    /// );
+    /// let current = SchemaSnapshot::default();
+    /// assert_eq!(
+    ///     current.migration_to(&desired),
+    ///     "DEFINE TABLE user TYPE NORMAL SCHEMAFULL;\n"
+    /// );
+    /// assert_eq!(desired.migration_to(&current), "REMOVE TABLE user;\n");
+    /// ```
+    pub fn changes_to(&self, desired: &SchemaSnapshot) -> Vec<SchemaChange> {
+        let mut defines = Vec::new();
+        let mut removes = Vec::new();
+        for (object, old, new) in [
+            (ObjectKind::Analyzer, &self.analyzers, &desired.analyzers),
+            (ObjectKind::Param, &self.params, &desired.params),
+            (ObjectKind::Function, &self.functions, &desired.functions),
+        ] {
+            compare(object, None, old, new, &mut defines, &mut removes);
+        }
+
+        let empty = TableSchema::default();
+        for (name, table) in &desired.tables {
+            let old = match self.tables.get(name) {
+                Some(old) if old.definition != table.definition => {
+                    defines.push(SchemaChange {
+                        kind: ChangeKind::Changed,
+                        object: ObjectKind::Table,
+                        name: name.clone(),
+                        sql: overwrite(ObjectKind::Table, &table.definition),
+                    });
+                    old
+                }
+                Some(old) => old,
+                None => {
+                    defines.push(SchemaChange {
+                        kind: ChangeKind::Added,
+                        object: ObjectKind::Table,
+                        name: name.clone(),
+                        sql: statement(&table.definition),
+                    });
+                    &empty
+                }
+            };
+            for (object, old, new) in [
+                (ObjectKind::Field, &old.fields, &table.fields),
+                (ObjectKind::Index, &old.indexes, &table.indexes),
+                (ObjectKind::Event, &old.events, &table.events),
+            ] {
+                compare(object, Some(name), old, new, &mut defines, &mut removes);
+            }
+        }
+        for name in self.tables.keys() {
+            if !desired.tables.contains_key(name) {
+                removes.push(SchemaChange {
+                    kind: ChangeKind::Removed,
+                    object: ObjectKind::Table,
+                    name: name.clone(),
+                    sql: format!("REMOVE TABLE {};", ident(name)),
+                });
+            }
+        }
+
+        removes.reverse();
+        defines.extend(removes);
+        defines
+    }
+
+    /// The statements of [`SchemaSnapshot::changes_to`], one per line; empty
+    /// when the schemas match.
+    pub fn migration_to(&self, desired: &SchemaSnapshot) -> String {
+        self.changes_to(desired)
+            .iter()
+            .map(|change| format!("{}\n", change.sql))
+            .collect()
+    }
+}
+
+/// Add the differences between `old` and `new` definitions of `object`s,
+/// on `table` if they belong to one.
+fn compare(
+    object: ObjectKind,
+    table: Option<&str>,
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+    defines: &mut Vec<SchemaChange>,
+    removes: &mut Vec<SchemaChange>,
+) {
+    let qualified = |name: &str| match table {
+        Some(table) => format!("{table}.{name}"),
+        None => name.to_string(),
+    };
+    for (name, definition) in new {
+        let (kind, sql) = match old.get(name) {
+            None => (ChangeKind::Added, statement(definition)),
+            Some(previous) if previous != definition => {
+                (ChangeKind::Changed, overwrite(object, definition))
+            }
+            Some(_) => continue,
+        };
+        defines.push(SchemaChange {
+            kind,
+            object,
+            name: qualified(name),
+            sql,
+        });
+    }
+    for name in old.keys().filter(|name| !new.contains_key(*name)) {
+        let target = match object {
+            ObjectKind::Param => format!("${name}"),
+            ObjectKind::Function => format!("fn::{name}"),
+            _ => ident(name),
+        };
+        let on = table
+            .map(|t| format!(" ON {}", ident(t)))
+            .unwrap_or_default();
+        removes.push(SchemaChange {
+            kind: ChangeKind::Removed,
+            object,
+            name: qualified(name),
+            sql: format!("REMOVE {} {target}{on};", object.keyword()),
+        });
+    }
+}
+
+/// `definition` as a statement ending in `;`.
+fn statement(definition: &str) -> String {
+    format!("{};", definition.trim_end().trim_end_matches(';'))
+}
+
+/// `definition` rewritten to replace the existing object.
+fn overwrite(object: ObjectKind, definition: &str) -> String {
+    let prefix = format!("DEFINE {} ", object.keyword());
+    match definition.strip_prefix(&prefix) {
+        Some(rest) => statement(&format!("{prefix}OVERWRITE {rest}")),
+        None => statement(definition),
+    }
+}
+
+/// `name` quoted with backticks unless it is a plain identifier.
+fn ident(name: &str) -> String {
+    if !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        name.to_string()
+    } else {
+        format!("`{name}`")
+    }
+}
+
+/// Run `sql`, made of `count` `INFO` statements, and take each result.
+async fn query_info<C: surrealdb::Connection>(
+    db: &Surreal<C>,
+    sql: String,
+    count: usize,
+) -> Result<Vec<serde_json::Value>> {
+    let mut response = db.query(sql).await.map_err(|e| eyre!(e.to_string()))?;
+    let mut infos = Vec::with_capacity(count);
+    for index in 0..count {
+        let info: Option<serde_json::Value> =
+            response.take(index).map_err(|e| eyre!(e.to_string()))?;
+        infos.push(info.unwrap_or_default());
+    }
+    Ok(infos)
+}
+
+/// The `name -> definition` map under `kind` in an `INFO` result.
+fn definitions(info: &serde_json::Value, kind: &str) -> BTreeMap<String, String> {
+    info.get(kind)
+        .and_then(|v| v.as_object())
+        .map(|o| {
+            o.iter()
+                .map(|(name, definition)| {
+                    let definition = match definition.as_str() {
+                        Some(sql) => sql.to_string(),
+                        None => definition.to_string(),
+                    };
+                    (name.clone(), definition)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
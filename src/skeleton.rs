@@ -0,0 +1,163 @@
+use crate::statements::{self, Statement, StatementKind};
+
+/// A starting point for the down script of `up`: one `REMOVE` statement
+/// per object `up` defines, in reverse order.
+///
+/// Tables, fields, indexes, events, functions, params and analyzers are
+/// recognised. Fields, indexes and events on a table the script also
+/// defines are covered by removing the table. Everything else, and
+/// definitions using `OVERWRITE` (the object may predate the migration),
+/// becomes a `-- TODO` comment naming the statement to undo by hand.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::skeleton::down_skeleton;
+///
+/// let up = "DEFINE TABLE user SCHEMAFULL;
+/// DEFINE FIELD email ON user TYPE string;
+/// DEFINE INDEX by_email ON TABLE post FIELDS author;
+/// UPDATE post SET author = NONE;";
+/// assert_eq!(
+///     down_skeleton(up),
+///     "-- TODO: undo line 4: UPDATE post SET author = NONE\n\
+///      REMOVE INDEX by_email ON post;\n\
+///      REMOVE TABLE user;\n"
+/// );
+/// ```
+pub fn down_skeleton(up: &str) -> String {
+    let statements = statements::split(up);
+    let tables = statements
+        .iter()
+        .filter_map(|s| match defined(s)? {
+            Defined::Table(name) => Some(name),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+    for statement in statements.iter().rev() {
+        match defined(statement) {
+            Some(Defined::On { table, .. }) if tables.contains(&table) => {}
+            Some(defined) => {
+                out.push_str(&defined.remove());
+                out.push('\n');
+            }
+            None => {
+                let first = statement.text.lines().next().unwrap_or_default().trim_end();
+                out.push_str(&format!("-- TODO: undo line {}: {first}\n", statement.line));
+            }
+        }
+    }
+    out
+}
+
+/// An object a `DEFINE` statement creates.
+enum Defined<'a> {
+    Table(&'a str),
+    /// A field, index or event on `table`.
+    On {
+        keyword: &'static str,
+        name: &'a str,
+        table: &'a str,
+    },
+    /// A function, param or analyzer.
+    Named {
+        keyword: &'static str,
+        name: &'a str,
+    },
+}
+
+impl Defined<'_> {
+    fn remove(&self) -> String {
+        match self {
+            Defined::Table(name) => format!("REMOVE TABLE {name};"),
+            Defined::On {
+                keyword,
+                name,
+                table,
+            } => format!("REMOVE {keyword} {name} ON {table};"),
+            Defined::Named { keyword, name } => format!("REMOVE {keyword} {name};"),
+        }
+    }
+}
+
+/// What `statement` defines, if it is a `DEFINE` without `OVERWRITE` of a
+/// recognised object.
+fn defined<'a>(statement: &Statement<'a>) -> Option<Defined<'a>> {
+    if statement.kind() != StatementKind::Define {
+        return None;
+    }
+    let mut words = words(statement.text).skip(1);
+    let object = words.next()?.to_ascii_uppercase();
+    let mut name = words.next()?;
+    loop {
+        match name.to_ascii_uppercase().as_str() {
+            "OVERWRITE" => return None,
+            "IF" | "NOT" | "EXISTS" => name = words.next()?,
+            _ => break,
+        }
+    }
+
+    let mut on = |keyword| {
+        if !words.next()?.eq_ignore_ascii_case("ON") {
+            return None;
+        }
+        let mut table = words.next()?;
+        if table.eq_ignore_ascii_case("TABLE") {
+            table = words.next()?;
+        }
+        Some(Defined::On {
+            keyword,
+            name,
+            table,
+        })
+    };
+    let named = |keyword, name| Some(Defined::Named { keyword, name });
+    match object.as_str() {
+        "TABLE" => Some(Defined::Table(name)),
+        "FIELD" => on("FIELD"),
+        "INDEX" => on("INDEX"),
+        "EVENT" => on("EVENT"),
+        "FUNCTION" => named("FUNCTION", name.split('(').next()?),
+        "PARAM" => named("PARAM", name),
+        "ANALYZER" => named("ANALYZER", name),
+        _ => None,
+    }
+}
+
+/// The whitespace-separated words of `text`, keeping backtick and `⟨⟩`
+/// quoted identifiers whole.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return None;
+        }
+        let mut quote = None;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| match quote {
+                Some(close) => {
+                    if c == close {
+                        quote = None;
+                    }
+                    false
+                }
+                None if c == '`' => {
+                    quote = Some('`');
+                    false
+                }
+                None if c == '⟨' => {
+                    quote = Some('⟩');
+                    false
+                }
+                None => c.is_whitespace(),
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let (word, tail) = rest.split_at(end);
+        rest = tail;
+        Some(word)
+    })
+}
@@ -0,0 +1,219 @@
+use crate::checksum::{checksum, fingerprint};
+use crate::metadata::NO_TRANSACTION;
+use crate::types::{Migration, MigrationKind, MigrationSource};
+use eyre::{Result, eyre};
+use serde::{Deserialize, Serialize};
+use std::ops::{Bound, RangeBounds};
+use std::path::{Path, PathBuf};
+
+/// One migration folded into a [`Squashed`] migration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplacedMigration {
+    /// The migration's file or directory name.
+    pub name: String,
+    pub kind: MigrationKind,
+    /// Checksum of its "up" SQL.
+    pub checksum: String,
+    /// Whether it had a down script.
+    pub has_down: bool,
+}
+
+/// What a [`Squashed`] migration replaced, for keeping next to the archived
+/// originals.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// File or directory name of the squashed migration.
+    pub squashed: String,
+    /// Fingerprint of the replaced migrations, as in a `RunReport`.
+    pub fingerprint: String,
+    /// The replaced migrations, in apply order.
+    pub replaces: Vec<ReplacedMigration>,
+}
+
+/// A migration consolidating a range of older ones. Produced by [`squash`].
+///
+/// `up` starts with a `-- replaces:` header, so a runner records it without
+/// running it on databases that applied every replaced migration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Squashed {
+    /// `<version of the last replaced migration>_<name>`, without extension.
+    pub name: String,
+    /// The replaced up scripts in order, each under a line naming it.
+    pub up: String,
+    /// The replaced down scripts in reverse order, when all of them had one.
+    pub down: Option<String>,
+    pub manifest: Manifest,
+}
+
+impl Squashed {
+    /// The entry name the migration is stored under: a folder when it has
+    /// a down script, otherwise a `.surql` file.
+    pub fn entry_name(&self) -> String {
+        if self.down.is_some() {
+            self.name.clone()
+        } else {
+            format!("{}.surql", self.name)
+        }
+    }
+
+    /// Write the migration into the migrations directory `dir`, returning
+    /// its path. Fails if the entry already exists.
+    pub fn write(&self, dir: impl AsRef<Path>) -> Result<PathBuf> {
+        let path = dir.as_ref().join(self.entry_name());
+        if path.exists() {
+            eyre::bail!("{} already exists", path.display());
+        }
+        match &self.down {
+            Some(down) => {
+                std::fs::create_dir(&path)?;
+                std::fs::write(path.join("up.surql"), &self.up)?;
+                std::fs::write(path.join("down.surql"), down)?;
+            }
+            None => std::fs::write(&path, &self.up)?,
+        }
+        Ok(path)
+    }
+}
+
+/// Combine the versioned migrations of `source` in `range` into one
+/// migration named `name` after the last one's version.
+///
+/// Range ends are migration names, with or without `.surql`; an unbounded
+/// end means the first or last versioned migration. The range must hold at
+/// least two migrations, none limited to some environments or already
+/// squashed. The originals' comment headers are dropped; if any opted out
+/// of the transaction wrapper, so does the result.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::squash::squash;
+/// use surreal_migraine::types::InMemorySource;
+///
+/// let src = InMemorySource::new()
+///     .with_migration("001_users", "DEFINE TABLE user;", Some("REMOVE TABLE user;"))
+///     .with_migration("002_posts", "DEFINE TABLE post;", Some("REMOVE TABLE post;"))
+///     .with_migration("003_tags", "DEFINE TABLE tag;", None);
+///
+/// let squashed = squash(&src, ..="002_posts", "baseline").unwrap();
+/// assert_eq!(squashed.entry_name(), "002_baseline");
+/// assert!(squashed.up.contains("-- replaces: 001_users, 002_posts\n"));
+/// assert!(squashed.down.unwrap().starts_with("\n-- 002_posts\nREMOVE TABLE post;"));
+/// assert_eq!(squashed.manifest.replaces.len(), 2);
+/// ```
+pub fn squash<'r, S: MigrationSource + ?Sized>(
+    source: &S,
+    range: impl RangeBounds<&'r str>,
+    name: &str,
+) -> Result<Squashed> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        eyre::bail!("invalid squashed migration name {name:?}");
+    }
+
+    let versioned = source
+        .list()?
+        .into_iter()
+        .filter(|m| m.kind != MigrationKind::Repeatable)
+        .collect::<Vec<_>>();
+    let position = |wanted: &str| {
+        versioned
+            .iter()
+            .position(|m| m.name == wanted || m.name.strip_suffix(".surql") == Some(wanted))
+            .ok_or_else(|| eyre!("no versioned migration named {wanted}"))
+    };
+    let start = match range.start_bound() {
+        Bound::Included(from) => position(from)?,
+        Bound::Excluded(from) => position(from)? + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(to) => Some(position(to)?),
+        Bound::Excluded(to) => position(to)?.checked_sub(1),
+        Bound::Unbounded => versioned.len().checked_sub(1),
+    };
+    let range = match end {
+        Some(end) if start < end => &versioned[start..=end],
+        _ => eyre::bail!("nothing to squash: the range must span at least two migrations"),
+    };
+
+    for migration in range {
+        if !migration.metadata.environments.is_empty() {
+            eyre::bail!(
+                "{} is limited to some environments and cannot be squashed",
+                migration.name
+            );
+        }
+        if !migration.metadata.replaces.is_empty() {
+            eyre::bail!("{} is already a squashed migration", migration.name);
+        }
+    }
+
+    let last = &range[range.len() - 1].name;
+    let (prefix, _) = last
+        .split_once('_')
+        .ok_or_else(|| eyre!("{last} has no version prefix"))?;
+    let squashed = format!("{prefix}_{name}");
+
+    let mut contents = Vec::new();
+    let mut ups = Vec::new();
+    let mut downs = Vec::new();
+    let mut replaces = Vec::new();
+    for migration in range {
+        let up = source.get_up(migration)?;
+        let down = source.get_down(migration)?;
+        ups.push(section(migration, &up));
+        if let Some(down) = &down {
+            downs.push(section(migration, down));
+        }
+        replaces.push(ReplacedMigration {
+            name: migration.name.clone(),
+            kind: migration.kind.clone(),
+            checksum: checksum(&up),
+            has_down: down.is_some(),
+        });
+        contents.push(up);
+    }
+    downs.reverse();
+
+    let names = range.iter().map(|m| m.name.as_str()).collect::<Vec<_>>();
+    let mut up = format!(
+        "-- migration: {squashed}\n-- replaces: {}\n",
+        names.join(", ")
+    );
+    if range.iter().any(|m| !m.metadata.transactional()) {
+        up.push_str(&format!("-- {NO_TRANSACTION}\n"));
+    }
+    up.push_str(&ups.concat());
+    let down = (downs.len() == range.len()).then(|| downs.concat());
+
+    let mut result = Squashed {
+        manifest: Manifest {
+            squashed: String::new(),
+            fingerprint: fingerprint(
+                range
+                    .iter()
+                    .zip(&contents)
+                    .map(|(m, up)| (m.name.as_str(), up.as_str())),
+            ),
+            replaces,
+        },
+        name: squashed,
+        up,
+        down,
+    };
+    result.manifest.squashed = result.entry_name();
+    Ok(result)
+}
+
+/// `script` without its comment header, under a line naming `migration`.
+fn section(migration: &Migration, script: &str) -> String {
+    let body = script
+        .lines()
+        .skip_while(|line| {
+            let line = line.trim();
+            line.is_empty() || line.starts_with("--")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    format!("\n-- {}\n{}\n", migration.name, body.trim_end())
+}
@@ -0,0 +1,463 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A single SurrealQL statement extracted from a migration script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Statement<'a> {
+    /// The statement text, without the terminating `;` and surrounding
+    /// whitespace. Leading comments are not included.
+    pub text: &'a str,
+    /// 1-based line on which the statement starts.
+    pub line: usize,
+    /// Byte offset of `text` within the script.
+    pub offset: usize,
+}
+
+/// Split a SurrealQL script into its top-level statements.
+///
+/// Statements are separated by `;`. Separators inside string literals,
+/// backtick identifiers, comments (`--`, `//`, `#` and `/* */`) and
+/// bracketed blocks (`{}`, `()`, `[]`, as used by `DEFINE FUNCTION`,
+/// `DEFINE EVENT` or `IF`) are ignored. Comment-only segments produce no
+/// statement.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::statements::split;
+///
+/// let sql = "-- header\nDEFINE TABLE user;\n\nDEFINE FUNCTION fn::a() { RETURN 1; };";
+/// let statements = split(sql);
+/// assert_eq!(statements.len(), 2);
+/// assert_eq!(statements[0].text, "DEFINE TABLE user");
+/// assert_eq!(statements[1].line, 4);
+/// ```
+pub fn split(sql: &str) -> Vec<Statement<'_>> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+
+    let mut i = 0;
+    let mut line = 1;
+    let mut depth = 0usize;
+    // Byte offset and line of the first significant character of the
+    // statement being scanned, and the offset just past its last one.
+    let mut start: Option<(usize, usize)> = None;
+    let mut end = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let next = bytes.get(i + 1).copied();
+
+        match c {
+            b'\n' => {
+                line += 1;
+                i += 1;
+            }
+            b'-' if next == Some(b'-') => i = skip_line(bytes, i),
+            b'/' if next == Some(b'/') => i = skip_line(bytes, i),
+            b'#' => i = skip_line(bytes, i),
+            b'/' if next == Some(b'*') => {
+                let close = sql[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |p| i + 2 + p + 2);
+                line += sql[i..close].matches('\n').count();
+                i = close;
+            }
+            b'\'' | b'"' | b'`' => {
+                start.get_or_insert((i, line));
+                end = skip_quoted(bytes, i);
+                line += sql[i..end].matches('\n').count();
+                i = end;
+            }
+            b'{' | b'(' | b'[' => {
+                start.get_or_insert((i, line));
+                depth += 1;
+                i += 1;
+                end = i;
+            }
+            b'}' | b')' | b']' => {
+                start.get_or_insert((i, line));
+                depth = depth.saturating_sub(1);
+                i += 1;
+                end = i;
+            }
+            b';' if depth == 0 => {
+                if let Some((from, at)) = start.take() {
+                    statements.push(Statement {
+                        text: &sql[from..end],
+                        line: at,
+                        offset: from,
+                    });
+                }
+                i += 1;
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            _ => {
+                start.get_or_insert((i, line));
+                i += 1;
+                end = i;
+            }
+        }
+    }
+
+    if let Some((from, at)) = start {
+        statements.push(Statement {
+            text: &sql[from..end],
+            line: at,
+            offset: from,
+        });
+    }
+
+    statements
+}
+
+/// Return the offset of the newline ending the comment starting at `i`.
+fn skip_line(bytes: &[u8], i: usize) -> usize {
+    bytes[i..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |p| i + p)
+}
+
+/// Return the offset just past the quoted literal starting at `i`.
+fn skip_quoted(bytes: &[u8], i: usize) -> usize {
+    let quote = bytes[i];
+    let mut j = i + 1;
+    while j < bytes.len() {
+        match bytes[j] {
+            b'\\' => j += 2,
+            b if b == quote => return j + 1,
+            _ => j += 1,
+        }
+    }
+    bytes.len()
+}
+
+impl Statement<'_> {
+    /// What the statement does, from its leading keyword.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use surreal_migraine::statements::{StatementKind, split};
+    ///
+    /// let statements = split("REMOVE INDEX idx ON user; RELATE user:a->wrote->post:b; LET $x = 1;");
+    /// assert_eq!(statements[0].kind(), StatementKind::Remove);
+    /// assert_eq!(statements[1].kind(), StatementKind::Relate);
+    /// assert_eq!(statements[2].kind(), StatementKind::Other);
+    /// ```
+    pub fn kind(&self) -> StatementKind {
+        let first = self
+            .text
+            .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        match first.as_str() {
+            "DEFINE" => StatementKind::Define,
+            "REMOVE" => StatementKind::Remove,
+            "ALTER" => StatementKind::Alter,
+            "CREATE" => StatementKind::Create,
+            "INSERT" => StatementKind::Insert,
+            "UPDATE" => StatementKind::Update,
+            "UPSERT" => StatementKind::Upsert,
+            "DELETE" => StatementKind::Delete,
+            "RELATE" => StatementKind::Relate,
+            "SELECT" => StatementKind::Select,
+            "USE" => StatementKind::Use,
+            _ => StatementKind::Other,
+        }
+    }
+
+    /// Tables this statement defines, removes or writes to.
+    ///
+    /// Recognises `DEFINE`/`REMOVE` of tables, fields, indexes and events,
+    /// and the `CREATE`, `UPDATE`, `UPSERT`, `DELETE`, `INSERT INTO` and
+    /// `RELATE` data statements. Reads (`SELECT`) are not reported. Record
+    /// ids such as `user:alice` resolve to their table.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use surreal_migraine::statements::split;
+    ///
+    /// let statements = split("DEFINE FIELD email ON TABLE user TYPE string; RELATE user:a->wrote->post:b;");
+    /// assert_eq!(statements[0].tables(), ["user"]);
+    /// assert_eq!(statements[1].tables(), ["wrote"]);
+    /// ```
+    pub fn tables(&self) -> Vec<String> {
+        let tokens = tokenize(self.text);
+        let word = |i: usize| tokens.get(i).map(|t| t.to_ascii_uppercase());
+        let mut tables = Vec::new();
+
+        let Some(first) = word(0) else {
+            return tables;
+        };
+        match first.as_str() {
+            "DEFINE" | "REMOVE" => match word(1).as_deref() {
+                Some("TABLE") => {
+                    if let Some(name) = skip_clauses(&tokens, 2) {
+                        tables.push(name);
+                    }
+                }
+                Some("FIELD" | "INDEX" | "EVENT") => {
+                    if let Some(on) = tokens.iter().position(|t| t.eq_ignore_ascii_case("ON")) {
+                        let at = if word(on + 1).as_deref() == Some("TABLE") {
+                            on + 2
+                        } else {
+                            on + 1
+                        };
+                        if let Some(name) = tokens.get(at) {
+                            tables.push(name.clone());
+                        }
+                    }
+                }
+                _ => {}
+            },
+            "CREATE" | "UPDATE" | "UPSERT" | "DELETE" => {
+                let mut at = 1;
+                while matches!(word(at).as_deref(), Some("ONLY" | "FROM")) {
+                    at += 1;
+                }
+                if let Some(name) = tokens.get(at).filter(|t| is_identifier(t)) {
+                    tables.push(name.clone());
+                }
+            }
+            "INSERT" => {
+                if let Some(into) = tokens.iter().position(|t| t.eq_ignore_ascii_case("INTO"))
+                    && let Some(name) = tokens.get(into + 1).filter(|t| is_identifier(t))
+                {
+                    tables.push(name.clone());
+                }
+            }
+            "RELATE" => {
+                let edge = tokens.windows(3).find_map(|w| match (&*w[0], &*w[1]) {
+                    ("-", ">") | ("<", "-") if is_identifier(&w[2]) => Some(w[2].clone()),
+                    _ => None,
+                });
+                tables.extend(edge);
+            }
+            _ => {}
+        }
+
+        tables
+    }
+}
+
+/// What a statement does, by its leading keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementKind {
+    Define,
+    Remove,
+    Alter,
+    Create,
+    Insert,
+    Update,
+    Upsert,
+    Delete,
+    Relate,
+    Select,
+    /// `USE NS`/`USE DB`, which switches the target of later statements.
+    Use,
+    /// Anything else: `LET`, `IF`, `FOR`, `RETURN`, transaction control, ...
+    Other,
+}
+
+impl StatementKind {
+    /// The kind's keyword in lowercase, as shown in reports.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StatementKind::Define => "define",
+            StatementKind::Remove => "remove",
+            StatementKind::Alter => "alter",
+            StatementKind::Create => "create",
+            StatementKind::Insert => "insert",
+            StatementKind::Update => "update",
+            StatementKind::Upsert => "upsert",
+            StatementKind::Delete => "delete",
+            StatementKind::Relate => "relate",
+            StatementKind::Select => "select",
+            StatementKind::Use => "use",
+            StatementKind::Other => "other",
+        }
+    }
+
+    /// `DEFINE`, `REMOVE` and `ALTER`.
+    pub fn is_schema(self) -> bool {
+        matches!(
+            self,
+            StatementKind::Define | StatementKind::Remove | StatementKind::Alter
+        )
+    }
+
+    /// Statements that write records: `CREATE`, `INSERT`, `UPDATE`,
+    /// `UPSERT`, `DELETE` and `RELATE`.
+    pub fn is_data(self) -> bool {
+        matches!(
+            self,
+            StatementKind::Create
+                | StatementKind::Insert
+                | StatementKind::Update
+                | StatementKind::Upsert
+                | StatementKind::Delete
+                | StatementKind::Relate
+        )
+    }
+}
+
+/// How many statements of each kind a script contains.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::statements::{StatementKind, StatementStats};
+///
+/// let stats = StatementStats::of("DEFINE TABLE user; DEFINE FIELD name ON user; CREATE user:a;");
+/// assert_eq!(stats.get(StatementKind::Define), 2);
+/// assert_eq!(stats.data(), 1);
+/// assert_eq!(stats.to_string(), "2 define, 1 create");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StatementStats(BTreeMap<StatementKind, usize>);
+
+impl StatementStats {
+    /// Classify every statement of `sql`.
+    pub fn of(sql: &str) -> Self {
+        let mut stats = Self::default();
+        for statement in split(sql) {
+            *stats.0.entry(statement.kind()).or_default() += 1;
+        }
+        stats
+    }
+
+    /// Number of statements of `kind`.
+    pub fn get(&self, kind: StatementKind) -> usize {
+        self.0.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Counts per kind, in `StatementKind` order, omitting absent kinds.
+    pub fn iter(&self) -> impl Iterator<Item = (StatementKind, usize)> + '_ {
+        self.0.iter().map(|(kind, count)| (*kind, *count))
+    }
+
+    /// Number of statements.
+    pub fn total(&self) -> usize {
+        self.0.values().sum()
+    }
+
+    /// Number of schema statements; see `StatementKind::is_schema`.
+    pub fn schema(&self) -> usize {
+        self.count(StatementKind::is_schema)
+    }
+
+    /// Number of data statements; see `StatementKind::is_data`.
+    pub fn data(&self) -> usize {
+        self.count(StatementKind::is_data)
+    }
+
+    /// `true` when nothing but schema statements would run.
+    pub fn is_schema_only(&self) -> bool {
+        self.schema() == self.total()
+    }
+
+    /// Add the counts of `other` to these.
+    pub fn merge(&mut self, other: &StatementStats) {
+        for (kind, count) in other.iter() {
+            *self.0.entry(kind).or_default() += count;
+        }
+    }
+
+    fn count(&self, pred: fn(StatementKind) -> bool) -> usize {
+        self.iter()
+            .filter(|(kind, _)| pred(*kind))
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+impl fmt::Display for StatementStats {
+    /// `2 define, 1 create`, or `no statements`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            return f.write_str("no statements");
+        }
+        let parts = self
+            .iter()
+            .map(|(kind, count)| format!("{count} {}", kind.as_str()))
+            .collect::<Vec<_>>();
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// Skip `IF [NOT] EXISTS` / `OVERWRITE` clauses starting at `at` and return
+/// the following identifier.
+fn skip_clauses(tokens: &[String], mut at: usize) -> Option<String> {
+    while let Some(t) = tokens.get(at) {
+        match t.to_ascii_uppercase().as_str() {
+            "IF" | "NOT" | "EXISTS" | "OVERWRITE" => at += 1,
+            _ => return Some(t.clone()),
+        }
+    }
+    None
+}
+
+fn is_identifier(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Break a statement into identifiers and single-character symbols.
+///
+/// Backtick and `⟨⟩` quoted identifiers are unquoted; string literals
+/// become a single opaque token.
+fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '`' | '⟨' => {
+                let close = if c == '`' { '`' } else { '⟩' };
+                let mut ident = String::new();
+                for (_, c) in chars.by_ref() {
+                    if c == close {
+                        break;
+                    }
+                    ident.push(c);
+                }
+                tokens.push(ident);
+            }
+            '\'' | '"' => {
+                let mut escaped = false;
+                for (_, n) in chars.by_ref() {
+                    match n {
+                        '\\' if !escaped => escaped = true,
+                        n if n == c && !escaped => break,
+                        _ => escaped = false,
+                    }
+                }
+                tokens.push("''".to_string());
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut end = i + c.len_utf8();
+                while let Some(&(j, n)) = chars.peek() {
+                    if !(n.is_alphanumeric() || n == '_') {
+                        break;
+                    }
+                    end = j + n.len_utf8();
+                    chars.next();
+                }
+                tokens.push(text[i..end].to_string());
+            }
+            c => tokens.push(c.to_string()),
+        }
+    }
+
+    tokens
+}
@@ -0,0 +1,169 @@
+//! Tracing spans and metrics around migration runs.
+//!
+//! With the `otel` feature every run gets a `migration_run` span and every
+//! migration a child `migration` span. Their fields follow the conventions
+//! of `tracing-opentelemetry` (`otel.name`, `otel.status_code`), so a
+//! subscriber with an OpenTelemetry layer exports them as OTLP spans with
+//! these attributes:
+//!
+//! - `migration.direction`: `up`, `down`, `redo` or `seed`
+//! - `migration.count` (run): how many migrations the run planned
+//! - `migration.name` (migration): the file or directory name
+//! - `migration.statements` (migration): statements in the executed script,
+//!   left out for migrations flagged `sensitive`
+//! - `migration.duration_ms`: wall-clock time
+//! - `migration.outcome`: `applied`, `reverted`, `skipped`, `failed`,
+//!   or `succeeded` for a whole run
+//!
+//! Without `otel` the span helpers return disabled spans.
+//!
+//! With the `metrics` feature the same points update these metrics through
+//! the `metrics` facade, for whichever recorder the application installed
+//! (e.g. `metrics-exporter-prometheus`):
+//!
+//! - `migrations_applied_total`: migrations applied
+//! - `migrations_reverted_total`: migrations reverted
+//! - `migration_failures_total`: migrations that failed, by `direction`
+//! - `migration_duration_seconds`: histogram of executed migrations, by
+//!   `direction` and `outcome`
+//! - `migration_runs_total`: runs, by `direction` and `outcome`
+
+use crate::types::{Migration, OutcomeStatus, RunDirection};
+use tracing::Span;
+
+/// The span covering one run in `direction` over `total` migrations.
+pub(crate) fn run(direction: RunDirection, total: usize) -> Span {
+    #[cfg(feature = "otel")]
+    {
+        tracing::info_span!(
+            "migration_run",
+            otel.name = format!("migrate {}", direction_name(direction)),
+            otel.status_code = tracing::field::Empty,
+            migration.direction = direction_name(direction),
+            migration.count = total,
+            migration.duration_ms = tracing::field::Empty,
+            migration.outcome = tracing::field::Empty,
+        )
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        let _ = (direction, total);
+        Span::none()
+    }
+}
+
+/// The span covering one migration of the `run` span, executing `script`.
+/// Sensitive migrations get no attributes derived from their script.
+pub(crate) fn migration(
+    run: &Span,
+    direction: RunDirection,
+    migration: &Migration,
+    script: &str,
+) -> Span {
+    #[cfg(feature = "otel")]
+    {
+        let name = migration.name.as_str();
+        let span = tracing::info_span!(
+            parent: run,
+            "migration",
+            otel.name = name,
+            otel.status_code = tracing::field::Empty,
+            migration.direction = direction_name(direction),
+            migration.name = name,
+            migration.statements = tracing::field::Empty,
+            migration.duration_ms = tracing::field::Empty,
+            migration.outcome = tracing::field::Empty,
+        );
+        if !migration.metadata.sensitive() {
+            span.record(
+                "migration.statements",
+                crate::statements::split(script).len(),
+            );
+        }
+        span
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        let _ = (run, direction, migration, script);
+        Span::none()
+    }
+}
+
+/// Record how a migration run in `direction` ended on its span and in the
+/// metrics; `None` means it was skipped.
+pub(crate) fn finish_migration(
+    span: &Span,
+    direction: RunDirection,
+    status: Option<OutcomeStatus>,
+    duration_ms: u64,
+) {
+    let outcome = match status {
+        Some(OutcomeStatus::Applied) => "applied",
+        Some(OutcomeStatus::Reverted) => "reverted",
+        Some(OutcomeStatus::Failed) => "failed",
+        None => "skipped",
+    };
+    finish(
+        span,
+        outcome,
+        status != Some(OutcomeStatus::Failed),
+        duration_ms,
+    );
+
+    #[cfg(feature = "metrics")]
+    {
+        let direction = direction_name(direction);
+        match status {
+            Some(OutcomeStatus::Applied) => {
+                metrics::counter!("migrations_applied_total").increment(1)
+            }
+            Some(OutcomeStatus::Reverted) => {
+                metrics::counter!("migrations_reverted_total").increment(1)
+            }
+            Some(OutcomeStatus::Failed) => {
+                metrics::counter!("migration_failures_total", "direction" => direction).increment(1)
+            }
+            None => return,
+        }
+        metrics::histogram!(
+            "migration_duration_seconds",
+            "direction" => direction,
+            "outcome" => outcome,
+        )
+        .record(duration_ms as f64 / 1000.0);
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = direction;
+}
+
+/// Record how a run in `direction` ended on its span and in the metrics.
+pub(crate) fn finish_run(span: &Span, direction: RunDirection, succeeded: bool, duration_ms: u64) {
+    let outcome = if succeeded { "succeeded" } else { "failed" };
+    finish(span, outcome, succeeded, duration_ms);
+
+    #[cfg(feature = "metrics")]
+    metrics::counter!(
+        "migration_runs_total",
+        "direction" => direction_name(direction),
+        "outcome" => outcome,
+    )
+    .increment(1);
+    #[cfg(not(feature = "metrics"))]
+    let _ = direction;
+}
+
+fn finish(span: &Span, outcome: &str, ok: bool, duration_ms: u64) {
+    span.record("migration.outcome", outcome);
+    span.record("migration.duration_ms", duration_ms);
+    span.record("otel.status_code", if ok { "ok" } else { "error" });
+}
+
+#[cfg(any(feature = "otel", feature = "metrics"))]
+fn direction_name(direction: RunDirection) -> &'static str {
+    match direction {
+        RunDirection::Up => "up",
+        RunDirection::Down => "down",
+        RunDirection::Redo => "redo",
+        RunDirection::Seed => "seed",
+    }
+}
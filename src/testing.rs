@@ -0,0 +1,457 @@
+use crate::MigrationRunner;
+use crate::schema::SchemaSnapshot;
+use crate::types::{InMemorySource, Migration, MigrationKind, MigrationSource};
+use eyre::{Result, eyre};
+use serde::Serialize;
+use similar::TextDiff;
+use std::path::Path;
+use surrealdb::Surreal;
+use surrealdb::engine::local::{Db, Mem};
+
+/// Set to `1` to make [`TestDb::assert_schema_snapshot`] write the current
+/// schema to the snapshot file instead of comparing against it.
+pub const UPDATE_SNAPSHOTS_VAR: &str = "SMG_UPDATE_SNAPSHOTS";
+
+/// A throwaway in-memory database for testing migrations, with assertion
+/// helpers that panic with a readable message when they fail.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::testing::TestDb;
+/// use surreal_migraine::types::InMemorySource;
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let source = InMemorySource::new()
+///     .with_migration("001_users", "DEFINE TABLE user SCHEMAFULL;
+/// DEFINE FIELD email ON user TYPE string;
+/// DEFINE INDEX by_email ON user FIELDS email UNIQUE;", None)
+///     .with_migration("002_admin", "CREATE user:admin SET email = 'admin@example.com';", None);
+///
+/// let db = TestDb::migrated(source).await.unwrap();
+/// db.assert_table_exists("user").await;
+/// db.assert_field_defined("user", "email").await;
+/// db.assert_index_present("user", "by_email").await;
+/// db.assert_rows("SELECT * FROM user", 1).await;
+/// # });
+/// ```
+pub struct TestDb {
+    db: Surreal<Db>,
+}
+
+impl TestDb {
+    /// An empty in-memory database, using namespace and database `test`.
+    pub async fn new() -> Result<Self> {
+        let db = Surreal::new::<Mem>(())
+            .await
+            .map_err(|e| eyre!(e.to_string()))?;
+        db.use_ns("test")
+            .use_db("test")
+            .await
+            .map_err(|e| eyre!(e.to_string()))?;
+        Ok(Self { db })
+    }
+
+    /// An in-memory database with every migration of `source` applied.
+    pub async fn migrated<S: MigrationSource>(source: S) -> Result<Self> {
+        let test = Self::new().await?;
+        test.runner(source).up().await?;
+        Ok(test)
+    }
+
+    /// The database, for queries the helpers do not cover.
+    pub fn db(&self) -> &Surreal<Db> {
+        &self.db
+    }
+
+    /// A runner over `source` on this database, for applying migrations
+    /// with custom settings (variables, environment, ...) or step by step.
+    pub fn runner<S: MigrationSource>(&self, source: S) -> MigrationRunner<'_, Db, S> {
+        MigrationRunner::new(&self.db, source)
+    }
+
+    /// The database's schema, without the runner's bookkeeping tables.
+    pub async fn schema(&self) -> Result<SchemaSnapshot> {
+        self.runner(InMemorySource::new()).schema_snapshot().await
+    }
+
+    /// Load the fixture at `path`: a `.surql` script, run as is, or a
+    /// `.json` object mapping table names to arrays of records, inserted
+    /// with [`TestDb::insert`] in table name order.
+    ///
+    /// ```json
+    /// { "user": [{ "id": "alice", "email": "alice@example.com" }] }
+    /// ```
+    pub async fn load_fixture(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| eyre!("cannot read {}: {e}", path.display()))?;
+        let loaded = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("surql") => self.run(&content).await,
+            Some("json") => self.insert_json(&content).await,
+            _ => Err(eyre!("not a .surql or .json fixture")),
+        };
+        loaded.map_err(|e| eyre!("fixture {}: {e}", path.display()))
+    }
+
+    /// Load every `.surql` and `.json` fixture in `dir`, in file name order
+    /// (prefix them like migrations to control it). Other files are
+    /// ignored.
+    ///
+    /// Fixtures are usually loaded between two sets of migrations, to run a
+    /// data migration against realistic records:
+    ///
+    /// ```rust,ignore
+    /// let db = TestDb::new().await?;
+    /// db.runner(schema_migrations).up().await?;
+    /// db.load_fixtures("tests/fixtures").await?;
+    /// db.runner(all_migrations).up().await?;
+    /// db.assert_rows("SELECT * FROM user WHERE email = NONE", 0).await;
+    /// ```
+    pub async fn load_fixtures(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let dir = dir.as_ref();
+        let mut paths = std::fs::read_dir(dir)
+            .map_err(|e| eyre!("cannot read {}: {e}", dir.display()))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.retain(|path| {
+            path.is_file()
+                && matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("surql" | "json")
+                )
+        });
+        paths.sort();
+        for path in paths {
+            self.load_fixture(path).await?;
+        }
+        Ok(())
+    }
+
+    /// Insert `records` into `table`. A record's `id`, when set, becomes
+    /// the record id (`"alice"` gives `user:alice`).
+    pub async fn insert<T: Serialize>(&self, table: &str, records: &[T]) -> Result<()> {
+        let records = records
+            .iter()
+            .map(serde_json::to_value)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        self.db
+            .query("INSERT INTO $table $records RETURN NONE")
+            .bind(("table", surrealdb::sql::Table::from(table)))
+            .bind(("records", records))
+            .await
+            .map_err(|e| eyre!(e.to_string()))?
+            .check()
+            .map_err(|e| eyre!(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn insert_json(&self, json: &str) -> Result<()> {
+        let tables: serde_json::Map<String, serde_json::Value> = serde_json::from_str(json)
+            .map_err(|e| eyre!("expected an object of table names to records: {e}"))?;
+        for (table, records) in tables {
+            let serde_json::Value::Array(records) = records else {
+                eyre::bail!("{table}: expected an array of records");
+            };
+            self.insert(&table, &records)
+                .await
+                .map_err(|e| eyre!("{table}: {e}"))?;
+        }
+        Ok(())
+    }
+
+    async fn run(&self, script: &str) -> Result<()> {
+        self.db
+            .query(script)
+            .await
+            .map_err(|e| eyre!(e.to_string()))?
+            .check()
+            .map_err(|e| eyre!(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Panic unless `table` is defined.
+    pub async fn assert_table_exists(&self, table: &str) {
+        let schema = self.expect_schema().await;
+        if !schema.tables.contains_key(table) {
+            let tables = schema.tables.keys().cloned().collect::<Vec<_>>();
+            panic!("table {table} does not exist (tables: {})", list(&tables));
+        }
+    }
+
+    /// Panic unless `field` is defined on `table`.
+    pub async fn assert_field_defined(&self, table: &str, field: &str) {
+        self.assert_table_exists(table).await;
+        let schema = self.expect_schema().await;
+        let fields = &schema.tables[table].fields;
+        if !fields.contains_key(field) {
+            let fields = fields.keys().cloned().collect::<Vec<_>>();
+            panic!(
+                "field {field} is not defined on {table} (fields: {})",
+                list(&fields)
+            );
+        }
+    }
+
+    /// Panic unless the index `index` is defined on `table`.
+    pub async fn assert_index_present(&self, table: &str, index: &str) {
+        self.assert_table_exists(table).await;
+        let schema = self.expect_schema().await;
+        let indexes = &schema.tables[table].indexes;
+        if !indexes.contains_key(index) {
+            let indexes = indexes.keys().cloned().collect::<Vec<_>>();
+            panic!(
+                "index {index} is not defined on {table} (indexes: {})",
+                list(&indexes)
+            );
+        }
+    }
+
+    /// Panic unless the schema matches the snapshot committed at `path`
+    /// (the canonical form of `SchemaSnapshot`), showing a diff when it
+    /// does not.
+    ///
+    /// A missing or outdated snapshot is written to `<path>.new` for review;
+    /// rename it over `path` to accept the change. With
+    /// [`UPDATE_SNAPSHOTS_VAR`] set to `1`, `path` is written directly and
+    /// the assertion passes.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// let db = TestDb::migrated(DiskSource::new("migrations")).await?;
+    /// db.assert_schema_snapshot("tests/snapshots/schema.json").await;
+    /// ```
+    pub async fn assert_schema_snapshot(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let current = self
+            .expect_schema()
+            .await
+            .to_canonical()
+            .unwrap_or_else(|e| panic!("could not serialize the schema: {e}"));
+        let pending = path.with_extension(match path.extension() {
+            Some(ext) => format!("{}.new", ext.to_string_lossy()),
+            None => "new".to_string(),
+        });
+        let write = |target: &Path| {
+            if let Some(dir) = target.parent() {
+                std::fs::create_dir_all(dir).unwrap_or_else(|e| panic!("{}: {e}", dir.display()));
+            }
+            std::fs::write(target, &current)
+                .unwrap_or_else(|e| panic!("{}: {e}", target.display()));
+        };
+
+        let committed = match std::fs::read_to_string(path) {
+            Ok(committed) => Some(committed),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => panic!("{}: {e}", path.display()),
+        };
+        let matches = committed
+            .as_deref()
+            .and_then(|json| SchemaSnapshot::parse(json).ok())
+            .and_then(|snapshot| snapshot.to_canonical().ok())
+            .is_some_and(|committed| committed == current);
+        if matches {
+            let _ = std::fs::remove_file(&pending);
+            return;
+        }
+        if std::env::var(UPDATE_SNAPSHOTS_VAR).is_ok_and(|v| v == "1") {
+            write(path);
+            let _ = std::fs::remove_file(&pending);
+            return;
+        }
+
+        write(&pending);
+        match committed {
+            None => panic!(
+                "no schema snapshot at {}; review {} and rename it, or rerun with {UPDATE_SNAPSHOTS_VAR}=1",
+                path.display(),
+                pending.display()
+            ),
+            Some(committed) => panic!(
+                "schema differs from the snapshot at {}; if the change is intended, rename {} over it or rerun with {UPDATE_SNAPSHOTS_VAR}=1\n{}",
+                path.display(),
+                pending.display(),
+                TextDiff::from_lines(&committed, &current)
+                    .unified_diff()
+                    .context_radius(3)
+                    .header("snapshot", "migrated")
+            ),
+        }
+    }
+
+    /// Panic unless the last statement of `query` returns `expected` rows.
+    pub async fn assert_rows(&self, query: &str, expected: usize) {
+        let (count, rows) = self
+            .count(query)
+            .await
+            .unwrap_or_else(|e| panic!("{query}: {e}"));
+        assert_eq!(
+            count, expected,
+            "{query} returned {count} row(s), expected {expected}: {rows}"
+        );
+    }
+
+    /// How many rows the last statement of `query` returns (a single
+    /// record counts as one, `NONE` as none), and the rows themselves.
+    async fn count(&self, query: &str) -> Result<(usize, surrealdb::Value)> {
+        let last = crate::statements::split(query)
+            .pop()
+            .ok_or_else(|| eyre!("no statement to count the rows of"))?;
+        let sql = format!(
+            "{}LET $__rows = ({});\nRETURN count($__rows);\nRETURN $__rows;",
+            &query[..last.offset],
+            last.text
+        );
+        let mut response = self.db.query(sql).await.map_err(|e| eyre!(e.to_string()))?;
+        let statements = response.num_statements();
+        let count: Option<usize> = response
+            .take(statements - 2)
+            .map_err(|e| eyre!(e.to_string()))?;
+        let rows: surrealdb::Value = response
+            .take(statements - 1)
+            .map_err(|e| eyre!(e.to_string()))?;
+        Ok((count.unwrap_or_default(), rows))
+    }
+
+    async fn expect_schema(&self) -> SchemaSnapshot {
+        self.schema()
+            .await
+            .unwrap_or_else(|e| panic!("could not read the schema: {e}"))
+    }
+}
+
+/// Panic unless every migration of `source` with a down script can be
+/// undone: on a fresh [`TestDb`], each migration is applied, reverted and
+/// applied again in order, and the schema after reverting must match the
+/// schema before it was applied.
+///
+/// Migrations without a down script are applied but not checked;
+/// repeatable migrations are applied first. The panic lists every
+/// migration that failed, with the schema objects its down script left
+/// behind or removed.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::testing::verify_reversibility;
+/// use surreal_migraine::types::InMemorySource;
+///
+/// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+/// let source = InMemorySource::new().with_migration(
+///     "001_users",
+///     "DEFINE TABLE user SCHEMAFULL; DEFINE FIELD email ON user TYPE string;",
+///     Some("REMOVE TABLE user;"),
+/// );
+/// verify_reversibility(source).await;
+/// # });
+/// ```
+pub async fn verify_reversibility<S: MigrationSource>(source: S) {
+    let test = TestDb::new()
+        .await
+        .unwrap_or_else(|e| panic!("could not open a test database: {e}"));
+    let migrations = source
+        .list()
+        .unwrap_or_else(|e| panic!("could not list migrations: {e}"));
+    let versioned = migrations
+        .iter()
+        .filter(|m| m.kind != MigrationKind::Repeatable)
+        .collect::<Vec<_>>();
+
+    let repeatables = Steps {
+        source: &source,
+        migrations: migrations
+            .iter()
+            .filter(|m| m.kind == MigrationKind::Repeatable)
+            .cloned()
+            .collect(),
+    };
+    if let Err(e) = test.runner(repeatables).up().await {
+        panic!("repeatable migrations failed: {e}");
+    }
+
+    let mut failures = Vec::new();
+    for (i, migration) in versioned.iter().enumerate() {
+        let name = &migration.name;
+        let steps = Steps {
+            source: &source,
+            migrations: migrations
+                .iter()
+                .filter(|m| {
+                    m.kind == MigrationKind::Repeatable
+                        || versioned[..=i].iter().any(|v| v.name == m.name)
+                })
+                .cloned()
+                .collect(),
+        };
+        let runner = test.runner(steps);
+        let before = test.expect_schema().await;
+        if let Err(e) = runner.up().await {
+            failures.push(format!("{name}: up failed: {e}"));
+            break;
+        }
+        match source.get_down(migration) {
+            Ok(Some(_)) => {}
+            Ok(None) => continue,
+            Err(e) => panic!("{name}: could not read the down script: {e}"),
+        }
+
+        let reverted = match i.checked_sub(1) {
+            Some(previous) => runner.down_to(&versioned[previous].name).await,
+            None => runner.down().await,
+        };
+        if let Err(e) = reverted {
+            failures.push(format!("{name}: down failed: {e}"));
+            break;
+        }
+        let changes = before.changes_to(&test.expect_schema().await);
+        if !changes.is_empty() {
+            let changes = changes
+                .iter()
+                .map(|change| format!("\n    {change}"))
+                .collect::<String>();
+            failures.push(format!(
+                "{name}: the schema after down differs from before up:{changes}"
+            ));
+        }
+        if let Err(e) = runner.up().await {
+            failures.push(format!("{name}: up failed after down: {e}"));
+            break;
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "migrations are not reversible:\n  {}",
+            failures.join("\n  ")
+        );
+    }
+}
+
+/// The migrations of `source` applied so far by [`verify_reversibility`].
+struct Steps<'s, S> {
+    source: &'s S,
+    migrations: Vec<Migration>,
+}
+
+impl<S: MigrationSource> MigrationSource for Steps<'_, S> {
+    fn list(&self) -> Result<Vec<Migration>> {
+        Ok(self.migrations.clone())
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        self.source.get_up(migration)
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        self.source.get_down(migration)
+    }
+}
+
+fn list(names: &[String]) -> String {
+    if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    }
+}
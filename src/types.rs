@@ -1,36 +1,125 @@
+use crate::metadata::MigrationMetadata;
 use eyre::Result;
 use include_dir::{Dir, DirEntry};
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::{HashMap, VecDeque},
     fs::read_to_string,
     path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
 };
-use surrealdb::RecordId;
+#[cfg(feature = "runner")]
+use surrealdb::{Datetime, RecordId};
 
 /// The kind of migration found in a migration source.
 ///
 /// - `File`: a single `.surql` file containing the "up" migration only.
 /// - `Paired`: a directory containing `up.surql` and `down.surql`.
+/// - `Repeatable`: a single `.surql` file prefixed with `R_` that is
+///   re-applied whenever its content changes.
 ///
 /// # Examples
 ///
 /// ```rust
-/// use crate::types::MigrationKind;
+/// use surreal_migraine::types::MigrationKind;
 ///
 /// let single = MigrationKind::File;
 /// let dir = MigrationKind::Paired;
 ///
 /// match single {
 ///     MigrationKind::File => assert!(true),
-///     MigrationKind::Paired => panic!("expected File"),
+///     MigrationKind::Paired | MigrationKind::Repeatable => panic!("expected File"),
 /// }
 /// ```
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum MigrationKind {
     /// A migration stored as a single `.surql` file (up-only).
     File,
     /// A migration stored as a directory with `up.surql` and `down.surql`.
     Paired,
+    /// A single `R_`-prefixed `.surql` file (up-only) that is applied after
+    /// all versioned migrations and re-applied whenever its checksum changes.
+    Repeatable,
+}
+
+/// Prefix marking a repeatable migration file name.
+pub const REPEATABLE_PREFIX: &str = "R_";
+
+/// Classify a discovered entry by name, returning the reason it is skipped
+/// for entries that are not migrations.
+///
+/// Names starting with an ASCII digit are versioned migrations (`File` or
+/// `Paired` depending on `is_dir`). Files starting with `R_` are
+/// `Repeatable`. Everything else is ignored.
+fn classify(name: &str, is_dir: bool) -> Result<MigrationKind, &'static str> {
+    if name.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+        Ok(if is_dir {
+            MigrationKind::Paired
+        } else {
+            MigrationKind::File
+        })
+    } else if !is_dir && name.starts_with(REPEATABLE_PREFIX) {
+        Ok(MigrationKind::Repeatable)
+    } else if is_dir {
+        Err("directory name does not start with a digit")
+    } else {
+        Err("file name does not start with a digit or `R_`")
+    }
+}
+
+/// An entry a source found but skipped because it is not a migration.
+///
+/// Hidden entries (names starting with `.`) and the lock file (see
+/// `lockfile::LOCK_FILE`) are skipped without being reported.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::types::{DiskSource, MigrationSource};
+///
+/// let dir = std::env::temp_dir().join("migraine_ignored_doc");
+/// std::fs::create_dir_all(&dir).unwrap();
+/// std::fs::write(dir.join("init.surql"), "DEFINE TABLE user;").unwrap();
+///
+/// let ignored = DiskSource::new(&dir).ignored().unwrap();
+/// assert_eq!(ignored[0].name, "init.surql");
+/// # std::fs::remove_dir_all(&dir).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IgnoredEntry {
+    /// The entry's file or directory name.
+    pub name: String,
+    /// Why the entry is not a migration.
+    pub reason: String,
+}
+
+/// Split sorted `(name, is_dir)` entries into migrations and ignored entries.
+fn discover<S: MigrationSource + ?Sized>(
+    source: &S,
+    entries: impl IntoIterator<Item = (String, bool)>,
+) -> (Vec<Migration>, Vec<IgnoredEntry>) {
+    let mut migrations = Vec::new();
+    let mut ignored = Vec::new();
+    for (name, is_dir) in entries {
+        match classify(&name, is_dir) {
+            Ok(kind) => {
+                let migration = Migration {
+                    name,
+                    kind,
+                    metadata: MigrationMetadata::default(),
+                };
+                migrations.push(with_metadata(source, migration));
+            }
+            Err(_) if name.starts_with('.') || name == crate::lockfile::LOCK_FILE => {}
+            Err(reason) => ignored.push(IgnoredEntry {
+                name,
+                reason: reason.to_string(),
+            }),
+        }
+    }
+    (migrations, ignored)
 }
 
 /// A migration entry found in a migration source.
@@ -39,20 +128,24 @@ pub enum MigrationKind {
 /// `MigrationSource`. The `name` is the file name (for `File` migrations)
 /// or directory name (for `Paired` migrations). The `kind` indicates how
 /// the migration is stored and how the source should load its contents.
+/// `metadata` holds the structured header parsed from the "up" SQL.
 ///
 /// # Examples
 ///
 /// ```rust
-/// use crate::types::{Migration, MigrationKind};
+/// use surreal_migraine::metadata::MigrationMetadata;
+/// use surreal_migraine::types::{Migration, MigrationKind};
 ///
 /// let file_migration = Migration {
 ///     name: "001_init.surql".to_string(),
 ///     kind: MigrationKind::File,
+///     metadata: MigrationMetadata::default(),
 /// };
 ///
 /// let paired_migration = Migration {
 ///     name: "002_add_posts".to_string(),
 ///     kind: MigrationKind::Paired,
+///     metadata: MigrationMetadata::default(),
 /// };
 ///
 /// assert_eq!(file_migration.kind, MigrationKind::File);
@@ -62,8 +155,21 @@ pub enum MigrationKind {
 pub struct Migration {
     /// The migration's file or directory name (e.g. `001_init.surql` or `002_add_posts`).
     pub name: String,
-    /// The storage kind for this migration: `File` or `Paired`.
+    /// The storage kind for this migration: `File`, `Paired` or `Repeatable`.
     pub kind: MigrationKind,
+    /// Metadata parsed from the comment header of the "up" SQL.
+    pub metadata: MigrationMetadata,
+}
+
+/// Fill in `migration.metadata` from the header of its "up" SQL.
+///
+/// Unreadable content yields empty metadata; the error resurfaces when the
+/// runner loads the migration.
+fn with_metadata<S: MigrationSource + ?Sized>(source: &S, mut migration: Migration) -> Migration {
+    if let Ok(content) = source.get_up(&migration) {
+        migration.metadata = MigrationMetadata::parse(&content);
+    }
+    migration
 }
 
 /// A persisted record representing an applied migration in the database.
@@ -85,12 +191,662 @@ pub struct Migration {
 /// };
 /// println!("applied migration: {}", rec.name);
 /// ```
+#[cfg(feature = "runner")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MigrationRecord {
     /// The SurrealDB record id assigned to this migration record.
     pub id: RecordId,
     /// The migration's file or directory name.
     pub name: String,
+    /// Checksum of the "up" SQL at the time it was applied.
+    ///
+    /// Records written before checksums were tracked have no checksum.
+    #[serde(default)]
+    pub checksum: Option<String>,
+    /// Algorithm `checksum` was computed with.
+    ///
+    /// Records written before algorithms were tracked have none and use
+    /// SHA-256.
+    #[serde(default)]
+    pub algorithm: Option<String>,
+    /// The "up" SQL as it was applied, used to diff against later edits.
+    ///
+    /// Records written before content was stored have none.
+    #[serde(default)]
+    pub content: Option<String>,
+    /// Whether the migration was recorded as part of a baseline instead of
+    /// being executed.
+    #[serde(default)]
+    pub baseline: bool,
+    /// When the migration was applied, assigned by the database.
+    ///
+    /// Records written before this was tracked have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_at: Option<Datetime>,
+    /// Position in the order migrations were applied, increasing with each
+    /// record. Reverts follow it rather than discovery order.
+    ///
+    /// Records written before this was tracked have none and count as
+    /// applied first, in discovery order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_seq: Option<u64>,
+    /// The run that applied the migration: every migration applied by one
+    /// `up()` (or `fresh()`, `redo()`) shares a batch, numbered from 1.
+    ///
+    /// Records written before batches were tracked have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<u64>,
+}
+
+/// How the runner submits a migration script to the database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExecutionMode {
+    /// Send the whole script as one query, wrapped in a transaction unless
+    /// it carries the `-- no-transaction` directive.
+    #[default]
+    Script,
+    /// Split the script into statements and execute them one at a time.
+    ///
+    /// Failures name the statement and its line number. Statements are not
+    /// wrapped in a transaction, so those before a failure stay applied.
+    PerStatement,
+}
+
+/// What `up()` does with a pending migration that sorts before one that is
+/// already applied.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutOfOrderPolicy {
+    /// Refuse to run and list the offending migrations.
+    Error,
+    /// Log a warning for each offending migration, then apply it.
+    #[default]
+    Warn,
+    /// Apply offending migrations without a warning.
+    Allow,
+}
+
+/// What the runner does with `USE NS`/`USE DB` statements in migration
+/// scripts.
+///
+/// A `USE` switches every later statement of the script, and the runner's
+/// own bookkeeping, away from the namespace and database the runner was
+/// connected to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UsePolicy {
+    /// Refuse to run and list the offending migrations and lines.
+    #[default]
+    Reject,
+    /// Remove the statements before executing, logging a warning for each
+    /// script they are removed from.
+    Strip,
+}
+
+/// Settings for the advisory lock taken by `MigrationRunner::with_lock()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockOptions {
+    /// How often the holder refreshes the lock's heartbeat.
+    pub heartbeat_interval: Duration,
+    /// How old a heartbeat must be before another runner may take the lock over.
+    pub stale_after: Duration,
+}
+
+impl Default for LockOptions {
+    /// Refresh every 5 seconds; consider the holder dead after 30 seconds.
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(5),
+            stale_after: Duration::from_secs(30),
+        }
+    }
+}
+
+/// A handle for cancelling a run from elsewhere, such as a Ctrl-C handler.
+///
+/// Pass a clone to `MigrationRunner::with_cancel_token()` and call
+/// `cancel()` to stop the run. The in-flight query is abandoned, and the
+/// migration it belonged to is recorded as failed, leaving the database
+/// dirty since part of it may have been applied.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::types::CancelToken;
+///
+/// let token = CancelToken::new();
+/// let handle = token.clone();
+/// handle.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[cfg(feature = "runner")]
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(std::sync::Arc<CancelState>);
+
+#[cfg(feature = "runner")]
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: std::sync::atomic::AtomicBool,
+    notify: tokio::sync::Notify,
+}
+
+#[cfg(feature = "runner")]
+impl CancelToken {
+    /// Create a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancel every run holding a clone of this token.
+    pub fn cancel(&self) {
+        self.0
+            .cancelled
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    /// Whether `cancel()` has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    /// Wait until the token is cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.0.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// A step of a run, reported to the listener passed to
+/// `MigrationRunner::with_progress()`.
+///
+/// `index` and `total` count the migrations (or, for `StatementStarted`,
+/// the statements of the current migration) the run executes; `index`
+/// starts at 0.
+#[cfg(feature = "runner")]
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// A run in `direction` is about to execute `total` migrations.
+    RunStarted {
+        direction: RunDirection,
+        total: usize,
+    },
+    /// A migration started.
+    MigrationStarted {
+        name: String,
+        index: usize,
+        total: usize,
+    },
+    /// A statement of `migration` is about to run. Only reported with
+    /// `ExecutionMode::PerStatement`, which splits scripts.
+    StatementStarted {
+        migration: String,
+        index: usize,
+        total: usize,
+    },
+    /// A migration finished, or was skipped because it has no down script
+    /// (`status` is `None`).
+    MigrationFinished {
+        name: String,
+        index: usize,
+        total: usize,
+        status: Option<OutcomeStatus>,
+        duration_ms: u64,
+    },
+    /// The run ended, successfully or not.
+    RunFinished {
+        direction: RunDirection,
+        succeeded: bool,
+    },
+}
+
+/// Receives the [`ProgressEvent`]s of a run, e.g. to drive a progress bar.
+///
+/// Implemented for closures. Listeners are called inline, between
+/// migrations and statements, so they should return quickly.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::types::{ProgressEvent, ProgressListener};
+///
+/// let listener = |event: &ProgressEvent| {
+///     if let ProgressEvent::MigrationStarted { name, index, total } = event {
+///         eprintln!("[{}/{total}] {name}", index + 1);
+///     }
+/// };
+/// listener.on_progress(&ProgressEvent::MigrationStarted {
+///     name: "001_users".into(),
+///     index: 0,
+///     total: 1,
+/// });
+/// ```
+#[cfg(feature = "runner")]
+pub trait ProgressListener {
+    /// Handle `event`.
+    fn on_progress(&self, event: &ProgressEvent);
+}
+
+#[cfg(feature = "runner")]
+impl<F: Fn(&ProgressEvent)> ProgressListener for F {
+    fn on_progress(&self, event: &ProgressEvent) {
+        self(event)
+    }
+}
+
+/// An event of a run started with `MigrationRunner::up_with_events()`.
+#[cfg(feature = "runner")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum MigrationEvent {
+    /// The run found these migrations to apply, in order.
+    Discovered { pending: Vec<String> },
+    /// A migration started executing.
+    Started { name: String },
+    /// A migration was applied and recorded.
+    Applied { name: String, duration_ms: u64 },
+    /// A migration failed; the run stops after it.
+    Failed { name: String, error: String },
+    /// A migration was passed over, for the given reason.
+    Skipped { name: String, reason: String },
+}
+
+/// The state of a discovered migration relative to the database.
+///
+/// Returned by `MigrationRunner::status()`, in discovery order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationStatus {
+    /// The migration's file or directory name.
+    pub name: String,
+    /// The storage kind of the migration.
+    pub kind: MigrationKind,
+    /// Whether a record for the migration exists in the `migrations` table.
+    pub applied: bool,
+    /// Checksum of the migration's current "up" SQL.
+    pub checksum: String,
+    /// Checksum recorded when the migration was applied, if any.
+    pub applied_checksum: Option<String>,
+    /// Whether the migration was recorded as baseline rather than executed.
+    #[serde(default)]
+    pub baseline: bool,
+    /// When the migration was applied, if it is and the time was recorded.
+    #[cfg(feature = "runner")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_at: Option<Datetime>,
+    /// Position in the apply order, if applied and recorded; see
+    /// `MigrationRecord::applied_seq`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied_seq: Option<u64>,
+    /// Batch of the run that applied the migration, if applied and
+    /// recorded; see `MigrationRecord::batch`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<u64>,
+}
+
+impl MigrationStatus {
+    /// Whether the current content matches what was applied.
+    ///
+    /// Returns `None` when the migration is pending or was applied before
+    /// checksums were recorded.
+    pub fn checksum_matches(&self) -> Option<bool> {
+        self.applied_checksum
+            .as_ref()
+            .map(|applied| *applied == self.checksum)
+    }
+}
+
+/// An applied migration whose content changed after it was applied.
+///
+/// Returned by `MigrationRunner::verify()`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChecksumMismatch {
+    /// The migration's file or directory name.
+    pub name: String,
+    /// Checksum recorded when the migration was applied.
+    pub applied_checksum: String,
+    /// Checksum of the migration's current "up" SQL.
+    pub checksum: String,
+    /// Unified diff from the applied SQL to the current SQL, when the applied
+    /// SQL was recorded.
+    pub diff: Option<String>,
+}
+
+/// The direction a migration run moved the schema in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunDirection {
+    /// Pending migrations were applied (`up()`).
+    Up,
+    /// Applied migrations were reverted (`down()`).
+    Down,
+    /// An applied migration was reverted and applied again (`redo()`).
+    Redo,
+    /// Seed scripts were run (`seed()`).
+    Seed,
+}
+
+/// The result of executing a single migration during a run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutcomeStatus {
+    /// The migration's up script ran and was recorded.
+    Applied,
+    /// The migration's down script ran and its record was removed.
+    Reverted,
+    /// Executing the migration failed; the run stopped here.
+    Failed,
+}
+
+/// Per-migration entry of a [`RunReport`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationOutcome {
+    /// The migration's file or directory name.
+    pub name: String,
+    /// What happened to the migration.
+    pub status: OutcomeStatus,
+    /// Wall-clock time spent executing the migration, in milliseconds.
+    pub duration_ms: u64,
+    /// The error message when `status` is `Failed`.
+    pub error: Option<String>,
+}
+
+/// A persisted summary of one `up()` or `down()` invocation.
+///
+/// A report is written to the `migration_runs` table at the end of every
+/// run, whether it succeeded or failed, so the history of a database can be
+/// inspected from any machine via `MigrationRunner::runs()`.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// for run in runner.runs().await? {
+///     println!("{:?} {} -> {:?}", run.direction, run.fingerprint, run.error);
+/// }
+/// ```
+#[cfg(feature = "runner")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunReport {
+    /// The SurrealDB record id, set once the report has been persisted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<RecordId>,
+    /// Whether the run applied or reverted migrations.
+    pub direction: RunDirection,
+    /// Fingerprint of the ordered migration names and contents the run planned to execute.
+    pub fingerprint: String,
+    /// Outcomes in execution order.
+    pub outcomes: Vec<MigrationOutcome>,
+    /// Total wall-clock duration of the run, in milliseconds.
+    pub duration_ms: u64,
+    /// The error that aborted the run, if any.
+    pub error: Option<String>,
+    /// Change-management ticket approving the run, e.g. `JIRA-123`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_ref: Option<String>,
+    /// Batch the migrations applied by the run were recorded with; `None`
+    /// when it applied nothing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<u64>,
+    /// When the run finished, assigned by the database on insert.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<Datetime>,
+}
+
+/// A named checkpoint in migration history, created by
+/// `MigrationRunner::tag()`.
+#[cfg(feature = "runner")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tag {
+    /// The tag name, typically an application release such as `v2.3.0`.
+    pub name: String,
+    /// The most recently applied migration when the tag was created.
+    pub migration: String,
+    /// When the tag was created, assigned by the database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<Datetime>,
+}
+
+/// Marker left behind when a migration fails partway through a run.
+///
+/// While it exists `up()` and `down()` refuse to run; inspect the database,
+/// fix the partial change by hand, then clear it with
+/// `MigrationRunner::repair()`.
+#[cfg(feature = "runner")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DirtyState {
+    /// The migration that failed.
+    pub migration: String,
+    /// Whether it failed while being applied or reverted.
+    pub direction: RunDirection,
+    /// The error the migration failed with.
+    pub error: String,
+    /// When the failure was recorded, assigned by the database.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failed_at: Option<Datetime>,
+}
+
+#[cfg(feature = "runner")]
+impl RunReport {
+    /// Create an empty report for a run in `direction` executing the plan
+    /// identified by `fingerprint`.
+    pub fn new(direction: RunDirection, fingerprint: String) -> Self {
+        Self {
+            id: None,
+            direction,
+            fingerprint,
+            outcomes: Vec::new(),
+            duration_ms: 0,
+            error: None,
+            change_ref: None,
+            batch: None,
+            finished_at: None,
+        }
+    }
+
+    /// Returns `true` when the run completed without error.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// What a successful `up()`, `down()` or `fresh()` did, returned to the
+/// caller.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let report = runner.up().await?;
+/// for name in report.applied() {
+///     println!("applied {name}");
+/// }
+/// for warning in &report.warnings {
+///     eprintln!("warning: {warning}");
+/// }
+/// ```
+#[cfg(feature = "runner")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationReport {
+    /// Whether the run applied or reverted migrations.
+    pub direction: RunDirection,
+    /// Migrations executed, in order, with their durations.
+    pub outcomes: Vec<MigrationOutcome>,
+    /// Migrations passed over: those limited to another environment, and
+    /// when reverting, those without a down script.
+    pub skipped: Vec<String>,
+    /// Batch the applied migrations were recorded with; `None` when the
+    /// run applied nothing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<u64>,
+    /// Total wall-clock duration of the run, in milliseconds.
+    pub duration_ms: u64,
+    /// Warnings raised during the run, such as migrations applied out of
+    /// order, accepted drift or stripped `USE` statements.
+    pub warnings: Vec<String>,
+}
+
+#[cfg(feature = "runner")]
+impl MigrationReport {
+    /// Names of the migrations the run applied.
+    pub fn applied(&self) -> impl Iterator<Item = &str> {
+        self.with_status(OutcomeStatus::Applied)
+    }
+
+    /// Names of the migrations the run reverted.
+    pub fn reverted(&self) -> impl Iterator<Item = &str> {
+        self.with_status(OutcomeStatus::Reverted)
+    }
+
+    /// Returns `true` when the run executed no migration.
+    pub fn is_empty(&self) -> bool {
+        self.outcomes.is_empty()
+    }
+
+    fn with_status(&self, status: OutcomeStatus) -> impl Iterator<Item = &str> {
+        self.outcomes
+            .iter()
+            .filter(move |o| o.status == status)
+            .map(|o| o.name.as_str())
+    }
+}
+
+/// A tenant database: a namespace and database sharing one schema with
+/// the other tenants.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Tenant {
+    /// The tenant's namespace.
+    pub namespace: String,
+    /// The tenant's database.
+    pub database: String,
+}
+
+impl Tenant {
+    /// The tenant database `database` in namespace `namespace`.
+    pub fn new(namespace: impl Into<String>, database: impl Into<String>) -> Self {
+        Self {
+            namespace: namespace.into(),
+            database: database.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Tenant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.namespace, self.database)
+    }
+}
+
+/// Which tenants `discover_tenants()` keeps, by `namespace/database`
+/// patterns in which `*` matches any run of characters.
+///
+/// A tenant is kept when it matches an include pattern (or there are
+/// none) and no exclude pattern. A pattern without `/` matches every
+/// database of the namespaces it matches.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::types::{Tenant, TenantFilter};
+///
+/// let filter = TenantFilter::new()
+///     .with_include("tenants")
+///     .with_exclude("tenants/*_archive");
+/// assert!(filter.matches(&Tenant::new("tenants", "acme")));
+/// assert!(!filter.matches(&Tenant::new("tenants", "acme_archive")));
+/// assert!(!filter.matches(&Tenant::new("ops", "main")));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TenantFilter {
+    /// Patterns a tenant must match one of; empty keeps every tenant.
+    pub include: Vec<String>,
+    /// Patterns of tenants to leave out.
+    pub exclude: Vec<String>,
+}
+
+impl TenantFilter {
+    /// A filter keeping every tenant.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep tenants matching `pattern` (and other include patterns).
+    pub fn with_include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Leave out tenants matching `pattern`.
+    pub fn with_exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Whether `tenant` passes the filter.
+    pub fn matches(&self, tenant: &Tenant) -> bool {
+        let name = tenant.to_string();
+        let matching = |pattern: &String| {
+            if pattern.contains('/') {
+                wildcard(pattern, &name)
+            } else {
+                wildcard(pattern, &tenant.namespace)
+            }
+        };
+        (self.include.is_empty() || self.include.iter().any(matching))
+            && !self.exclude.iter().any(matching)
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` stands for any run of
+/// characters.
+fn wildcard(pattern: &str, text: &str) -> bool {
+    let Some((first, rest)) = pattern.split_once('*') else {
+        return pattern == text;
+    };
+    let Some(mut text) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = rest.split('*').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return text.ends_with(part);
+        }
+        match text.find(part) {
+            Some(at) => text = &text[at + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// What `up_for_tenants()` does after a tenant fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TenantFailurePolicy {
+    /// Stop; later tenants are not touched.
+    #[default]
+    FailFast,
+    /// Go on with the next tenant.
+    Continue,
+}
+
+/// How migrating one tenant of `up_for_tenants()` went.
+#[cfg(feature = "runner")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TenantOutcome {
+    /// The migrated tenant.
+    pub tenant: Tenant,
+    /// What the run did, when it succeeded.
+    pub report: Option<MigrationReport>,
+    /// The error that failed the tenant's run.
+    pub error: Option<String>,
+}
+
+#[cfg(feature = "runner")]
+impl TenantOutcome {
+    /// Returns `true` when the tenant was migrated without error.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
 }
 
 /// A source of migrations.
@@ -132,6 +888,44 @@ pub trait MigrationSource {
     /// Returns `Ok(Some(sql))` when a down migration exists, `Ok(None)` when the
     /// migration is up-only, or an `Err` if loading failed.
     fn get_down(&self, migration: &Migration) -> Result<Option<String>>;
+
+    /// List the entries `list()` skipped, with the reason for each.
+    ///
+    /// Sources that never skip anything keep the default, which returns an
+    /// empty list.
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        Ok(Vec::new())
+    }
+
+    /// List migrations and check them for duplicate numeric prefixes and,
+    /// optionally, gaps. See `validate::validate`.
+    fn validate(&self, check_gaps: bool) -> Result<crate::validate::ValidationReport> {
+        Ok(crate::validate::validate(&self.list()?, check_gaps))
+    }
+
+    /// Lint every migration's scripts for secrets and personal data. See
+    /// `lint::lint`.
+    fn lint(&self) -> Result<Vec<crate::lint::Finding>> {
+        crate::lint::lint(self)
+    }
+}
+
+impl<S: MigrationSource + ?Sized> MigrationSource for &S {
+    fn list(&self) -> Result<Vec<Migration>> {
+        (**self).list()
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        (**self).get_up(migration)
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        (**self).get_down(migration)
+    }
+
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        (**self).ignored()
+    }
 }
 
 /// A `MigrationSource` implementation that reads migrations from the filesystem.
@@ -139,8 +933,9 @@ pub trait MigrationSource {
 /// `DiskSource` expects a directory containing migration entries. Each entry
 /// may be either a single `.surql` file (treated as `MigrationKind::File`) or
 /// a directory (treated as `MigrationKind::Paired`) containing `up.surql` and
-/// `down.surql` files. Entries whose names do not start with an ASCII digit
-/// are ignored by `list()`.
+/// `down.surql` files. Files prefixed with `R_` are treated as
+/// `MigrationKind::Repeatable`. Other entries whose names do not start with
+/// an ASCII digit are ignored by `list()`.
 ///
 /// # Examples
 ///
@@ -185,14 +980,32 @@ impl DiskSource {
             source: path.into(),
         }
     }
+
+    /// Read the directory, sorted by path.
+    fn discover(&self) -> Result<(Vec<Migration>, Vec<IgnoredEntry>)> {
+        let mut entries: Vec<_> = std::fs::read_dir(&self.source)?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        entries.sort_by_key(|e| e.path());
+
+        let entries = entries.into_iter().filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_str()?.to_string();
+            Some((name, path.is_dir()))
+        });
+        Ok(discover(self, entries))
+    }
 }
 
 impl MigrationSource for DiskSource {
     /// Filesystem-backed implementation details.
     ///
     /// - `list()` enumerates directory entries, sorts them, filters out
-    ///   entries whose names don't start with an ASCII digit, and maps files
-    ///   to `MigrationKind::File` and directories to `MigrationKind::Paired`.
+    ///   entries that aren't migrations, and maps files to
+    ///   `MigrationKind::File` (or `MigrationKind::Repeatable` for `R_` files)
+    ///   and directories to `MigrationKind::Paired`. Each migration's header
+    ///   is parsed into `Migration::metadata`.
     ///
     /// Example:
     ///
@@ -205,49 +1018,26 @@ impl MigrationSource for DiskSource {
     /// }
     /// ```
     fn list(&self) -> Result<Vec<Migration>> {
-        let mut migrations = Vec::new();
-
-        let mut entries: Vec<_> = std::fs::read_dir(&self.source)?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        entries.sort_by_key(|e| e.path());
-
-        for entry in entries {
-            let path = entry.path();
-
-            let name = match path.file_name().and_then(|s| s.to_str()) {
-                Some(n) => n.to_string(),
-                None => continue,
-            };
-
-            if !name.chars().next().is_some_and(|c| c.is_ascii_digit()) {
-                continue;
-            }
-
-            let kind = if path.is_dir() {
-                MigrationKind::Paired
-            } else {
-                MigrationKind::File
-            };
-
-            migrations.push(Migration { name, kind });
-        }
+        Ok(self.discover()?.0)
+    }
 
-        Ok(migrations)
+    /// Entries of the directory that are not migrations.
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        Ok(self.discover()?.1)
     }
 
     /// Read the "up" SQL for `migration`.
     ///
     /// For `MigrationKind::Paired` the function reads `<dir>/up.surql`.
-    /// For `MigrationKind::File` it reads the file directly.
+    /// For `MigrationKind::File` and `MigrationKind::Repeatable` it reads the
+    /// file directly.
     ///
     /// Example:
     ///
     /// ```rust,ignore
     /// use crate::types::{DiskSource, MigrationSource, Migration, MigrationKind};
     /// let src = DiskSource::new("migrations");
-    /// let m = Migration { name: "001_init.surql".to_string(), kind: MigrationKind::File };
+    /// let m = Migration { name: "001_init.surql".to_string(), kind: MigrationKind::File, metadata: Default::default() };
     /// let up = src.get_up(&m).expect("read up");
     /// println!("up sql: {} bytes", up.len());
     /// ```
@@ -260,7 +1050,7 @@ impl MigrationSource for DiskSource {
                 let content = read_to_string(up_path)?;
                 Ok(content)
             }
-            MigrationKind::File => {
+            MigrationKind::File | MigrationKind::Repeatable => {
                 let content = read_to_string(path)?;
                 Ok(content)
             }
@@ -270,15 +1060,15 @@ impl MigrationSource for DiskSource {
     /// Read the "down" SQL for `migration`, if present.
     ///
     /// Returns `Ok(Some(sql))` for paired migrations that include `down.surql`,
-    /// `Ok(None)` for file-based (up-only) migrations, or an `Err` on IO
-    /// failures.
+    /// `Ok(None)` for file-based and repeatable (up-only) migrations, or an
+    /// `Err` on IO failures.
     ///
     /// Example:
     ///
     /// ```rust,ignore
     /// use crate::types::{DiskSource, MigrationSource, Migration, MigrationKind};
     /// let src = DiskSource::new("migrations");
-    /// let m = Migration { name: "002_add_posts".to_string(), kind: MigrationKind::Paired };
+    /// let m = Migration { name: "002_add_posts".to_string(), kind: MigrationKind::Paired, metadata: Default::default() };
     /// match src.get_down(&m).expect("read down") {
     ///     Some(sql) => println!("down sql: {} bytes", sql.len()),
     ///     None => println!("no down migration"),
@@ -293,7 +1083,7 @@ impl MigrationSource for DiskSource {
                 let content = read_to_string(down_path)?;
                 Ok(Some(content))
             }
-            MigrationKind::File => Ok(None),
+            MigrationKind::File | MigrationKind::Repeatable => Ok(None),
         }
     }
 }
@@ -304,7 +1094,8 @@ impl MigrationSource for DiskSource {
 /// `EmbeddedSource` wraps an `include_dir::Dir` and exposes the same
 /// semantics as `DiskSource`: entries may be either files (mapped to
 /// `MigrationKind::File`) or directories (mapped to `MigrationKind::Paired`).
-/// Names that do not start with an ASCII digit are ignored by `list()`.
+/// Files prefixed with `R_` are mapped to `MigrationKind::Repeatable`. Other
+/// names that do not start with an ASCII digit are ignored by `list()`.
 ///
 /// Use this when you want to embed migration SQL into the binary rather
 /// than read from disk at runtime.
@@ -346,17 +1137,30 @@ impl<'a> EmbeddedSource<'a> {
     /// let migrations = src.list().unwrap();
     /// assert!(!migrations.is_empty());
     /// ```
-    pub fn new(source: &'a Dir<'a>) -> Self {
+    pub const fn new(source: &'a Dir<'a>) -> Self {
         Self { source }
     }
+
+    fn discover(&self) -> (Vec<Migration>, Vec<IgnoredEntry>) {
+        let entries = self.source.entries().iter().map(|entry| {
+            let name = entry
+                .path()
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            (name, matches!(entry, DirEntry::Dir(_)))
+        });
+        discover(self, entries)
+    }
 }
 
 impl MigrationSource for EmbeddedSource<'_> {
     /// List embedded migrations.
     ///
     /// This enumerates entries in the embedded directory, converts names to
-    /// UTF-8, filters out entries that don't start with an ASCII digit, and
-    /// classifies each entry as `File` or `Paired`.
+    /// UTF-8, filters out entries that aren't migrations, and classifies each
+    /// entry as `File`, `Paired` or `Repeatable`.
     ///
     /// Example:
     ///
@@ -366,42 +1170,25 @@ impl MigrationSource for EmbeddedSource<'_> {
     /// assert!(!items.is_empty());
     /// ```
     fn list(&self) -> Result<Vec<Migration>> {
-        let mut migrations = Vec::new();
-
-        for entry in self.source.entries() {
-            let path = entry.path();
-
-            let name = path
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
-
-            if !name.chars().next().is_some_and(|c| c.is_ascii_digit()) {
-                continue;
-            }
-
-            let kind = match entry {
-                DirEntry::File(_) => MigrationKind::File,
-                DirEntry::Dir(_) => MigrationKind::Paired,
-            };
-
-            migrations.push(Migration { name, kind });
-        }
+        Ok(self.discover().0)
+    }
 
-        Ok(migrations)
+    /// Embedded entries that are not migrations.
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        Ok(self.discover().1)
     }
 
     /// Read the "up" SQL for the given embedded migration.
     ///
     /// For `MigrationKind::Paired` this reads `up.surql` from the embedded
-    /// directory. For `MigrationKind::File` it reads the file contents.
+    /// directory. For `MigrationKind::File` and `MigrationKind::Repeatable` it
+    /// reads the file contents.
     ///
     /// Example:
     ///
     /// ```rust,ignore
     /// let src = EmbeddedSource::new(&MIGS);
-    /// let m = Migration { name: "001_init.surql".to_string(), kind: MigrationKind::File };
+    /// let m = Migration { name: "001_init.surql".to_string(), kind: MigrationKind::File, metadata: Default::default() };
     /// let up = src.get_up(&m).unwrap();
     /// println!("embedded up sql length: {}", up.len());
     /// ```
@@ -423,7 +1210,7 @@ impl MigrationSource for EmbeddedSource<'_> {
                     .ok_or_else(|| eyre::eyre!("failed to read contents of up.surql as UTF-8"))?;
                 Ok(content.to_string())
             }
-            MigrationKind::File => {
+            MigrationKind::File | MigrationKind::Repeatable => {
                 let file = self
                     .source
                     .get_file(&migration.name)
@@ -439,14 +1226,14 @@ impl MigrationSource for EmbeddedSource<'_> {
     /// Read the "down" SQL for the given embedded migration, if present.
     ///
     /// Returns `Ok(Some(sql))` when `down.surql` exists in an embedded paired
-    /// migration, `Ok(None)` for file-based migrations, or an `Err` if the
-    /// embedded asset cannot be read as UTF-8.
+    /// migration, `Ok(None)` for file-based and repeatable migrations, or an
+    /// `Err` if the embedded asset cannot be read as UTF-8.
     ///
     /// Example:
     ///
     /// ```rust,ignore
     /// let src = EmbeddedSource::new(&MIGS);
-    /// let m = Migration { name: "002_add_posts".to_string(), kind: MigrationKind::Paired };
+    /// let m = Migration { name: "002_add_posts".to_string(), kind: MigrationKind::Paired, metadata: Default::default() };
     /// if let Some(down) = src.get_down(&m).unwrap() {
     ///     println!("embedded down sql: {} bytes", down.len());
     /// }
@@ -454,19 +1241,438 @@ impl MigrationSource for EmbeddedSource<'_> {
     fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
         match migration.kind {
             MigrationKind::Paired => {
+                let file_path = Path::new(&migration.name).join("down.surql");
+
                 let dir = self
                     .source
                     .get_dir(&migration.name)
                     .ok_or_else(|| eyre::eyre!("migration directory not found"))?;
                 let file = dir
-                    .get_file("down.surql")
+                    .get_file(file_path)
                     .ok_or_else(|| eyre::eyre!("down.surql not found"))?;
                 let content = file
                     .contents_utf8()
                     .ok_or_else(|| eyre::eyre!("failed to read contents of down.surql as UTF-8"))?;
                 Ok(Some(content.to_string()))
             }
-            MigrationKind::File => Ok(None),
+            MigrationKind::File | MigrationKind::Repeatable => Ok(None),
+        }
+    }
+}
+
+/// A `MigrationSource` that merges the migrations of several other sources.
+///
+/// Sources are consulted in the order they were added. When two sources
+/// expose a migration with the same name, the one added last wins, which
+/// lets a deployment layer disk overrides on top of a core embedded set.
+/// `list()` merges the sources by name while keeping each source's own
+/// order, so sorted sources interleave by name and a source with an order
+/// of its own (such as a `DomainSource` manifest) keeps it. An overriding
+/// migration takes its place in the order of the source it comes from.
+///
+/// `list()` records which source owns each migration, so loading scripts
+/// does not list the sources again.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use crate::types::{CompositeSource, DiskSource, EmbeddedSource, MigrationSource};
+///
+/// static CORE: include_dir::Dir = include_dir!("migrations");
+/// let src = CompositeSource::new()
+///     .with_source(EmbeddedSource::new(&CORE))
+///     .with_source(DiskSource::new("/etc/app/migrations"));
+/// for m in src.list().unwrap() {
+///     println!("{}", m.name);
+/// }
+/// ```
+#[derive(Default)]
+pub struct CompositeSource {
+    /// Underlying sources, in precedence order (later entries override earlier ones).
+    sources: Vec<Box<dyn MigrationSource + Send + Sync>>,
+    /// Index of the source owning each migration, with the entry as that
+    /// source reports it, as of the last `list()`.
+    owners: Mutex<HashMap<String, (usize, Migration)>>,
+}
+
+impl CompositeSource {
+    /// Create an empty `CompositeSource`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `source`, giving it precedence over every source added before it.
+    pub fn with_source<S: MigrationSource + Send + Sync + 'static>(mut self, source: S) -> Self {
+        self.sources.push(Box::new(source));
+        self
+    }
+
+    /// Find the source that owns `name`, returning it together with the
+    /// migration entry as that source reports it. The sources are listed
+    /// only when `name` is not in the index yet.
+    fn resolve(&self, name: &str) -> Result<(&dyn MigrationSource, Migration)> {
+        let mut owner = self.owners.lock().unwrap().get(name).cloned();
+        if owner.is_none() {
+            self.list()?;
+            owner = self.owners.lock().unwrap().get(name).cloned();
+        }
+        let (index, migration) =
+            owner.ok_or_else(|| eyre::eyre!("migration `{name}` not found in any source"))?;
+        Ok((self.sources[index].as_ref(), migration))
+    }
+}
+
+impl MigrationSource for CompositeSource {
+    /// List the merged, de-duplicated migrations of all sources, each
+    /// source's in its own order.
+    fn list(&self) -> Result<Vec<Migration>> {
+        let listed = self
+            .sources
+            .iter()
+            .map(|source| source.list())
+            .collect::<Result<Vec<_>>>()?;
+        let mut owners = HashMap::new();
+        for (index, migrations) in listed.iter().enumerate() {
+            for m in migrations {
+                owners.insert(m.name.clone(), (index, m.clone()));
+            }
+        }
+
+        // Take the smallest name at the head of a source next, skipping
+        // migrations a later source overrides.
+        let mut queues = listed
+            .into_iter()
+            .enumerate()
+            .map(|(index, migrations)| {
+                migrations
+                    .into_iter()
+                    .filter(|m| owners[&m.name].0 == index)
+                    .collect::<VecDeque<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut merged = Vec::with_capacity(owners.len());
+        while let Some(queue) = queues
+            .iter_mut()
+            .filter(|queue| !queue.is_empty())
+            .min_by(|a, b| a[0].name.cmp(&b[0].name))
+        {
+            merged.extend(queue.pop_front());
+        }
+
+        *self.owners.lock().unwrap() = owners;
+        Ok(merged)
+    }
+
+    /// Load the "up" SQL from the source that owns `migration`.
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        let (source, owned) = self.resolve(&migration.name)?;
+        source.get_up(&owned)
+    }
+
+    /// Load the "down" SQL from the source that owns `migration`.
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        let (source, owned) = self.resolve(&migration.name)?;
+        source.get_down(&owned)
+    }
+
+    /// Entries skipped by any of the sources.
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        let mut ignored = Vec::new();
+        for source in &self.sources {
+            ignored.extend(source.ignored()?);
+        }
+        Ok(ignored)
+    }
+}
+
+/// A `MigrationSource` for migrations organized in one subdirectory per
+/// domain, e.g. `migrations/billing/20240101120000_init.surql`.
+///
+/// Every subdirectory of the root that is not itself a migration is a
+/// domain, read like a `DiskSource`. Migrations are named
+/// `<domain>/<entry>`. By default the domains are interleaved by numeric
+/// prefix (timestamps work well), with ties broken by domain name; set a
+/// manifest with `with_manifest` to fix the global order explicitly.
+/// Repeatable migrations follow all versioned ones.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// use surreal_migraine::types::DomainSource;
+///
+/// let src = DomainSource::new("migrations").with_manifest("order.txt");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DomainSource {
+    /// Directory containing one subdirectory per domain.
+    root: PathBuf,
+    /// Optional ordering manifest, relative to `root`.
+    manifest: Option<PathBuf>,
+}
+
+impl DomainSource {
+    /// Create a source reading domain subdirectories of `root`.
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+            manifest: None,
+        }
+    }
+
+    /// Order migrations by the manifest at `path` (relative to the root).
+    ///
+    /// The manifest lists one `<domain>/<entry>` name per line; blank lines
+    /// and `#` comments are ignored. Every migration must be listed exactly
+    /// once, so a merge that adds a migration without placing it fails
+    /// loudly instead of picking an order silently.
+    pub fn with_manifest(mut self, path: impl AsRef<Path>) -> Self {
+        self.manifest = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Subdirectories of the root that are not migrations themselves, sorted.
+    fn domains(&self) -> Result<Vec<String>> {
+        let mut domains: Vec<_> = std::fs::read_dir(&self.root)?
+            .filter_map(|r| r.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .filter_map(|p| Some(p.file_name()?.to_str()?.to_string()))
+            .filter(|name| classify(name, true).is_err())
+            .collect();
+        domains.sort();
+        Ok(domains)
+    }
+
+    /// The domain source owning `name`, and the migration name within it.
+    fn resolve(&self, migration: &Migration) -> Result<(DiskSource, Migration)> {
+        let (domain, entry) = migration
+            .name
+            .split_once('/')
+            .ok_or_else(|| eyre::eyre!("migration {} has no domain", migration.name))?;
+        let owned = Migration {
+            name: entry.to_string(),
+            ..migration.clone()
+        };
+        Ok((DiskSource::new(self.root.join(domain)), owned))
+    }
+
+    fn order_by_manifest(
+        &self,
+        manifest: &Path,
+        migrations: Vec<Migration>,
+    ) -> Result<Vec<Migration>> {
+        let path = self.root.join(manifest);
+        let content = read_to_string(&path)
+            .map_err(|e| eyre::eyre!("failed to read {}: {e}", path.display()))?;
+
+        let mut by_name = migrations
+            .into_iter()
+            .map(|m| (m.name.clone(), m))
+            .collect::<std::collections::BTreeMap<_, _>>();
+        let mut ordered = Vec::new();
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let migration = by_name.remove(line).ok_or_else(|| {
+                eyre::eyre!("{}: unknown or repeated migration {line}", path.display())
+            })?;
+            ordered.push(migration);
+        }
+        if !by_name.is_empty() {
+            eyre::bail!(
+                "{}: migrations missing from the manifest: {}",
+                path.display(),
+                by_name.into_keys().collect::<Vec<_>>().join(", ")
+            );
+        }
+        Ok(ordered)
+    }
+}
+
+impl MigrationSource for DomainSource {
+    /// List the migrations of every domain in global order.
+    fn list(&self) -> Result<Vec<Migration>> {
+        let mut migrations = Vec::new();
+        for domain in self.domains()? {
+            for mut migration in DiskSource::new(self.root.join(&domain)).list()? {
+                migration.name = format!("{domain}/{}", migration.name);
+                migrations.push(migration);
+            }
+        }
+
+        if let Some(manifest) = &self.manifest {
+            return self.order_by_manifest(manifest, migrations);
+        }
+
+        // Stable sort: equal prefixes keep domain order.
+        migrations.sort_by_key(|m| {
+            let entry = m.name.split_once('/').map_or(m.name.as_str(), |(_, e)| e);
+            (
+                m.kind == MigrationKind::Repeatable,
+                crate::validate::numeric_prefix(entry).unwrap_or(u64::MAX),
+            )
+        });
+        Ok(migrations)
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        let (source, owned) = self.resolve(migration)?;
+        source.get_up(&owned)
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        let (source, owned) = self.resolve(migration)?;
+        source.get_down(&owned)
+    }
+
+    /// Entries skipped inside the domains, named `<domain>/<entry>`.
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        let mut ignored = Vec::new();
+        for domain in self.domains()? {
+            for mut entry in DiskSource::new(self.root.join(&domain)).ignored()? {
+                entry.name = format!("{domain}/{}", entry.name);
+                ignored.push(entry);
+            }
         }
+        Ok(ignored)
+    }
+}
+
+/// A `MigrationSource` backed by migrations registered in memory.
+///
+/// Intended for tests: each migration is a `(name, up_sql, down_sql)`
+/// tuple. Names prefixed with `R_` are reported as
+/// `MigrationKind::Repeatable`; otherwise migrations with a down script are
+/// `MigrationKind::Paired` and the rest `MigrationKind::File`. `list()`
+/// returns them sorted by name, matching the other sources.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::types::{InMemorySource, MigrationKind, MigrationSource};
+///
+/// let src = InMemorySource::new()
+///     .with_migration("001_init", "DEFINE TABLE user;", None)
+///     .with_migration("002_posts", "DEFINE TABLE post;", Some("REMOVE TABLE post;"));
+///
+/// let migrations = src.list().unwrap();
+/// assert_eq!(migrations[1].kind, MigrationKind::Paired);
+/// assert_eq!(src.get_down(&migrations[1]).unwrap().as_deref(), Some("REMOVE TABLE post;"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct InMemorySource {
+    /// Registered migrations keyed by name: `(up_sql, down_sql)`.
+    migrations: std::collections::BTreeMap<String, (String, Option<String>)>,
+}
+
+impl InMemorySource {
+    /// Create an empty `InMemorySource`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a migration, replacing any existing migration with the same name.
+    pub fn with_migration(
+        mut self,
+        name: impl Into<String>,
+        up: impl Into<String>,
+        down: Option<&str>,
+    ) -> Self {
+        self.add(name, up, down);
+        self
+    }
+
+    /// Register a migration in place, replacing any existing migration with the same name.
+    pub fn add(&mut self, name: impl Into<String>, up: impl Into<String>, down: Option<&str>) {
+        self.migrations
+            .insert(name.into(), (up.into(), down.map(str::to_string)));
+    }
+
+    fn get(&self, migration: &Migration) -> Result<&(String, Option<String>)> {
+        self.migrations
+            .get(&migration.name)
+            .ok_or_else(|| eyre::eyre!("migration `{}` not found", migration.name))
+    }
+}
+
+impl MigrationSource for InMemorySource {
+    fn list(&self) -> Result<Vec<Migration>> {
+        Ok(self
+            .migrations
+            .iter()
+            .map(|(name, (up, down))| Migration {
+                name: name.clone(),
+                metadata: MigrationMetadata::parse(up),
+                kind: if name.starts_with(REPEATABLE_PREFIX) {
+                    MigrationKind::Repeatable
+                } else if down.is_some() {
+                    MigrationKind::Paired
+                } else {
+                    MigrationKind::File
+                },
+            })
+            .collect())
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        Ok(self.get(migration)?.0.clone())
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        Ok(self.get(migration)?.1.clone())
+    }
+}
+
+/// Scripts read up front from a directory-shaped listing (a bucket prefix,
+/// a git tree, an archive), classified like the entries of a `DiskSource`.
+#[cfg(any(feature = "archive", feature = "git", feature = "object-store"))]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Listing {
+    /// Up and down scripts keyed by migration name.
+    scripts: std::collections::BTreeMap<String, (String, Option<String>)>,
+    migrations: Vec<Migration>,
+    ignored: Vec<IgnoredEntry>,
+}
+
+#[cfg(any(feature = "archive", feature = "git", feature = "object-store"))]
+impl Listing {
+    /// Classify the sorted top-level `(name, is_dir)` entries; `scripts`
+    /// holds the up and down scripts of those that have an up script.
+    pub(crate) fn new(
+        entries: Vec<(String, bool)>,
+        scripts: std::collections::BTreeMap<String, (String, Option<String>)>,
+    ) -> Self {
+        let mut listing = Self {
+            scripts,
+            ..Self::default()
+        };
+        (listing.migrations, listing.ignored) = discover(&listing, entries);
+        listing
+    }
+
+    fn get(&self, migration: &Migration) -> Result<&(String, Option<String>)> {
+        self.scripts
+            .get(&migration.name)
+            .ok_or_else(|| eyre::eyre!("migration `{}` not found", migration.name))
+    }
+}
+
+#[cfg(any(feature = "archive", feature = "git", feature = "object-store"))]
+impl MigrationSource for Listing {
+    fn list(&self) -> Result<Vec<Migration>> {
+        Ok(self.migrations.clone())
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        Ok(self.get(migration)?.0.clone())
+    }
+
+    fn get_down(&self, migration: &Migration) -> Result<Option<String>> {
+        Ok(self.get(migration)?.1.clone())
+    }
+
+    fn ignored(&self) -> Result<Vec<IgnoredEntry>> {
+        Ok(self.ignored.clone())
     }
 }
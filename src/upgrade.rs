@@ -0,0 +1,229 @@
+use crate::types::{Migration, MigrationSource};
+use eyre::{Result, eyre};
+use std::fmt;
+use std::str::FromStr;
+
+/// A SurrealDB server version, compared by major and minor number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+}
+
+impl FromStr for Version {
+    type Err = eyre::Report;
+
+    /// Parse `3`, `3.0` or `3.0.1`; the patch number is ignored.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || eyre!("invalid version {s:?}, expected e.g. `3.0`");
+        let mut parts = s.trim().trim_start_matches('v').split('.');
+        let major = parts
+            .next()
+            .and_then(|p| p.parse().ok())
+            .ok_or_else(invalid)?;
+        let minor = match parts.next() {
+            Some(p) => p.parse().map_err(|_| invalid())?,
+            None => 0,
+        };
+        if parts.next().is_some_and(|p| p.parse::<u32>().is_err()) || parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self { major, minor })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// SurrealQL syntax that a server version removed or changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    /// The first version that no longer accepts the syntax.
+    pub since: Version,
+    /// Text to look for, matched case-insensitively. Ends that are letters
+    /// or digits must fall on a word boundary.
+    pub pattern: &'static str,
+    /// What changed.
+    pub message: &'static str,
+    /// What to write instead.
+    pub fix: &'static str,
+}
+
+/// Syntax changes of SurrealDB major versions. Extend this table as new
+/// versions are released.
+pub const RULES: &[Rule] = &[
+    Rule {
+        since: Version::new(2, 0),
+        pattern: "DEFINE SCOPE",
+        message: "scopes were replaced by record access methods",
+        fix: "DEFINE ACCESS ... ON DATABASE TYPE RECORD",
+    },
+    Rule {
+        since: Version::new(2, 0),
+        pattern: "DEFINE TOKEN",
+        message: "tokens were replaced by JWT access methods",
+        fix: "DEFINE ACCESS ... TYPE JWT",
+    },
+    Rule {
+        since: Version::new(2, 0),
+        pattern: "meta::id",
+        message: "the meta:: functions were renamed",
+        fix: "record::id",
+    },
+    Rule {
+        since: Version::new(2, 0),
+        pattern: "meta::tb",
+        message: "the meta:: functions were renamed",
+        fix: "record::tb",
+    },
+    Rule {
+        since: Version::new(2, 0),
+        pattern: "string::endsWith",
+        message: "camel-case string functions were renamed",
+        fix: "string::ends_with",
+    },
+    Rule {
+        since: Version::new(2, 0),
+        pattern: "string::startsWith",
+        message: "camel-case string functions were renamed",
+        fix: "string::starts_with",
+    },
+    Rule {
+        since: Version::new(3, 0),
+        pattern: "<future>",
+        message: "futures were removed",
+        fix: "DEFINE FIELD ... COMPUTED <expression>",
+    },
+    Rule {
+        since: Version::new(3, 0),
+        pattern: "SEARCH ANALYZER",
+        message: "search indexes were renamed to full-text indexes",
+        fix: "FULLTEXT ANALYZER",
+    },
+    Rule {
+        since: Version::new(3, 0),
+        pattern: "MTREE",
+        message: "M-tree vector indexes were removed",
+        fix: "HNSW",
+    },
+    Rule {
+        since: Version::new(3, 0),
+        pattern: "::is::",
+        message: "`::is::` functions were renamed",
+        fix: "::is_ (e.g. string::is_email)",
+    },
+    Rule {
+        since: Version::new(3, 0),
+        pattern: "::from::",
+        message: "`::from::` functions were renamed",
+        fix: "::from_ (e.g. duration::from_secs)",
+    },
+];
+
+/// A use of syntax the target version no longer accepts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Advice {
+    /// The migration the script belongs to.
+    pub migration: String,
+    /// Whether the advice is about the down script.
+    pub down: bool,
+    /// 1-based line of the match.
+    pub line: usize,
+    pub rule: Rule,
+}
+
+/// Find syntax in `source` that SurrealDB `to` removed or changed, using the
+/// built-in [`RULES`].
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::types::InMemorySource;
+/// use surreal_migraine::upgrade::{Version, advise};
+///
+/// let src = InMemorySource::new().with_migration(
+///     "001_search",
+///     "DEFINE INDEX body ON post FIELDS body SEARCH ANALYZER simple BM25;",
+///     None,
+/// );
+/// let advice = advise(&src, "3.0".parse().unwrap()).unwrap();
+/// assert_eq!(advice[0].rule.fix, "FULLTEXT ANALYZER");
+/// assert!(advise(&src, Version::new(2, 1)).unwrap().is_empty());
+/// ```
+pub fn advise<S: MigrationSource + ?Sized>(source: &S, to: Version) -> Result<Vec<Advice>> {
+    advise_with(source, to, RULES)
+}
+
+/// Like [`advise`], with a custom rule table.
+pub fn advise_with<S: MigrationSource + ?Sized>(
+    source: &S,
+    to: Version,
+    rules: &[Rule],
+) -> Result<Vec<Advice>> {
+    let rules = rules
+        .iter()
+        .filter(|rule| rule.since <= to)
+        .collect::<Vec<_>>();
+    let mut advice = Vec::new();
+    for migration in source.list()? {
+        advise_script(
+            &mut advice,
+            &migration,
+            &source.get_up(&migration)?,
+            false,
+            &rules,
+        );
+        if let Some(down) = source.get_down(&migration)? {
+            advise_script(&mut advice, &migration, &down, true, &rules);
+        }
+    }
+    Ok(advice)
+}
+
+fn advise_script(
+    advice: &mut Vec<Advice>,
+    migration: &Migration,
+    content: &str,
+    down: bool,
+    rules: &[&Rule],
+) {
+    for (index, line) in content.lines().enumerate() {
+        let code = line
+            .split("--")
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        for rule in rules {
+            if contains_word(&code, &rule.pattern.to_ascii_lowercase()) {
+                advice.push(Advice {
+                    migration: migration.name.clone(),
+                    down,
+                    line: index + 1,
+                    rule: **rule,
+                });
+            }
+        }
+    }
+}
+
+/// Whether `pattern` occurs in `text` with word boundaries at its
+/// alphanumeric ends.
+fn contains_word(text: &str, pattern: &str) -> bool {
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let starts_word = pattern.starts_with(is_word);
+    let ends_word = pattern.ends_with(is_word);
+    let clear = |bounded: bool, c: Option<char>| !bounded || !c.is_some_and(is_word);
+    text.match_indices(pattern).any(|(at, _)| {
+        clear(starts_word, text[..at].chars().next_back())
+            && clear(ends_word, text[at + pattern.len()..].chars().next())
+    })
+}
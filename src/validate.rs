@@ -0,0 +1,433 @@
+use crate::ordering::{NumericOrdering, Ordering};
+use crate::types::{Migration, MigrationKind};
+
+/// Two or more migrations sharing the same version prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicatePrefix {
+    /// The shared prefix, as written in the first of `names` (`003_a` and
+    /// `3_b` share prefix `003`).
+    pub prefix: String,
+    /// Names of the conflicting migrations, in listing order.
+    pub names: Vec<String>,
+}
+
+/// Structured result of `validate`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    /// Prefixes used by more than one migration. These make ordering depend
+    /// on the rest of the name and usually come from merging two branches.
+    pub duplicates: Vec<DuplicatePrefix>,
+    /// Prefixes missing between the lowest and highest used prefix. Only
+    /// filled in when gap checking was requested.
+    pub gaps: Vec<u64>,
+    /// Problems with the migrations' `depends` headers.
+    pub dependencies: Vec<DependencyProblem>,
+}
+
+impl ValidationReport {
+    /// `true` when no duplicates or dependency problems were found. Gaps are
+    /// advisory and do not make a report invalid.
+    pub fn is_valid(&self) -> bool {
+        self.duplicates.is_empty() && self.dependencies.is_empty()
+    }
+}
+
+/// A dependency declared in a migration's `depends` (or `depends-on`)
+/// header that cannot be satisfied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencyProblem {
+    /// `name` depends on a migration that does not exist.
+    Unknown { name: String, dependency: String },
+    /// `name` depends on a migration ordered after it, so applying in
+    /// order would run the dependency too late.
+    Later { name: String, dependency: String },
+    /// Migrations depending on each other in a cycle; each depends on the
+    /// next, and the last on the first.
+    Cycle { names: Vec<String> },
+}
+
+impl std::fmt::Display for DependencyProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DependencyProblem::Unknown { name, dependency } => {
+                write!(f, "{name} depends on {dependency}, which does not exist")
+            }
+            DependencyProblem::Later { name, dependency } => {
+                write!(f, "{name} depends on {dependency}, which comes after it")
+            }
+            DependencyProblem::Cycle { names } => write!(
+                f,
+                "{} -> {} form a dependency cycle",
+                names.join(" -> "),
+                names[0]
+            ),
+        }
+    }
+}
+
+/// Parse the leading numeric prefix of a migration name, e.g. `003_users`
+/// gives `3`. Names without a `<digits>_` prefix yield `None`.
+pub fn numeric_prefix(name: &str) -> Option<u64> {
+    let (digits, _) = name.split_once('_')?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// Check versioned migrations for duplicate numeric prefixes and, when
+/// `check_gaps` is set, for holes in the numbering.
+///
+/// Repeatable migrations have no version and are ignored. Gap checking is
+/// only meaningful for sequential (`001_`, `002_`, ...) prefixes; leave it
+/// off for timestamp prefixes.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::types::{InMemorySource, MigrationSource};
+/// use surreal_migraine::validate::validate;
+///
+/// let src = InMemorySource::new()
+///     .with_migration("001_init", "", None)
+///     .with_migration("003_users", "", None)
+///     .with_migration("003_posts", "", None);
+/// let report = validate(&src.list().unwrap(), true);
+/// assert_eq!(report.duplicates[0].names, ["003_posts", "003_users"]);
+/// assert_eq!(report.gaps, [2]);
+/// ```
+pub fn validate(migrations: &[Migration], check_gaps: bool) -> ValidationReport {
+    let mut report = validate_with(migrations, &NumericOrdering);
+
+    let prefixes = migrations
+        .iter()
+        .filter(|m| m.kind != MigrationKind::Repeatable)
+        .filter_map(|m| numeric_prefix(&m.name))
+        .collect::<std::collections::BTreeSet<_>>();
+    if let (true, Some(&first), Some(&last)) = (check_gaps, prefixes.first(), prefixes.last()) {
+        report.gaps = (first..last).filter(|p| !prefixes.contains(p)).collect();
+    }
+
+    report
+}
+
+/// Check versioned migrations for duplicate versions under a custom
+/// `ordering`. Gaps are never reported; they only make sense for
+/// sequential numeric prefixes.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::ordering::NumericOrdering;
+/// use surreal_migraine::types::{InMemorySource, MigrationSource};
+/// use surreal_migraine::validate::validate_with;
+///
+/// let src = InMemorySource::new()
+///     .with_migration("003_users", "", None)
+///     .with_migration("3_posts", "", None);
+/// let report = validate_with(&src.list().unwrap(), &NumericOrdering);
+/// assert_eq!(report.duplicates[0].prefix, "003");
+/// ```
+pub fn validate_with(migrations: &[Migration], ordering: &dyn Ordering) -> ValidationReport {
+    let mut versioned = migrations
+        .iter()
+        .filter(|m| m.kind != MigrationKind::Repeatable)
+        .filter_map(|m| Some((ordering.version(&m.name)?, m.name.as_str())))
+        .collect::<Vec<_>>();
+    versioned.sort_by(|a, b| ordering.compare(a.0, b.0).then(a.1.cmp(b.1)));
+
+    let duplicates = versioned
+        .chunk_by(|a, b| ordering.compare(a.0, b.0).is_eq())
+        .filter(|group| group.len() > 1)
+        .map(|group| DuplicatePrefix {
+            prefix: group[0].0.to_string(),
+            names: group.iter().map(|(_, name)| name.to_string()).collect(),
+        })
+        .collect();
+
+    ValidationReport {
+        duplicates,
+        gaps: Vec::new(),
+        dependencies: dependencies(migrations, &[], true),
+    }
+}
+
+/// Whether `name`, as written in a `depends` header, refers to
+/// `migration`; the `.surql` extension may be left out.
+pub fn refers_to(migration: &Migration, name: &str) -> bool {
+    migration.name == name || migration.name.strip_suffix(".surql") == Some(name)
+}
+
+/// Check the dependencies `migrations` declare. A dependency is satisfied
+/// by a listed migration or by one of the `applied` ones; with `ordered`,
+/// a listed dependency must also come before the migration needing it, as
+/// when migrations are applied one after another.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::types::{InMemorySource, MigrationSource};
+/// use surreal_migraine::validate::dependencies;
+///
+/// let src = InMemorySource::new()
+///     .with_migration("001_a", "-- depends: 002_b\n", None)
+///     .with_migration("002_b", "-- depends: 001_a\n", None)
+///     .with_migration("003_c", "-- depends-on: 000_init, 009_gone\n", None);
+/// let problems = dependencies(&src.list().unwrap(), &["000_init"], true);
+/// assert_eq!(
+///     problems.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+///     [
+///         "001_a -> 002_b -> 001_a form a dependency cycle",
+///         "003_c depends on 009_gone, which does not exist",
+///     ]
+/// );
+/// ```
+pub fn dependencies(
+    migrations: &[Migration],
+    applied: &[&str],
+    ordered: bool,
+) -> Vec<DependencyProblem> {
+    let mut problems = Vec::new();
+    let mut edges = vec![Vec::new(); migrations.len()];
+    for (index, migration) in migrations.iter().enumerate() {
+        for dependency in &migration.metadata.depends {
+            match migrations.iter().position(|m| refers_to(m, dependency)) {
+                Some(position) => edges[index].push((position, dependency)),
+                None if applied
+                    .iter()
+                    .any(|a| *a == dependency || a.strip_suffix(".surql") == Some(dependency)) => {}
+                None => problems.push(DependencyProblem::Unknown {
+                    name: migration.name.clone(),
+                    dependency: dependency.clone(),
+                }),
+            }
+        }
+    }
+
+    let mut cycles = Vec::new();
+    let mut state = vec![Visit::New; migrations.len()];
+    for index in 0..migrations.len() {
+        find_cycles(index, &edges, &mut state, &mut Vec::new(), &mut cycles);
+    }
+    let in_cycle = cycles
+        .iter()
+        .flat_map(|cycle: &Vec<usize>| cycle.iter().copied())
+        .collect::<std::collections::HashSet<_>>();
+
+    if ordered {
+        for (index, targets) in edges.iter().enumerate() {
+            for &(target, dependency) in targets {
+                if target > index && !(in_cycle.contains(&index) && in_cycle.contains(&target)) {
+                    problems.push(DependencyProblem::Later {
+                        name: migrations[index].name.clone(),
+                        dependency: dependency.clone(),
+                    });
+                }
+            }
+        }
+    }
+    let mut found = cycles
+        .into_iter()
+        .map(|cycle| DependencyProblem::Cycle {
+            names: cycle
+                .into_iter()
+                .map(|i| migrations[i].name.clone())
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    found.append(&mut problems);
+    found
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    New,
+    Active,
+    Done,
+}
+
+/// Depth-first search from `index`, adding every cycle closed by an edge
+/// back into the current `path` to `cycles`.
+fn find_cycles(
+    index: usize,
+    edges: &[Vec<(usize, &String)>],
+    state: &mut [Visit],
+    path: &mut Vec<usize>,
+    cycles: &mut Vec<Vec<usize>>,
+) {
+    if state[index] != Visit::New {
+        return;
+    }
+    state[index] = Visit::Active;
+    path.push(index);
+    for &(target, _) in &edges[index] {
+        match state[target] {
+            Visit::New => find_cycles(target, edges, state, path, cycles),
+            Visit::Active => {
+                let start = path.iter().position(|&i| i == target).unwrap_or(0);
+                cycles.push(path[start..].to_vec());
+            }
+            Visit::Done => {}
+        }
+    }
+    path.pop();
+    state[index] = Visit::Done;
+}
+
+/// A migration whose version clashes with another migration or with the
+/// order a database applied migrations in, typically after merging two
+/// branches that both added a migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// Migrations sharing a version prefix.
+    SharedPrefix {
+        prefix: String,
+        /// Names of the conflicting migrations, in listing order.
+        names: Vec<String>,
+    },
+    /// A migration with a lower version than `after`, which the database
+    /// applied before it (`applied`) or which it has not applied yet.
+    OutOfOrder {
+        name: String,
+        after: String,
+        applied: bool,
+    },
+}
+
+impl std::fmt::Display for Conflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Conflict::SharedPrefix { prefix, names } => {
+                write!(f, "{} share the version prefix {prefix}", names.join(", "))
+            }
+            Conflict::OutOfOrder {
+                name,
+                after,
+                applied: true,
+            } => write!(f, "{name} was applied after {after}"),
+            Conflict::OutOfOrder {
+                name,
+                after,
+                applied: false,
+            } => write!(f, "{name} is pending but {after} is already applied"),
+        }
+    }
+}
+
+/// A rename that resolves a [`Conflict`], e.g. with `smg rename`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renumber {
+    pub from: String,
+    pub to: String,
+}
+
+/// The conflicts among `migrations`, given the names of the applied ones
+/// in the order they were applied (empty when no database is involved),
+/// and renames that resolve them.
+///
+/// Of migrations sharing a prefix, the first applied one (or the first
+/// listed, when none is) keeps it. Migrations applied after, or pending
+/// behind, a higher version are out of order. Every other migration in a
+/// conflict is renumbered past the highest version in use, in the order
+/// the database applied them, so prefix order matches history again;
+/// renaming applied migrations also needs their records moved, which
+/// `smg rename` does.
+///
+/// # Examples
+///
+/// ```rust
+/// use surreal_migraine::types::{InMemorySource, MigrationSource};
+/// use surreal_migraine::validate::{Renumber, conflicts};
+///
+/// let src = InMemorySource::new()
+///     .with_migration("006_users", "", None)
+///     .with_migration("007_posts", "", None)
+///     .with_migration("007_tags", "", None)
+///     .with_migration("008_likes", "", None);
+/// let (conflicts, renames) =
+///     conflicts(&src.list().unwrap(), &["006_users", "007_tags", "008_likes"]);
+/// assert_eq!(
+///     conflicts.iter().map(|c| c.to_string()).collect::<Vec<_>>(),
+///     [
+///         "007_posts, 007_tags share the version prefix 007",
+///         "007_posts is pending but 008_likes is already applied",
+///     ]
+/// );
+/// assert_eq!(
+///     renames,
+///     [Renumber { from: "007_posts".into(), to: "009_posts".into() }]
+/// );
+/// ```
+pub fn conflicts(migrations: &[Migration], applied: &[&str]) -> (Vec<Conflict>, Vec<Renumber>) {
+    let versioned = migrations
+        .iter()
+        .filter(|m| m.kind != MigrationKind::Repeatable)
+        .filter_map(|m| Some((m.name.as_str(), numeric_prefix(&m.name)?)))
+        .collect::<Vec<_>>();
+    let version = |name: &str| versioned.iter().find(|(n, _)| *n == name).map(|(_, v)| *v);
+    let position = |name: &str| applied.iter().position(|a| *a == name);
+
+    let mut conflicts = Vec::new();
+    let mut moved = Vec::new();
+    for duplicate in validate(migrations, false).duplicates {
+        let keeper = duplicate
+            .names
+            .iter()
+            .filter(|name| position(name).is_some())
+            .min_by_key(|name| position(name))
+            .unwrap_or(&duplicate.names[0])
+            .clone();
+        moved.extend(duplicate.names.iter().filter(|n| **n != keeper).cloned());
+        conflicts.push(Conflict::SharedPrefix {
+            prefix: duplicate.prefix,
+            names: duplicate.names,
+        });
+    }
+
+    let mut highest: Option<(&str, u64)> = None;
+    for name in applied {
+        let Some(v) = version(name) else { continue };
+        match highest {
+            Some((after, max)) if v < max => {
+                conflicts.push(Conflict::OutOfOrder {
+                    name: name.to_string(),
+                    after: after.to_string(),
+                    applied: true,
+                });
+                if !moved.iter().any(|m| m == name) {
+                    moved.push(name.to_string());
+                }
+            }
+            Some((_, max)) if v == max => {}
+            _ => highest = Some((name, v)),
+        }
+    }
+    if let Some((after, max)) = highest {
+        for (name, v) in &versioned {
+            if *v < max && position(name).is_none() {
+                conflicts.push(Conflict::OutOfOrder {
+                    name: name.to_string(),
+                    after: after.to_string(),
+                    applied: false,
+                });
+                if !moved.iter().any(|m| m == name) {
+                    moved.push(name.to_string());
+                }
+            }
+        }
+    }
+
+    // Applied migrations first, in history order, then pending ones.
+    moved.sort_by_key(|name| (position(name).unwrap_or(usize::MAX), version(name)));
+    let mut next = versioned.iter().map(|(_, v)| *v).max().unwrap_or_default();
+    let renames = moved
+        .into_iter()
+        .map(|from| {
+            next += 1;
+            let digits = from.bytes().take_while(u8::is_ascii_digit).count();
+            let to = format!("{next:0digits$}{}", &from[digits..]);
+            Renumber { from, to }
+        })
+        .collect();
+    (conflicts, renames)
+}
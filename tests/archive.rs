@@ -0,0 +1,82 @@
+use std::io::Write;
+use surreal_migraine::archive::ArchiveSource;
+use surreal_migraine::types::{MigrationKind, MigrationSource};
+
+const FILES: &[(&str, &str)] = &[
+    ("001_users.surql", "DEFINE TABLE user;"),
+    ("002_posts/up.surql", "DEFINE TABLE post;"),
+    ("002_posts/down.surql", "REMOVE TABLE post;"),
+    (
+        "R_views.surql",
+        "DEFINE TABLE active AS SELECT * FROM user;",
+    ),
+    ("README.md", "not a migration"),
+];
+
+fn tar_gz(root: &str) -> Vec<u8> {
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for (path, content) in FILES {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("{root}{path}"), content.as_bytes())
+            .unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+fn zip(root: &str) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (path, content) in FILES {
+        writer
+            .start_file(
+                format!("{root}{path}"),
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+fn assert_bundle(source: &ArchiveSource) {
+    let migrations = source.list().unwrap();
+    assert_eq!(
+        migrations
+            .iter()
+            .map(|m| (m.name.as_str(), m.kind.clone()))
+            .collect::<Vec<_>>(),
+        [
+            ("001_users.surql", MigrationKind::File),
+            ("002_posts", MigrationKind::Paired),
+            ("R_views.surql", MigrationKind::Repeatable),
+        ]
+    );
+    assert_eq!(source.get_up(&migrations[0]).unwrap(), "DEFINE TABLE user;");
+    assert_eq!(
+        source.get_down(&migrations[1]).unwrap().as_deref(),
+        Some("REMOVE TABLE post;")
+    );
+    assert_eq!(source.ignored().unwrap()[0].name, "README.md");
+}
+
+#[test]
+fn reads_tar_gz_bundles() {
+    assert_bundle(&ArchiveSource::from_bytes(&tar_gz("")).unwrap());
+    assert_bundle(&ArchiveSource::from_bytes(&tar_gz("./migrations/")).unwrap());
+}
+
+#[test]
+fn reads_zip_bundles_from_disk() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("migrations-1.0.zip");
+    std::fs::write(&path, zip("migrations/")).unwrap();
+    assert_bundle(&ArchiveSource::open(&path).unwrap());
+
+    std::fs::write(&path, b"PK\x03\x04 truncated").unwrap();
+    let err = ArchiveSource::open(&path).unwrap_err().to_string();
+    assert!(err.contains("corrupt zip archive"), "{err}");
+}
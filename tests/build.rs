@@ -0,0 +1,51 @@
+use std::fs;
+use surreal_migraine::build::validate_migrations;
+
+#[test]
+fn valid_migrations_pass() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::create_dir(dir.path().join("002_posts")).unwrap();
+    fs::write(dir.path().join("002_posts/up.surql"), "DEFINE TABLE post;").unwrap();
+    fs::write(
+        dir.path().join("002_posts/down.surql"),
+        "REMOVE TABLE post;",
+    )
+    .unwrap();
+    // Templated scripts are not parsed.
+    fs::write(
+        dir.path().join("R_params.surql"),
+        "DEFINE PARAM $region VALUE {{region}};",
+    )
+    .unwrap();
+
+    validate_migrations(dir.path()).unwrap();
+}
+
+#[test]
+fn every_problem_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    fs::write(dir.path().join("1_accounts.surql"), "DEFINE TABLE account;").unwrap();
+    fs::write(dir.path().join("002_posts.surql"), "DEFINE TABEL post;").unwrap();
+    fs::create_dir(dir.path().join("003_tags")).unwrap();
+    fs::write(dir.path().join("003_tags/up.surql"), "DEFINE TABLE tag;").unwrap();
+
+    let err = validate_migrations(dir.path()).unwrap_err().to_string();
+    assert!(err.starts_with("3 problem(s) in "), "{err}");
+    assert!(err.contains("002_posts.surql: syntax error"), "{err}");
+    assert!(err.contains("003_tags/down.surql: missing"), "{err}");
+    assert!(
+        err.contains("001_users.surql, 1_accounts.surql: share the version prefix 001"),
+        "{err}"
+    );
+}
+
+#[test]
+fn missing_directory_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let err = validate_migrations(dir.path().join("migrations"))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("does not exist"), "{err}");
+}
@@ -0,0 +1,58 @@
+use std::ffi::{CStr, CString};
+use std::ptr;
+use surreal_migraine::capi::{migraine_free_string, migraine_run_up, migraine_status_json};
+
+#[test]
+fn run_up_and_status_json() {
+    let url = CString::new("mem://").unwrap();
+    let ns = CString::new("test").unwrap();
+    let db = CString::new("test").unwrap();
+    let dir = CString::new("tests/migrations").unwrap();
+
+    unsafe {
+        let err = migraine_run_up(
+            url.as_ptr(),
+            ns.as_ptr(),
+            db.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            dir.as_ptr(),
+        );
+        assert!(err.is_null());
+
+        let json = migraine_status_json(
+            url.as_ptr(),
+            ns.as_ptr(),
+            db.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            dir.as_ptr(),
+        );
+        let value: serde_json::Value =
+            serde_json::from_str(CStr::from_ptr(json).to_str().unwrap()).unwrap();
+        migraine_free_string(json);
+
+        assert_eq!(value["migrations"].as_array().unwrap().len(), 2);
+    }
+}
+
+#[test]
+fn run_up_reports_errors() {
+    let url = CString::new("mem://").unwrap();
+    let ns = CString::new("test").unwrap();
+
+    unsafe {
+        let err = migraine_run_up(
+            url.as_ptr(),
+            ns.as_ptr(),
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+        );
+        assert!(!err.is_null());
+        let message = CStr::from_ptr(err).to_string_lossy().into_owned();
+        migraine_free_string(err);
+        assert!(message.contains("database"));
+    }
+}
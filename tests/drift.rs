@@ -0,0 +1,23 @@
+use surreal_migraine::drift::{Drift, DriftKind, DriftResolver, Resolution, ResolutionFile};
+
+#[test]
+fn resolution_file_defaults_to_abort() {
+    let file = ResolutionFile::parse("001_a = accept\n\n# comment\n  002_b=repair  \n").unwrap();
+    let drift = |name: &str| Drift {
+        name: name.into(),
+        kind: DriftKind::MissingSource,
+    };
+
+    assert_eq!(file.resolve(&drift("001_a")), Resolution::Accept);
+    assert_eq!(file.resolve(&drift("002_b")), Resolution::Repair);
+    assert_eq!(file.resolve(&drift("003_c")), Resolution::Abort);
+}
+
+#[test]
+fn resolution_file_rejects_bad_lines() {
+    let err = ResolutionFile::parse("001_a = maybe")
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("line 1"), "{err}");
+    assert!(ResolutionFile::parse("001_a").is_err());
+}
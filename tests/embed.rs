@@ -0,0 +1,44 @@
+use surreal_migraine::embed_migrations;
+use surreal_migraine::types::{EmbeddedSource, Migration, MigrationKind, MigrationSource};
+use surreal_migraine::{Dir, include_dir};
+
+static MIGRATIONS: EmbeddedSource<'static> = embed_migrations!("tests/migrations");
+static INCLUDED: Dir = include_dir!("tests/migrations");
+
+#[test]
+fn embedded_migrations_match_include_dir() {
+    let included = EmbeddedSource::new(&INCLUDED);
+    let migrations = MIGRATIONS.list().unwrap();
+    let names = |migrations: &[Migration]| {
+        migrations
+            .iter()
+            .map(|m| m.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names(&migrations), names(&included.list().unwrap()));
+    assert_eq!(
+        migrations
+            .iter()
+            .map(|m| m.kind.clone())
+            .collect::<Vec<_>>(),
+        [MigrationKind::File, MigrationKind::Paired]
+    );
+
+    for migration in &migrations {
+        assert_eq!(
+            MIGRATIONS.get_up(migration).unwrap(),
+            included.get_up(migration).unwrap()
+        );
+        assert_eq!(
+            MIGRATIONS.get_down(migration).unwrap(),
+            included.get_down(migration).unwrap()
+        );
+    }
+    assert!(MIGRATIONS.ignored().unwrap().is_empty());
+}
+
+#[test]
+fn embeds_as_an_expression() {
+    let source = embed_migrations!("tests/migrations");
+    assert_eq!(source.list().unwrap().len(), 2);
+}
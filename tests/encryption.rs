@@ -0,0 +1,112 @@
+use surreal_migraine::MigrationRunner;
+use surreal_migraine::encryption::{EncryptedSource, HEADER, encrypt, generate_key, parse_key};
+use surreal_migraine::types::{DiskSource, InMemorySource, MigrationSource};
+use surrealdb::Surreal;
+use surrealdb::engine::local::Mem;
+
+const SEED: &str =
+    "-- description: seed the admin account\nCREATE user:admin SET password = 'hunter2';";
+
+fn source(key: &[u8; 32]) -> InMemorySource {
+    InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration(
+            "002_seed",
+            encrypt(SEED, key),
+            Some(&encrypt("DELETE user:admin;", key)),
+        )
+}
+
+#[test]
+fn decrypts_encrypted_scripts_and_passes_plaintext_through() {
+    let key = parse_key(&generate_key()).unwrap();
+    let inner = source(&key);
+    let encrypted = inner.list().unwrap();
+    assert!(inner.get_up(&encrypted[1]).unwrap().starts_with(HEADER));
+    assert!(!inner.get_up(&encrypted[1]).unwrap().contains("hunter2"));
+
+    let source = EncryptedSource::new(inner, &key);
+    let migrations = source.list().unwrap();
+    assert_eq!(source.get_up(&migrations[0]).unwrap(), "DEFINE TABLE user;");
+    assert_eq!(source.get_up(&migrations[1]).unwrap(), SEED);
+    assert_eq!(
+        source.get_down(&migrations[1]).unwrap().as_deref(),
+        Some("DELETE user:admin;")
+    );
+    assert_eq!(
+        migrations[1].metadata.description.as_deref(),
+        Some("seed the admin account")
+    );
+    assert!(!migrations[0].metadata.sensitive());
+    assert!(migrations[1].metadata.sensitive());
+    assert!(format!("{source:?}").contains("key: Some(\"<redacted>\")"));
+}
+
+#[test]
+fn reports_a_wrong_or_missing_key() {
+    let key = [1u8; 32];
+    let source = EncryptedSource::new(source(&key), &[2u8; 32]);
+    let err = source.list().unwrap_err().to_string();
+    assert_eq!(err, "cannot decrypt 002_seed: wrong key or corrupt content");
+
+    let err = parse_key("c2hvcnQ=").unwrap_err().to_string();
+    assert!(err.contains("must be 32 bytes, got 5"), "{err}");
+}
+
+#[test]
+fn lists_paired_migrations_without_a_down_script() {
+    let key = [4u8; 32];
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("001_users")).unwrap();
+    std::fs::write(
+        dir.path().join("001_users/up.surql"),
+        encrypt("-- description: add users\nDEFINE TABLE user;", &key),
+    )
+    .unwrap();
+
+    let source = EncryptedSource::new(DiskSource::new(dir.path()), &key);
+    let migrations = source.list().unwrap();
+    assert_eq!(
+        migrations[0].metadata.description.as_deref(),
+        Some("add users")
+    );
+    assert!(migrations[0].metadata.sensitive());
+    assert!(source.get_down(&migrations[0]).is_err());
+}
+
+#[tokio::test]
+async fn does_not_record_decrypted_content() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let key = [3u8; 32];
+    let runner = MigrationRunner::new(&db, EncryptedSource::new(source(&key), &key));
+    runner.up().await.unwrap();
+
+    let records: Vec<serde_json::Value> = db
+        .query("SELECT name, content FROM migrations ORDER BY name")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(
+        records,
+        [
+            serde_json::json!({ "name": "001_users", "content": "DEFINE TABLE user;" }),
+            serde_json::json!({ "name": "002_seed", "content": null }),
+        ]
+    );
+    assert!(!serde_json::to_string(&records).unwrap().contains("hunter2"));
+
+    let checksums: Vec<Option<String>> = db
+        .query("SELECT VALUE checksum FROM migrations")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(checksums.iter().all(Option::is_some));
+    assert!(runner.verify().await.unwrap().is_empty());
+}
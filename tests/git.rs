@@ -0,0 +1,107 @@
+use std::path::Path;
+use std::process::Command;
+use surreal_migraine::git::GitSource;
+use surreal_migraine::types::{MigrationKind, MigrationSource};
+
+fn git(repo: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {args:?} failed");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// A repository whose `db/migrations` holds two migrations at tag `v1`
+/// and a third, plus an edited first one, on the branch after it.
+fn repository(root: &Path) -> std::path::PathBuf {
+    let repo = root.join("app");
+    let dir = repo.join("db/migrations");
+    std::fs::create_dir_all(dir.join("002_posts")).unwrap();
+    std::fs::write(dir.join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    std::fs::write(dir.join("002_posts/up.surql"), "DEFINE TABLE post;").unwrap();
+    std::fs::write(dir.join("002_posts/down.surql"), "REMOVE TABLE post;").unwrap();
+    std::fs::write(dir.join("notes.txt"), "not a migration").unwrap();
+    git(&repo, &["init", "--quiet", "--initial-branch", "main"]);
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "--quiet", "-m", "v1"]);
+    git(&repo, &["tag", "v1"]);
+
+    std::fs::write(dir.join("001_users.surql"), "DEFINE TABLE users;").unwrap();
+    std::fs::write(dir.join("003_tags.surql"), "DEFINE TABLE tag;").unwrap();
+    git(&repo, &["add", "."]);
+    git(&repo, &["commit", "--quiet", "-m", "v2"]);
+    repo
+}
+
+#[test]
+fn reads_migrations_at_a_tag() {
+    let tmp = tempfile::tempdir().unwrap();
+    let repo = repository(tmp.path());
+    let source = GitSource::open(&repo, "v1", "db/migrations").unwrap();
+
+    let migrations = source.list().unwrap();
+    assert_eq!(
+        migrations
+            .iter()
+            .map(|m| (m.name.as_str(), m.kind.clone()))
+            .collect::<Vec<_>>(),
+        [
+            ("001_users.surql", MigrationKind::File),
+            ("002_posts", MigrationKind::Paired),
+        ]
+    );
+    assert_eq!(source.get_up(&migrations[0]).unwrap(), "DEFINE TABLE user;");
+    assert_eq!(
+        source.get_down(&migrations[1]).unwrap().as_deref(),
+        Some("REMOVE TABLE post;")
+    );
+    assert_eq!(source.ignored().unwrap()[0].name, "notes.txt");
+    assert_eq!(source.commit(), git(&repo, &["rev-parse", "v1"]).trim());
+
+    let head = GitSource::open(&repo, "main", "db/migrations").unwrap();
+    assert_eq!(head.list().unwrap().len(), 3);
+}
+
+#[test]
+fn fetches_a_ref_into_a_cache() {
+    let tmp = tempfile::tempdir().unwrap();
+    let repo = repository(tmp.path());
+    let url = format!("file://{}", repo.display());
+    let cache = tmp.path().join("cache.git");
+
+    let source = GitSource::fetch(&url, "v1", "db/migrations/", &cache).unwrap();
+    let migrations = source.list().unwrap();
+    assert_eq!(migrations.len(), 2);
+    assert_eq!(source.get_up(&migrations[0]).unwrap(), "DEFINE TABLE user;");
+
+    // The cache is reused for the next ref.
+    let source = GitSource::fetch(&url, "main", "db/migrations", &cache).unwrap();
+    assert_eq!(source.list().unwrap().len(), 3);
+
+    let err = GitSource::fetch(&url, "v9", "db/migrations", &cache)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("git fetch"), "{err}");
+}
+
+#[test]
+fn refuses_refs_and_urls_git_would_read_as_options() {
+    let tmp = tempfile::tempdir().unwrap();
+    let repo = repository(tmp.path());
+    let url = format!("file://{}", repo.display());
+    let cache = tmp.path().join("cache.git");
+    let marker = tmp.path().join("pwned");
+    let upload_pack = format!("--upload-pack=touch {}", marker.display());
+
+    let err = GitSource::fetch(&url, &upload_pack, "db/migrations", &cache)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("must not start with `-`"), "{err}");
+    assert!(GitSource::fetch(&upload_pack, "v1", "db/migrations", &cache).is_err());
+    assert!(GitSource::open(&repo, "--all", "db/migrations").is_err());
+    assert!(!marker.exists());
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use surreal_migraine::checksum::Sha256Hasher;
+use surreal_migraine::http::HttpSource;
+use surreal_migraine::lockfile::LockFile;
+use surreal_migraine::types::{InMemorySource, MigrationKind, MigrationSource};
+
+/// Serve `files` on a local port until the test ends, answering 401 to
+/// requests without `authorization` and 404 to unknown paths.
+fn serve(files: HashMap<String, String>, authorization: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/releases/1.0/", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(&stream);
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            let mut authorized = false;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                authorized |= line.to_ascii_lowercase().trim()
+                    == format!("authorization: {authorization}").to_ascii_lowercase();
+            }
+            let path = request
+                .split(' ')
+                .nth(1)
+                .unwrap()
+                .trim_start_matches("/releases/1.0/");
+            let (status, body) = match files.get(path) {
+                _ if !authorized => ("401 Unauthorized", ""),
+                Some(body) => ("200 OK", body.as_str()),
+                None => ("404 Not Found", ""),
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+        }
+    });
+    url
+}
+
+fn published() -> HashMap<String, String> {
+    let source = InMemorySource::new()
+        .with_migration("001_users.surql", "DEFINE TABLE user;", None)
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE post;",
+            Some("REMOVE TABLE post;"),
+        )
+        .with_migration(
+            "R_views.surql",
+            "DEFINE TABLE active AS SELECT * FROM user;",
+            None,
+        );
+    let lock = LockFile::generate(&source, &Sha256Hasher).unwrap();
+    HashMap::from([
+        ("migrations.lock".to_string(), lock.render()),
+        (
+            "001_users.surql".to_string(),
+            "DEFINE TABLE user;".to_string(),
+        ),
+        (
+            "002_posts/up.surql".to_string(),
+            "DEFINE TABLE post;".to_string(),
+        ),
+        (
+            "002_posts/down.surql".to_string(),
+            "REMOVE TABLE post;".to_string(),
+        ),
+        (
+            "R_views.surql".to_string(),
+            "DEFINE TABLE active AS SELECT * FROM user;".to_string(),
+        ),
+    ])
+}
+
+#[tokio::test]
+async fn fetches_the_manifest_and_migrations() {
+    let url = serve(published(), "Bearer token");
+    let source = HttpSource::fetch(&url, Some("Bearer token")).await.unwrap();
+
+    let migrations = source.list().unwrap();
+    assert_eq!(
+        migrations
+            .iter()
+            .map(|m| (m.name.as_str(), m.kind.clone()))
+            .collect::<Vec<_>>(),
+        [
+            ("001_users.surql", MigrationKind::File),
+            ("002_posts", MigrationKind::Paired),
+            ("R_views.surql", MigrationKind::Repeatable),
+        ]
+    );
+    assert_eq!(source.get_up(&migrations[1]).unwrap(), "DEFINE TABLE post;");
+    assert_eq!(
+        source.get_down(&migrations[1]).unwrap().as_deref(),
+        Some("REMOVE TABLE post;")
+    );
+    assert_eq!(source.get_down(&migrations[0]).unwrap(), None);
+}
+
+#[tokio::test]
+async fn rejects_missing_auth_and_tampered_migrations() {
+    let url = serve(published(), "Bearer token");
+    let err = HttpSource::fetch(&url, None).await.unwrap_err().to_string();
+    assert!(err.contains("401"), "{err}");
+
+    let mut files = published();
+    files.insert("001_users.surql".into(), "REMOVE TABLE user;".into());
+    let url = serve(files, "Bearer token");
+    let err = HttpSource::fetch(&url, Some("Bearer token"))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("001_users.surql does not match its checksum"),
+        "{err}"
+    );
+}
@@ -0,0 +1,67 @@
+use std::fs;
+use surreal_migraine::checksum::{Sha256Hasher, Sha512Hasher};
+use surreal_migraine::lockfile::{LOCK_FILE, LockFile, LockMismatch};
+use surreal_migraine::types::{DiskSource, InMemorySource, MigrationSource};
+
+fn source() -> InMemorySource {
+    InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user;", None)
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE post;",
+            Some("REMOVE TABLE post;"),
+        )
+        .with_migration(
+            "R_views",
+            "DEFINE TABLE active AS SELECT * FROM user;",
+            None,
+        )
+}
+
+#[test]
+fn lock_files_round_trip() {
+    let lock = LockFile::generate(&source(), &Sha512Hasher).unwrap();
+    let text = lock.render();
+    assert!(text.contains("\nalgorithm sha512\n001_users "), "{text}");
+    assert_eq!(LockFile::parse(&text).unwrap(), lock);
+
+    let err = LockFile::parse("001_users abc\n").unwrap_err().to_string();
+    assert!(err.contains("expected `algorithm <name>`"), "{err}");
+}
+
+#[test]
+fn verify_reports_every_difference() {
+    let lock = LockFile::generate(&source(), &Sha256Hasher).unwrap();
+    assert!(lock.verify(&source()).unwrap().is_empty());
+
+    let edited = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user SCHEMAFULL;", None)
+        .with_migration("003_tags", "DEFINE TABLE tag;", None)
+        .with_migration(
+            "R_views",
+            "DEFINE TABLE active AS SELECT * FROM user;",
+            None,
+        );
+    assert_eq!(
+        lock.verify(&edited).unwrap(),
+        [
+            LockMismatch::Changed("001_users".into()),
+            LockMismatch::Added("003_tags".into()),
+            LockMismatch::Removed("002_posts".into()),
+        ]
+    );
+}
+
+#[test]
+fn discovery_skips_the_lock_file() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(dir.path().join("001_users.surql"), "DEFINE TABLE user;").unwrap();
+    let source = DiskSource::new(dir.path());
+    LockFile::generate(&source, &Sha256Hasher)
+        .unwrap()
+        .write(dir.path().join(LOCK_FILE))
+        .unwrap();
+
+    assert_eq!(source.list().unwrap().len(), 1);
+    assert!(source.ignored().unwrap().is_empty());
+}
@@ -0,0 +1,85 @@
+use eyre::Result;
+use surreal_migraine::metadata::MigrationMetadata;
+use surreal_migraine::types::{DiskSource, MigrationSource};
+use tempfile::tempdir;
+
+#[test]
+fn parses_known_and_extra_keys() {
+    let sql = "-- migration: add_users\n\
+               -- Description: Adds the users table\n\
+               -- author: jane\n\
+               -- tags: core, auth ,\n\
+               -- flags: no-transaction\n\
+               -- a free-form comment line\n\
+               \n\
+               DEFINE TABLE users;\n\
+               -- tags: ignored";
+    let meta = MigrationMetadata::parse(sql);
+
+    assert_eq!(meta.description.as_deref(), Some("Adds the users table"));
+    assert_eq!(meta.author.as_deref(), Some("jane"));
+    assert_eq!(meta.tags, ["core", "auth"]);
+    assert!(meta.has_flag("no-transaction"));
+    assert_eq!(
+        meta.extra.get("migration").map(String::as_str),
+        Some("add_users")
+    );
+}
+
+#[test]
+fn no_header_yields_default() {
+    let meta = MigrationMetadata::parse("DEFINE TABLE users;\n-- author: nobody");
+    assert_eq!(meta, MigrationMetadata::default());
+}
+
+#[test]
+fn disk_source_exposes_metadata() -> Result<()> {
+    let tmpdir = tempdir()?;
+    let paired = tmpdir.path().join("001_users");
+    std::fs::create_dir_all(&paired)?;
+    std::fs::write(
+        paired.join("up.surql"),
+        "-- description: users\nDEFINE TABLE users;",
+    )?;
+    std::fs::write(paired.join("down.surql"), "REMOVE TABLE users;")?;
+
+    let list = DiskSource::new(tmpdir.path()).list()?;
+    assert_eq!(list[0].metadata.description.as_deref(), Some("users"));
+
+    Ok(())
+}
+
+#[test]
+fn bare_directive_becomes_flag() {
+    let meta = MigrationMetadata::parse("-- no-transaction\n-- Some prose here\nDEFINE TABLE t;");
+    assert_eq!(meta.flags, ["no-transaction"]);
+    assert!(!meta.transactional());
+}
+
+#[test]
+fn env_limits_migration_to_environments() {
+    let meta = MigrationMetadata::parse("-- env: dev, test\nCREATE user:seed;");
+    assert_eq!(meta.environments, ["dev", "test"]);
+    assert!(meta.applies_to("dev"));
+    assert!(!meta.applies_to("prod"));
+    assert!(MigrationMetadata::parse("DEFINE TABLE user;").applies_to("prod"));
+}
+
+#[test]
+fn namespace_and_database_directives() {
+    let meta = MigrationMetadata::parse("-- ns: ops\n-- database: audit\nDEFINE TABLE log;");
+    assert_eq!(meta.namespace.as_deref(), Some("ops"));
+    assert_eq!(meta.database.as_deref(), Some("audit"));
+    assert!(!meta.extra.contains_key("ns"));
+}
+
+#[test]
+fn depends_accepts_a_bracketed_list() {
+    let meta =
+        MigrationMetadata::parse("-- depends: [001_users, 002_posts.surql]\nDEFINE TABLE t;");
+    assert_eq!(meta.depends, ["001_users", "002_posts.surql"]);
+    let meta = MigrationMetadata::parse("-- depends: 001_users\nDEFINE TABLE t;");
+    assert_eq!(meta.depends, ["001_users"]);
+    let meta = MigrationMetadata::parse("-- depends-on: 001_users\nDEFINE TABLE t;");
+    assert_eq!(meta.depends, ["001_users"]);
+}
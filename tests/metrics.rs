@@ -0,0 +1,66 @@
+use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+use surreal_migraine::MigrationRunner;
+use surreal_migraine::types::InMemorySource;
+use surrealdb::Surreal;
+use surrealdb::engine::local::Mem;
+
+#[tokio::test]
+async fn runs_update_counters_and_histograms() {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let _guard = metrics::set_default_local_recorder(&recorder);
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE post;",
+            Some("REMOVE TABLE post;"),
+        )
+        .with_migration("003_bad.surql", "THROW 'boom';", None);
+    let runner = MigrationRunner::new(&db, source);
+    let err = runner.up().await.unwrap_err();
+    assert!(err.to_string().contains("boom"), "{err}");
+    runner.repair().await.unwrap();
+    runner.down().await.unwrap();
+
+    let mut metrics = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let mut labels = key
+                .key()
+                .labels()
+                .map(|l| format!("{}={}", l.key(), l.value()))
+                .collect::<Vec<_>>();
+            labels.sort();
+            let value = match value {
+                DebugValue::Counter(n) => n.to_string(),
+                DebugValue::Histogram(samples) => format!("{} samples", samples.len()),
+                DebugValue::Gauge(g) => g.to_string(),
+            };
+            format!("{} {} {value}", key.key().name(), labels.join(","))
+        })
+        .collect::<Vec<_>>();
+    metrics.sort();
+    assert_eq!(
+        metrics,
+        [
+            "migration_duration_seconds direction=down,outcome=reverted 2 samples",
+            "migration_duration_seconds direction=up,outcome=applied 2 samples",
+            "migration_duration_seconds direction=up,outcome=failed 1 samples",
+            "migration_failures_total direction=up 1",
+            "migration_runs_total direction=down,outcome=succeeded 1",
+            "migration_runs_total direction=up,outcome=failed 1",
+            "migrations_applied_total  2",
+            "migrations_reverted_total  2",
+        ]
+    );
+}
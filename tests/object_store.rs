@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use surreal_migraine::checksum::Sha256Hasher;
+use surreal_migraine::lockfile::LockFile;
+use surreal_migraine::object_store::ObjectStoreSource;
+use surreal_migraine::object_store::store::memory::InMemory;
+use surreal_migraine::object_store::store::path::Path;
+use surreal_migraine::object_store::store::{ObjectStore, PutPayload};
+use surreal_migraine::types::{InMemorySource, MigrationKind, MigrationSource};
+
+async fn put(store: &InMemory, path: &str, content: &str) {
+    store
+        .put(&Path::from(path), PutPayload::from(content.to_string()))
+        .await
+        .unwrap();
+}
+
+/// A bucket holding a release's migrations under `app/1.0/migrations`, and
+/// an unrelated object next to them.
+async fn bucket() -> InMemory {
+    let store = InMemory::new();
+    put(
+        &store,
+        "app/1.0/migrations/001_users.surql",
+        "DEFINE TABLE user;",
+    )
+    .await;
+    put(
+        &store,
+        "app/1.0/migrations/002_posts/up.surql",
+        "DEFINE TABLE post;",
+    )
+    .await;
+    put(
+        &store,
+        "app/1.0/migrations/002_posts/down.surql",
+        "REMOVE TABLE post;",
+    )
+    .await;
+    put(
+        &store,
+        "app/1.0/migrations/R_views.surql",
+        "DEFINE TABLE active AS SELECT * FROM user;",
+    )
+    .await;
+    put(&store, "app/1.0/migrations/notes.txt", "not a migration").await;
+    put(
+        &store,
+        "app/0.9/migrations/001_users.surql",
+        "DEFINE TABLE users;",
+    )
+    .await;
+    store
+}
+
+#[tokio::test]
+async fn loads_the_migrations_under_a_prefix() {
+    let source = ObjectStoreSource::fetch(Arc::new(bucket().await), "app/1.0/migrations")
+        .await
+        .unwrap();
+
+    let migrations = source.list().unwrap();
+    assert_eq!(
+        migrations
+            .iter()
+            .map(|m| (m.name.as_str(), m.kind.clone()))
+            .collect::<Vec<_>>(),
+        [
+            ("001_users.surql", MigrationKind::File),
+            ("002_posts", MigrationKind::Paired),
+            ("R_views.surql", MigrationKind::Repeatable),
+        ]
+    );
+    assert_eq!(source.get_up(&migrations[0]).unwrap(), "DEFINE TABLE user;");
+    assert_eq!(
+        source.get_down(&migrations[1]).unwrap().as_deref(),
+        Some("REMOVE TABLE post;")
+    );
+    assert_eq!(source.ignored().unwrap()[0].name, "notes.txt");
+}
+
+#[tokio::test]
+async fn checks_the_migrations_against_a_lock_file() {
+    let locked = InMemorySource::new()
+        .with_migration("001_users.surql", "DEFINE TABLE user;", None)
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE post;",
+            Some("REMOVE TABLE post;"),
+        )
+        .with_migration(
+            "R_views.surql",
+            "DEFINE TABLE active AS SELECT * FROM user;",
+            None,
+        );
+    let lock = LockFile::generate(&locked, &Sha256Hasher).unwrap().render();
+
+    let store = bucket().await;
+    put(&store, "app/1.0/migrations/migrations.lock", &lock).await;
+    let store = Arc::new(store);
+    let source = ObjectStoreSource::fetch(store.clone(), "app/1.0/migrations")
+        .await
+        .unwrap();
+    assert_eq!(source.list().unwrap().len(), 3);
+
+    put(
+        &store,
+        "app/1.0/migrations/001_users.surql",
+        "REMOVE TABLE user;",
+    )
+    .await;
+    let err = ObjectStoreSource::fetch(store, "app/1.0/migrations")
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("001_users.surql changed since it was locked"),
+        "{err}"
+    );
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use surreal_migraine::MigrationRunner;
+use surreal_migraine::types::InMemorySource;
+use surrealdb::Surreal;
+use surrealdb::engine::local::Mem;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+type Fields = BTreeMap<String, String>;
+
+/// A span with its parent and fields.
+#[derive(Debug)]
+struct Captured {
+    id: Id,
+    parent: Option<Id>,
+    fields: Fields,
+}
+
+/// Collects the fields of every span, in creation order.
+#[derive(Clone, Default)]
+struct Spans(Arc<Mutex<Vec<Captured>>>);
+
+struct Collect<'a>(&'a mut Fields);
+
+impl Visit for Collect<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_owned(), value.to_owned());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name().to_owned(), format!("{value:?}"));
+    }
+}
+
+impl<S> Layer<S> for Spans
+where
+    S: tracing::Subscriber + for<'l> tracing_subscriber::registry::LookupSpan<'l>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::new();
+        fields.insert("span".into(), attrs.metadata().name().into());
+        attrs.record(&mut Collect(&mut fields));
+        let parent = ctx.span_scope(id).and_then(|mut s| {
+            s.next();
+            s.next().map(|p| p.id())
+        });
+        self.0.lock().unwrap().push(Captured {
+            id: id.clone(),
+            parent,
+            fields,
+        });
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut spans = self.0.lock().unwrap();
+        if let Some(span) = spans.iter_mut().find(|s| s.id == *id) {
+            values.record(&mut Collect(&mut span.fields));
+        }
+    }
+}
+
+fn field<'f>(fields: &'f Fields, name: &str) -> &'f str {
+    fields.get(name).map(String::as_str).unwrap_or_default()
+}
+
+#[tokio::test]
+async fn runs_and_migrations_get_spans() {
+    let spans = Spans::default();
+    let _guard =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(spans.clone()));
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user; DEFINE FIELD name ON user TYPE string;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration("002_bad.surql", "THROW 'boom';", None);
+    let runner = MigrationRunner::new(&db, source);
+    let err = runner.up().await.unwrap_err();
+    assert!(err.to_string().contains("boom"), "{err}");
+
+    let spans = spans.0.lock().unwrap();
+    let ours = spans
+        .iter()
+        .filter(|s| s.fields["span"].starts_with("migration"))
+        .collect::<Vec<_>>();
+    assert_eq!(ours.len(), 3, "{ours:#?}");
+
+    let run = &ours[0].fields;
+    assert_eq!(field(run, "span"), "migration_run");
+    assert_eq!(field(run, "otel.name"), "migrate up");
+    assert_eq!(field(run, "migration.count"), "2");
+    assert_eq!(field(run, "migration.outcome"), "failed");
+    assert_eq!(field(run, "otel.status_code"), "error");
+    assert!(run.contains_key("migration.duration_ms"));
+
+    let users = &ours[1].fields;
+    assert_eq!(ours[1].parent.as_ref(), Some(&ours[0].id));
+    assert_eq!(field(users, "migration.name"), "001_users");
+    assert_eq!(field(users, "migration.direction"), "up");
+    assert_eq!(field(users, "migration.statements"), "2");
+    assert_eq!(field(users, "migration.outcome"), "applied");
+    assert_eq!(field(users, "otel.status_code"), "ok");
+
+    let bad = &ours[2].fields;
+    assert_eq!(field(bad, "migration.name"), "002_bad.surql");
+    assert_eq!(field(bad, "migration.outcome"), "failed");
+    assert_eq!(field(bad, "otel.status_code"), "error");
+}
@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+use surreal_migraine::placeholders::substitute;
+
+#[test]
+fn substitutes_known_and_reports_unknown() {
+    let vars = HashMap::from([
+        ("owner".to_string(), "alice".to_string()),
+        ("app.env".to_string(), "prod".to_string()),
+    ]);
+
+    let sql = substitute("CREATE t SET o = '{{owner}}', e = '{{ app.env }}';", &vars).unwrap();
+    assert_eq!(sql, "CREATE t SET o = 'alice', e = 'prod';");
+
+    let err = substitute("{{b}} {{a}} {{b}}", &vars)
+        .unwrap_err()
+        .to_string();
+    assert!(err.ends_with("a, b"), "{err}");
+}
+
+#[test]
+fn leaves_object_literals_alone() {
+    let vars = HashMap::new();
+    let sql = "CREATE t CONTENT {{a: 1}.a: 2}; RETURN {{ }};";
+    assert_eq!(substitute(sql, &vars).unwrap(), sql);
+}
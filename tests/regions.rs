@@ -0,0 +1,51 @@
+use surreal_migraine::connect::ConnectOptions;
+use surreal_migraine::regions::RegionOrchestrator;
+use surreal_migraine::types::InMemorySource;
+
+fn mem() -> ConnectOptions {
+    ConnectOptions {
+        url: "mem://".into(),
+        namespace: "test".into(),
+        database: "test".into(),
+        ..Default::default()
+    }
+}
+
+fn orchestrator() -> RegionOrchestrator {
+    RegionOrchestrator::new()
+        .with_region("canary", mem())
+        .with_region("eu", mem())
+        .with_region("us", mem())
+}
+
+#[tokio::test]
+async fn applies_regions_in_order() {
+    let source = InMemorySource::new().with_migration("001_init", "DEFINE TABLE user;", None);
+
+    let migrated = orchestrator().up(&source).await.unwrap();
+    assert_eq!(migrated, ["canary", "eu", "us"]);
+
+    let resumed = orchestrator()
+        .with_continue_from("eu")
+        .up(&source)
+        .await
+        .unwrap();
+    assert_eq!(resumed, ["eu", "us"]);
+
+    assert!(
+        orchestrator()
+            .with_continue_from("apac")
+            .up(&source)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn halts_at_failing_canary() {
+    let source = InMemorySource::new().with_migration("001_bad", "THROW 'boom';", None);
+
+    let err = orchestrator().up(&source).await.unwrap_err().to_string();
+    assert!(err.starts_with("region canary failed"), "{err}");
+    assert!(err.contains("with_continue_from(\"canary\")"), "{err}");
+}
@@ -1,6 +1,15 @@
-use surreal_migraine::types::MigrationRecord;
+use surreal_migraine::catalog::CatalogSource;
+use surreal_migraine::checksum::Sha256Hasher;
+use surreal_migraine::hooks::{Hook, HookPoint};
+use surreal_migraine::lockfile::LockFile;
+use surreal_migraine::types::{
+    DiskSource, ExecutionMode, InMemorySource, LockOptions, MigrationEvent, MigrationKind,
+    MigrationRecord, MigrationSource, OutOfOrderPolicy, OutcomeStatus, ProgressEvent, RunDirection,
+    UsePolicy,
+};
 use surreal_migraine::{MigrationRunner, types::EmbeddedSource};
 
+use std::time::Duration;
 use surreal_migraine::{Dir, include_dir};
 use surrealdb::Surreal;
 use surrealdb::engine::local::Mem;
@@ -28,3 +37,2348 @@ async fn test_migrations_apply_successfully() {
         .unwrap();
     assert!(!users.is_empty(), "Users table should have been created");
 }
+
+#[tokio::test]
+async fn test_runs_are_persisted() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let runner = MigrationRunner::new(&db, EmbeddedSource::new(&TEST_MIGRATIONS));
+    runner.up().await.unwrap();
+    runner.down().await.unwrap();
+
+    let runs = runner.runs().await.unwrap();
+    assert_eq!(runs.len(), 2);
+
+    assert_eq!(runs[0].direction, RunDirection::Up);
+    assert!(runs[0].is_success());
+    assert_eq!(runs[0].outcomes.len(), 2);
+    assert!(
+        runs[0]
+            .outcomes
+            .iter()
+            .all(|o| o.status == OutcomeStatus::Applied)
+    );
+
+    assert_eq!(runs[1].direction, RunDirection::Down);
+    assert_eq!(runs[1].outcomes.len(), 1);
+    assert_eq!(runs[1].outcomes[0].status, OutcomeStatus::Reverted);
+
+    let id = runs[0].id.as_ref().unwrap().key().to_string();
+    let fetched = runner.run(&id).await.unwrap().unwrap();
+    assert_eq!(fetched.fingerprint, runs[0].fingerprint);
+}
+
+#[tokio::test]
+async fn test_last_run_is_this_runners_own_report() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user;", None)
+        .with_migration("002_bad", "THROW 'boom';", None);
+    let runner = MigrationRunner::new(&db, source);
+    assert_eq!(runner.last_run().await.unwrap(), None);
+
+    assert!(runner.up().await.is_err());
+    // Another deployer's run recorded after ours.
+    MigrationRunner::new(&db, InMemorySource::new())
+        .seed(&InMemorySource::new())
+        .await
+        .unwrap();
+
+    let last = runner.last_run().await.unwrap().unwrap();
+    assert_eq!(last.outcomes.len(), 2);
+    assert!(last.error.unwrap().contains("boom"));
+    assert_eq!(runner.runs().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_change_ref_is_recorded_with_runs() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    MigrationRunner::new(&db, EmbeddedSource::new(&TEST_MIGRATIONS))
+        .with_change_ref("JIRA-123")
+        .up()
+        .await
+        .unwrap();
+    let runner = MigrationRunner::new(&db, EmbeddedSource::new(&TEST_MIGRATIONS));
+    runner.down().await.unwrap();
+
+    let runs = runner.runs().await.unwrap();
+    assert_eq!(runs[0].change_ref.as_deref(), Some("JIRA-123"));
+    assert_eq!(runs[1].change_ref, None);
+}
+
+#[tokio::test]
+async fn test_lock_file_mismatch_blocks_up() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let locked = InMemorySource::new().with_migration("001_users", "DEFINE TABLE user;", None);
+    let lock = LockFile::generate(&locked, &Sha256Hasher).unwrap();
+
+    let edited = InMemorySource::new().with_migration("001_users", "DEFINE TABLE users;", None);
+    let err = MigrationRunner::new(&db, edited)
+        .with_lock_file(lock.clone())
+        .up()
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("001_users changed since it was locked"),
+        "{err}"
+    );
+
+    let runner = MigrationRunner::new(&db, locked).with_lock_file(lock);
+    runner.up().await.unwrap();
+    assert!(runner.status().await.unwrap()[0].applied);
+}
+
+#[tokio::test]
+async fn test_failed_run_is_persisted() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("001_ok.surql"), "DEFINE TABLE ok;").unwrap();
+    std::fs::write(dir.path().join("002_bad.surql"), "THROW 'boom';").unwrap();
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let runner = MigrationRunner::new(&db, DiskSource::new(dir.path()));
+    assert!(runner.up().await.is_err());
+
+    let runs = runner.runs().await.unwrap();
+    assert_eq!(runs.len(), 1);
+    assert!(!runs[0].is_success());
+    assert_eq!(runs[0].outcomes[1].name, "002_bad.surql");
+    assert_eq!(runs[0].outcomes[1].status, OutcomeStatus::Failed);
+}
+
+#[tokio::test]
+async fn test_in_memory_source_up_and_down() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE users;", None)
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE posts;",
+            Some("REMOVE TABLE posts;"),
+        );
+
+    let runner = MigrationRunner::new(&db, source);
+    runner.up().await.unwrap();
+
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(result.len(), 2);
+
+    runner.down().await.unwrap();
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0].name, "001_users");
+}
+
+#[tokio::test]
+async fn test_catalog_source_distributes_migrations() {
+    let catalog = Surreal::new::<Mem>(()).await.unwrap();
+    catalog.use_ns("control").use_db("catalog").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration("002_posts.surql", "DEFINE TABLE post;", None)
+        .with_migration(
+            "R_views.surql",
+            "DEFINE TABLE active AS SELECT * FROM user;",
+            None,
+        );
+    CatalogSource::publish(&catalog, "migration_catalog", &source)
+        .await
+        .unwrap();
+    // Publishing again replaces the catalog rather than adding to it.
+    CatalogSource::publish(&catalog, "migration_catalog", &source)
+        .await
+        .unwrap();
+
+    let fetched = CatalogSource::fetch(&catalog, "migration_catalog")
+        .await
+        .unwrap();
+    let migrations = fetched.list().unwrap();
+    assert_eq!(
+        migrations
+            .iter()
+            .map(|m| (m.name.as_str(), m.kind.clone()))
+            .collect::<Vec<_>>(),
+        [
+            ("001_users", MigrationKind::Paired),
+            ("002_posts.surql", MigrationKind::File),
+            ("R_views.surql", MigrationKind::Repeatable),
+        ]
+    );
+    assert_eq!(
+        fetched.get_down(&migrations[0]).unwrap().as_deref(),
+        Some("REMOVE TABLE user;")
+    );
+
+    for tenant in ["acme", "globex"] {
+        let db = Surreal::new::<Mem>(()).await.unwrap();
+        db.use_ns(tenant).use_db("app").await.unwrap();
+        MigrationRunner::new(&db, fetched.clone())
+            .up()
+            .await
+            .unwrap();
+        let applied: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+        assert_eq!(applied.len(), 3, "{tenant}");
+    }
+}
+
+#[tokio::test]
+async fn test_progress_events() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user; DEFINE FIELD name ON user TYPE string;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration("002_posts.surql", "DEFINE TABLE post;", None);
+    let events = std::sync::Mutex::new(Vec::new());
+    let runner = MigrationRunner::new(&db, source)
+        .with_execution_mode(ExecutionMode::PerStatement)
+        .with_progress(|event: &ProgressEvent| events.lock().unwrap().push(event.clone()));
+
+    runner.up().await.unwrap();
+    runner.down().await.unwrap();
+    drop(runner);
+
+    let summary = events
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|event| match event {
+            ProgressEvent::RunStarted { direction, total } => format!("{direction:?} {total}"),
+            ProgressEvent::MigrationStarted { name, index, .. } => format!("start {index} {name}"),
+            ProgressEvent::StatementStarted { index, total, .. } => {
+                format!("statement {}/{total}", index + 1)
+            }
+            ProgressEvent::MigrationFinished { name, status, .. } => {
+                format!("finish {name} {status:?}")
+            }
+            ProgressEvent::RunFinished { succeeded, .. } => format!("done {succeeded}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            "Up 2",
+            "start 0 001_users",
+            "statement 1/2",
+            "statement 2/2",
+            "finish 001_users Some(Applied)",
+            "start 1 002_posts.surql",
+            "statement 1/1",
+            "finish 002_posts.surql Some(Applied)",
+            "done true",
+            "Down 2",
+            "start 0 002_posts.surql",
+            "finish 002_posts.surql None",
+            "start 1 001_users",
+            "statement 1/1",
+            "finish 001_users Some(Reverted)",
+            "done true",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_up_with_events() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user;", None)
+        .with_migration("002_seed", "-- env: dev\nCREATE user:dev;", None)
+        .with_migration("003_bad", "THROW 'boom';", None);
+    let runner = MigrationRunner::new(&db, source).with_environment("prod");
+
+    let (run, mut events) = runner.up_with_events();
+    let collect = async {
+        let mut seen = Vec::new();
+        while let Some(event) = events.recv().await {
+            seen.push(event);
+        }
+        seen
+    };
+    let (result, seen) = tokio::join!(run, collect);
+    assert!(result.unwrap_err().to_string().contains("boom"));
+
+    let summary = seen
+        .into_iter()
+        .map(|event| match event {
+            MigrationEvent::Discovered { pending } => format!("discovered {}", pending.join(" ")),
+            MigrationEvent::Started { name } => format!("started {name}"),
+            MigrationEvent::Applied { name, .. } => format!("applied {name}"),
+            MigrationEvent::Failed { name, error } => {
+                assert!(error.contains("boom"), "{error}");
+                format!("failed {name}")
+            }
+            MigrationEvent::Skipped { name, .. } => format!("skipped {name}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            "discovered 001_users 003_bad",
+            "skipped 002_seed",
+            "started 001_users",
+            "applied 001_users",
+            "started 003_bad",
+            "failed 003_bad",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_timeouts_fail_slow_migrations() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user;", None)
+        .with_migration("002_slow", "SLEEP 2s;", Some("REMOVE TABLE user;"));
+    let runner = MigrationRunner::new(&db, source)
+        .with_migration_timeout(Duration::from_millis(100))
+        .with_cleanup_on_failure(true);
+
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(
+        err.contains("002_slow: migration timeout of 100ms exceeded"),
+        "{err}"
+    );
+    // The abandoned query may still run, so its down script is not.
+    assert!(!err.contains("cleaned up"), "{err}");
+    assert_eq!(runner.dirty().await.unwrap().unwrap().migration, "002_slow");
+    runner.repair().await.unwrap();
+
+    let runner = runner
+        .with_migration_timeout(Duration::from_secs(60))
+        .with_run_timeout(Duration::from_millis(100));
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(
+        err.contains("002_slow: run timeout of 100ms exceeded"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn test_migration_report() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "USE NS test;\nDEFINE TABLE user;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration("002_posts.surql", "DEFINE TABLE post;", None)
+        .with_migration("003_seed", "-- env: dev\nCREATE user:dev;", None);
+    let runner = MigrationRunner::new(&db, source)
+        .with_environment("prod")
+        .with_use_policy(UsePolicy::Strip);
+
+    let report = runner.up().await.unwrap();
+    assert_eq!(report.direction, RunDirection::Up);
+    assert_eq!(
+        report.applied().collect::<Vec<_>>(),
+        ["001_users", "002_posts.surql"]
+    );
+    assert_eq!(report.skipped, ["003_seed"]);
+    assert_eq!(report.warnings, ["removed USE statements from 001_users"]);
+    assert!(
+        report
+            .outcomes
+            .iter()
+            .all(|o| o.duration_ms <= report.duration_ms)
+    );
+
+    assert!(runner.up().await.unwrap().is_empty());
+
+    let report = runner.down_to("001_users").await.unwrap();
+    assert!(report.is_empty());
+    assert_eq!(report.skipped, ["002_posts.surql"]);
+    assert_eq!(
+        report.warnings,
+        ["002_posts.surql has no down script and was left applied"]
+    );
+
+    let report = runner.down().await.unwrap();
+    assert_eq!(report.reverted().collect::<Vec<_>>(), ["001_users"]);
+}
+
+#[tokio::test]
+async fn test_repeatable_migrations_reapply_on_change() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE users;", None)
+        .with_migration("R_views", "DEFINE TABLE active_users;", None);
+
+    let mut runner = MigrationRunner::new(&db, source);
+    runner.up().await.unwrap();
+    runner.up().await.unwrap();
+
+    runner
+        .source
+        .add("R_views", "DEFINE TABLE active_users_v2;", None);
+    runner.up().await.unwrap();
+
+    let runs = runner.runs().await.unwrap();
+    let applied: Vec<Vec<&str>> = runs
+        .iter()
+        .map(|r| r.outcomes.iter().map(|o| o.name.as_str()).collect())
+        .collect();
+    assert_eq!(
+        applied,
+        vec![vec!["001_users", "R_views"], vec![], vec!["R_views"]]
+    );
+
+    let records: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(records.len(), 2);
+}
+
+#[tokio::test]
+async fn test_no_transaction_directive() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    // Without the directive the failing statement rolls back the whole
+    // script; with it, statements before the failure persist.
+    let source = InMemorySource::new().with_migration(
+        "001_partial",
+        "-- no-transaction\nCREATE marker:one;\nTHROW 'boom';",
+        None,
+    );
+
+    let runner = MigrationRunner::new(&db, source);
+    assert!(runner.up().await.is_err());
+
+    let markers: Vec<surrealdb::RecordId> = db
+        .query("SELECT VALUE id FROM marker")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(markers.len(), 1);
+}
+
+#[tokio::test]
+async fn test_lock_is_released_after_run() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let runner = MigrationRunner::new(&db, EmbeddedSource::new(&TEST_MIGRATIONS))
+        .with_lock(LockOptions::default());
+    runner.up().await.unwrap();
+
+    let locks: Vec<surrealdb::RecordId> = db
+        .query("SELECT VALUE id FROM migration_lock")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(locks.is_empty());
+}
+
+#[tokio::test]
+async fn test_live_lock_blocks_and_stale_lock_is_taken_over() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    db.query("CREATE migration_lock:runner SET owner = 'other', heartbeat = time::now()")
+        .await
+        .unwrap();
+
+    let runner = MigrationRunner::new(&db, EmbeddedSource::new(&TEST_MIGRATIONS))
+        .with_lock(LockOptions::default());
+    let err = runner.up().await.unwrap_err();
+    assert!(err.to_string().contains("locked by other"), "{err}");
+
+    db.query("UPDATE migration_lock:runner SET heartbeat = time::now() - 1h")
+        .await
+        .unwrap();
+    runner.up().await.unwrap();
+
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(result.len(), 2);
+}
+
+#[tokio::test]
+async fn test_heartbeat_is_refreshed_during_long_migration() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new().with_migration(
+        "001_slow",
+        "-- no-transaction\nCREATE beat:first SET at = (SELECT VALUE heartbeat FROM ONLY migration_lock:runner);\nSLEEP 300ms;\nCREATE beat:second SET at = (SELECT VALUE heartbeat FROM ONLY migration_lock:runner);",
+        None,
+    );
+    let runner = MigrationRunner::new(&db, source).with_lock(LockOptions {
+        heartbeat_interval: Duration::from_millis(50),
+        stale_after: Duration::from_secs(30),
+    });
+    runner.up().await.unwrap();
+
+    let changed: bool = db
+        .query("RETURN beat:first.at != beat:second.at")
+        .await
+        .unwrap()
+        .take::<Option<bool>>(0)
+        .unwrap()
+        .unwrap();
+    assert!(
+        changed,
+        "heartbeat was not refreshed while the migration ran"
+    );
+}
+
+#[tokio::test]
+async fn test_run_stops_when_the_lock_is_taken_over() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    // Another runner takes over the lock while the migration runs.
+    let source = InMemorySource::new().with_migration(
+        "001_slow",
+        "-- no-transaction\nUPDATE migration_lock:runner SET owner = 'other';\nSLEEP 300ms;",
+        None,
+    );
+    let runner = MigrationRunner::new(&db, source).with_lock(LockOptions {
+        heartbeat_interval: Duration::from_millis(50),
+        stale_after: Duration::from_secs(30),
+    });
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(err.contains("took over the migration lock"), "{err}");
+
+    // The lock is still the new owner's, and the migration was not recorded.
+    let owner: Option<String> = db
+        .query("RETURN migration_lock:runner.owner")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(owner.as_deref(), Some("other"));
+    assert!(!runner.status().await.unwrap()[0].applied);
+}
+
+#[tokio::test]
+async fn test_per_statement_mode_reports_failing_line() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new().with_migration(
+        "001_steps",
+        "-- migration: steps\nDEFINE TABLE a;\nDEFINE TABLE b;\n\nTHROW 'boom';\nDEFINE TABLE c;",
+        None,
+    );
+    let runner = MigrationRunner::new(&db, source).with_execution_mode(ExecutionMode::PerStatement);
+
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(err.contains("statement 3/4 (line 5)"), "{err}");
+    #[cfg(not(feature = "miette"))]
+    assert!(err.contains("THROW 'boom'"), "{err}");
+}
+
+#[cfg(feature = "miette")]
+#[tokio::test]
+async fn test_per_statement_failure_is_a_diagnostic() {
+    use surreal_migraine::diagnostic::StatementError;
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let script = "DEFINE TABLE a;\nTHROW 'boom';";
+    let source = InMemorySource::new().with_migration("001_steps", script, None);
+    let runner = MigrationRunner::new(&db, source).with_execution_mode(ExecutionMode::PerStatement);
+
+    let err = runner.up().await.unwrap_err();
+    let diagnostic = err.downcast_ref::<StatementError>().unwrap();
+    assert_eq!(diagnostic.line, 2);
+    let span = diagnostic.span.offset()..diagnostic.span.offset() + diagnostic.span.len();
+    assert_eq!(&script[span], "THROW 'boom'");
+    assert!(diagnostic.help.is_some());
+}
+
+#[tokio::test]
+async fn test_out_of_order_policy() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_a", "DEFINE TABLE a;", None)
+        .with_migration("003_c", "DEFINE TABLE c;", None);
+    let mut runner = MigrationRunner::new(&db, source).with_out_of_order(OutOfOrderPolicy::Error);
+    runner.up().await.unwrap();
+
+    runner.source.add("002_b", "DEFINE TABLE b;", None);
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(err.contains("002_b"), "{err}");
+    assert!(err.contains("003_c"), "{err}");
+
+    let runner = runner.with_out_of_order(OutOfOrderPolicy::Warn);
+    runner.up().await.unwrap();
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(result.len(), 3);
+}
+
+#[tokio::test]
+async fn test_owned_tables_refuse_foreign_migrations() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user;", None)
+        .with_migration("002_billing", "DEFINE TABLE invoice;", None);
+    let runner = MigrationRunner::new(&db, source).with_owned_tables(["user"]);
+
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(err.contains("002_billing (invoice)"), "{err}");
+
+    // Nothing ran: ownership is checked before execution.
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert!(result.is_empty());
+}
+
+#[tokio::test]
+async fn test_use_statements_are_rejected_or_stripped() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_user", "DEFINE TABLE user;", None)
+        .with_migration(
+            "002_other",
+            "USE NS other DB other;\nDEFINE TABLE elsewhere;\nUSE DB test ;\nDEFINE TABLE here;",
+            None,
+        );
+    let runner = MigrationRunner::new(&db, source);
+
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(err.contains("002_other (line 1, 3)"), "{err}");
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert!(result.is_empty());
+
+    let runner = runner.with_use_policy(UsePolicy::Strip);
+    runner.up().await.unwrap();
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(result.len(), 2);
+    let info: Option<serde_json::Value> = db.query("INFO FOR DB").await.unwrap().take(0).unwrap();
+    let info = info.unwrap();
+    assert!(info["tables"].get("elsewhere").is_some());
+    assert!(info["tables"].get("here").is_some());
+}
+
+#[tokio::test]
+async fn test_verify_diffs_edited_migrations() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let original = InMemorySource::new().with_migration("001_init", "DEFINE TABLE user;\n", None);
+    MigrationRunner::new(&db, original).up().await.unwrap();
+
+    let edited = InMemorySource::new().with_migration("001_init", "DEFINE TABLE users;\n", None);
+    let runner = MigrationRunner::new(&db, edited);
+
+    let mismatches = runner.verify().await.unwrap();
+    assert_eq!(mismatches.len(), 1);
+    let diff = mismatches[0].diff.as_deref().unwrap();
+    assert!(diff.contains("-DEFINE TABLE user;"), "{diff}");
+    assert!(diff.contains("+DEFINE TABLE users;"), "{diff}");
+
+    // Unverified runs ignore the edit; verified runs refuse with the diff.
+    runner.up().await.unwrap();
+    let err = runner
+        .with_checksum_verification(true)
+        .up()
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("001_init: applied"), "{err}");
+    assert!(err.contains("+DEFINE TABLE users;"), "{err}");
+}
+
+#[tokio::test]
+async fn test_failed_migration_marks_dirty_until_repaired() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("001_bad.surql"), "THROW 'boom';").unwrap();
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let runner = MigrationRunner::new(&db, DiskSource::new(dir.path()));
+    assert!(runner.up().await.is_err());
+
+    let state = runner.dirty().await.unwrap().expect("dirty marker");
+    assert_eq!(state.migration, "001_bad.surql");
+    assert_eq!(state.direction, RunDirection::Up);
+    assert!(state.error.contains("boom"), "{}", state.error);
+    assert!(state.failed_at.is_some());
+
+    // The file is fixed, but the marker still blocks the run.
+    std::fs::write(dir.path().join("001_bad.surql"), "DEFINE TABLE ok;").unwrap();
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(err.contains("database is dirty"), "{err}");
+
+    let cleared = runner.repair().await.unwrap().expect("cleared marker");
+    assert_eq!(cleared.migration, "001_bad.surql");
+    assert!(runner.dirty().await.unwrap().is_none());
+    runner.up().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_failed_run_keeps_its_error_when_the_report_is_not_recorded() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new().with_migration(
+        "001_bad",
+        "-- no-transaction\nDEFINE FIELD OVERWRITE direction ON migration_runs TYPE int;\nTHROW 'boom';",
+        None,
+    );
+    let runner = MigrationRunner::new(&db, source);
+
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(err.contains("boom"), "{err}");
+    assert!(runner.runs().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_redo_reapplies_last_migration() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE users;",
+            Some("REMOVE TABLE users;"),
+        )
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE posts; CREATE posts:one;",
+            Some("REMOVE TABLE posts;"),
+        );
+    let runner = MigrationRunner::new(&db, source);
+    runner.up().await.unwrap();
+    db.query("CREATE posts:two").await.unwrap();
+
+    runner.redo().await.unwrap();
+
+    // The down script dropped posts:two; the up script recreated posts:one.
+    let posts: Vec<surrealdb::RecordId> = db
+        .query("SELECT VALUE id FROM posts")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(posts.len(), 1);
+
+    let runs = runner.runs().await.unwrap();
+    let redo = runs.last().unwrap();
+    assert_eq!(redo.direction, RunDirection::Redo);
+    let statuses: Vec<_> = redo.outcomes.iter().map(|o| o.status).collect();
+    assert_eq!(statuses, [OutcomeStatus::Reverted, OutcomeStatus::Applied]);
+    assert_eq!(redo.outcomes[0].name, "002_posts");
+
+    runner.redo_named("001_users").await.unwrap();
+    assert!(runner.redo_named("003_missing").await.is_err());
+    assert_eq!(
+        runner
+            .status()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|s| s.applied)
+            .count(),
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_tags_mark_checkpoints() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let mut runner = MigrationRunner::new(
+        &db,
+        InMemorySource::new().with_migration("001_a", "DEFINE TABLE a;", Some("REMOVE TABLE a;")),
+    );
+    runner.up().await.unwrap();
+    assert_eq!(runner.tag("v1.0.0").await.unwrap().migration, "001_a");
+    assert!(runner.tag("v1.0.0").await.is_err());
+
+    runner
+        .source
+        .add("002_b", "DEFINE TABLE b;", Some("REMOVE TABLE b;"));
+    runner
+        .source
+        .add("003_c", "DEFINE TABLE c;", Some("REMOVE TABLE c;"));
+    runner.up().await.unwrap();
+    runner.tag("v1.1.0").await.unwrap();
+
+    let tags: Vec<_> = runner
+        .tags()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|t| t.name)
+        .collect();
+    assert_eq!(tags, ["v1.0.0", "v1.1.0"]);
+
+    let since: Vec<_> = runner
+        .status_since_tag("v1.0.0")
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(since, ["002_b", "003_c"]);
+
+    runner.down_to_tag("v1.0.0").await.unwrap();
+    let applied: Vec<_> = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|s| s.applied)
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(applied, ["001_a"]);
+    assert!(runner.down_to_tag("v9").await.is_err());
+}
+
+#[tokio::test]
+async fn test_tag_points_at_the_migration_applied_last() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_a", "DEFINE TABLE a;", Some("REMOVE TABLE a;"))
+        .with_migration("003_c", "DEFINE TABLE c;", Some("REMOVE TABLE c;"));
+    MigrationRunner::new(&db, source).up().await.unwrap();
+
+    // 002 is discovered before 003 but applied after it.
+    let source = InMemorySource::new()
+        .with_migration("001_a", "DEFINE TABLE a;", Some("REMOVE TABLE a;"))
+        .with_migration("002_b", "DEFINE TABLE b;", Some("REMOVE TABLE b;"))
+        .with_migration("003_c", "DEFINE TABLE c;", Some("REMOVE TABLE c;"));
+    let runner = MigrationRunner::new(&db, source).with_out_of_order(OutOfOrderPolicy::Allow);
+    runner.up().await.unwrap();
+
+    assert_eq!(runner.tag("v1.0.0").await.unwrap().migration, "002_b");
+}
+
+#[tokio::test]
+async fn test_fresh_requires_opt_in_and_rebuilds() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new().with_migration(
+        "001_init",
+        "DEFINE TABLE user; DEFINE FUNCTION fn::greet() { RETURN 'hi'; }; DEFINE PARAM $limit VALUE 5;",
+        None,
+    );
+    let runner = MigrationRunner::new(&db, source).with_lock(LockOptions::default());
+    runner.up().await.unwrap();
+    db.query("CREATE user:alice; DEFINE TABLE scratch;")
+        .await
+        .unwrap();
+
+    let err = runner.fresh().await.unwrap_err().to_string();
+    assert!(err.contains("with_fresh_allowed"), "{err}");
+
+    let runner = runner.with_fresh_allowed(true);
+    runner.fresh().await.unwrap();
+
+    let users: Vec<surrealdb::RecordId> = db
+        .query("SELECT VALUE id FROM user")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(users.is_empty());
+    let info: Option<serde_json::Value> = db.query("INFO FOR DB").await.unwrap().take(0).unwrap();
+    let info = info.unwrap();
+    assert!(info["tables"].get("scratch").is_none());
+    assert!(info["tables"].get("user").is_some());
+    assert!(info["functions"].get("greet").is_some());
+
+    // History was rebuilt from scratch.
+    assert_eq!(runner.runs().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_plan_includes_ordered_rollback() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let mut runner = MigrationRunner::new(
+        &db,
+        InMemorySource::new().with_migration("001_a", "DEFINE TABLE a;", Some("REMOVE TABLE a;")),
+    );
+    runner.up().await.unwrap();
+    runner
+        .source
+        .add("002_b", "DEFINE TABLE b;", Some("REMOVE TABLE b;"));
+    runner
+        .source
+        .add("003_c", "DEFINE TABLE c;", Some("REMOVE TABLE c;"));
+    runner.source.add("R_views", "DEFINE TABLE v;", None);
+
+    let plan = runner.plan().await.unwrap();
+    let up: Vec<_> = plan.up.iter().map(|m| m.name.as_str()).collect();
+    let rollback: Vec<_> = plan.rollback.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(up, ["002_b", "003_c", "R_views"]);
+    assert_eq!(rollback, ["003_c", "002_b"]);
+    assert!(plan.is_reversible());
+    assert!(plan.stats().is_schema_only());
+    assert_eq!(plan.stats().to_string(), "3 define");
+
+    let dir = tempfile::tempdir().unwrap();
+    plan.write(dir.path()).unwrap();
+    let script = std::fs::read_to_string(dir.path().join("up.surql")).unwrap();
+    assert!(script.contains("-- migration: 002_b\n-- statements: 1 define\n"));
+    let script = std::fs::read_to_string(dir.path().join("rollback.surql")).unwrap();
+    assert!(script.find("REMOVE TABLE c;").unwrap() < script.find("REMOVE TABLE b;").unwrap());
+
+    // Planning executes nothing; a migration without a down script makes
+    // the rollback incomplete.
+    runner.source.add("004_d", "DEFINE TABLE d;", None);
+    let plan = runner.plan().await.unwrap();
+    assert_eq!(plan.irreversible, ["004_d"]);
+    assert!(plan.write(dir.path().join("next")).is_err());
+    assert_eq!(plan.fingerprint.len(), 64);
+}
+
+#[tokio::test]
+async fn test_mark_applied_baselines_without_executing() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_init", "THROW 'must not run';", None)
+        .with_migration("002_users", "DEFINE TABLE users;", None);
+    let runner = MigrationRunner::new(&db, source);
+
+    assert!(runner.mark_applied(&["003_missing"]).await.is_err());
+    runner.mark_applied(&["001_init"]).await.unwrap();
+    runner.mark_applied(&["001_init"]).await.unwrap();
+    runner.up().await.unwrap();
+
+    let statuses = runner.status().await.unwrap();
+    assert!(
+        statuses
+            .iter()
+            .all(|s| s.applied && s.checksum_matches() == Some(true))
+    );
+    assert!(statuses.iter().all(|s| s.applied_at.is_some()));
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(result.len(), 2);
+}
+
+#[tokio::test]
+async fn test_rename_record_keeps_renamed_migrations_applied() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let before = InMemorySource::new()
+        .with_migration("001_init", "DEFINE TABLE init;", None)
+        .with_migration("002_users", "DEFINE TABLE users;", None);
+    let runner = MigrationRunner::new(&db, before);
+    runner.up().await.unwrap();
+    assert!(runner.rename_record("002_users", "001_init").await.is_err());
+    assert!(
+        !runner
+            .rename_record("003_missing", "003_other")
+            .await
+            .unwrap()
+    );
+
+    let after = InMemorySource::new()
+        .with_migration("001_init", "DEFINE TABLE init;", None)
+        .with_migration("002_accounts", "THROW 'must not run again';", None);
+    let runner = MigrationRunner::new(&db, after);
+    assert!(
+        runner
+            .rename_record("002_users", "002_accounts")
+            .await
+            .unwrap()
+    );
+    runner.up().await.unwrap();
+
+    let statuses = runner.status().await.unwrap();
+    assert!(statuses.iter().all(|s| s.applied), "{statuses:?}");
+    assert_eq!(statuses[1].name, "002_accounts");
+}
+
+#[tokio::test]
+async fn test_rename_record_moves_tags_and_the_dirty_marker() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let before = InMemorySource::new()
+        .with_migration("001_init", "DEFINE TABLE init;", None)
+        .with_migration(
+            "002_users",
+            "DEFINE TABLE users;",
+            Some("THROW 'cannot revert';"),
+        );
+    let runner = MigrationRunner::new(&db, before);
+    runner.up().await.unwrap();
+    runner.tag("v1").await.unwrap();
+    let err = runner.down_to("001_init").await.unwrap_err();
+    assert!(err.to_string().contains("cannot revert"), "{err}");
+    assert_eq!(
+        runner.dirty().await.unwrap().unwrap().migration,
+        "002_users"
+    );
+
+    assert!(
+        runner
+            .rename_record("002_users", "002_accounts")
+            .await
+            .unwrap()
+    );
+    let tags = runner.tags().await.unwrap();
+    assert_eq!(tags[0].migration, "002_accounts");
+    assert_eq!(
+        runner.dirty().await.unwrap().unwrap().migration,
+        "002_accounts"
+    );
+
+    let after = InMemorySource::new()
+        .with_migration("001_init", "DEFINE TABLE init;", None)
+        .with_migration("002_accounts", "DEFINE TABLE users;", None);
+    let runner = MigrationRunner::new(&db, after);
+    assert!(runner.status_since_tag("v1").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_baseline_records_legacy_migrations() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    db.query("DEFINE TABLE legacy_table;").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_legacy", "THROW 'already in the schema';", None)
+        .with_migration("002_legacy", "THROW 'already in the schema';", None)
+        .with_migration("003_new", "DEFINE TABLE fresh_table;", None);
+    let runner = MigrationRunner::new(&db, source).with_baseline("002_legacy");
+
+    let plan = runner.plan().await.unwrap();
+    assert_eq!(plan.up.len(), 1);
+    assert_eq!(plan.up[0].name, "003_new");
+
+    runner.up().await.unwrap();
+    let baseline: Vec<_> = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| (s.name, s.applied, s.baseline))
+        .collect();
+    assert_eq!(
+        baseline,
+        [
+            ("001_legacy".to_string(), true, true),
+            ("002_legacy".to_string(), true, true),
+            ("003_new".to_string(), true, false),
+        ]
+    );
+
+    let runner = runner.with_baseline("999_missing");
+    assert!(runner.up().await.is_err());
+}
+
+#[tokio::test]
+async fn test_baseline_only_adopts_existing_databases() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_legacy", "DEFINE TABLE legacy_table;", None)
+        .with_migration("002_new", "DEFINE TABLE new_table;", None);
+    let runner = MigrationRunner::new(&db, source)
+        .with_baseline("001_legacy")
+        .with_fresh_allowed(true);
+    let executed = async || {
+        runner
+            .status()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.applied, s.baseline))
+            .collect::<Vec<_>>()
+    };
+    let all_run = [
+        ("001_legacy".to_string(), true, false),
+        ("002_new".to_string(), true, false),
+    ];
+
+    // A new database has no schema to adopt, so the legacy migration runs.
+    runner.up().await.unwrap();
+    assert_eq!(executed().await, all_run);
+
+    // So does a wiped one.
+    runner.fresh().await.unwrap();
+    assert_eq!(executed().await, all_run);
+    let tables: Option<serde_json::Value> = db
+        .query("INFO FOR DB")
+        .await
+        .unwrap()
+        .take("tables")
+        .unwrap();
+    assert!(tables.unwrap().get("legacy_table").is_some());
+}
+
+#[tokio::test]
+async fn test_drift_resolver_repairs_and_aborts() {
+    use surreal_migraine::drift::{DriftKind, Resolution};
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let original = InMemorySource::new()
+        .with_migration("001_a", "DEFINE TABLE a;", None)
+        .with_migration("002_gone", "DEFINE TABLE gone;", None);
+    MigrationRunner::new(&db, original).up().await.unwrap();
+
+    let edited = InMemorySource::new().with_migration("001_a", "DEFINE TABLE a SCHEMAFULL;", None);
+
+    let err = MigrationRunner::new(&db, edited.clone())
+        .with_drift_resolver(|_: &_| Resolution::Abort)
+        .up()
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("aborted on drift"), "{err}");
+
+    let runner = MigrationRunner::new(&db, edited)
+        .with_checksum_verification(true)
+        .with_drift_resolver(|drift: &surreal_migraine::drift::Drift| {
+            match &drift.kind {
+                DriftKind::ChecksumMismatch { diff } => assert!(diff.is_some()),
+                DriftKind::MissingSource => assert_eq!(drift.name, "002_gone"),
+            }
+            Resolution::Repair
+        });
+    runner.up().await.unwrap();
+
+    assert!(runner.verify().await.unwrap().is_empty());
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(result.len(), 1);
+}
+
+#[tokio::test]
+async fn test_variables_are_substituted_before_execution() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new().with_migration(
+        "001_params",
+        "DEFINE PARAM $bucket VALUE '{{bucket}}';",
+        None,
+    );
+    let runner = MigrationRunner::new(&db, source).with_variables([("bucket", "assets-staging")]);
+    runner.up().await.unwrap();
+
+    let bucket: Option<String> = db.query("RETURN $bucket").await.unwrap().take(0).unwrap();
+    assert_eq!(bucket.as_deref(), Some("assets-staging"));
+    let status = runner.status().await.unwrap();
+    assert_eq!(status[0].checksum_matches(), Some(true));
+}
+
+#[tokio::test]
+async fn test_hasher_is_recorded_per_migration() {
+    use surreal_migraine::checksum::Sha512Hasher;
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let mut runner = MigrationRunner::new(
+        &db,
+        InMemorySource::new().with_migration("001_a", "DEFINE TABLE a;", None),
+    );
+    runner.up().await.unwrap();
+
+    let mut runner = MigrationRunner::new(&db, std::mem::take(&mut runner.source))
+        .with_hasher(Sha512Hasher)
+        .with_checksum_verification(true);
+    runner.source.add("002_b", "DEFINE TABLE b;", None);
+    runner.up().await.unwrap();
+
+    let records: Vec<MigrationRecord> = db
+        .query("SELECT * FROM migrations ORDER BY name")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    let algorithms: Vec<_> = records.iter().map(|r| r.algorithm.as_deref()).collect();
+    assert_eq!(algorithms, [Some("sha256"), Some("sha512")]);
+
+    assert!(runner.verify().await.unwrap().is_empty());
+    let status = runner.status().await.unwrap();
+    assert!(status.iter().all(|s| s.checksum_matches() == Some(true)));
+}
+
+#[tokio::test]
+async fn test_environment_skips_migrations_for_other_environments() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_schema", "DEFINE TABLE user;", None)
+        .with_migration("002_seed", "-- env: dev, test\nCREATE user:dev;", None);
+    let runner = MigrationRunner::new(&db, source).with_environment("prod");
+    runner.up().await.unwrap();
+
+    let names: Vec<_> = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(names, ["001_schema"]);
+    let users: Vec<surrealdb::RecordId> = db
+        .query("SELECT VALUE id FROM user")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert!(users.is_empty());
+
+    let runner = runner.with_environment("dev");
+    runner.up().await.unwrap();
+    assert_eq!(runner.status().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_seeds_rerun_without_being_recorded() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let runner = MigrationRunner::new(
+        &db,
+        InMemorySource::new().with_migration("001_counter", "DEFINE TABLE counter;", None),
+    );
+    runner.up().await.unwrap();
+
+    let seeds = InMemorySource::new().with_migration(
+        "001_bump",
+        "UPSERT counter:main SET hits += 1;",
+        None,
+    );
+    runner.seed(&seeds).await.unwrap();
+    runner.seed(&seeds).await.unwrap();
+
+    let hits: Option<i64> = db
+        .query("RETURN counter:main.hits")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(hits, Some(2));
+
+    let result: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(result.len(), 1);
+    let runs = runner.runs().await.unwrap();
+    assert_eq!(runs.last().unwrap().direction, RunDirection::Seed);
+}
+
+#[tokio::test]
+async fn test_seed_scale_multiplies_generated_data() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let seeds = InMemorySource::new().with_migration(
+        "001_users",
+        "FOR $i IN 1..=({{ seed.scale }} * 3) { CREATE user; };",
+        None,
+    );
+    let count = || async {
+        db.query("RETURN count(SELECT * FROM user)")
+            .await
+            .unwrap()
+            .take::<Option<i64>>(0)
+            .unwrap()
+    };
+
+    let runner = MigrationRunner::new(&db, InMemorySource::new());
+    runner.seed(&seeds).await.unwrap();
+    assert_eq!(count().await, Some(3));
+
+    db.query("DELETE user").await.unwrap();
+    let runner = MigrationRunner::new(&db, InMemorySource::new()).with_seed_scale(10);
+    runner.seed(&seeds).await.unwrap();
+    assert_eq!(count().await, Some(30));
+
+    // A variable of the same name wins over the scale.
+    db.query("DELETE user").await.unwrap();
+    let runner = MigrationRunner::new(&db, InMemorySource::new())
+        .with_seed_scale(10)
+        .with_variables([("seed.scale", "2")]);
+    runner.seed(&seeds).await.unwrap();
+    assert_eq!(count().await, Some(6));
+
+    // Schema migrations do not see the scale.
+    let source = InMemorySource::new().with_migration(
+        "001_users",
+        "FOR $i IN 1..={{ seed.scale }} { CREATE {{ table }}; };",
+        None,
+    );
+    let runner = MigrationRunner::new(&db, source)
+        .with_seed_scale(10)
+        .with_variables([("table", "user")]);
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(
+        err.contains("no value for placeholders: seed.scale"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn test_cleanup_on_failure_runs_down_script() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new().with_migration(
+        "001_partial",
+        "-- no-transaction\nDEFINE TABLE partial;\nTHROW 'boom';",
+        Some("REMOVE TABLE IF EXISTS partial;"),
+    );
+    let runner = MigrationRunner::new(&db, source).with_cleanup_on_failure(true);
+
+    let err = runner.up().await.unwrap_err();
+    assert!(format!("{err:#}").contains("cleaned up"), "{err:#}");
+
+    let info: Option<serde_json::Value> = db.query("INFO FOR DB").await.unwrap().take(0).unwrap();
+    assert!(info.unwrap()["tables"].get("partial").is_none());
+    assert!(runner.dirty().await.unwrap().is_none());
+
+    let runs = runner.runs().await.unwrap();
+    let statuses: Vec<_> = runs[0].outcomes.iter().map(|o| o.status).collect();
+    assert_eq!(statuses, [OutcomeStatus::Failed, OutcomeStatus::Reverted]);
+}
+
+/// `W<week>-<name>`, weeks compared numerically.
+struct WeekOrdering;
+
+impl surreal_migraine::ordering::Ordering for WeekOrdering {
+    fn version<'n>(&self, name: &'n str) -> Option<&'n str> {
+        name.split_once('-').map(|(week, _)| week)
+    }
+
+    fn compare(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        let week = |v: &str| v.strip_prefix('W')?.parse::<u32>().ok();
+        week(a).cmp(&week(b))
+    }
+}
+
+#[tokio::test]
+async fn test_custom_ordering_drives_up_and_status() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    // Listed by name, W10 would run before the table it indexes exists.
+    let source = InMemorySource::new()
+        .with_migration(
+            "W9-users",
+            "DEFINE TABLE user SCHEMAFULL; DEFINE FIELD name ON user TYPE string;",
+            None,
+        )
+        .with_migration("W10-index", "DEFINE INDEX name ON user FIELDS name;", None)
+        .with_migration("R_views", "DEFINE TABLE seen;", None);
+    let runner = MigrationRunner::new(&db, source).with_ordering(WeekOrdering);
+
+    let names = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["W9-users", "W10-index", "R_views"]);
+
+    runner.up().await.unwrap();
+    let runs = runner.runs().await.unwrap();
+    let applied = runs[0]
+        .outcomes
+        .iter()
+        .map(|o| o.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(applied, ["W9-users", "W10-index", "R_views"]);
+}
+
+#[tokio::test]
+async fn test_cancel_token_stops_a_running_migration() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_init", "DEFINE TABLE user;", None)
+        .with_migration("002_backfill", "SLEEP 10s;", None)
+        .with_migration("003_after", "DEFINE TABLE post;", None);
+    let token = surreal_migraine::types::CancelToken::new();
+    let runner = MigrationRunner::new(&db, source).with_cancel_token(token.clone());
+
+    let cancel = async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        token.cancel();
+    };
+    let started = std::time::Instant::now();
+    let (result, ()) = tokio::join!(runner.up(), cancel);
+
+    let err = result.unwrap_err().to_string();
+    assert!(err.contains("002_backfill: cancelled"), "{err}");
+    assert!(started.elapsed() < Duration::from_secs(5));
+
+    let statuses = runner.status().await.unwrap();
+    let applied = statuses.iter().map(|s| s.applied).collect::<Vec<_>>();
+    assert_eq!(applied, [true, false, false]);
+    assert_eq!(
+        runner.dirty().await.unwrap().unwrap().migration,
+        "002_backfill"
+    );
+}
+
+#[tokio::test]
+async fn test_db_param_placeholders_resolve_from_the_database() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    db.query("DEFINE PARAM $tier VALUE 'gold'; DEFINE PARAM $seats VALUE 25;")
+        .await
+        .unwrap();
+
+    let source = InMemorySource::new().with_migration(
+        "001_plan",
+        "CREATE plan:current SET tier = '{{ db.param.tier }}', seats = {{db.param.seats}};",
+        None,
+    );
+    MigrationRunner::new(&db, source).up().await.unwrap();
+
+    let tier: Option<String> = db
+        .query("RETURN plan:current.tier")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(tier.as_deref(), Some("gold"));
+    let seats: Option<i64> = db
+        .query("RETURN plan:current.seats")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(seats, Some(25));
+
+    // Params are resolved before anything runs.
+    let source = InMemorySource::new()
+        .with_migration("002_users", "DEFINE TABLE user;", None)
+        .with_migration(
+            "003_region",
+            "CREATE r SET v = '{{ db.param.region }}';",
+            None,
+        );
+    let runner = MigrationRunner::new(&db, source);
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(err.contains("the database has no param $region"), "{err}");
+    assert!(runner.status().await.unwrap().iter().all(|s| !s.applied));
+}
+
+#[tokio::test]
+async fn test_custom_migrations_table() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    // The application owns a `migrations` table of its own.
+    db.query("DEFINE TABLE migrations SCHEMAFULL; DEFINE FIELD route ON migrations TYPE string;")
+        .await
+        .unwrap();
+
+    let source = EmbeddedSource::new(&TEST_MIGRATIONS);
+    let runner = MigrationRunner::new(&db, source).with_table("schema_history");
+    runner.up().await.unwrap();
+
+    let history: Vec<MigrationRecord> = db.select("schema_history").await.unwrap();
+    assert_eq!(history.len(), 2);
+    let own: Vec<serde_json::Value> = db.select("migrations").await.unwrap();
+    assert!(own.is_empty());
+    assert!(runner.status().await.unwrap().iter().all(|s| s.applied));
+
+    runner.down_to("000_init_schema.surql").await.unwrap();
+    let history: Vec<MigrationRecord> = db.select("schema_history").await.unwrap();
+    assert_eq!(history.len(), 1);
+
+    let source = EmbeddedSource::new(&TEST_MIGRATIONS);
+    let err = MigrationRunner::new(&db, source)
+        .with_table("history; REMOVE TABLE user")
+        .up()
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("invalid migrations table name"), "{err}");
+}
+
+#[tokio::test]
+async fn test_bookkeeping_in_separate_database() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("app").use_db("main").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_a", "DEFINE TABLE a;", Some("REMOVE TABLE a;"))
+        .with_migration("002_b", "DEFINE TABLE b;", Some("REMOVE TABLE b;"));
+    let runner = MigrationRunner::new(&db, source)
+        .with_bookkeeping("ops", "migrations_app")
+        .with_lock(LockOptions::default());
+    runner.up().await.unwrap();
+    runner.tag("v1").await.unwrap();
+    runner.down_to("001_a").await.unwrap();
+
+    // The application database only holds the migrated schema.
+    let info: Option<serde_json::Value> = db.query("INFO FOR DB").await.unwrap().take(0).unwrap();
+    let tables = info.unwrap()["tables"].clone();
+    assert_eq!(
+        tables.as_object().unwrap().keys().collect::<Vec<_>>(),
+        ["a"]
+    );
+
+    let statuses = runner.status().await.unwrap();
+    assert!(statuses[0].applied && !statuses[1].applied);
+    assert_eq!(runner.tags().await.unwrap()[0].migration, "002_b");
+    assert_eq!(runner.runs().await.unwrap().len(), 2);
+
+    db.use_ns("ops").use_db("migrations_app").await.unwrap();
+    let history: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].name, "001_a");
+}
+
+#[tokio::test]
+async fn test_squashed_migration_takes_over_applied_records() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let originals = InMemorySource::new()
+        .with_migration("001_a", "DEFINE TABLE a;", None)
+        .with_migration("002_b", "DEFINE TABLE b;", None)
+        .with_migration("003_c", "DEFINE TABLE c;", None);
+    MigrationRunner::new(&db, originals).up().await.unwrap();
+
+    let squashed = || {
+        InMemorySource::new()
+            .with_migration(
+                "002_squashed",
+                "-- replaces: 001_a, 002_b\nDEFINE TABLE a;\nDEFINE TABLE b;",
+                None,
+            )
+            .with_migration("003_c", "DEFINE TABLE c;", None)
+            .with_migration("004_d", "DEFINE TABLE d;", None)
+    };
+
+    // Already migrated: recorded in place of the originals, not run.
+    let runner = MigrationRunner::new(&db, squashed());
+    let plan = runner.plan().await.unwrap();
+    assert_eq!(plan.up.len(), 1, "{plan:?}");
+    runner.up().await.unwrap();
+    let statuses = runner.status().await.unwrap();
+    let seqs = statuses
+        .iter()
+        .map(|s| (s.name.as_str(), s.applied, s.applied_seq))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        seqs,
+        [
+            ("002_squashed", true, Some(2)),
+            ("003_c", true, Some(3)),
+            ("004_d", true, Some(4)),
+        ]
+    );
+    assert!(statuses[0].baseline);
+    assert!(runner.verify().await.unwrap().is_empty());
+
+    // A fresh database runs it.
+    let fresh = Surreal::new::<Mem>(()).await.unwrap();
+    fresh.use_ns("test").use_db("test").await.unwrap();
+    MigrationRunner::new(&fresh, squashed()).up().await.unwrap();
+    let info: Option<serde_json::Value> =
+        fresh.query("INFO FOR DB").await.unwrap().take(0).unwrap();
+    let tables = info.unwrap()["tables"].clone();
+    assert!(["a", "b", "c", "d"].iter().all(|t| tables.get(t).is_some()));
+
+    // A partly migrated one cannot take either path.
+    let partial = Surreal::new::<Mem>(()).await.unwrap();
+    partial.use_ns("test").use_db("test").await.unwrap();
+    let first = InMemorySource::new().with_migration("001_a", "DEFINE TABLE a;", None);
+    MigrationRunner::new(&partial, first).up().await.unwrap();
+    let err = MigrationRunner::new(&partial, squashed())
+        .up()
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("not applied: 002_b"), "{err}");
+}
+
+#[tokio::test]
+async fn test_down_reverts_in_applied_order() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_a", "DEFINE TABLE a;", Some("REMOVE TABLE a;"))
+        .with_migration("003_c", "DEFINE TABLE c;", Some("REMOVE TABLE c;"));
+    MigrationRunner::new(&db, source).up().await.unwrap();
+
+    // 002 lands after 003 was applied.
+    let source = InMemorySource::new()
+        .with_migration("001_a", "DEFINE TABLE a;", Some("REMOVE TABLE a;"))
+        .with_migration("002_b", "DEFINE TABLE b;", Some("REMOVE TABLE b;"))
+        .with_migration("003_c", "DEFINE TABLE c;", Some("REMOVE TABLE c;"));
+    let runner = MigrationRunner::new(&db, source).with_out_of_order(OutOfOrderPolicy::Allow);
+    runner.up().await.unwrap();
+
+    let seqs = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| (s.name, s.applied_seq))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        seqs,
+        [
+            ("001_a".to_string(), Some(1)),
+            ("002_b".to_string(), Some(3)),
+            ("003_c".to_string(), Some(2)),
+        ]
+    );
+
+    runner.down_to("001_a").await.unwrap();
+    let reverted = runner
+        .runs()
+        .await
+        .unwrap()
+        .pop()
+        .unwrap()
+        .outcomes
+        .into_iter()
+        .map(|o| o.name)
+        .collect::<Vec<_>>();
+    assert_eq!(reverted, ["002_b", "003_c"]);
+}
+
+#[tokio::test]
+async fn test_ensure_tenant_migrates_each_tenant_once() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("app").use_db("main").await.unwrap();
+    let source = InMemorySource::new().with_migration("001_users", "DEFINE TABLE user;", None);
+    let runner = MigrationRunner::new(&db, source);
+
+    let (a, b) = tokio::join!(
+        runner.ensure_tenant("tenants", "acme"),
+        runner.ensure_tenant("tenants", "acme")
+    );
+    a.unwrap();
+    b.unwrap();
+    runner.ensure_tenant("tenants", "globex").await.unwrap();
+    runner.ensure_tenant("tenants", "acme").await.unwrap();
+
+    // Each tenant was migrated by one run, in its own database.
+    for tenant in ["acme", "globex"] {
+        let mut response = db
+            .query(format!(
+                "USE NS tenants DB {tenant}; RETURN count(SELECT * FROM migration_runs); INFO FOR DB;"
+            ))
+            .await
+            .unwrap();
+        let runs: Option<i64> = response.take(1).unwrap();
+        assert_eq!(runs, Some(1), "{tenant}");
+        let tables: Option<serde_json::Value> = response.take((2, "tables")).unwrap();
+        assert!(tables.unwrap().get("user").is_some(), "{tenant}");
+    }
+
+    // The client's session and database were left alone.
+    let session: Option<String> = db
+        .query("RETURN session::db()")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(session.as_deref(), Some("main"));
+    let tables: Option<serde_json::Value> = db
+        .query("INFO FOR DB")
+        .await
+        .unwrap()
+        .take("tables")
+        .unwrap();
+    assert_eq!(tables.unwrap(), serde_json::json!({}));
+}
+
+#[tokio::test]
+async fn test_retry_policy_retries_only_transient_errors() {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use surreal_migraine::retry::RetryPolicy;
+
+    let policy = RetryPolicy::new(3).with_initial_backoff(Duration::from_millis(1));
+    let tries = AtomicU32::new(0);
+    let value = policy
+        .run("query", || async {
+            match tries.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err(eyre::eyre!("IO error: Connection reset by peer")),
+                _ => Ok(42),
+            }
+        })
+        .await
+        .unwrap();
+    assert_eq!((value, tries.load(Ordering::SeqCst)), (42, 3));
+
+    let tries = AtomicU32::new(0);
+    let err = policy
+        .run("query", || async {
+            tries.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(eyre::eyre!("Connection refused (os error 111)"))
+        })
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("refused"), "{err}");
+    assert_eq!(tries.load(Ordering::SeqCst), 3);
+
+    let tries = AtomicU32::new(0);
+    let err = policy
+        .run("query", || async {
+            tries.fetch_add(1, Ordering::SeqCst);
+            Err::<(), _>(eyre::eyre!("Parse error: unexpected token"))
+        })
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Parse error"), "{err}");
+    assert_eq!(tries.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_retry_runs_idempotent_migrations() {
+    use surreal_migraine::retry::RetryPolicy;
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new().with_migration(
+        "001_users",
+        "-- idempotent\nDEFINE TABLE IF NOT EXISTS user;",
+        None,
+    );
+    let runner = MigrationRunner::new(&db, source).with_retry(RetryPolicy::new(3));
+    assert!(runner.dirty().await.unwrap().is_none());
+    assert_eq!(runner.up().await.unwrap().applied().count(), 1);
+    assert!(runner.status().await.unwrap().iter().all(|s| s.applied));
+}
+
+#[tokio::test]
+async fn test_batches_group_migrations_applied_together() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let first = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE post;",
+            Some("REMOVE TABLE post;"),
+        );
+    assert_eq!(
+        MigrationRunner::new(&db, first.clone())
+            .up()
+            .await
+            .unwrap()
+            .batch,
+        Some(1)
+    );
+
+    let second = first
+        .with_migration("003_tags", "DEFINE TABLE tag;", Some("REMOVE TABLE tag;"))
+        .with_migration(
+            "004_likes",
+            "DEFINE TABLE like;",
+            Some("REMOVE TABLE like;"),
+        );
+    let runner = MigrationRunner::new(&db, second);
+    let report = runner.up().await.unwrap();
+    assert_eq!(report.batch, Some(2));
+    assert_eq!(runner.up().await.unwrap().batch, None);
+
+    let batches = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| (s.name, s.batch))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        batches,
+        [
+            ("001_users".to_string(), Some(1)),
+            ("002_posts".to_string(), Some(1)),
+            ("003_tags".to_string(), Some(2)),
+            ("004_likes".to_string(), Some(2)),
+        ]
+    );
+    let runs = runner.runs().await.unwrap();
+    assert_eq!(
+        runs.iter().map(|r| r.batch).collect::<Vec<_>>(),
+        [Some(1), Some(2), None]
+    );
+
+    let report = runner.down_to("003_tags").await.unwrap();
+    assert_eq!(report.batch, None);
+    assert_eq!(runner.up().await.unwrap().batch, Some(3));
+    let status = runner.status().await.unwrap();
+    assert_eq!(status[3].batch, Some(3));
+}
+
+#[tokio::test]
+async fn test_down_last_batch_reverts_the_last_run() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let first = InMemorySource::new().with_migration(
+        "001_users",
+        "DEFINE TABLE user;",
+        Some("REMOVE TABLE user;"),
+    );
+    let runner = MigrationRunner::new(&db, first.clone());
+    assert!(runner.down_last_batch().await.unwrap().is_empty());
+    runner.up().await.unwrap();
+
+    let second = first
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE post;",
+            Some("REMOVE TABLE post;"),
+        )
+        .with_migration("003_tags", "DEFINE TABLE tag;", Some("REMOVE TABLE tag;"));
+    let runner = MigrationRunner::new(&db, second);
+    runner.up().await.unwrap();
+
+    let report = runner.down_last_batch().await.unwrap();
+    assert_eq!(
+        report.reverted().collect::<Vec<_>>(),
+        ["003_tags", "002_posts"]
+    );
+    let applied = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|s| s.applied)
+        .map(|s| s.name)
+        .collect::<Vec<_>>();
+    assert_eq!(applied, ["001_users"]);
+
+    let report = runner.down_last_batch().await.unwrap();
+    assert_eq!(report.reverted().collect::<Vec<_>>(), ["001_users"]);
+}
+
+#[tokio::test]
+async fn test_down_last_batch_skips_a_reapplied_repeatable() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration("R_views", "DEFINE TABLE active_users;", None);
+    let mut runner = MigrationRunner::new(&db, source);
+    runner.up().await.unwrap();
+    runner.source.add(
+        "002_posts",
+        "DEFINE TABLE post;",
+        Some("REMOVE TABLE post;"),
+    );
+    runner.up().await.unwrap();
+
+    // The last run only re-applies the repeatable.
+    runner
+        .source
+        .add("R_views", "DEFINE TABLE active_users_v2;", None);
+    let report = runner.up().await.unwrap();
+    assert_eq!(report.applied().collect::<Vec<_>>(), ["R_views"]);
+
+    let report = runner.down_last_batch().await.unwrap();
+    assert_eq!(report.reverted().collect::<Vec<_>>(), ["002_posts"]);
+}
+
+#[tokio::test]
+async fn test_up_for_tenants_reports_each_tenant() {
+    use surreal_migraine::types::{Tenant, TenantFailurePolicy};
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("tenants").use_db("globex").await.unwrap();
+    db.query("DEFINE TABLE user SCHEMAFULL").await.unwrap();
+    let source = InMemorySource::new().with_migration("001_users", "DEFINE TABLE user;", None);
+    let runner = MigrationRunner::new(&db, source);
+    let tenants = || ["acme", "globex", "initech"].map(|db| Tenant::new("tenants", db));
+
+    let outcomes = runner
+        .up_for_tenants(tenants(), TenantFailurePolicy::FailFast)
+        .await
+        .unwrap();
+    let attempted = outcomes
+        .iter()
+        .map(|o| (o.tenant.database.as_str(), o.is_success()))
+        .collect::<Vec<_>>();
+    assert_eq!(attempted, [("acme", true), ("globex", false)]);
+    assert_eq!(outcomes[0].report.as_ref().unwrap().applied().count(), 1);
+    let error = outcomes[1].error.as_deref().unwrap();
+    assert!(error.contains("already exists"), "{error}");
+
+    let outcomes = runner
+        .up_for_tenants(tenants(), TenantFailurePolicy::Continue)
+        .await
+        .unwrap();
+    let attempted = outcomes
+        .iter()
+        .map(|o| (o.tenant.to_string(), o.is_success()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        attempted,
+        [
+            ("tenants/acme".to_string(), true),
+            ("tenants/globex".to_string(), false),
+            ("tenants/initech".to_string(), true),
+        ]
+    );
+    assert!(outcomes[0].report.as_ref().unwrap().is_empty());
+
+    // The client's session stayed where it was.
+    let session: Option<String> = db
+        .query("RETURN session::db()")
+        .await
+        .unwrap()
+        .take(0)
+        .unwrap();
+    assert_eq!(session.as_deref(), Some("globex"));
+
+    let runner = runner.with_bookkeeping("ops", "migrations");
+    let err = runner
+        .up_for_tenants(tenants(), TenantFailurePolicy::Continue)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("with_bookkeeping()"), "{err}");
+}
+
+#[tokio::test]
+async fn test_dropped_tenant_run_leaves_the_runner_on_the_client_database() {
+    use surreal_migraine::types::{Tenant, TenantFailurePolicy};
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("app").use_db("main").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user;", None)
+        .with_migration("002_slow", "SLEEP 500ms;", None);
+    let runner = MigrationRunner::new(&db, source);
+
+    let tenants = [Tenant::new("tenants", "acme")];
+    let dropped = tokio::time::timeout(
+        Duration::from_millis(100),
+        runner.up_for_tenants(tenants, TenantFailurePolicy::FailFast),
+    )
+    .await;
+    assert!(dropped.is_err());
+
+    runner.up().await.unwrap();
+    let applied: Vec<MigrationRecord> = db.select("migrations").await.unwrap();
+    assert_eq!(applied.len(), 2);
+    runner.ensure_tenant("tenants", "globex").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_discover_tenants_applies_the_filter() {
+    use surreal_migraine::types::{Tenant, TenantFailurePolicy, TenantFilter};
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.query(
+        "DEFINE NAMESPACE tenants; DEFINE NAMESPACE ops;
+        USE NS tenants; DEFINE DATABASE acme; DEFINE DATABASE globex; DEFINE DATABASE demo_one;
+        USE NS ops; DEFINE DATABASE main;",
+    )
+    .await
+    .unwrap()
+    .check()
+    .unwrap();
+    let source = InMemorySource::new().with_migration("001_users", "DEFINE TABLE user;", None);
+    let runner = MigrationRunner::new(&db, source);
+
+    let everything = runner.discover_tenants(&TenantFilter::new()).await.unwrap();
+    assert_eq!(everything.len(), 4);
+
+    let filter = TenantFilter::new()
+        .with_include("tenants")
+        .with_exclude("*/demo_*");
+    let tenants = runner.discover_tenants(&filter).await.unwrap();
+    assert_eq!(
+        tenants,
+        [
+            Tenant::new("tenants", "acme"),
+            Tenant::new("tenants", "globex")
+        ]
+    );
+
+    let outcomes = runner
+        .up_for_tenants(tenants, TenantFailurePolicy::FailFast)
+        .await
+        .unwrap();
+    assert!(outcomes.iter().all(|o| o.is_success()));
+}
+
+#[tokio::test]
+async fn test_migrations_run_in_the_declared_namespace_and_database() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("app").use_db("main").await.unwrap();
+    for mode in [ExecutionMode::Script, ExecutionMode::PerStatement] {
+        let suffix = mode == ExecutionMode::Script;
+        let table = format!("log_{suffix}");
+        let source = InMemorySource::new()
+            .with_migration(
+                "001_ops",
+                format!("-- namespace: ops\n-- db: audit\nDEFINE TABLE {table}; CREATE {table}:1;"),
+                None,
+            )
+            .with_migration("002_users", format!("DEFINE TABLE user_{suffix};"), None);
+        let runner = MigrationRunner::new(&db, source)
+            .with_execution_mode(mode)
+            .with_table(format!("migrations_{suffix}"));
+        runner.up().await.unwrap();
+
+        // The script ran in ops/audit; the session and records stayed in app/main.
+        let logged: Option<i64> = db
+            .query(format!(
+                "USE NS ops DB audit; RETURN count(SELECT * FROM {table})"
+            ))
+            .await
+            .unwrap()
+            .take(1)
+            .unwrap();
+        assert_eq!(logged, Some(1), "{mode:?}");
+        let tables: Option<serde_json::Value> = db
+            .query("INFO FOR DB")
+            .await
+            .unwrap()
+            .take("tables")
+            .unwrap();
+        let tables = tables.unwrap();
+        assert!(tables.get(&table).is_none(), "{mode:?}");
+        assert!(tables.get(format!("user_{suffix}")).is_some(), "{mode:?}");
+        assert!(runner.status().await.unwrap().iter().all(|s| s.applied));
+    }
+}
+
+#[tokio::test]
+async fn test_down_reverts_in_the_declared_namespace_and_database() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("app").use_db("main").await.unwrap();
+    db.query("DEFINE TABLE log; CREATE log:1;").await.unwrap();
+    let source = InMemorySource::new().with_migration(
+        "001_ops",
+        "-- namespace: ops\n-- db: audit\nDEFINE TABLE log; CREATE log:1;",
+        Some("REMOVE TABLE log;"),
+    );
+    let runner = MigrationRunner::new(&db, source);
+    runner.up().await.unwrap();
+    runner.down().await.unwrap();
+
+    let tables = |sql: &'static str| {
+        let db = &db;
+        async move {
+            let mut response = db.query(sql).await.unwrap();
+            let tables: Option<serde_json::Value> = response.take((1, "tables")).unwrap();
+            tables.unwrap()
+        }
+    };
+    // The down script ran where the up script did, not in app/main.
+    let audit = tables("USE NS ops DB audit; INFO FOR DB;").await;
+    assert!(audit.get("log").is_none());
+    let main = tables("USE NS app DB main; INFO FOR DB;").await;
+    assert!(main.get("log").is_some());
+}
+
+#[tokio::test]
+async fn test_parallel_up_follows_dependencies() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration("001_users", "SLEEP 200ms; DEFINE TABLE user;", None)
+        .with_migration("002_posts", "SLEEP 200ms; DEFINE TABLE post;", None)
+        .with_migration("003_tags", "SLEEP 200ms; DEFINE TABLE tag;", None)
+        .with_migration(
+            "004_authors",
+            "-- depends: [001_users, 002_posts]\nDEFINE FIELD author ON post TYPE record<user>;",
+            None,
+        );
+    let events = std::sync::Mutex::new(Vec::new());
+    let runner = MigrationRunner::new(&db, source)
+        .with_parallelism(3)
+        .with_progress(|event: &ProgressEvent| match event {
+            ProgressEvent::MigrationStarted { name, .. } => {
+                events.lock().unwrap().push(format!("start {name}"))
+            }
+            ProgressEvent::MigrationFinished { name, .. } => {
+                events.lock().unwrap().push(format!("finish {name}"))
+            }
+            _ => {}
+        });
+
+    let report = runner.up().await.unwrap();
+    assert_eq!(report.applied().count(), 4);
+    let mut seqs = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|s| s.applied_seq.unwrap())
+        .collect::<Vec<_>>();
+    seqs.sort();
+    assert_eq!(seqs, [1, 2, 3, 4]);
+    drop(runner);
+
+    let events = events.into_inner().unwrap();
+    let at = |event: &str| events.iter().position(|e| e == event).unwrap();
+    // The independent migrations run together; the dependent one waits.
+    assert!(at("start 003_tags") < at("finish 001_users"), "{events:?}");
+    assert!(
+        at("start 004_authors") > at("finish 001_users"),
+        "{events:?}"
+    );
+    assert!(
+        at("start 004_authors") > at("finish 002_posts"),
+        "{events:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_parallel_up_stops_at_failures_and_cycles() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user;", None)
+        .with_migration("002_bad", "THROW 'boom';", None)
+        .with_migration(
+            "003_after_bad",
+            "-- depends: 002_bad\nDEFINE TABLE post;",
+            None,
+        );
+    let runner = MigrationRunner::new(&db, source).with_parallelism(4);
+    let err = runner.up().await.unwrap_err();
+    assert!(err.to_string().contains("boom"), "{err}");
+    let applied = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|s| s.applied)
+        .map(|s| s.name)
+        .collect::<Vec<_>>();
+    assert_eq!(applied, ["001_users"]);
+
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration("001_a", "-- depends: 002_b\nDEFINE TABLE a;", None)
+        .with_migration("002_b", "-- depends: 001_a\nDEFINE TABLE b;", None)
+        .with_migration("003_c", "DEFINE TABLE c;", None);
+    let runner = MigrationRunner::new(&db, source).with_parallelism(2);
+    let err = runner.up().await.unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("001_a -> 002_b -> 001_a form a dependency cycle"),
+        "{err}"
+    );
+    assert!(runner.status().await.unwrap().iter().all(|s| !s.applied));
+}
+
+#[tokio::test]
+async fn test_up_refuses_unsatisfied_dependencies() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new().with_migration("001_users", "DEFINE TABLE user;", None);
+    MigrationRunner::new(&db, source).up().await.unwrap();
+
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user;", None)
+        .with_migration(
+            "002_posts",
+            "-- depends-on: 001_users, 003_tags\nDEFINE TABLE post;",
+            None,
+        )
+        .with_migration("003_tags", "-- depends: 009_gone\nDEFINE TABLE tag;", None);
+    let runner = MigrationRunner::new(&db, source.clone());
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(
+        err.contains("002_posts depends on 003_tags, which comes after it"),
+        "{err}"
+    );
+    assert!(
+        err.contains("003_tags depends on 009_gone, which does not exist"),
+        "{err}"
+    );
+    assert_eq!(
+        runner
+            .status()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|s| s.applied)
+            .count(),
+        1
+    );
+
+    // Applying in parallel only needs the dependency to be planned.
+    let source = source.with_migration("009_gone", "DEFINE TABLE gone;", None);
+    let runner = MigrationRunner::new(&db, source).with_parallelism(2);
+    let report = runner.up().await.unwrap();
+    assert_eq!(report.outcomes.len(), 3);
+}
+
+#[tokio::test]
+async fn test_parallel_failure_marks_dirty_while_siblings_finish() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration("001_broken", "THROW 'boom';", None)
+        .with_migration("002_slow", "SLEEP 200ms;\nDEFINE TABLE slow;", None);
+    let runner = MigrationRunner::new(&db, source).with_parallelism(2);
+    let err = runner.up().await.unwrap_err();
+    assert!(err.to_string().contains("boom"), "{err}");
+
+    let state = runner.dirty().await.unwrap().expect("dirty marker");
+    assert_eq!(state.migration, "001_broken");
+    let applied = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|s| s.applied)
+        .map(|s| s.name)
+        .collect::<Vec<_>>();
+    assert_eq!(applied, ["002_slow"]);
+}
+
+/// The entries the hooks of `test_hooks_*` logged, in order.
+async fn hook_log(db: &Surreal<surrealdb::engine::local::Db>) -> Vec<String> {
+    db.query("RETURN log:hooks.entries ?? []")
+        .await
+        .unwrap()
+        .take::<Vec<String>>(0)
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_hooks_run_around_runs_and_migrations() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE post;",
+            Some("REMOVE TABLE post;"),
+        );
+    let log = "UPSERT log:hooks SET entries += string::concat($direction, ' ', $hook, ' ', $migration ?? $outcome ?? '-');";
+    let mut runner = MigrationRunner::new(&db, source);
+    for point in [
+        HookPoint::BeforeAll,
+        HookPoint::BeforeEach,
+        HookPoint::AfterEach,
+        HookPoint::AfterAll,
+    ] {
+        runner = runner.with_hook(point, Hook::Sql(log.into()));
+    }
+
+    runner.up().await.unwrap();
+    assert_eq!(
+        hook_log(&db).await,
+        [
+            "up before-all -",
+            "up before-each 001_users",
+            "up after-each 001_users",
+            "up before-each 002_posts",
+            "up after-each 002_posts",
+            "up after-all succeeded",
+        ]
+    );
+
+    // Runs without migrations to execute run no hooks.
+    db.query("DELETE log").await.unwrap();
+    runner.up().await.unwrap();
+    assert!(hook_log(&db).await.is_empty());
+
+    runner.down_to("001_users").await.unwrap();
+    assert_eq!(
+        hook_log(&db).await,
+        [
+            "down before-all -",
+            "down before-each 002_posts",
+            "down after-each 002_posts",
+            "down after-all succeeded",
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_failing_hooks_fail_the_run_without_dirtying_it() {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let out = dir.path().join("hooks.log");
+    let source = InMemorySource::new()
+        .with_migration("001_users", "DEFINE TABLE user;", None)
+        .with_migration("002_posts", "DEFINE TABLE post;", None);
+    let runner = MigrationRunner::new(&db, source)
+        .with_hook(
+            HookPoint::BeforeEach,
+            Hook::Command(r#"test "$SMG_MIGRATION" != 002_posts"#.into()),
+        )
+        .with_hook(
+            HookPoint::AfterAll,
+            Hook::Command(format!(
+                r#"echo "$SMG_HOOK $SMG_DIRECTION $SMG_OUTCOME" >> {}"#,
+                out.display()
+            )),
+        );
+
+    let err = runner.up().await.unwrap_err().to_string();
+    assert!(
+        err.contains("before-each hook for 002_posts failed"),
+        "{err}"
+    );
+    assert!(err.contains("exited with"), "{err}");
+    assert_eq!(
+        std::fs::read_to_string(&out).unwrap(),
+        "after-all up failed\n"
+    );
+    let applied = runner
+        .status()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|s| s.applied)
+        .map(|s| s.name)
+        .collect::<Vec<_>>();
+    assert_eq!(applied, ["001_users"]);
+    assert!(runner.dirty().await.unwrap().is_none());
+}
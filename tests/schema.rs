@@ -0,0 +1,180 @@
+use surreal_migraine::MigrationRunner;
+use surreal_migraine::schema::SchemaSnapshot;
+use surreal_migraine::types::InMemorySource;
+use surrealdb::Surreal;
+use surrealdb::engine::local::{Db, Mem};
+use tempfile::tempdir;
+
+const SCHEMA: [&str; 4] = [
+    "DEFINE TABLE user SCHEMAFULL;
+DEFINE FIELD email ON user TYPE string;
+DEFINE FIELD name ON user TYPE string;
+DEFINE INDEX unique_email ON user FIELDS email UNIQUE;",
+    "DEFINE EVENT welcome ON user WHEN $event = 'CREATE' THEN (CREATE mail SET to = $after.email);
+DEFINE TABLE mail SCHEMALESS;",
+    "DEFINE FUNCTION fn::greet($name: string) { RETURN 'Hi ' + $name; };
+DEFINE PARAM $limit VALUE 10;",
+    "DEFINE ANALYZER simple TOKENIZERS blank FILTERS lowercase;",
+];
+
+async fn migrated(order: &[usize]) -> Surreal<Db> {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    let mut source = InMemorySource::new();
+    for (version, index) in order.iter().enumerate() {
+        source = source.with_migration(format!("{:03}_part", version + 1), SCHEMA[*index], None);
+    }
+    MigrationRunner::new(&db, source).up().await.unwrap();
+    db
+}
+
+#[tokio::test]
+async fn snapshot_holds_the_schema_without_bookkeeping() {
+    let db = migrated(&[0, 1, 2, 3]).await;
+    let snapshot = MigrationRunner::new(&db, InMemorySource::new())
+        .schema_snapshot()
+        .await
+        .unwrap();
+
+    assert_eq!(snapshot.tables.keys().collect::<Vec<_>>(), ["mail", "user"]);
+    let user = &snapshot.tables["user"];
+    assert!(user.definition.starts_with("DEFINE TABLE user"), "{user:?}");
+    assert_eq!(user.fields.keys().collect::<Vec<_>>(), ["email", "name"]);
+    assert!(user.indexes["unique_email"].contains("UNIQUE"));
+    assert!(user.events.contains_key("welcome"));
+    assert!(snapshot.functions.contains_key("greet"));
+    assert!(snapshot.params.contains_key("limit"));
+    assert!(snapshot.analyzers.contains_key("simple"));
+}
+
+#[tokio::test]
+async fn snapshot_is_canonical_and_round_trips() {
+    let first = SchemaSnapshot::capture(&migrated(&[0, 1, 2, 3]).await)
+        .await
+        .unwrap()
+        .without_tables(["migrations"]);
+    let second = SchemaSnapshot::capture(&migrated(&[3, 2, 0, 1]).await)
+        .await
+        .unwrap()
+        .without_tables(["migrations"]);
+    assert_eq!(
+        first.to_canonical().unwrap(),
+        second.to_canonical().unwrap()
+    );
+
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("schema.json");
+    first.write(&path).unwrap();
+    assert_eq!(SchemaSnapshot::read(&path).unwrap(), first);
+    assert!(SchemaSnapshot::parse("{\"tables\": 1}").is_err());
+}
+
+async fn schema_of(sql: &str) -> (Surreal<Db>, SchemaSnapshot) {
+    let db = Surreal::new::<Mem>(()).await.unwrap();
+    db.use_ns("test").use_db("test").await.unwrap();
+    db.query(sql).await.unwrap().check().unwrap();
+    let snapshot = SchemaSnapshot::capture(&db).await.unwrap();
+    (db, snapshot)
+}
+
+#[tokio::test]
+async fn migration_to_reconciles_two_schemas() {
+    let (db, current) = schema_of(
+        "DEFINE TABLE user SCHEMAFULL;
+DEFINE FIELD name ON user TYPE string;
+DEFINE FIELD nick ON user TYPE string;
+DEFINE TABLE legacy;
+DEFINE FUNCTION fn::old() { RETURN 1; };",
+    )
+    .await;
+    let (_, desired) = schema_of(
+        "DEFINE TABLE user SCHEMAFULL;
+DEFINE FIELD name ON user TYPE option<string>;
+DEFINE FIELD email ON user TYPE string;
+DEFINE INDEX unique_email ON user FIELDS email UNIQUE;
+DEFINE PARAM $limit VALUE 10;",
+    )
+    .await;
+
+    let changes = current
+        .changes_to(&desired)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            "added param limit",
+            "added field user.email",
+            "changed field user.name",
+            "added index user.unique_email",
+            "removed table legacy",
+            "removed field user.nick",
+            "removed function old",
+        ]
+    );
+
+    let up = current.migration_to(&desired);
+    assert!(
+        up.contains("DEFINE FIELD OVERWRITE name ON user TYPE option<string>"),
+        "{up}"
+    );
+    db.query(&up).await.unwrap().check().unwrap();
+    let migrated = SchemaSnapshot::capture(&db).await.unwrap();
+    assert_eq!(migrated, desired);
+
+    db.query(desired.migration_to(&current))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+    assert_eq!(SchemaSnapshot::capture(&db).await.unwrap(), current);
+    assert_eq!(current.migration_to(&current), "");
+}
+
+#[tokio::test]
+async fn verify_schema_reports_out_of_band_changes() {
+    let db = migrated(&[0, 1, 2, 3]).await;
+    let mut source = InMemorySource::new();
+    for (version, sql) in SCHEMA.iter().enumerate() {
+        source = source.with_migration(format!("{:03}_part", version + 1), *sql, None);
+    }
+    // Pending migrations are not part of the expected schema.
+    let source = source.with_migration("005_pending", "DEFINE TABLE later;", None);
+    let runner = MigrationRunner::new(&db, source);
+
+    let scratch = Surreal::new::<Mem>(()).await.unwrap();
+    scratch.use_ns("replay").use_db("replay").await.unwrap();
+    assert_eq!(runner.verify_schema(&scratch).await.unwrap(), []);
+
+    db.query(
+        "DEFINE FIELD OVERWRITE name ON user TYPE option<string>;
+DEFINE INDEX by_name ON user FIELDS name;
+REMOVE ANALYZER simple;",
+    )
+    .await
+    .unwrap()
+    .check()
+    .unwrap();
+
+    let scratch = Surreal::new::<Mem>(()).await.unwrap();
+    scratch.use_ns("replay").use_db("replay").await.unwrap();
+    let changes = runner
+        .verify_schema(&scratch)
+        .await
+        .unwrap()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        changes,
+        [
+            "changed field user.name",
+            "added index user.by_name",
+            "removed analyzer simple",
+        ]
+    );
+
+    // The scratch database now holds the replayed schema.
+    assert!(runner.verify_schema(&scratch).await.is_err());
+}
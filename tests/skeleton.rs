@@ -0,0 +1,45 @@
+use surreal_migraine::skeleton::down_skeleton;
+
+#[test]
+fn removes_defined_objects_in_reverse_order() {
+    let up = "-- migration: profiles
+DEFINE TABLE IF NOT EXISTS profile SCHEMAFULL;
+DEFINE FIELD address.city ON TABLE profile TYPE string;
+DEFINE FIELD bio ON user TYPE option<string>;
+DEFINE INDEX `by bio` ON user FIELDS bio;
+DEFINE EVENT audit ON user WHEN $event = 'UPDATE' THEN {
+    CREATE log SET at = time::now();
+};
+DEFINE FUNCTION fn::greet($name: string) { RETURN 'Hi ' + $name; };
+DEFINE PARAM $limit VALUE 10;
+DEFINE ANALYZER simple TOKENIZERS blank;";
+
+    assert_eq!(
+        down_skeleton(up),
+        "REMOVE ANALYZER simple;
+REMOVE PARAM $limit;
+REMOVE FUNCTION fn::greet;
+REMOVE EVENT audit ON user;
+REMOVE INDEX `by bio` ON user;
+REMOVE FIELD bio ON user;
+REMOVE TABLE profile;
+"
+    );
+}
+
+#[test]
+fn leaves_todos_for_what_it_cannot_reverse() {
+    let up = "DEFINE FIELD OVERWRITE name ON user TYPE string;
+UPDATE user SET name = string::trim(name)
+    WHERE name != NONE;
+DEFINE USER ops ON DATABASE PASSWORD 'x' ROLES EDITOR;";
+
+    assert_eq!(
+        down_skeleton(up),
+        "-- TODO: undo line 4: DEFINE USER ops ON DATABASE PASSWORD 'x' ROLES EDITOR
+-- TODO: undo line 2: UPDATE user SET name = string::trim(name)
+-- TODO: undo line 1: DEFINE FIELD OVERWRITE name ON user TYPE string
+"
+    );
+    assert_eq!(down_skeleton("-- nothing yet\n"), "");
+}
@@ -1,5 +1,9 @@
 use eyre::Result;
-use surreal_migraine::types::{DiskSource, MigrationKind, MigrationSource};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use surreal_migraine::types::{
+    CompositeSource, DiskSource, DomainSource, Migration, MigrationKind, MigrationSource,
+};
 use tempfile::tempdir;
 
 #[test]
@@ -47,3 +51,194 @@ fn disk_source_list_and_gets() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn composite_source_merges_and_overrides() -> Result<()> {
+    let core_dir = tempdir()?;
+    let override_dir = tempdir()?;
+
+    std::fs::write(core_dir.path().join("001_init.surql"), "CREATE TABLE core;")?;
+    std::fs::write(
+        core_dir.path().join("003_posts.surql"),
+        "CREATE TABLE post;",
+    )?;
+    std::fs::write(
+        override_dir.path().join("001_init.surql"),
+        "CREATE TABLE overridden;",
+    )?;
+    std::fs::write(
+        override_dir.path().join("002_local.surql"),
+        "CREATE TABLE local;",
+    )?;
+
+    let src = CompositeSource::new()
+        .with_source(DiskSource::new(core_dir.path()))
+        .with_source(DiskSource::new(override_dir.path()));
+
+    let list = src.list()?;
+    let names: Vec<_> = list.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["001_init.surql", "002_local.surql", "003_posts.surql"]
+    );
+
+    assert_eq!(src.get_up(&list[0])?, "CREATE TABLE overridden;");
+    assert_eq!(src.get_up(&list[2])?, "CREATE TABLE post;");
+
+    Ok(())
+}
+
+/// A source listing `names` in the given order and counting its listings.
+struct Listed {
+    names: Vec<&'static str>,
+    lists: Arc<AtomicUsize>,
+}
+
+impl MigrationSource for Listed {
+    fn list(&self) -> Result<Vec<Migration>> {
+        self.lists.fetch_add(1, Ordering::SeqCst);
+        Ok(self
+            .names
+            .iter()
+            .map(|name| Migration {
+                name: name.to_string(),
+                kind: MigrationKind::File,
+                metadata: Default::default(),
+            })
+            .collect())
+    }
+
+    fn get_up(&self, migration: &Migration) -> Result<String> {
+        Ok(format!("-- {}", migration.name))
+    }
+
+    fn get_down(&self, _: &Migration) -> Result<Option<String>> {
+        Ok(None)
+    }
+}
+
+#[test]
+fn composite_source_keeps_each_source_order_and_lists_once() -> Result<()> {
+    let lists = Arc::new(AtomicUsize::new(0));
+    let source = |names| Listed {
+        names,
+        lists: lists.clone(),
+    };
+    let src = CompositeSource::new()
+        .with_source(source(vec!["001_a", "003_c", "002_b"]))
+        .with_source(source(vec!["002_b", "004_d"]));
+
+    let list = src.list()?;
+    let names: Vec<_> = list.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["001_a", "002_b", "003_c", "004_d"]);
+
+    for m in &list {
+        assert_eq!(src.get_up(m)?, format!("-- {}", m.name));
+        assert_eq!(src.get_down(m)?, None);
+    }
+    assert_eq!(lists.load(Ordering::SeqCst), 2);
+
+    let src = CompositeSource::new().with_source(source(vec!["003_c", "001_a", "002_b"]));
+    let list = src.list()?;
+    let names: Vec<_> = list.iter().map(|m| m.name.as_str()).collect();
+    assert_eq!(names, ["003_c", "001_a", "002_b"]);
+
+    Ok(())
+}
+
+#[test]
+fn disk_source_lists_repeatable_migrations() -> Result<()> {
+    let tmpdir = tempdir()?;
+    std::fs::write(tmpdir.path().join("001_init.surql"), "DEFINE TABLE a;")?;
+    std::fs::write(tmpdir.path().join("R_views.surql"), "DEFINE TABLE v;")?;
+    std::fs::write(tmpdir.path().join("notes.surql"), "-- ignored")?;
+
+    let ds = DiskSource::new(tmpdir.path());
+    let list = ds.list()?;
+
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[1].name, "R_views.surql");
+    assert_eq!(list[1].kind, MigrationKind::Repeatable);
+    assert_eq!(ds.get_up(&list[1])?, "DEFINE TABLE v;");
+    assert_eq!(ds.get_down(&list[1])?, None);
+
+    Ok(())
+}
+
+#[test]
+fn domain_source_interleaves_by_prefix_or_manifest() -> Result<()> {
+    let tmpdir = tempdir()?;
+    let root = tmpdir.path();
+    std::fs::create_dir_all(root.join("billing/20240102_invoices"))?;
+    std::fs::write(
+        root.join("billing/20240102_invoices/up.surql"),
+        "DEFINE TABLE invoice;",
+    )?;
+    std::fs::write(
+        root.join("billing/20240102_invoices/down.surql"),
+        "REMOVE TABLE invoice;",
+    )?;
+    std::fs::create_dir_all(root.join("auth"))?;
+    std::fs::write(root.join("auth/20240101_users.surql"), "DEFINE TABLE user;")?;
+    std::fs::write(
+        root.join("auth/20240103_sessions.surql"),
+        "DEFINE TABLE session;",
+    )?;
+    std::fs::write(root.join("auth/R_views.surql"), "DEFINE TABLE v;")?;
+
+    let src = DomainSource::new(root);
+    let names: Vec<_> = src.list()?.into_iter().map(|m| m.name).collect();
+    assert_eq!(
+        names,
+        [
+            "auth/20240101_users.surql",
+            "billing/20240102_invoices",
+            "auth/20240103_sessions.surql",
+            "auth/R_views.surql",
+        ]
+    );
+
+    let invoices = &src.list()?[1];
+    assert_eq!(invoices.kind, MigrationKind::Paired);
+    assert_eq!(src.get_up(invoices)?, "DEFINE TABLE invoice;");
+    assert_eq!(
+        src.get_down(invoices)?.as_deref(),
+        Some("REMOVE TABLE invoice;")
+    );
+
+    std::fs::write(
+        root.join("order.txt"),
+        "# global order\nbilling/20240102_invoices\nauth/20240101_users.surql\n",
+    )?;
+    let src = DomainSource::new(root).with_manifest("order.txt");
+    let err = src.list().unwrap_err().to_string();
+    assert!(err.contains("missing from the manifest"), "{err}");
+
+    std::fs::write(
+        root.join("order.txt"),
+        "billing/20240102_invoices\nauth/20240101_users.surql\nauth/20240103_sessions.surql\nauth/R_views.surql\n",
+    )?;
+    let names: Vec<_> = src.list()?.into_iter().map(|m| m.name).collect();
+    assert_eq!(names[0], "billing/20240102_invoices");
+    Ok(())
+}
+
+#[test]
+fn disk_source_reports_ignored_entries() -> Result<()> {
+    let tmpdir = tempdir()?;
+    let tmp = tmpdir.path();
+    std::fs::write(tmp.join("001_init.surql"), "DEFINE TABLE user;")?;
+    std::fs::write(tmp.join("init.surql"), "DEFINE TABLE post;")?;
+    std::fs::write(tmp.join(".gitkeep"), "")?;
+    std::fs::create_dir_all(tmp.join("R_views"))?;
+
+    let ds = DiskSource::new(tmp);
+    assert_eq!(ds.list()?.len(), 1);
+
+    let ignored = ds.ignored()?;
+    let names = ignored.iter().map(|e| e.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["R_views", "init.surql"]);
+    assert!(ignored[0].reason.contains("directory"));
+    assert!(ignored[1].reason.contains("digit"));
+    Ok(())
+}
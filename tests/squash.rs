@@ -0,0 +1,81 @@
+use surreal_migraine::squash::squash;
+use surreal_migraine::types::{DiskSource, InMemorySource, MigrationSource};
+use tempfile::tempdir;
+
+fn source() -> InMemorySource {
+    InMemorySource::new()
+        .with_migration(
+            "001_a",
+            "-- author: ann\nDEFINE TABLE a;",
+            Some("REMOVE TABLE a;"),
+        )
+        .with_migration("002_b", "-- no-transaction\nDEFINE TABLE b;", None)
+        .with_migration("003_c", "DEFINE TABLE c;", Some("REMOVE TABLE c;"))
+        .with_migration("R__views", "DEFINE TABLE v AS SELECT * FROM a;", None)
+}
+
+#[test]
+fn combines_ups_in_order_and_records_what_was_replaced() {
+    let squashed = squash(&source(), "001_a"..="002_b", "base").unwrap();
+
+    assert_eq!(squashed.name, "002_base");
+    assert_eq!(
+        squashed.up,
+        "-- migration: 002_base\n-- replaces: 001_a, 002_b\n-- no-transaction\n\
+         \n-- 001_a\nDEFINE TABLE a;\n\n-- 002_b\nDEFINE TABLE b;\n"
+    );
+    // 002_b has no down script, so neither has the result.
+    assert_eq!(squashed.down, None);
+    assert_eq!(squashed.manifest.squashed, "002_base.surql");
+    let replaced = &squashed.manifest.replaces;
+    assert_eq!(
+        replaced
+            .iter()
+            .map(|r| (r.name.as_str(), r.has_down))
+            .collect::<Vec<_>>(),
+        [("001_a", true), ("002_b", false)]
+    );
+    assert_eq!(replaced[0].checksum.len(), 64);
+}
+
+#[test]
+fn reverses_downs_and_skips_repeatables() {
+    let src = InMemorySource::new()
+        .with_migration("001_a", "DEFINE TABLE a;", Some("REMOVE TABLE a;"))
+        .with_migration("R__views", "DEFINE TABLE v;", None)
+        .with_migration("002_b", "DEFINE TABLE b;", Some("REMOVE TABLE b;"));
+
+    let squashed = squash(&src, .., "all").unwrap();
+    assert!(!squashed.up.contains("TABLE v"));
+    assert_eq!(
+        squashed.down.as_deref(),
+        Some("\n-- 002_b\nREMOVE TABLE b;\n\n-- 001_a\nREMOVE TABLE a;\n")
+    );
+    assert_eq!(squashed.entry_name(), "002_all");
+}
+
+#[test]
+fn rejects_short_ranges_and_unknown_names() {
+    let err = squash(&source(), "002_b".."003_c", "x").unwrap_err();
+    assert!(err.to_string().contains("at least two migrations"), "{err}");
+    let err = squash(&source(), ..="009_z", "x").unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("no versioned migration named 009_z")
+    );
+    let err = squash(&source(), .., "../x").unwrap_err();
+    assert!(err.to_string().contains("invalid squashed migration name"));
+}
+
+#[test]
+fn written_migration_is_discovered_with_its_replaces_header() {
+    let dir = tempdir().unwrap();
+    let squashed = squash(&source(), .."003_c", "base").unwrap();
+    let path = squashed.write(dir.path()).unwrap();
+    assert!(path.ends_with("002_base.surql"));
+    assert!(squashed.write(dir.path()).is_err());
+
+    let found = DiskSource::new(dir.path()).list().unwrap();
+    assert_eq!(found[0].metadata.replaces, ["001_a", "002_b"]);
+    assert!(!found[0].metadata.transactional());
+}
@@ -0,0 +1,98 @@
+use surreal_migraine::statements::{StatementKind, StatementStats, split};
+
+#[test]
+fn splits_on_top_level_semicolons() {
+    let sql = "-- migration: init\n\
+               DEFINE TABLE user;\n\
+               DEFINE FIELD name ON user TYPE string; DEFINE FIELD age ON user TYPE int;\n";
+    let statements = split(sql);
+
+    let texts: Vec<_> = statements.iter().map(|s| s.text).collect();
+    assert_eq!(
+        texts,
+        [
+            "DEFINE TABLE user",
+            "DEFINE FIELD name ON user TYPE string",
+            "DEFINE FIELD age ON user TYPE int",
+        ]
+    );
+    let lines: Vec<_> = statements.iter().map(|s| s.line).collect();
+    assert_eq!(lines, [2, 3, 3]);
+}
+
+#[test]
+fn ignores_separators_in_strings_comments_and_blocks() {
+    let sql = "CREATE note SET body = 'a; b \\' c;';\n\
+               /* block; comment\n spanning lines */\n\
+               DEFINE FUNCTION fn::greet($name: string) {\n    RETURN \"Hello; \" + $name;\n};\n\
+               # hash comment;\n\
+               DEFINE EVENT ev ON user WHEN true THEN { CREATE log; }";
+    let statements = split(sql);
+
+    assert_eq!(statements.len(), 3);
+    assert_eq!(statements[0].text, "CREATE note SET body = 'a; b \\' c;'");
+    assert_eq!(statements[1].line, 4);
+    assert!(statements[1].text.ends_with('}'));
+    assert_eq!(statements[2].line, 8);
+    assert!(statements[2].text.starts_with("DEFINE EVENT"));
+}
+
+#[test]
+fn comment_only_script_has_no_statements() {
+    assert!(split("-- nothing here\n// or here\n;\n").is_empty());
+}
+
+#[test]
+fn reports_touched_tables() {
+    let sql = "DEFINE TABLE IF NOT EXISTS user SCHEMAFULL;\n\
+               DEFINE FIELD emails.* ON user TYPE string;\n\
+               DEFINE INDEX idx ON TABLE `post` COLUMNS title;\n\
+               REMOVE EVENT OVERWRITE ev ON comment;\n\
+               CREATE ONLY audit:one SET at = time::now();\n\
+               DELETE FROM session WHERE expired;\n\
+               INSERT INTO tag { name: 'x' };\n\
+               RELATE user:a->likes->post:b;\n\
+               SELECT * FROM secrets;";
+    let tables: Vec<Vec<String>> = split(sql).iter().map(|s| s.tables()).collect();
+
+    assert_eq!(
+        tables,
+        [
+            vec!["user"],
+            vec!["user"],
+            vec!["post"],
+            vec!["comment"],
+            vec!["audit"],
+            vec!["session"],
+            vec!["tag"],
+            vec!["likes"],
+            vec![],
+        ]
+    );
+}
+
+#[test]
+fn counts_statements_by_kind() {
+    let stats = StatementStats::of(
+        "-- migration: backfill\n\
+         define table user;\n\
+         ALTER TABLE user COMMENT 'people';\n\
+         UPDATE user SET active = true;\n\
+         UPSERT user:admin SET role = 'admin';\n\
+         RELATE user:a->likes->post:b;\n\
+         FOR $u IN (SELECT * FROM user) { CREATE audit SET user = $u.id; };",
+    );
+
+    assert_eq!(stats.get(StatementKind::Define), 1);
+    assert_eq!(stats.get(StatementKind::Other), 1);
+    assert_eq!((stats.schema(), stats.data(), stats.total()), (2, 3, 6));
+    assert!(!stats.is_schema_only());
+    assert_eq!(
+        stats.to_string(),
+        "1 define, 1 alter, 1 update, 1 upsert, 1 relate, 1 other"
+    );
+    assert_eq!(
+        StatementStats::of("-- nothing").to_string(),
+        "no statements"
+    );
+}
@@ -0,0 +1,235 @@
+use surreal_migraine::testing::{TestDb, verify_reversibility};
+use surreal_migraine::types::InMemorySource;
+
+fn source() -> InMemorySource {
+    InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user SCHEMAFULL;
+DEFINE FIELD email ON user TYPE string;
+DEFINE FIELD address ON user TYPE option<object>;
+DEFINE FIELD address.city ON user TYPE option<string>;
+DEFINE INDEX by_email ON user FIELDS email UNIQUE;",
+            None,
+        )
+        .with_migration(
+            "002_seed",
+            "CREATE user:a SET email = 'a@example.com';
+CREATE user:b SET email = 'b@example.com', address.city = 'Haifa';",
+            None,
+        )
+}
+
+#[tokio::test]
+async fn helpers_pass_on_the_migrated_schema() {
+    let db = TestDb::migrated(source()).await.unwrap();
+
+    db.assert_table_exists("user").await;
+    db.assert_field_defined("user", "address.city").await;
+    db.assert_index_present("user", "by_email").await;
+    db.assert_rows("SELECT * FROM user", 2).await;
+    db.assert_rows(
+        "LET $city = 'Haifa'; SELECT * FROM user WHERE address.city = $city",
+        1,
+    )
+    .await;
+    db.assert_rows("SELECT * FROM ONLY user:a", 1).await;
+    db.assert_rows("SELECT * FROM user WHERE email = 'c'", 0)
+        .await;
+
+    // Bookkeeping stays out of the schema.
+    assert_eq!(
+        db.schema().await.unwrap().tables.keys().collect::<Vec<_>>(),
+        ["user"]
+    );
+}
+
+#[tokio::test]
+#[should_panic(expected = "table post does not exist (tables: user)")]
+async fn missing_table_panics_with_the_known_tables() {
+    let db = TestDb::migrated(source()).await.unwrap();
+    db.assert_table_exists("post").await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "index by_name is not defined on user (indexes: by_email)")]
+async fn missing_index_panics_with_the_known_indexes() {
+    let db = TestDb::migrated(source()).await.unwrap();
+    db.assert_index_present("user", "by_name").await;
+}
+
+#[tokio::test]
+#[should_panic(expected = "returned 2 row(s), expected 3")]
+async fn row_count_mismatch_panics() {
+    let db = TestDb::migrated(source()).await.unwrap();
+    db.assert_rows("SELECT * FROM user", 3).await;
+}
+
+#[tokio::test]
+async fn runner_applies_migrations_step_by_step() {
+    let db = TestDb::new().await.unwrap();
+    let runner = db.runner(source());
+    runner.mark_applied(&["001_users"]).await.unwrap();
+    runner.up().await.unwrap();
+
+    // The seed ran without the schema, so `user` is schemaless.
+    db.assert_rows("SELECT * FROM user", 2).await;
+    assert!(db.schema().await.unwrap().tables["user"].fields.is_empty());
+}
+
+#[tokio::test]
+async fn schema_snapshot_matches_a_written_snapshot() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("schema.json");
+    let db = TestDb::migrated(source()).await.unwrap();
+    db.schema().await.unwrap().write(&path).unwrap();
+    std::fs::write(dir.path().join("schema.json.new"), "stale").unwrap();
+
+    db.assert_schema_snapshot(&path).await;
+    assert!(!dir.path().join("schema.json.new").exists());
+}
+
+#[tokio::test]
+async fn missing_schema_snapshot_is_written_for_review() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("snapshots/schema.json");
+    let check = tokio::spawn({
+        let path = path.clone();
+        async move {
+            let db = TestDb::migrated(source()).await.unwrap();
+            db.assert_schema_snapshot(path).await;
+        }
+    });
+    assert!(check.await.unwrap_err().is_panic());
+    assert!(!path.exists());
+
+    let pending = std::fs::read_to_string(dir.path().join("snapshots/schema.json.new")).unwrap();
+    assert!(pending.contains("DEFINE INDEX by_email"), "{pending}");
+}
+
+#[tokio::test]
+#[should_panic(expected = "+        \"name\": \"DEFINE FIELD name ON user TYPE string")]
+async fn schema_snapshot_mismatch_panics_with_a_diff() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("schema.json");
+    let db = TestDb::migrated(source()).await.unwrap();
+    db.schema().await.unwrap().write(&path).unwrap();
+
+    db.db()
+        .query("DEFINE FIELD name ON user TYPE string")
+        .await
+        .unwrap();
+    db.assert_schema_snapshot(&path).await;
+}
+
+#[tokio::test]
+async fn fixtures_load_before_a_data_migration() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("01_users.json"),
+        r#"{ "user": [
+            { "id": "x", "email": "X@Example.com" },
+            { "id": "y", "email": "y@example.com", "address": { "city": "Eilat" } }
+        ] }"#,
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("02_more.surql"),
+        "CREATE user:z SET email = 'Z@EXAMPLE.COM';",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("README.md"), "not a fixture").unwrap();
+
+    let db = TestDb::new().await.unwrap();
+    db.runner(source()).up().await.unwrap();
+    db.load_fixtures(dir.path()).await.unwrap();
+    db.assert_rows("SELECT * FROM user:y WHERE address.city = 'Eilat'", 1)
+        .await;
+
+    let lowercase = source().with_migration(
+        "003_lowercase_emails",
+        "UPDATE user SET email = string::lowercase(email);",
+        None,
+    );
+    db.runner(lowercase).up().await.unwrap();
+    db.assert_rows(
+        "SELECT * FROM user WHERE email != string::lowercase(email)",
+        0,
+    )
+    .await;
+    db.assert_rows("SELECT * FROM user", 5).await;
+}
+
+#[tokio::test]
+async fn invalid_fixtures_name_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("users.json");
+    std::fs::write(&path, r#"{ "user": { "id": "a" } }"#).unwrap();
+
+    let db = TestDb::migrated(source()).await.unwrap();
+    let err = db.load_fixture(&path).await.unwrap_err().to_string();
+    assert!(err.contains("users.json"), "{err}");
+    assert!(err.contains("user: expected an array of records"), "{err}");
+
+    // Records are checked against the schema.
+    std::fs::write(&path, r#"{ "user": [{ "id": "z", "email": 1 }] }"#).unwrap();
+    assert!(db.load_fixture(&path).await.is_err());
+}
+
+#[tokio::test]
+async fn reversible_migrations_pass() {
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user SCHEMAFULL;
+DEFINE FIELD email ON user TYPE string;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration(
+            "002_index",
+            "DEFINE INDEX by_email ON user FIELDS email UNIQUE;",
+            Some("REMOVE INDEX by_email ON user;"),
+        )
+        // Up-only migrations are applied but not checked.
+        .with_migration("003_seed", "CREATE user:a SET email = 'a';", None)
+        .with_migration(
+            "R_functions",
+            "DEFINE FUNCTION OVERWRITE fn::one() { 1 };",
+            None,
+        );
+    verify_reversibility(source).await;
+}
+
+#[tokio::test]
+async fn incomplete_down_scripts_are_reported() {
+    let source = InMemorySource::new()
+        .with_migration(
+            "001_users",
+            "DEFINE TABLE user SCHEMAFULL;
+DEFINE FIELD email ON user TYPE string;",
+            Some("REMOVE TABLE user;"),
+        )
+        .with_migration(
+            "002_posts",
+            "DEFINE TABLE IF NOT EXISTS post;
+DEFINE FIELD name ON user TYPE option<string>;",
+            Some("REMOVE FIELD name ON user;"),
+        )
+        .with_migration(
+            "003_drop_email",
+            "REMOVE FIELD email ON user;",
+            Some("-- nothing to undo"),
+        );
+    let check = tokio::spawn(verify_reversibility(source));
+    let panic = check.await.unwrap_err().into_panic();
+    let message = panic.downcast_ref::<String>().unwrap();
+    assert_eq!(
+        message,
+        "migrations are not reversible:
+  002_posts: the schema after down differs from before up:
+    added table post
+  003_drop_email: the schema after down differs from before up:
+    removed field user.email
+  003_drop_email: up failed after down: The field 'email' does not exist"
+    );
+}
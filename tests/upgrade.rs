@@ -0,0 +1,71 @@
+use surreal_migraine::types::InMemorySource;
+use surreal_migraine::upgrade::{RULES, Rule, Version, advise, advise_with};
+
+#[test]
+fn parses_versions() {
+    assert_eq!("3.0".parse::<Version>().unwrap(), Version::new(3, 0));
+    assert_eq!("v2.1.4".parse::<Version>().unwrap(), Version::new(2, 1));
+    assert_eq!("3".parse::<Version>().unwrap(), Version::new(3, 0));
+    assert!("three".parse::<Version>().is_err());
+    assert!("3.0.1.2".parse::<Version>().is_err());
+}
+
+#[test]
+fn reports_syntax_removed_up_to_the_target_version() {
+    let src = InMemorySource::new()
+        .with_migration(
+            "001_auth",
+            "DEFINE SCOPE account SESSION 24h;\nDEFINE FIELD ok ON user VALUE string::is::email(email);",
+            Some("REMOVE SCOPE account;"),
+        )
+        .with_migration(
+            "002_posts",
+            "-- was: DEFINE TOKEN\nDEFINE FIELD age ON user VALUE <future> { time::now() - born };",
+            None,
+        );
+
+    let to_2 = advise(&src, Version::new(2, 0)).unwrap();
+    assert_eq!(to_2.len(), 1, "{to_2:?}");
+    assert_eq!((to_2[0].migration.as_str(), to_2[0].line), ("001_auth", 1));
+
+    let to_3 = advise(&src, "3.0".parse().unwrap()).unwrap();
+    let found = to_3
+        .iter()
+        .map(|a| (a.migration.as_str(), a.line, a.rule.pattern))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        [
+            ("001_auth", 1, "DEFINE SCOPE"),
+            ("001_auth", 2, "::is::"),
+            ("002_posts", 2, "<future>"),
+        ]
+    );
+}
+
+#[test]
+fn matches_whole_words_only() {
+    let src = InMemorySource::new().with_migration(
+        "001_idx",
+        "DEFINE FIELD mtrees ON forest TYPE int;\nDEFINE INDEX v ON doc FIELDS v MTREE DIMENSION 3;",
+        None,
+    );
+    let advice = advise(&src, Version::new(3, 0)).unwrap();
+    assert_eq!(advice.len(), 1);
+    assert_eq!(advice[0].line, 2);
+}
+
+#[test]
+fn custom_rules_extend_the_table() {
+    let mut rules = RULES.to_vec();
+    rules.push(Rule {
+        since: Version::new(4, 0),
+        pattern: "RELATE",
+        message: "pretend RELATE went away",
+        fix: "INSERT RELATION",
+    });
+    let src = InMemorySource::new().with_migration("001_rel", "RELATE a:1->likes->b:1;", None);
+    assert!(advise(&src, Version::new(4, 0)).unwrap().is_empty());
+    let advice = advise_with(&src, Version::new(4, 0), &rules).unwrap();
+    assert_eq!(advice[0].rule.fix, "INSERT RELATION");
+}
@@ -0,0 +1,171 @@
+use surreal_migraine::ordering::{NumericOrdering, Ordering};
+use surreal_migraine::types::{InMemorySource, MigrationSource};
+use surreal_migraine::validate::{
+    Conflict, DependencyProblem, DuplicatePrefix, Renumber, conflicts, dependencies,
+    numeric_prefix, validate_with,
+};
+
+#[test]
+fn parses_numeric_prefixes() {
+    assert_eq!(numeric_prefix("003_users"), Some(3));
+    assert_eq!(numeric_prefix("20240101120000_init"), Some(20240101120000));
+    assert_eq!(numeric_prefix("R_views"), None);
+    assert_eq!(numeric_prefix("v1_init"), None);
+    assert_eq!(numeric_prefix("_init"), None);
+}
+
+#[test]
+fn reports_duplicates_and_optional_gaps() {
+    let src = InMemorySource::new()
+        .with_migration("001_init", "", None)
+        .with_migration("003_a", "", None)
+        .with_migration("3_b", "", None)
+        .with_migration("006_c", "", None)
+        .with_migration("R_views", "", None);
+
+    let report = src.validate(false).unwrap();
+    assert!(!report.is_valid());
+    assert_eq!(
+        report.duplicates,
+        [DuplicatePrefix {
+            prefix: "003".into(),
+            names: vec!["003_a".into(), "3_b".into()],
+        }]
+    );
+    assert!(report.gaps.is_empty());
+
+    let report = src.validate(true).unwrap();
+    assert_eq!(report.gaps, [2, 4, 5]);
+}
+
+#[test]
+fn gaps_alone_are_valid() {
+    let src = InMemorySource::new()
+        .with_migration("001_init", "", None)
+        .with_migration("004_users", "", None);
+
+    let report = src.validate(true).unwrap();
+    assert!(report.is_valid());
+    assert_eq!(report.gaps, [2, 3]);
+}
+
+/// `<year>-W<week>-<name>`, weeks compared numerically.
+struct SprintOrdering;
+
+impl Ordering for SprintOrdering {
+    fn version<'n>(&self, name: &'n str) -> Option<&'n str> {
+        let (year, rest) = name.split_once("-W")?;
+        let (week, _) = rest.split_once('-')?;
+        name.get(..year.len() + 2 + week.len())
+    }
+
+    fn compare(&self, a: &str, b: &str) -> std::cmp::Ordering {
+        let key = |v: &str| {
+            let (year, week) = v.split_once("-W")?;
+            Some((year.parse::<u32>().ok()?, week.parse::<u32>().ok()?))
+        };
+        key(a).cmp(&key(b))
+    }
+}
+
+#[test]
+fn custom_ordering_finds_duplicate_versions() {
+    let src = InMemorySource::new()
+        .with_migration("2024-W5-users", "", None)
+        .with_migration("2024-W05-posts", "", None)
+        .with_migration("2024-W10-tags", "", None);
+
+    let migrations = src.list().unwrap();
+    assert!(validate_with(&migrations, &NumericOrdering).is_valid());
+
+    let report = validate_with(&migrations, &SprintOrdering);
+    assert_eq!(
+        report.duplicates,
+        [DuplicatePrefix {
+            prefix: "2024-W05".into(),
+            names: vec!["2024-W05-posts".into(), "2024-W5-users".into()],
+        }]
+    );
+}
+
+#[test]
+fn conflicts_follow_the_applied_history() {
+    let src = InMemorySource::new()
+        .with_migration("001_users", "", None)
+        .with_migration("002_posts", "", None)
+        .with_migration("003_tags", "", None)
+        .with_migration("0010_likes", "", None)
+        .with_migration("R_views", "", None);
+
+    // 002 was merged after 003 had been applied, and applied anyway.
+    let (found, renames) = conflicts(
+        &src.list().unwrap(),
+        &["001_users", "003_tags", "002_posts"],
+    );
+    assert_eq!(
+        found,
+        [Conflict::OutOfOrder {
+            name: "002_posts".into(),
+            after: "003_tags".into(),
+            applied: true,
+        }]
+    );
+    assert_eq!(
+        renames,
+        [Renumber {
+            from: "002_posts".into(),
+            to: "011_posts".into(),
+        }]
+    );
+
+    let (found, renames) = conflicts(&src.list().unwrap(), &[]);
+    assert!(found.is_empty());
+    assert!(renames.is_empty());
+}
+
+#[test]
+fn reports_unsatisfiable_dependencies() {
+    let src = InMemorySource::new()
+        .with_migration("001_users.surql", "DEFINE TABLE user;", None)
+        .with_migration("002_posts", "-- depends-on: 001_users\n", None)
+        .with_migration("003_tags", "-- depends: 004_likes, 009_gone\n", None)
+        .with_migration("004_likes", "-- depends: 002_posts\n", None);
+    let migrations = src.list().unwrap();
+    assert_eq!(
+        dependencies(&migrations, &[], true),
+        [
+            DependencyProblem::Unknown {
+                name: "003_tags".into(),
+                dependency: "009_gone".into(),
+            },
+            DependencyProblem::Later {
+                name: "003_tags".into(),
+                dependency: "004_likes".into(),
+            },
+        ]
+    );
+    // Applied migrations satisfy dependencies; unordered runs only need
+    // dependencies to be present.
+    assert!(dependencies(&migrations, &["009_gone.surql"], false).is_empty());
+
+    let report = src.validate(false).unwrap();
+    assert!(!report.is_valid());
+    assert_eq!(report.dependencies.len(), 2);
+
+    let src = InMemorySource::new()
+        .with_migration("001_a", "-- depends: 003_c\n", None)
+        .with_migration("002_b", "-- depends: 001_a\n", None)
+        .with_migration("003_c", "-- depends: 002_b\n", None)
+        .with_migration("004_d", "-- depends: 004_d\n", None);
+    let problems = dependencies(&src.list().unwrap(), &[], true)
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        problems,
+        [
+            "001_a -> 003_c -> 002_b -> 001_a form a dependency cycle",
+            "004_d -> 004_d form a dependency cycle",
+        ]
+    );
+}